@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 use tracing::info;
 
 use lightyear::client::components::ComponentSyncMode;
-use lightyear::prelude::server::{ControlledBy, Replicate, SyncTarget};
+use lightyear::prelude::server::{ControlledBy, Replicate, SpatialPosition, SyncTarget};
 use lightyear::prelude::*;
 use lightyear::shared::replication::components::NetworkRelevanceMode;
 use UserAction;
@@ -40,8 +40,8 @@ impl PlayerBundle {
                 target: NetworkTarget::Single(id),
                 ..default()
             },
-            // use network relevance for replication
-            relevance_mode: NetworkRelevanceMode::InterestManagement,
+            // all clients can see all the player entities
+            relevance_mode: NetworkRelevanceMode::All,
             ..default()
         };
         Self {
@@ -76,6 +76,12 @@ pub struct PlayerId(pub ClientId);
 #[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Deref, DerefMut)]
 pub struct Position(pub(crate) Vec2);
 
+impl SpatialPosition for Position {
+    fn position(&self) -> Vec3 {
+        self.0.extend(0.0)
+    }
+}
+
 impl Add for Position {
     type Output = Position;
     #[inline]
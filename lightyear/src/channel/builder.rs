@@ -14,8 +14,7 @@ use crate::channel::senders::sequenced_unreliable::SequencedUnreliableSender;
 use crate::channel::senders::unordered_unreliable::UnorderedUnreliableSender;
 use crate::channel::senders::unordered_unreliable_with_acks::UnorderedUnreliableWithAcksSender;
 use crate::channel::senders::ChannelSender;
-#[cfg(feature = "trace")]
-use crate::channel::stats::send::ChannelSendStats;
+use crate::channel::stats::ChannelStats;
 use crate::prelude::ChannelKind;
 
 /// A ChannelContainer is a struct that implements the [`Channel`] trait
@@ -24,10 +23,7 @@ pub struct ChannelContainer {
     pub setting: ChannelSettings,
     pub(crate) receiver: ChannelReceiver,
     pub(crate) sender: ChannelSender,
-    // we will put this behind the trace feature for now, as this is pretty niche
-    // and might be performance heavy
-    #[cfg(feature = "trace")]
-    pub(crate) sender_stats: ChannelSendStats,
+    pub(crate) stats: ChannelStats,
 }
 
 /// A `Channel` is an abstraction for a way to send messages over the network
@@ -45,7 +41,7 @@ pub struct ChannelContainer {
 /// app.add_channel::<MyChannel>(ChannelSettings {
 ///     mode: ChannelMode::UnorderedUnreliable,
 ///     direction: ChannelDirection::Bidirectional,
-///     priority: 1.0,
+///     ..default()
 /// });
 /// ```
 pub trait Channel: 'static {
@@ -110,8 +106,7 @@ impl ChannelContainer {
             setting: settings_clone,
             receiver,
             sender,
-            #[cfg(feature = "trace")]
-            sender_stats: ChannelSendStats::default(),
+            stats: ChannelStats::default(),
         }
     }
 }
@@ -120,6 +115,8 @@ impl ChannelContainer {
 #[derive(Clone, Debug, PartialEq)]
 pub struct ChannelSettings {
     pub mode: ChannelMode,
+    /// In which direction messages can be sent on this channel
+    pub direction: ChannelDirection,
     /// How often should we try to send messages on this channel.
     /// Set to `Duration::default()` to send messages every frame if possible.
     pub send_frequency: Duration,
@@ -131,6 +128,7 @@ impl Default for ChannelSettings {
     fn default() -> Self {
         Self {
             mode: ChannelMode::UnorderedUnreliable,
+            direction: ChannelDirection::Bidirectional,
             send_frequency: Duration::default(),
             priority: 1.0,
         }
@@ -191,6 +189,18 @@ pub enum ChannelDirection {
     Bidirectional,
 }
 
+impl ChannelDirection {
+    /// Returns true if every direction allowed by `other` is also allowed by `self`
+    pub fn contains(&self, other: ChannelDirection) -> bool {
+        *self == ChannelDirection::Bidirectional || *self == other
+    }
+
+    /// Returns true if there is at least one direction allowed by both `self` and `other`
+    pub fn overlaps(&self, other: ChannelDirection) -> bool {
+        self.contains(other) || other.contains(*self)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct ReliableSettings {
     /// Duration to wait before resending a packet if it has not been acked
@@ -244,3 +254,28 @@ pub struct InputChannel;
 /// Channel to send messages related to Authority transfers
 /// This is an Ordered Reliable channel
 pub struct AuthorityChannel;
+
+#[derive(ChannelInternal)]
+/// Channel used during the connection handshake to check that the client and server protocols match.
+/// This is an Ordered Reliable channel
+pub struct HandshakeChannel;
+
+#[derive(ChannelInternal)]
+/// Channel used to send the world snapshots when the replication uses [`SendUpdatesMode::Snapshot`](crate::prelude::SendUpdatesMode::Snapshot).
+/// This is an Unordered Unreliable channel
+pub struct SnapshotChannel;
+
+#[derive(ChannelInternal)]
+/// Channel used by the clients to ack the snapshots that they reconstructed.
+/// This is a Sequenced Unreliable channel, because only the latest ack matters
+pub struct SnapshotAckChannel;
+
+#[derive(ChannelInternal)]
+/// Channel used to send the state checksums used for desync detection.
+/// This is an Unordered Unreliable channel
+pub struct ChecksumChannel;
+
+#[derive(ChannelInternal)]
+/// Channel used by the clients to send their interpolation delay to the server, for lag compensation.
+/// This is a Sequenced Reliable channel, because only the latest delay matters
+pub struct InterpolationDelayChannel;
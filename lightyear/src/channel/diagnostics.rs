@@ -0,0 +1,154 @@
+//! Compute Diagnostics based on per-channel statistics (bandwidth, resends, ack latency)
+
+use bevy::app::{App, Plugin};
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::utils::{Duration, HashMap};
+
+use crate::channel::stats::ChannelStats;
+use crate::protocol::channel::{ChannelKind, ChannelRegistry};
+
+/// Plugin to compute network diagnostics for each registered [`Channel`](crate::prelude::Channel).
+///
+/// The diagnostics are named `channel/<channel_name>/<measurement>`, for example
+/// `channel/EntityUpdatesChannel/kb_sent`.
+pub struct ChannelDiagnosticsPlugin {
+    pub history_len: usize,
+    pub flush_interval: Duration,
+}
+
+impl Default for ChannelDiagnosticsPlugin {
+    fn default() -> Self {
+        Self {
+            history_len: 60,
+            flush_interval: Duration::from_millis(200),
+        }
+    }
+}
+
+impl ChannelDiagnosticsPlugin {
+    /// Number of messages (or fragments) sent per second
+    pub const MESSAGES_SENT: &'static str = "messages_sent";
+    /// Number of KB sent per second
+    pub const KB_SENT: &'static str = "kb_sent";
+    /// Number of messages (or fragments) received per second
+    pub const MESSAGES_RECEIVED: &'static str = "messages_received";
+    /// Number of KB received per second
+    pub const KB_RECEIVED: &'static str = "kb_received";
+    /// Number of fragments sent per second
+    pub const FRAGMENTS_SENT: &'static str = "fragments_sent";
+    /// Number of messages (or fragments) resent per second
+    pub const MESSAGES_RESENT: &'static str = "messages_resent";
+    /// Number of messages (or fragments) dropped by the priority filter per second
+    pub const MESSAGES_DROPPED: &'static str = "messages_dropped";
+    /// Smoothed ack latency
+    pub const ACK_LATENCY: &'static str = "ack_latency.ms";
+    /// Number of messages buffered in the channel
+    pub const QUEUE_DEPTH: &'static str = "queue_depth";
+
+    const MEASUREMENTS: [(&'static str, &'static str); 9] = [
+        (Self::MESSAGES_SENT, "messages/s"),
+        (Self::KB_SENT, "KB/s"),
+        (Self::MESSAGES_RECEIVED, "messages/s"),
+        (Self::KB_RECEIVED, "KB/s"),
+        (Self::FRAGMENTS_SENT, "fragments/s"),
+        (Self::MESSAGES_RESENT, "messages/s"),
+        (Self::MESSAGES_DROPPED, "messages/s"),
+        (Self::ACK_LATENCY, "ms"),
+        (Self::QUEUE_DEPTH, "messages"),
+    ];
+
+    /// Path of the diagnostic `measurement` for the channel named `channel_name`
+    pub fn path(channel_name: &str, measurement: &str) -> DiagnosticPath {
+        DiagnosticPath::from_components(["channel", channel_name, measurement])
+    }
+
+    /// Add the measurements for every channel.
+    ///
+    /// `elapsed` is the current elapsed real time; `previous` contains the stats at the time of the
+    /// previous flush, so that we can compute rates.
+    pub(crate) fn add_measurements(
+        stats: HashMap<ChannelKind, ChannelStats>,
+        previous: &mut PreviousChannelStats,
+        registry: &ChannelRegistry,
+        elapsed: Duration,
+        diagnostics: &mut Diagnostics,
+    ) {
+        let delta_seconds = elapsed.saturating_sub(previous.elapsed).as_secs_f64();
+        if delta_seconds == 0.0 {
+            return;
+        }
+        for (kind, stats) in stats.iter() {
+            let Some(name) = registry.name(kind) else {
+                continue;
+            };
+            let prev = previous.stats.get(kind).copied().unwrap_or_default();
+            let rate =
+                |current: usize, prev: usize| current.saturating_sub(prev) as f64 / delta_seconds;
+            diagnostics.add_measurement(&Self::path(name, Self::MESSAGES_SENT), || {
+                rate(stats.send.messages_sent(), prev.send.messages_sent())
+            });
+            diagnostics.add_measurement(&Self::path(name, Self::KB_SENT), || {
+                rate(stats.send.bytes_sent(), prev.send.bytes_sent()) / 1000.0
+            });
+            diagnostics.add_measurement(&Self::path(name, Self::MESSAGES_RECEIVED), || {
+                rate(
+                    stats.receive.messages_received(),
+                    prev.receive.messages_received(),
+                )
+            });
+            diagnostics.add_measurement(&Self::path(name, Self::KB_RECEIVED), || {
+                rate(
+                    stats.receive.bytes_received(),
+                    prev.receive.bytes_received(),
+                ) / 1000.0
+            });
+            diagnostics.add_measurement(&Self::path(name, Self::FRAGMENTS_SENT), || {
+                rate(stats.send.fragments_sent(), prev.send.fragments_sent())
+            });
+            diagnostics.add_measurement(&Self::path(name, Self::MESSAGES_RESENT), || {
+                rate(stats.send.messages_resent(), prev.send.messages_resent())
+            });
+            diagnostics.add_measurement(&Self::path(name, Self::MESSAGES_DROPPED), || {
+                rate(stats.send.messages_dropped(), prev.send.messages_dropped())
+            });
+            diagnostics.add_measurement(&Self::path(name, Self::ACK_LATENCY), || {
+                stats.send.ack_latency().as_secs_f64() * 1000.0
+            });
+            diagnostics.add_measurement(&Self::path(name, Self::QUEUE_DEPTH), || {
+                stats.send.queue_depth() as f64
+            });
+        }
+        previous.stats = stats;
+        previous.elapsed = elapsed;
+    }
+}
+
+/// Channel stats at the time of the last diagnostics flush
+#[derive(Default, Debug)]
+pub(crate) struct PreviousChannelStats {
+    stats: HashMap<ChannelKind, ChannelStats>,
+    elapsed: Duration,
+}
+
+impl Plugin for ChannelDiagnosticsPlugin {
+    fn build(&self, _: &mut App) {}
+
+    fn finish(&self, app: &mut App) {
+        // register the diagnostics in `finish` so that all the channels have been added to the registry
+        let names = app
+            .world()
+            .resource::<ChannelRegistry>()
+            .names()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        for name in names {
+            for (measurement, suffix) in Self::MEASUREMENTS {
+                app.register_diagnostic(
+                    Diagnostic::new(Self::path(&name, measurement))
+                        .with_suffix(suffix)
+                        .with_max_history_length(self.history_len),
+                );
+            }
+        }
+    }
+}
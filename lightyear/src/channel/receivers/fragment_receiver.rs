@@ -4,7 +4,6 @@ use bytes::Bytes;
 use tracing::trace;
 
 use crate::packet::message::{FragmentData, MessageId};
-use crate::packet::packet::FRAGMENT_SIZE;
 use crate::prelude::Tick;
 use crate::shared::time_manager::WrappedTime;
 
@@ -53,7 +52,7 @@ impl FragmentReceiver {
         // completed the fragmented message!
         if let Some(payload) = fragment_message.receive_fragment(
             fragment.fragment_id as usize,
-            fragment.bytes.as_ref(),
+            fragment.bytes,
             current_time,
         ) {
             self.fragment_messages.remove(&fragment.message_id);
@@ -66,12 +65,14 @@ impl FragmentReceiver {
 
 #[derive(Debug, Clone)]
 /// Data structure to reconstruct a single fragmented message from individual fragments
+///
+/// The fragments are stored individually and concatenated once they have all been received,
+/// so that we don't need to know the fragment size used by the sender (it can change during
+/// the lifetime of a connection, for example after MTU discovery)
 pub struct FragmentConstructor {
     num_fragments: usize,
     num_received_fragments: usize,
-    received: Vec<bool>,
-    // bytes: Bytes,
-    bytes: Vec<u8>,
+    fragments: Vec<Option<Bytes>>,
 
     tick: Tick,
     last_received: Option<WrappedTime>,
@@ -82,8 +83,7 @@ impl FragmentConstructor {
         Self {
             num_fragments,
             num_received_fragments: 0,
-            received: vec![false; num_fragments],
-            bytes: vec![0; num_fragments * FRAGMENT_SIZE],
+            fragments: vec![None; num_fragments],
             tick,
             last_received: None,
         }
@@ -92,32 +92,24 @@ impl FragmentConstructor {
     pub fn receive_fragment(
         &mut self,
         fragment_index: usize,
-        bytes: &[u8],
+        bytes: Bytes,
         received_time: Option<WrappedTime>,
     ) -> Option<(Tick, Bytes)> {
         self.last_received = received_time;
 
-        let is_last_fragment = fragment_index == self.num_fragments - 1;
-
-        // TODO: check sizes?
-
-        if !self.received[fragment_index] {
-            self.received[fragment_index] = true;
+        let fragment = self.fragments.get_mut(fragment_index)?;
+        if fragment.is_none() {
+            *fragment = Some(bytes);
             self.num_received_fragments += 1;
-
-            if is_last_fragment {
-                let len = (self.num_fragments - 1) * FRAGMENT_SIZE + bytes.len();
-                self.bytes.resize(len, 0);
-            }
-
-            let start = fragment_index * FRAGMENT_SIZE;
-            let end = start + bytes.len();
-            self.bytes[start..end].copy_from_slice(bytes);
         }
 
         if self.num_received_fragments == self.num_fragments {
             trace!("Received all fragments!");
-            let payload = std::mem::take(&mut self.bytes);
+            let len = self.fragments.iter().flatten().map(|b| b.len()).sum();
+            let mut payload = Vec::with_capacity(len);
+            for fragment in std::mem::take(&mut self.fragments).into_iter().flatten() {
+                payload.extend_from_slice(fragment.as_ref());
+            }
             return Some((self.tick, payload.into()));
         }
 
@@ -128,6 +120,7 @@ impl FragmentConstructor {
 #[cfg(test)]
 mod tests {
     use crate::channel::senders::fragment_sender::FragmentSender;
+    use crate::packet::packet::FRAGMENT_SIZE;
 
     use super::*;
 
@@ -149,4 +142,35 @@ mod tests {
             Some((Tick(0), message_bytes.clone()))
         );
     }
+
+    #[test]
+    fn test_receiver_smaller_fragment_size() {
+        let mut receiver = FragmentReceiver::new();
+        let num_bytes = FRAGMENT_SIZE + 10;
+        let message_bytes = Bytes::from((0..num_bytes).map(|i| i as u8).collect::<Vec<_>>());
+        let mut sender = FragmentSender::new();
+        sender.fragment_size = FRAGMENT_SIZE / 3;
+        let fragments = sender
+            .build_fragments(MessageId(0), None, message_bytes.clone())
+            .unwrap();
+        assert_eq!(fragments.len(), 4);
+
+        // fragments can arrive in any order
+        assert_eq!(
+            receiver.receive_fragment(fragments[3].clone(), Tick(0), None),
+            None
+        );
+        assert_eq!(
+            receiver.receive_fragment(fragments[1].clone(), Tick(0), None),
+            None
+        );
+        assert_eq!(
+            receiver.receive_fragment(fragments[0].clone(), Tick(0), None),
+            None
+        );
+        assert_eq!(
+            receiver.receive_fragment(fragments[2].clone(), Tick(0), None),
+            Some((Tick(0), message_bytes.clone()))
+        );
+    }
 }
@@ -5,6 +5,7 @@ use crossbeam_channel::Receiver;
 use enum_dispatch::enum_dispatch;
 
 use crate::packet::message::{MessageAck, MessageId, SendMessage};
+use crate::protocol::message::MessageKind;
 use crate::serialize::SerializationError;
 use crate::shared::ping::manager::PingManager;
 use crate::shared::tick_manager::TickManager;
@@ -42,6 +43,24 @@ pub trait ChannelSend {
         priority: f32,
     ) -> Result<Option<MessageId>, SerializationError>;
 
+    /// Queues a message to be transmitted, replacing any message with the same [`CoalescingKey`]
+    /// that is still waiting to be sent.
+    ///
+    /// Only the unreliable channels ([`ChannelMode::UnorderedUnreliable`], [`ChannelMode::SequencedUnreliable`]
+    /// and [`ChannelMode::UnorderedUnreliableWithAcks`]) coalesce messages; the reliable channels send every message.
+    ///
+    /// [`ChannelMode::UnorderedUnreliable`]: crate::channel::builder::ChannelMode::UnorderedUnreliable
+    /// [`ChannelMode::SequencedUnreliable`]: crate::channel::builder::ChannelMode::SequencedUnreliable
+    /// [`ChannelMode::UnorderedUnreliableWithAcks`]: crate::channel::builder::ChannelMode::UnorderedUnreliableWithAcks
+    fn buffer_send_coalesced(
+        &mut self,
+        message: Bytes,
+        priority: f32,
+        _key: CoalescingKey,
+    ) -> Result<Option<MessageId>, SerializationError> {
+        self.buffer_send(message, priority)
+    }
+
     /// Reads from the buffer of messages to send to prepare a list of Packets
     /// that can be sent over the network for this channel
     fn send_packet(&mut self) -> (VecDeque<SendMessage>, VecDeque<SendMessage>);
@@ -58,6 +77,28 @@ pub trait ChannelSend {
 
     /// Send nacks to the subscribers of nacks
     fn send_nacks(&mut self, nack: MessageId);
+
+    /// Number of messages (or fragments) currently buffered in the channel
+    fn num_buffered_messages(&self) -> usize;
+
+    /// Total number of messages (or fragments) that had to be sent again because they
+    /// were not acked in time
+    fn num_resent_messages(&self) -> usize {
+        0
+    }
+
+    /// Set the maximum size of the fragments of a message that is too big to fit in a single packet
+    fn set_fragment_size(&mut self, fragment_size: usize);
+}
+
+/// Messages buffered on an unreliable channel with the same [`CoalescingKey`] replace each other:
+/// only the most recent one is sent in the next packet
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct CoalescingKey {
+    /// Type of the message
+    pub(crate) kind: MessageKind,
+    /// User-provided key, to keep one message per key (for example one per entity)
+    pub(crate) key: u64,
 }
 
 /// Enum dispatch lets us derive ChannelSend on each enum variant
@@ -76,6 +76,8 @@ pub struct ReliableSender {
     /// Factor that makes sure that the priority accumulates at the same right even the channel
     /// sends messages infrequently
     priority_multiplier: f32,
+    /// Number of messages (or fragments) that were sent again because they were not acked in time
+    num_resent_messages: usize,
 }
 
 impl ReliableSender {
@@ -99,6 +101,7 @@ impl ReliableSender {
             current_time: WrappedTime::default(),
             timer,
             priority_multiplier: 1.0,
+            num_resent_messages: 0,
         }
     }
 }
@@ -215,6 +218,9 @@ impl ChannelSend for ReliableSender {
                                 priority: unacked_message_with_priority.accumulated_priority,
                             });
                             self.message_ids_to_send.insert(message_info);
+                            if last_sent.is_some() {
+                                self.num_resent_messages += 1;
+                            }
                             *last_sent = Some(self.current_time);
                         }
                     }
@@ -236,6 +242,9 @@ impl ChannelSend for ReliableSender {
                                     priority: unacked_message_with_priority.accumulated_priority,
                                 });
                                 self.message_ids_to_send.insert(message_info);
+                                if f.last_sent.is_some() {
+                                    self.num_resent_messages += 1;
+                                }
                                 f.last_sent = Some(self.current_time);
                             }
                         })
@@ -333,6 +342,18 @@ impl ChannelSend for ReliableSender {
             sender.send(nack).unwrap();
         }
     }
+
+    fn set_fragment_size(&mut self, fragment_size: usize) {
+        self.fragment_sender.set_fragment_size(fragment_size);
+    }
+
+    fn num_buffered_messages(&self) -> usize {
+        self.unacked_messages.len()
+    }
+
+    fn num_resent_messages(&self) -> usize {
+        self.num_resent_messages
+    }
 }
 
 #[cfg(test)]
@@ -375,6 +396,7 @@ mod tests {
         sender.current_time += Duration::from_millis(200);
         let (single, _) = sender.send_packet();
         assert_eq!(single.len(), 1);
+        assert_eq!(sender.num_resent_messages(), 1);
         assert_eq!(
             single.front().unwrap(),
             &SendMessage {
@@ -1,12 +1,13 @@
 use bevy::time::{Timer, TimerMode};
 use bevy::utils::Duration;
+use bevy::utils::{Entry, HashMap};
 use std::collections::VecDeque;
 
 use bytes::Bytes;
 use crossbeam_channel::{Receiver, Sender};
 
 use crate::channel::senders::fragment_sender::FragmentSender;
-use crate::channel::senders::ChannelSend;
+use crate::channel::senders::{ChannelSend, CoalescingKey};
 use crate::packet::message::{MessageAck, MessageData, MessageId, SendMessage, SingleData};
 use crate::serialize::SerializationError;
 use crate::shared::ping::manager::PingManager;
@@ -21,6 +22,8 @@ pub struct SequencedUnreliableSender {
     single_messages_to_send: VecDeque<SendMessage>,
     /// list of fragmented messages that we want to fit into packets and send
     fragmented_messages_to_send: VecDeque<SendMessage>,
+    /// Index in `single_messages_to_send` of the latest message buffered for each [`CoalescingKey`]
+    coalesced_messages: HashMap<CoalescingKey, usize>,
 
     /// Message id to use for the next message to be sent
     next_send_message_id: MessageId,
@@ -42,6 +45,7 @@ impl SequencedUnreliableSender {
         Self {
             single_messages_to_send: VecDeque::new(),
             fragmented_messages_to_send: VecDeque::new(),
+            coalesced_messages: HashMap::default(),
             next_send_message_id: MessageId(0),
             fragment_sender: FragmentSender::new(),
             nack_senders: vec![],
@@ -86,12 +90,50 @@ impl ChannelSend for SequencedUnreliableSender {
         Ok(Some(message_id))
     }
 
+    /// Buffer a message that replaces the previous message buffered with the same key.
+    ///
+    /// The new message takes the slot and the [`MessageId`] of the message it replaces, so that
+    /// the messages buffered after it (for other keys) still have higher ids and are not dropped by the receiver.
+    /// Messages that need to be fragmented are not coalesced.
+    fn buffer_send_coalesced(
+        &mut self,
+        message: Bytes,
+        priority: f32,
+        key: CoalescingKey,
+    ) -> Result<Option<MessageId>, SerializationError> {
+        if message.len() > self.fragment_sender.fragment_size {
+            return self.buffer_send(message, priority);
+        }
+        match self.coalesced_messages.entry(key) {
+            Entry::Occupied(entry) => {
+                let slot = &mut self.single_messages_to_send[*entry.get()];
+                let MessageData::Single(data) = &mut slot.data else {
+                    unreachable!("coalesced messages are never fragmented")
+                };
+                data.bytes = message;
+                slot.priority = priority;
+                Ok(data.id)
+            }
+            Entry::Vacant(entry) => {
+                let message_id = self.next_send_message_id;
+                entry.insert(self.single_messages_to_send.len());
+                self.single_messages_to_send.push_back(SendMessage {
+                    data: MessageData::Single(SingleData::new(Some(message_id), message)),
+                    priority,
+                });
+                self.next_send_message_id += 1;
+                Ok(Some(message_id))
+            }
+        }
+    }
+
     /// Take messages from the buffer of messages to be sent, and build a list of packets
     /// to be sent
     fn send_packet(&mut self) -> (VecDeque<SendMessage>, VecDeque<SendMessage>) {
         if self.timer.as_ref().is_some_and(|t| !t.finished()) {
             return (VecDeque::new(), VecDeque::new());
         }
+        self.coalesced_messages.clear();
         (
             std::mem::take(&mut self.single_messages_to_send),
             std::mem::take(&mut self.fragmented_messages_to_send),
@@ -122,12 +164,24 @@ impl ChannelSend for SequencedUnreliableSender {
             sender.send(nack).unwrap();
         }
     }
+
+    fn set_fragment_size(&mut self, fragment_size: usize) {
+        self.fragment_sender.set_fragment_size(fragment_size);
+    }
+
+    fn num_buffered_messages(&self) -> usize {
+        self.single_messages_to_send.len() + self.fragmented_messages_to_send.len()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::prelude::{PingConfig, TickConfig};
+    use crate::channel::receivers::sequenced_unreliable::SequencedUnreliableReceiver;
+    use crate::channel::receivers::ChannelReceive;
+    use crate::packet::message::ReceiveMessage;
+    use crate::prelude::{PingConfig, Tick, TickConfig};
+    use crate::protocol::message::MessageKind;
     #[test]
     fn test_sequenced_unreliable_sender_internals() {
         let mut sender = SequencedUnreliableSender::new(Duration::from_secs(1));
@@ -152,4 +206,68 @@ mod tests {
         let (single, _) = sender.send_packet();
         assert_eq!(single.len(), 1);
     }
+
+    #[test]
+    fn test_coalescing() {
+        let mut sender = SequencedUnreliableSender::new(Duration::default());
+        let key = |key| CoalescingKey {
+            kind: MessageKind::of::<u8>(),
+            key,
+        };
+        assert_eq!(
+            sender
+                .buffer_send_coalesced(Bytes::from("a"), 1.0, key(0))
+                .unwrap(),
+            Some(MessageId(0))
+        );
+        assert_eq!(
+            sender
+                .buffer_send_coalesced(Bytes::from("x"), 1.0, key(1))
+                .unwrap(),
+            Some(MessageId(1))
+        );
+        sender.buffer_send(Bytes::from("y"), 1.0).unwrap();
+        // the replacement keeps the id of the replaced message
+        assert_eq!(
+            sender
+                .buffer_send_coalesced(Bytes::from("b"), 1.0, key(0))
+                .unwrap(),
+            Some(MessageId(0))
+        );
+        let (single, _) = sender.send_packet();
+        let sent = single
+            .iter()
+            .map(|message| {
+                let MessageData::Single(data) = &message.data else {
+                    unreachable!()
+                };
+                (data.id.unwrap(), data.bytes.clone())
+            })
+            .collect::<Vec<_>>();
+        // the messages are sent in increasing id order, so the receiver does not drop any of them
+        assert_eq!(
+            sent,
+            vec![
+                (MessageId(0), Bytes::from("b")),
+                (MessageId(1), Bytes::from("x")),
+                (MessageId(2), Bytes::from("y")),
+            ]
+        );
+
+        // the receiver reads all the messages
+        let mut receiver = SequencedUnreliableReceiver::new();
+        for message in single {
+            receiver
+                .buffer_recv(ReceiveMessage {
+                    data: message.data,
+                    remote_sent_tick: Tick(0),
+                })
+                .unwrap();
+        }
+        let mut received = vec![];
+        while let Some((_, bytes)) = receiver.read_message() {
+            received.push(bytes);
+        }
+        assert_eq!(received, vec!["b", "x", "y"]);
+    }
 }
@@ -1,13 +1,14 @@
 use bevy::prelude::Timer;
 use bevy::time::TimerMode;
 use bevy::utils::Duration;
+use bevy::utils::{Entry, HashMap};
 use std::collections::VecDeque;
 
 use bytes::Bytes;
 use crossbeam_channel::{Receiver, Sender};
 
 use crate::channel::senders::fragment_sender::FragmentSender;
-use crate::channel::senders::ChannelSend;
+use crate::channel::senders::{ChannelSend, CoalescingKey};
 use crate::packet::message::{MessageAck, MessageData, MessageId, SendMessage, SingleData};
 use crate::serialize::SerializationError;
 use crate::shared::ping::manager::PingManager;
@@ -22,6 +23,8 @@ pub struct UnorderedUnreliableSender {
     single_messages_to_send: VecDeque<SendMessage>,
     /// list of fragmented messages that we want to fit into packets and send
     fragmented_messages_to_send: VecDeque<SendMessage>,
+    /// Index in `single_messages_to_send` of the latest message buffered for each [`CoalescingKey`]
+    coalesced_messages: HashMap<CoalescingKey, usize>,
     /// Fragmented messages need an id (so they can be reconstructed), this keeps track
     /// of the next id to use
     next_send_fragmented_message_id: MessageId,
@@ -43,6 +46,7 @@ impl UnorderedUnreliableSender {
         Self {
             single_messages_to_send: VecDeque::new(),
             fragmented_messages_to_send: VecDeque::new(),
+            coalesced_messages: HashMap::default(),
             next_send_fragmented_message_id: MessageId::default(),
             fragment_sender: FragmentSender::new(),
             nack_senders: vec![],
@@ -88,11 +92,38 @@ impl ChannelSend for UnorderedUnreliableSender {
         }
     }
 
+    /// Buffer a message that replaces the previous message buffered with the same key.
+    ///
+    /// Messages that need to be fragmented are not coalesced.
+    fn buffer_send_coalesced(
+        &mut self,
+        message: Bytes,
+        priority: f32,
+        key: CoalescingKey,
+    ) -> Result<Option<MessageId>, SerializationError> {
+        if message.len() > self.fragment_sender.fragment_size {
+            return self.buffer_send(message, priority);
+        }
+        let send_message = SendMessage {
+            data: MessageData::Single(SingleData::new(None, message)),
+            priority,
+        };
+        match self.coalesced_messages.entry(key) {
+            Entry::Occupied(entry) => self.single_messages_to_send[*entry.get()] = send_message,
+            Entry::Vacant(entry) => {
+                entry.insert(self.single_messages_to_send.len());
+                self.single_messages_to_send.push_back(send_message);
+            }
+        }
+        Ok(None)
+    }
+
     /// Take messages from the buffer of messages to be sent, and build a list of packets to be sent
     fn send_packet(&mut self) -> (VecDeque<SendMessage>, VecDeque<SendMessage>) {
         if self.timer.as_ref().is_some_and(|t| !t.finished()) {
             return (VecDeque::new(), VecDeque::new());
         }
+        self.coalesced_messages.clear();
         (
             std::mem::take(&mut self.single_messages_to_send),
             std::mem::take(&mut self.fragmented_messages_to_send),
@@ -123,12 +154,62 @@ impl ChannelSend for UnorderedUnreliableSender {
             sender.send(nack).unwrap();
         }
     }
+
+    fn set_fragment_size(&mut self, fragment_size: usize) {
+        self.fragment_sender.set_fragment_size(fragment_size);
+    }
+
+    fn num_buffered_messages(&self) -> usize {
+        self.single_messages_to_send.len() + self.fragmented_messages_to_send.len()
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::protocol::message::MessageKind;
+
     // #[test]
     // fn test_unordered_unreliable_sender_internals() {
     //     todo!()
     // }
+
+    #[test]
+    fn test_coalescing() {
+        let mut sender = UnorderedUnreliableSender::new(Duration::default());
+        let key = |key| CoalescingKey {
+            kind: MessageKind::of::<u8>(),
+            key,
+        };
+        sender
+            .buffer_send_coalesced(Bytes::from("a1"), 1.0, key(0))
+            .unwrap();
+        sender.buffer_send(Bytes::from("b"), 1.0).unwrap();
+        sender
+            .buffer_send_coalesced(Bytes::from("c"), 1.0, key(1))
+            .unwrap();
+        sender
+            .buffer_send_coalesced(Bytes::from("a2"), 2.0, key(0))
+            .unwrap();
+        assert_eq!(sender.num_buffered_messages(), 3);
+
+        // the latest message replaced the previous message with the same key, at the same position
+        let (single, _) = sender.send_packet();
+        let bytes = single
+            .iter()
+            .map(|m| match &m.data {
+                MessageData::Single(single) => single.bytes.clone(),
+                MessageData::Fragment(_) => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(bytes, vec!["a2", "b", "c"]);
+        assert_eq!(single[0].priority, 2.0);
+
+        // once the messages are sent, a new message with the same key is buffered again
+        sender
+            .buffer_send_coalesced(Bytes::from("a3"), 1.0, key(0))
+            .unwrap();
+        let (single, _) = sender.send_packet();
+        assert_eq!(single.len(), 1);
+    }
 }
@@ -1,5 +1,6 @@
 use bevy::prelude::{Timer, TimerMode};
 use bevy::utils::Duration;
+use bevy::utils::{Entry, HashMap};
 use std::collections::VecDeque;
 
 use bytes::Bytes;
@@ -7,7 +8,7 @@ use crossbeam_channel::{Receiver, Sender};
 
 use crate::channel::senders::fragment_ack_receiver::FragmentAckReceiver;
 use crate::channel::senders::fragment_sender::FragmentSender;
-use crate::channel::senders::ChannelSend;
+use crate::channel::senders::{ChannelSend, CoalescingKey};
 use crate::packet::message::{MessageAck, MessageData, MessageId, SendMessage, SingleData};
 use crate::serialize::SerializationError;
 use crate::shared::ping::manager::PingManager;
@@ -25,6 +26,8 @@ pub struct UnorderedUnreliableWithAcksSender {
     single_messages_to_send: VecDeque<SendMessage>,
     /// list of fragmented messages that we want to fit into packets and send
     fragmented_messages_to_send: VecDeque<SendMessage>,
+    /// Index in `single_messages_to_send` of the latest message buffered for each [`CoalescingKey`]
+    coalesced_messages: HashMap<CoalescingKey, usize>,
     /// Message id to use for the next message to be sent
     next_send_message_id: MessageId,
     /// Used to split a message into fragments if the message is too big
@@ -53,6 +56,7 @@ impl UnorderedUnreliableWithAcksSender {
         Self {
             single_messages_to_send: VecDeque::new(),
             fragmented_messages_to_send: VecDeque::new(),
+            coalesced_messages: HashMap::default(),
             next_send_message_id: MessageId::default(),
             fragment_sender: FragmentSender::new(),
             ack_senders: Vec::new(),
@@ -105,11 +109,49 @@ impl ChannelSend for UnorderedUnreliableWithAcksSender {
         Ok(Some(message_id))
     }
 
+    /// Buffer a message that replaces the previous message buffered with the same key.
+    ///
+    /// The new message takes the slot and the [`MessageId`] of the message it replaces, so that
+    /// the subscribers to the acks are notified with the id that was returned for that key.
+    /// Messages that need to be fragmented are not coalesced.
+    fn buffer_send_coalesced(
+        &mut self,
+        message: Bytes,
+        priority: f32,
+        key: CoalescingKey,
+    ) -> Result<Option<MessageId>, SerializationError> {
+        if message.len() > self.fragment_sender.fragment_size {
+            return self.buffer_send(message, priority);
+        }
+        match self.coalesced_messages.entry(key) {
+            Entry::Occupied(entry) => {
+                let slot = &mut self.single_messages_to_send[*entry.get()];
+                let MessageData::Single(data) = &mut slot.data else {
+                    unreachable!("coalesced messages are never fragmented")
+                };
+                data.bytes = message;
+                slot.priority = priority;
+                Ok(data.id)
+            }
+            Entry::Vacant(entry) => {
+                let message_id = self.next_send_message_id;
+                entry.insert(self.single_messages_to_send.len());
+                self.single_messages_to_send.push_back(SendMessage {
+                    data: MessageData::Single(SingleData::new(Some(message_id), message)),
+                    priority,
+                });
+                self.next_send_message_id += 1;
+                Ok(Some(message_id))
+            }
+        }
+    }
+
     /// Take messages from the buffer of messages to be sent, and build a list of packets to be sent
     fn send_packet(&mut self) -> (VecDeque<SendMessage>, VecDeque<SendMessage>) {
         if self.timer.as_ref().is_some_and(|t| !t.finished()) {
             return (VecDeque::new(), VecDeque::new());
         }
+        self.coalesced_messages.clear();
         (
             std::mem::take(&mut self.single_messages_to_send),
             std::mem::take(&mut self.fragmented_messages_to_send),
@@ -163,11 +205,20 @@ impl ChannelSend for UnorderedUnreliableWithAcksSender {
             sender.send(nack).unwrap();
         }
     }
+
+    fn set_fragment_size(&mut self, fragment_size: usize) {
+        self.fragment_sender.set_fragment_size(fragment_size);
+    }
+
+    fn num_buffered_messages(&self) -> usize {
+        self.single_messages_to_send.len() + self.fragmented_messages_to_send.len()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::packet::packet::FRAGMENT_SIZE;
+    use crate::protocol::message::MessageKind;
 
     use super::*;
 
@@ -213,4 +264,61 @@ mod tests {
         });
         assert_eq!(receiver.try_recv().unwrap(), message_id);
     }
+
+    #[test]
+    fn test_coalescing() {
+        let mut sender = UnorderedUnreliableWithAcksSender::new(Duration::default());
+        let receiver = sender.subscribe_acks();
+        let key = |key| CoalescingKey {
+            kind: MessageKind::of::<u8>(),
+            key,
+        };
+        assert_eq!(
+            sender
+                .buffer_send_coalesced(Bytes::from("a"), 1.0, key(0))
+                .unwrap(),
+            Some(MessageId(0))
+        );
+        sender.buffer_send(Bytes::from("x"), 1.0).unwrap();
+        // the replacement keeps the id of the replaced message
+        assert_eq!(
+            sender
+                .buffer_send_coalesced(Bytes::from("b"), 1.0, key(0))
+                .unwrap(),
+            Some(MessageId(0))
+        );
+        assert_eq!(sender.num_buffered_messages(), 2);
+        let (single, _) = sender.send_packet();
+        let sent = single
+            .iter()
+            .map(|message| {
+                let MessageData::Single(data) = &message.data else {
+                    unreachable!()
+                };
+                (data.id.unwrap(), data.bytes.clone())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sent,
+            vec![
+                (MessageId(0), Bytes::from("b")),
+                (MessageId(1), Bytes::from("x")),
+            ]
+        );
+
+        // the ack of the latest message is reported with the id returned for the key
+        sender.receive_ack(&MessageAck {
+            message_id: MessageId(0),
+            fragment_id: None,
+        });
+        assert_eq!(receiver.try_recv().unwrap(), MessageId(0));
+
+        // once the packet is sent, a new message with the same key gets a new id
+        assert_eq!(
+            sender
+                .buffer_send_coalesced(Bytes::from("c"), 1.0, key(0))
+                .unwrap(),
+            Some(MessageId(2))
+        );
+    }
 }
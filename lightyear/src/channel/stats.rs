@@ -1,10 +1,40 @@
-pub(crate) mod send {
-    /// TODO: maybe this should be directly on the ChannelSender?
+//! Per-channel statistics about the messages sent and received on a connection
+use bevy::utils::Duration;
+
+/// Weight given to a new ack latency sample in the exponential moving average
+const ACK_LATENCY_SMOOTHING: f32 = 0.1;
+
+/// Send and receive statistics for a single channel of a connection
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub struct ChannelStats {
+    pub send: send::ChannelSendStats,
+    pub receive: receive::ChannelReceiveStats,
+}
+
+impl ChannelStats {
+    /// Combine the stats of the same channel on another connection into these stats
+    pub(crate) fn merge(&mut self, other: &ChannelStats) {
+        self.send.merge(&other.send);
+        self.receive.merge(&other.receive);
+    }
+}
+
+pub mod send {
+    use super::*;
+
+    /// Statistics about the messages sent on a channel.
+    ///
+    /// All counters are cumulative since the connection was established.
     #[derive(Default, Copy, Clone, Debug, PartialEq)]
     pub struct ChannelSendStats {
         num_single_messages_sent: usize,
         num_fragment_messages_sent: usize,
         num_bytes_sent: usize,
+        num_messages_resent: usize,
+        num_messages_dropped: usize,
+        num_messages_acked: usize,
+        ack_latency: Duration,
+        queue_depth: usize,
     }
 
     impl ChannelSendStats {
@@ -24,8 +54,145 @@ pub(crate) mod send {
             self.num_bytes_sent = self.num_bytes_sent.saturating_add(num_bytes);
         }
 
+        pub(crate) fn set_messages_resent(&mut self, num: usize) {
+            self.num_messages_resent = num;
+        }
+
+        pub(crate) fn add_messages_dropped(&mut self, num: usize) {
+            self.num_messages_dropped += num;
+        }
+
+        /// Record that a message sent on this channel was acked by the remote peer,
+        /// `latency` after it was sent.
+        pub(crate) fn add_message_acked(&mut self, latency: Duration) {
+            self.ack_latency = if self.num_messages_acked == 0 {
+                latency
+            } else {
+                self.ack_latency.mul_f32(1.0 - ACK_LATENCY_SMOOTHING)
+                    + latency.mul_f32(ACK_LATENCY_SMOOTHING)
+            };
+            self.num_messages_acked += 1;
+        }
+
+        pub(crate) fn set_queue_depth(&mut self, queue_depth: usize) {
+            self.queue_depth = queue_depth;
+        }
+
+        pub(crate) fn merge(&mut self, other: &ChannelSendStats) {
+            let num_acked = self.num_messages_acked + other.num_messages_acked;
+            if num_acked > 0 {
+                // weight the ack latency of each connection by the number of acked messages
+                self.ack_latency = (self.ack_latency * self.num_messages_acked as u32
+                    + other.ack_latency * other.num_messages_acked as u32)
+                    / num_acked as u32;
+            }
+            self.num_single_messages_sent += other.num_single_messages_sent;
+            self.num_fragment_messages_sent += other.num_fragment_messages_sent;
+            self.num_bytes_sent = self.num_bytes_sent.saturating_add(other.num_bytes_sent);
+            self.num_messages_resent += other.num_messages_resent;
+            self.num_messages_dropped += other.num_messages_dropped;
+            self.num_messages_acked = num_acked;
+            self.queue_depth += other.queue_depth;
+        }
+
+        /// Total number of messages (single messages or message fragments) sent
         pub fn messages_sent(&self) -> usize {
             self.num_single_messages_sent + self.num_fragment_messages_sent
         }
+
+        /// Number of message fragments sent
+        pub fn fragments_sent(&self) -> usize {
+            self.num_fragment_messages_sent
+        }
+
+        /// Number of message bytes sent (excluding packet headers)
+        pub fn bytes_sent(&self) -> usize {
+            self.num_bytes_sent
+        }
+
+        /// Number of messages (or fragments) that were sent again because they were not acked in time.
+        ///
+        /// Only reliable channels resend messages.
+        pub fn messages_resent(&self) -> usize {
+            self.num_messages_resent
+        }
+
+        /// Number of messages (or fragments) that were ready to be sent, but were not included
+        /// in a packet by the priority filter because the bandwidth quota was reached.
+        ///
+        /// Messages on reliable channels will be sent again later.
+        pub fn messages_dropped(&self) -> usize {
+            self.num_messages_dropped
+        }
+
+        /// Number of messages (or fragments) that were acked by the remote peer.
+        ///
+        /// Only channels that track acks receive them.
+        pub fn messages_acked(&self) -> usize {
+            self.num_messages_acked
+        }
+
+        /// Smoothed duration between sending a packet containing a message and receiving the ack for it
+        pub fn ack_latency(&self) -> Duration {
+            self.ack_latency
+        }
+
+        /// Number of messages currently buffered in the channel.
+        ///
+        /// For reliable channels, this includes the messages that were sent but not acked yet.
+        pub fn queue_depth(&self) -> usize {
+            self.queue_depth
+        }
+    }
+}
+
+pub mod receive {
+    /// Statistics about the messages received on a channel.
+    ///
+    /// All counters are cumulative since the connection was established.
+    #[derive(Default, Copy, Clone, Debug, PartialEq)]
+    pub struct ChannelReceiveStats {
+        num_single_messages_received: usize,
+        num_fragment_messages_received: usize,
+        num_bytes_received: usize,
+    }
+
+    impl ChannelReceiveStats {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn add_single_message_received(&mut self, num_bytes: usize) {
+            self.num_single_messages_received += 1;
+            self.num_bytes_received = self.num_bytes_received.saturating_add(num_bytes);
+        }
+
+        pub fn add_fragment_message_received(&mut self, num_bytes: usize) {
+            self.num_fragment_messages_received += 1;
+            self.num_bytes_received = self.num_bytes_received.saturating_add(num_bytes);
+        }
+
+        pub(crate) fn merge(&mut self, other: &ChannelReceiveStats) {
+            self.num_single_messages_received += other.num_single_messages_received;
+            self.num_fragment_messages_received += other.num_fragment_messages_received;
+            self.num_bytes_received = self
+                .num_bytes_received
+                .saturating_add(other.num_bytes_received);
+        }
+
+        /// Total number of messages (single messages or message fragments) received
+        pub fn messages_received(&self) -> usize {
+            self.num_single_messages_received + self.num_fragment_messages_received
+        }
+
+        /// Number of message fragments received
+        pub fn fragments_received(&self) -> usize {
+            self.num_fragment_messages_received
+        }
+
+        /// Number of message bytes received (excluding packet headers)
+        pub fn bytes_received(&self) -> usize {
+            self.num_bytes_received
+        }
     }
 }
@@ -9,6 +9,8 @@ use crate::client::interpolation::plugin::InterpolationConfig;
 use crate::client::prediction::plugin::PredictionConfig;
 use crate::client::sync::SyncConfig;
 use crate::connection::client::NetConfig;
+use crate::connection::netcode::MAX_PACKET_SIZE;
+use crate::packet::mtu::MtuDiscoveryConfig;
 use crate::shared::config::SharedConfig;
 use crate::shared::ping::manager::PingConfig;
 use crate::shared::replication::plugin::ReplicationConfig;
@@ -60,6 +62,14 @@ pub struct PacketConfig {
     pub send_bandwidth_cap: Quota,
     /// If false, there is no bandwidth cap and all messages are sent as soon as possible
     pub bandwidth_cap_enabled: bool,
+    /// Maximum number of bytes in a packet. Messages that are bigger will be fragmented.
+    ///
+    /// Cannot exceed [`MAX_PACKET_SIZE`], which is the maximum payload size of the netcode protocol.
+    /// Lower it if some networks drop packets that are too big.
+    pub max_packet_size: usize,
+    /// If set, the connection will start with small packets and probe for the largest packet size
+    /// (up to `max_packet_size`) that can reach the remote peer
+    pub mtu_discovery: Option<MtuDiscoveryConfig>,
 }
 
 impl Default for PacketConfig {
@@ -69,6 +79,8 @@ impl Default for PacketConfig {
             // 56 KB/s bandwidth cap
             send_bandwidth_cap: Quota::per_second(nonzero!(56000u32)),
             bandwidth_cap_enabled: false,
+            max_packet_size: MAX_PACKET_SIZE,
+            mtu_discovery: None,
         }
     }
 }
@@ -89,6 +101,16 @@ impl PacketConfig {
         self.bandwidth_cap_enabled = true;
         self
     }
+
+    pub fn with_max_packet_size(mut self, max_packet_size: usize) -> Self {
+        self.max_packet_size = max_packet_size;
+        self
+    }
+
+    pub fn with_mtu_discovery(mut self, mtu_discovery: MtuDiscoveryConfig) -> Self {
+        self.mtu_discovery = Some(mtu_discovery);
+        self
+    }
 }
 
 /// The configuration object that lets you create a `ClientPlugin` with the desired settings.
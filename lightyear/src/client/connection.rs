@@ -4,32 +4,40 @@ use bevy::ecs::entity::MapEntities;
 use bevy::prelude::{Resource, World};
 use bevy::utils::{Duration, HashMap};
 use bytes::Bytes;
-use tracing::{debug, trace, trace_span};
+use tracing::{debug, error, trace, trace_span, warn};
 
 use crate::channel::builder::{
-    EntityActionsChannel, EntityUpdatesChannel, PingChannel, PongChannel,
+    EntityActionsChannel, EntityUpdatesChannel, HandshakeChannel, InterpolationDelayChannel,
+    PingChannel, PongChannel, SnapshotAckChannel, SnapshotChannel,
 };
 
 use crate::channel::receivers::ChannelReceive;
-use crate::channel::senders::ChannelSend;
+use crate::channel::senders::{ChannelSend, CoalescingKey};
+use crate::channel::stats::ChannelStats;
 use crate::client::config::ClientConfig;
 use crate::client::error::ClientError;
 use crate::client::sync::SyncConfig;
 use crate::connection::netcode::MAX_PACKET_SIZE;
+use crate::connection::server::DeniedReason;
 use crate::packet::message_manager::MessageManager;
+use crate::packet::mtu::MtuConfig;
 use crate::packet::packet_builder::{Payload, RecvPayload};
 use crate::packet::priority_manager::PriorityConfig;
 use crate::prelude::client::PredictionConfig;
-use crate::prelude::{Channel, ChannelKind, ClientId, Message, ReplicationConfig};
+use crate::prelude::{
+    Channel, ChannelDirection, ChannelKind, ClientId, Message, ReplicationConfig,
+};
 use crate::protocol::channel::ChannelRegistry;
 use crate::protocol::component::ComponentRegistry;
-use crate::protocol::message::{MessageRegistry, MessageType};
+use crate::protocol::fingerprint::{HandshakeMessage, ProtocolFingerprint};
+use crate::protocol::message::{MessageKind, MessageRegistry, MessageType};
 use crate::protocol::registry::NetId;
 use crate::serialize::reader::Reader;
 use crate::serialize::writer::Writer;
 use crate::serialize::{SerializationError, ToBytes};
 use crate::server::error::ServerError;
 use crate::shared::events::connection::ConnectionEvents;
+use crate::shared::message::InterpolationDelayMessage;
 use crate::shared::message::MessageSend;
 use crate::shared::ping::manager::{PingConfig, PingManager};
 use crate::shared::ping::message::{Ping, Pong};
@@ -37,6 +45,7 @@ use crate::shared::replication::delta::DeltaManager;
 use crate::shared::replication::network_target::NetworkTarget;
 use crate::shared::replication::receive::ReplicationReceiver;
 use crate::shared::replication::send::ReplicationSender;
+use crate::shared::replication::snapshot::{SnapshotAckMessage, SnapshotMessage, SnapshotReceiver};
 use crate::shared::replication::{EntityActionsMessage, EntityUpdatesMessage, ReplicationSend};
 use crate::shared::replication::{ReplicationPeer, ReplicationReceive};
 use crate::shared::sets::ClientMarker;
@@ -72,6 +81,8 @@ pub struct ConnectionManager {
     pub(crate) delta_manager: DeltaManager,
     pub(crate) replication_sender: ReplicationSender,
     pub replication_receiver: ReplicationReceiver,
+    /// Reconstructs the snapshots sent by the server, when it uses [`SendUpdatesMode::Snapshot`](crate::prelude::SendUpdatesMode::Snapshot)
+    pub(crate) snapshot_receiver: SnapshotReceiver,
     pub(crate) events: ConnectionEvents,
     pub ping_manager: PingManager,
     pub(crate) sync_manager: SyncManager,
@@ -87,7 +98,14 @@ pub struct ConnectionManager {
     /// We use this so that:
     /// - in host server mode, we deserialize the bytes and push them to the server's Message Events queue directly
     /// - in non-host server mode, we buffer the bytes to the message manager as usual
-    pub(crate) messages_to_send: Vec<(Bytes, ChannelKind)>,
+    pub(crate) messages_to_send: Vec<(Bytes, ChannelKind, Option<CoalescingKey>)>,
+
+    /// Fingerprint of the client's protocol, sent to the server when we connect
+    pub(crate) protocol_fingerprint: ProtocolFingerprint,
+    /// Set if the server denied the connection during the handshake
+    pub(crate) denied_reason: Option<DeniedReason>,
+    /// Interpolation delay that we last sent to the server
+    sent_interpolation_delay: Option<Duration>,
 }
 
 // NOTE: useful when we sometimes need to create a temporary fake ConnectionManager
@@ -108,10 +126,12 @@ impl Default for ConnectionManager {
                 &ChannelRegistry::default(),
                 0.0,
                 PriorityConfig::default(),
+                MtuConfig::default(),
             ),
             delta_manager: DeltaManager::default(),
             replication_sender,
             replication_receiver,
+            snapshot_receiver: SnapshotReceiver::default(),
             ping_manager: PingManager::new(PingConfig::default()),
             sync_manager: SyncManager::new(SyncConfig::default(), PredictionConfig::default()),
             events: ConnectionEvents::default(),
@@ -120,6 +140,9 @@ impl Default for ConnectionManager {
             received_messages: HashMap::default(),
             writer: Writer::with_capacity(0),
             messages_to_send: Vec::default(),
+            protocol_fingerprint: ProtocolFingerprint::default(),
+            denied_reason: None,
+            sent_interpolation_delay: None,
         }
     }
 }
@@ -138,7 +161,11 @@ impl ConnectionManager {
             channel_registry,
             client_config.packet.nack_rtt_multiple,
             client_config.packet.into(),
+            client_config.packet.into(),
         );
+        // the client only has one connection, to the server
+        #[cfg(feature = "metrics")]
+        message_manager.set_metrics_connection("server");
         // get notified when a replication-update message gets acked/nacked
         let entity_updates_sender = &mut message_manager
             .channels
@@ -165,6 +192,7 @@ impl ConnectionManager {
             delta_manager: DeltaManager::default(),
             replication_sender,
             replication_receiver,
+            snapshot_receiver: SnapshotReceiver::default(),
             ping_manager: PingManager::new(client_config.ping),
             sync_manager: SyncManager::new(client_config.sync, client_config.prediction),
             events: ConnectionEvents::default(),
@@ -173,6 +201,14 @@ impl ConnectionManager {
             received_messages: HashMap::default(),
             writer: Writer::with_capacity(MAX_PACKET_SIZE),
             messages_to_send: Vec::default(),
+            protocol_fingerprint: ProtocolFingerprint::new(
+                channel_registry,
+                component_registry,
+                message_registry,
+                client_config.shared.protocol_version,
+            ),
+            denied_reason: None,
+            sent_interpolation_delay: None,
         }
     }
 
@@ -182,6 +218,18 @@ impl ConnectionManager {
         self.sync_manager.is_synced()
     }
 
+    /// Get the send/receive statistics of the [`Channel`] `C`
+    pub fn channel_stats<C: Channel>(&self) -> Option<&ChannelStats> {
+        self.message_manager.channel_stats::<C>()
+    }
+
+    /// Maximum number of bytes in the packets sent to the server.
+    ///
+    /// This can increase over time if MTU discovery is enabled.
+    pub fn max_packet_size(&self) -> usize {
+        self.message_manager.max_packet_size()
+    }
+
     /// Returns true if we received a new server packet on this frame
     pub(crate) fn received_new_server_tick(&self) -> bool {
         self.sync_manager.duration_since_latest_received_server_tick == Duration::default()
@@ -227,6 +275,40 @@ impl ConnectionManager {
         Ok(())
     }
 
+    /// Send our [`ProtocolFingerprint`] to the server, so that it can check that our protocols match
+    pub(crate) fn send_protocol_fingerprint(&mut self) -> Result<(), ClientError> {
+        let message = HandshakeMessage::Protocol(self.protocol_fingerprint.clone());
+        let mut writer = Writer::with_capacity(message.len());
+        message.to_bytes(&mut writer)?;
+        let message_bytes = writer.to_bytes();
+        self.message_manager
+            .buffer_send(message_bytes, ChannelKind::of::<HandshakeChannel>())?;
+        Ok(())
+    }
+
+    /// Send our interpolation delay to the server if it changed, so that the server can
+    /// use it for lag compensation.
+    ///
+    /// The delay is rounded down to the millisecond, so that a delay that changes slightly every frame
+    /// (for example an [`AdaptiveInterpolationDelay`](crate::prelude::client::AdaptiveInterpolationDelay))
+    /// doesn't cause a message to be sent every frame.
+    pub(crate) fn send_interpolation_delay(&mut self, delay: Duration) -> Result<(), ClientError> {
+        let delay = Duration::from_millis(delay.as_millis() as u64);
+        if self.sent_interpolation_delay == Some(delay) {
+            return Ok(());
+        }
+        let message = InterpolationDelayMessage(delay);
+        let mut writer = Writer::with_capacity(message.len());
+        message.to_bytes(&mut writer)?;
+        let message_bytes = writer.to_bytes();
+        self.message_manager.buffer_send(
+            message_bytes,
+            ChannelKind::of::<InterpolationDelayChannel>(),
+        )?;
+        self.sent_interpolation_delay = Some(delay);
+        Ok(())
+    }
+
     // TODO: we need `&mut self` because MapEntities requires `&mut EntityMapper` even though it's not needed here
     /// Convert entities in the message to be compatible with the remote world
     pub fn map_entities_to_remote<M: Message + MapEntities>(&mut self, message: &mut M) {
@@ -253,6 +335,36 @@ impl ConnectionManager {
         self.erased_send_message_to_target(message, ChannelKind::of::<C>(), target)
     }
 
+    /// Send a [`Message`] to the server using a specific unreliable [`Channel`], keeping only the
+    /// latest message for a given `key`.
+    ///
+    /// If several messages of type `M` with the same `key` are sent before the channel sends its next packet,
+    /// only the most recent one is sent. This is useful for messages where only the latest value matters
+    /// (cursor position, aim direction, etc.). The key can be any value that identifies the message,
+    /// for example an entity converted with [`Entity::to_bits`](bevy::prelude::Entity::to_bits).
+    ///
+    /// Messages are coalesced on the [`ChannelMode::UnorderedUnreliable`], [`ChannelMode::SequencedUnreliable`]
+    /// and [`ChannelMode::UnorderedUnreliableWithAcks`] channels. On reliable channels, all messages are sent.
+    ///
+    /// [`ChannelMode::UnorderedUnreliable`]: crate::prelude::ChannelMode::UnorderedUnreliable
+    /// [`ChannelMode::SequencedUnreliable`]: crate::prelude::ChannelMode::SequencedUnreliable
+    /// [`ChannelMode::UnorderedUnreliableWithAcks`]: crate::prelude::ChannelMode::UnorderedUnreliableWithAcks
+    pub fn send_message_coalesced<C: Channel, M: Message>(
+        &mut self,
+        message: &mut M,
+        key: u64,
+    ) -> Result<(), ClientError> {
+        self.buffer_message(
+            message,
+            ChannelKind::of::<C>(),
+            NetworkTarget::None,
+            Some(CoalescingKey {
+                kind: MessageKind::of::<M>(),
+                key,
+            }),
+        )
+    }
+
     /// Serialize a message and buffer it internally so that it can be sent later
     fn erased_send_message_to_target<M: Message>(
         &mut self,
@@ -260,6 +372,21 @@ impl ConnectionManager {
         channel_kind: ChannelKind,
         target: NetworkTarget,
     ) -> Result<(), ClientError> {
+        self.buffer_message(message, channel_kind, target, None)
+    }
+
+    fn buffer_message<M: Message>(
+        &mut self,
+        message: &M,
+        channel_kind: ChannelKind,
+        target: NetworkTarget,
+        coalescing_key: Option<CoalescingKey>,
+    ) -> Result<(), ClientError> {
+        self.message_registry.check_send::<M>(
+            channel_kind,
+            &self.message_manager.channel_registry,
+            ChannelDirection::ClientToServer,
+        )?;
         // write the target first
         // NOTE: this is ok to do because most of the time (without rebroadcast, this just adds 1 byte)
         target.to_bytes(&mut self.writer)?;
@@ -272,7 +399,8 @@ impl ConnectionManager {
         let message_bytes = self.writer.split();
 
         // TODO: emit logs/metrics about the message being buffered?
-        self.messages_to_send.push((message_bytes, channel_kind));
+        self.messages_to_send
+            .push((message_bytes, channel_kind, coalescing_key));
         Ok(())
     }
 
@@ -318,7 +446,7 @@ impl ConnectionManager {
         // go through messages_to_send, deserialize them and make the server receive them
         self.messages_to_send
             .drain(..)
-            .try_for_each(|(message_bytes, channel_kind)| {
+            .try_for_each(|(message_bytes, channel_kind, _)| {
                 server_manager
                     .connection_mut(local_client_id)?
                     .receive_message(
@@ -363,13 +491,21 @@ impl ConnectionManager {
             })?;
 
         // buffer the messages into the message manager
-        self.messages_to_send
-            .drain(..)
-            .try_for_each(|(message_bytes, channel_kind)| {
-                self.message_manager
-                    .buffer_send(message_bytes, channel_kind)?;
+        self.messages_to_send.drain(..).try_for_each(
+            |(message_bytes, channel_kind, coalescing_key)| {
+                match coalescing_key {
+                    Some(key) => self.message_manager.buffer_send_coalesced(
+                        message_bytes,
+                        channel_kind,
+                        key,
+                    )?,
+                    None => self
+                        .message_manager
+                        .buffer_send(message_bytes, channel_kind)?,
+                };
                 Ok::<(), ClientError>(())
-            })?;
+            },
+        )?;
 
         // get the payloads from the message manager
         let payloads = self.message_manager.send_packets(tick_manager.tick());
@@ -428,12 +564,22 @@ impl ConnectionManager {
                             time = ?pong.pong_sent_time,
                             "Updated server pong generation"
                         )
+                    } else if *channel_kind == ChannelKind::of::<HandshakeChannel>() {
+                        if let HandshakeMessage::Denied(reason) =
+                            HandshakeMessage::from_bytes(&mut reader)?
+                        {
+                            error!(?reason, "The server denied the connection");
+                            self.denied_reason = Some(reason);
+                        }
                     } else if *channel_kind == ChannelKind::of::<EntityActionsChannel>() {
                         let actions = EntityActionsMessage::from_bytes(&mut reader)?;
                         self.replication_receiver.recv_actions(actions, tick);
                     } else if *channel_kind == ChannelKind::of::<EntityUpdatesChannel>() {
                         let updates = EntityUpdatesMessage::from_bytes(&mut reader)?;
                         self.replication_receiver.recv_updates(updates, tick);
+                    } else if *channel_kind == ChannelKind::of::<SnapshotChannel>() {
+                        let snapshot = SnapshotMessage::from_bytes(&mut reader)?;
+                        self.snapshot_receiver.recv(snapshot)?;
                     } else {
                         // TODO: this code is copy-pasted from self.receive_message because of borrow checker limitations
                         // identify the type of message
@@ -441,27 +587,39 @@ impl ConnectionManager {
                         let single_data = reader.consume();
                         match self.message_registry.message_type(net_id) {
                             #[cfg(feature = "leafwing")]
-                            MessageType::LeafwingInput => {
+                            Some(MessageType::LeafwingInput) => {
                                 self.received_leafwing_input_messages
                                     .entry(net_id)
                                     .or_default()
                                     .push(single_data);
                             }
-                            MessageType::NativeInput => {
+                            Some(MessageType::NativeInput) => {
                                 todo!()
                             }
-                            MessageType::Normal => {
+                            Some(MessageType::Normal) => {
                                 self.received_messages
                                     .entry(net_id)
                                     .or_default()
                                     .push(single_data);
                             }
+                            None => {
+                                warn!(?net_id, "Received an unknown message, ignoring it");
+                            }
                         }
                     }
                 }
                 Ok::<(), SerializationError>(())
             })?;
 
+        // let the server know which snapshot it can use as baseline
+        if let Some(tick) = self.snapshot_receiver.ack() {
+            let message = SnapshotAckMessage(tick);
+            let mut writer = Writer::with_capacity(message.len());
+            message.to_bytes(&mut writer)?;
+            self.message_manager
+                .buffer_send(writer.to_bytes(), ChannelKind::of::<SnapshotAckChannel>())?;
+        }
+
         if self.sync_manager.is_synced() {
             // Check if we have any replication messages we can apply to the World (and emit events)
             self.replication_receiver.apply_world(
@@ -471,6 +629,12 @@ impl ConnectionManager {
                 tick_manager.tick(),
                 &mut self.events,
             );
+            self.snapshot_receiver.apply_world(
+                world,
+                &mut self.replication_receiver,
+                &self.component_registry,
+                &mut self.events,
+            );
         }
         Ok(())
     }
@@ -482,21 +646,24 @@ impl ConnectionManager {
         let single_data = reader.consume();
         match self.message_registry.message_type(net_id) {
             #[cfg(feature = "leafwing")]
-            MessageType::LeafwingInput => {
+            Some(MessageType::LeafwingInput) => {
                 self.received_leafwing_input_messages
                     .entry(net_id)
                     .or_default()
                     .push(single_data);
             }
-            MessageType::NativeInput => {
+            Some(MessageType::NativeInput) => {
                 todo!()
             }
-            MessageType::Normal => {
+            Some(MessageType::Normal) => {
                 self.received_messages
                     .entry(net_id)
                     .or_default()
                     .push(single_data);
             }
+            None => {
+                warn!(?net_id, "Received an unknown message, ignoring it");
+            }
         }
         Ok(())
     }
@@ -517,6 +684,7 @@ impl ConnectionManager {
         {
             trace!("new last recv server tick: {:?}", tick);
             self.sync_manager.latest_received_server_tick = Some(tick);
+            self.sync_manager.record_server_update_gap();
             // TODO: add 'received_new_server_tick' ?
             // we probably actually physically received the packet some time between our last `receive` and now.
             // Let's add delta / 2 as a compromise
@@ -591,6 +759,8 @@ impl ReplicationSend for ConnectionManager {
 
 #[cfg(test)]
 mod tests {
+    use bevy::utils::Duration;
+
     use crate::prelude::{client, server, ClientConnectionManager, RemoteEntityMap};
     use crate::tests::protocol::EntityMessage;
     use crate::tests::stepper::BevyStepper;
@@ -644,4 +814,35 @@ mod tests {
         assert!(RemoteEntityMap::is_mapped(message.0));
         assert_eq!(RemoteEntityMap::mark_unmapped(message.0), server_entity);
     }
+
+    /// The interpolation delay is only sent again when it changed by at least a millisecond
+    #[test]
+    fn test_send_interpolation_delay() {
+        let mut stepper = BevyStepper::default();
+        let mut manager = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConnectionManager>();
+        manager
+            .send_interpolation_delay(Duration::from_micros(100_200))
+            .unwrap();
+        assert_eq!(
+            manager.sent_interpolation_delay,
+            Some(Duration::from_millis(100))
+        );
+        manager
+            .send_interpolation_delay(Duration::from_micros(100_700))
+            .unwrap();
+        assert_eq!(
+            manager.sent_interpolation_delay,
+            Some(Duration::from_millis(100))
+        );
+        manager
+            .send_interpolation_delay(Duration::from_micros(99_900))
+            .unwrap();
+        assert_eq!(
+            manager.sent_interpolation_delay,
+            Some(Duration::from_millis(99))
+        );
+    }
 }
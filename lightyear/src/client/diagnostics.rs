@@ -1,13 +1,14 @@
+use crate::channel::diagnostics::{ChannelDiagnosticsPlugin, PreviousChannelStats};
 use crate::client::connection::ConnectionManager;
 use crate::client::prediction::diagnostics::PredictionDiagnosticsPlugin;
 use bevy::app::{App, Plugin, PostUpdate};
 use bevy::diagnostic::Diagnostics;
-use bevy::prelude::{not, Condition, IntoSystemConfigs, Real, Res, ResMut, Time};
+use bevy::prelude::{not, Condition, IntoSystemConfigs, Local, Real, Res, ResMut, Time};
 use bevy::time::common_conditions::on_timer;
 use bevy::utils::Duration;
 
 use crate::connection::client::{ClientConnection, NetClient};
-use crate::prelude::{client::is_disconnected, is_host_server};
+use crate::prelude::{client::is_disconnected, is_host_server, ChannelRegistry};
 use crate::shared::ping::diagnostics::PingDiagnosticsPlugin;
 use crate::transport::io::IoDiagnosticsPlugin;
 
@@ -39,6 +40,27 @@ fn ping_diagnostics_system(connection: Res<ConnectionManager>, diagnostics: Diag
     PingDiagnosticsPlugin::add_measurements(&connection.ping_manager, diagnostics);
 }
 
+fn channel_diagnostics_system(
+    connection: Res<ConnectionManager>,
+    channel_registry: Res<ChannelRegistry>,
+    time: Res<Time<Real>>,
+    mut previous: Local<PreviousChannelStats>,
+    mut diagnostics: Diagnostics,
+) {
+    let stats = connection
+        .message_manager
+        .all_channel_stats()
+        .map(|(kind, stats)| (*kind, *stats))
+        .collect();
+    ChannelDiagnosticsPlugin::add_measurements(
+        stats,
+        &mut previous,
+        &channel_registry,
+        time.elapsed(),
+        &mut diagnostics,
+    );
+}
+
 impl Plugin for ClientDiagnosticsPlugin {
     fn build(&self, app: &mut App) {
         {
@@ -52,6 +74,20 @@ impl Plugin for ClientDiagnosticsPlugin {
                 ),
             );
         }
+        {
+            let channel_plugin = ChannelDiagnosticsPlugin::default();
+            let flush_interval = channel_plugin.flush_interval;
+            // the plugin might already have been added by the server in HostServer mode
+            if !app.is_plugin_added::<ChannelDiagnosticsPlugin>() {
+                app.add_plugins(channel_plugin);
+            }
+            app.add_systems(
+                PostUpdate,
+                channel_diagnostics_system.run_if(
+                    on_timer(flush_interval).and_then(not(is_host_server.or_else(is_disconnected))),
+                ),
+            );
+        }
         app.add_plugins(PredictionDiagnosticsPlugin::default());
 
         {
@@ -62,7 +62,7 @@ use crate::inputs::leafwing::input_message::InputTarget;
 use crate::inputs::leafwing::LeafwingUserAction;
 use crate::prelude::{
     is_host_server, ChannelKind, ChannelRegistry, InputMessage, MessageRegistry,
-    ReplicateOnceComponent, TickManager,
+    ReplicateOnceComponent, Tick, TickManager,
 };
 use crate::protocol::message::MessageKind;
 use crate::serialize::reader::Reader;
@@ -84,6 +84,16 @@ pub struct LeafwingInputConfig<A> {
     ///  for the 3 last packets.
     // TODO: this seems unused now
     pub packet_redundancy: u16,
+    /// If true, the server forwards the inputs that it receives from each client to the other clients,
+    /// so that they can predict the remote players' entities with their actual inputs
+    /// (the inputs are applied to the `Predicted` entity, and re-used during rollbacks).
+    ///
+    /// Each client only receives the inputs of the entities that are replicated to it.
+    /// Only the leafwing inputs can be forwarded: the inputs of the native
+    /// [`InputPlugin`](crate::prelude::InputPlugin) are not.
+    ///
+    /// This is useful for games where every player is predicted, such as fighting games.
+    pub rebroadcast_inputs: bool,
 
     // TODO: add an option where we send all diffs vs send only just-pressed diffs
     pub(crate) _marker: PhantomData<A>,
@@ -113,6 +123,7 @@ impl<A> Default for LeafwingInputConfig<A> {
         LeafwingInputConfig {
             // input_delay_ticks: 0,
             packet_redundancy: 4,
+            rebroadcast_inputs: false,
             _marker: PhantomData,
         }
     }
@@ -142,6 +153,11 @@ fn is_input_delay(config: Res<ClientConfig>) -> bool {
         || config.prediction.maximum_predicted_ticks < 30
 }
 
+/// Returns true if the server forwards the inputs of the other clients
+fn is_rebroadcasting_inputs<A: LeafwingUserAction>(config: Res<LeafwingInputConfig<A>>) -> bool {
+    config.rebroadcast_inputs
+}
+
 impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A>
 // FLOW WITH INPUT DELAY
 // - pre-update: run leafwing to update the current ActionState, which is the action-state for tick T + delay
@@ -234,7 +250,9 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A>
                     buffer_action_state::<A>,
                     // If InputDelay is enabled, we get the ActionState for the current tick
                     // from the InputBuffer (which was added to the InputBuffer input_delay ticks ago)
-                    get_non_rollback_action_state::<A>.run_if(is_input_delay),
+                    // We also need this for remote players if the server rebroadcasts their inputs
+                    get_non_rollback_action_state::<A>
+                        .run_if(is_input_delay.or_else(is_rebroadcasting_inputs::<A>)),
                 )
                     .chain()
                     .run_if(not(is_in_rollback)),
@@ -444,16 +462,26 @@ fn get_non_rollback_action_state<A: LeafwingUserAction>(
     // - remote player: we want to reduce the amount of rollbacks by updating the ActionState
     //   as fast as possible (the inputs are broadcasted with no delay)
     mut action_state_query: Query<
-        (Entity, &mut ActionState<A>, &InputBuffer<A>),
+        (
+            Entity,
+            &mut ActionState<A>,
+            &InputBuffer<A>,
+            Has<InputMap<A>>,
+        ),
         // With<InputMap<A>>,
     >,
 ) {
     let tick = tick_manager.tick();
-    for (entity, mut action_state, input_buffer) in action_state_query.iter_mut() {
+    for (entity, mut action_state, input_buffer, is_local) in action_state_query.iter_mut() {
         // We only apply the ActionState from the buffer if we have one.
-        // If we don't (which could happen for remote inputs), we won't do anything.
+        // Remote inputs usually arrive after the current tick, so we use the most recent remote input instead.
         // This is equivalent to considering that the remote player will keep playing the last action they played.
-        if let Some(action) = input_buffer.get(tick) {
+        let action = if is_local {
+            input_buffer.get(tick)
+        } else {
+            remote_action_state(input_buffer, tick)
+        };
+        if let Some(action) = action {
             *action_state = action.clone();
             debug!(
                 ?entity,
@@ -466,6 +494,22 @@ fn get_non_rollback_action_state<A: LeafwingUserAction>(
     }
 }
 
+/// ActionState of a remote player at the given tick.
+///
+/// If we haven't received the remote inputs for this tick yet, we consider that the remote player
+/// keeps playing the last action that we received.
+fn remote_action_state<A: LeafwingUserAction>(
+    input_buffer: &InputBuffer<A>,
+    tick: Tick,
+) -> Option<&ActionState<A>> {
+    input_buffer.get(tick).or_else(|| {
+        input_buffer
+            .end_tick()
+            .filter(|end_tick| tick > *end_tick)
+            .and_then(|_| input_buffer.get_last())
+    })
+}
+
 /// During rollback, fetch the action-state from the InputBuffer for the corresponding tick and use that
 /// to set the ActionState resource/component.
 ///
@@ -510,7 +554,9 @@ fn get_rollback_action_state<A: LeafwingUserAction>(
     }
     for (entity, mut action_state, input_buffer) in remote_player_query.iter_mut() {
         // TODO: should we reuse the existing ActionState as an optimization?
-        *action_state = input_buffer.get(tick).cloned().unwrap_or_default();
+        *action_state = remote_action_state(input_buffer, tick)
+            .cloned()
+            .unwrap_or_default();
         debug!(
             ?tick,
             ?entity,
@@ -796,7 +842,8 @@ fn receive_remote_player_input_messages<A: LeafwingUserAction>(
                                 error!(?entity, ?diffs, end_tick = ?message.end_tick, "received input message for unrecognized entity");
                             }
                         } else {
-                            error!("received remote player input message for unrecognized entity");
+                            // the entity might not be replicated to this client
+                            debug!("received remote player input message for unrecognized entity");
                         }
                     }
                 }
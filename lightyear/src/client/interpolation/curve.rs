@@ -0,0 +1,365 @@
+//! Interpolation along a curve, using the neighbouring server samples and optionally a derivative component
+//!
+//! Interpolation functions registered with [`add_interpolation_fn`](crate::prelude::AppComponentExt::add_interpolation_fn)
+//! only receive the two samples that surround the interpolation time, so the interpolated motion is a straight
+//! line between two server updates. With a low server send rate, entities that turn quickly look jagged.
+//!
+//! Instead you can register:
+//! - a [`CurveFn`] with [`add_curve_interpolation_fn`](crate::prelude::AppComponentExt::add_curve_interpolation_fn),
+//!   which also receives the samples before and after the interpolation interval (for example [`catmull_rom`])
+//! - a [`HermiteFn`] with [`add_hermite_interpolation_fn`](crate::prelude::AppComponentExt::add_hermite_interpolation_fn),
+//!   which also receives the value of a derivative component `D` (for example a velocity) at the start and end of the
+//!   interval (for example [`cubic_hermite`]). If the derivative is not available, the [`CurveFn`] is used if one is
+//!   registered, otherwise the regular interpolation function.
+//!
+//! Implementations are provided for `Transform` in [`crate::utils::bevy`], and for the avian components in
+//! `crate::utils::avian2d` and `crate::utils::avian3d`.
+use std::ops::{Add, Div, Mul, Sub};
+
+use bevy::prelude::{Commands, Component, Entity, Query, Res, With, Without};
+use tracing::trace;
+
+use crate::client::components::SyncComponent;
+use crate::client::interpolation::interpolate::InterpolateStatus;
+use crate::client::interpolation::interpolation_history::ConfirmedHistory;
+use crate::prelude::{ComponentRegistry, TickManager};
+use crate::shared::tick_manager::Tick;
+
+/// The server samples used to interpolate a component between `start` and `end`
+#[derive(Debug)]
+pub struct CurveSamples<'a, C> {
+    /// Sample before `start`, along with its time relative to the interval between `start` and `end`
+    /// (for example `-1.0` if the samples are evenly spaced)
+    pub before: Option<(f32, &'a C)>,
+    pub start: &'a C,
+    pub end: &'a C,
+    /// Sample after `end`, along with its time relative to the interval between `start` and `end`
+    /// (for example `2.0` if the samples are evenly spaced)
+    pub after: Option<(f32, &'a C)>,
+    /// Duration between `start` and `end`, in seconds. Used to convert derivatives
+    pub duration: f32,
+}
+
+/// Function used to interpolate a component between `samples.start` and `samples.end`, using the neighbouring samples.
+/// `t` goes from 0.0 (`start`) to 1.0 (`end`)
+pub type CurveFn<C> = fn(samples: &CurveSamples<C>, t: f32) -> C;
+
+/// Function used to interpolate a component between `samples.start` and `samples.end`, using the values of the
+/// derivative component `D` at `start` and at `end`.
+/// `t` goes from 0.0 (`start`) to 1.0 (`end`)
+pub type HermiteFn<C, D> =
+    fn(samples: &CurveSamples<C>, start_derivative: &D, end_derivative: &D, t: f32) -> C;
+
+/// Cubic Hermite spline between `p0` and `p1`, with tangents `m0` and `m1`.
+///
+/// The tangents are expressed per unit of `t`, i.e. a velocity must be multiplied by the duration of the interval.
+pub fn cubic_hermite<V, S>(p0: V, m0: V, p1: V, m1: V, t: S) -> V
+where
+    V: Add<Output = V> + Mul<S, Output = V>,
+    S: Copy + From<f32> + Add<Output = S> + Sub<Output = S> + Mul<Output = S>,
+{
+    let (one, two, three) = (S::from(1.0), S::from(2.0), S::from(3.0));
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let h00 = two * t3 - three * t2 + one;
+    let h10 = t3 - two * t2 + t;
+    let h01 = three * t2 - two * t3;
+    let h11 = t3 - t2;
+    p0 * h00 + m0 * h10 + p1 * h01 + m1 * h11
+}
+
+/// Tangents at `p0` and `p1` (per unit of `t`) of a Catmull-Rom spline going through `before`, `p0`, `p1` and `after`.
+///
+/// `before` and `after` are given with their time relative to the interval between `p0` and `p1`. If they are missing,
+/// the tangent is the one of the straight line between `p0` and `p1`.
+pub fn catmull_rom_tangents<V, S>(
+    before: Option<(S, V)>,
+    p0: V,
+    p1: V,
+    after: Option<(S, V)>,
+) -> (V, V)
+where
+    V: Copy + Sub<Output = V> + Div<S, Output = V>,
+    S: Copy + From<f32> + Sub<Output = S>,
+{
+    let one = S::from(1.0);
+    let m0 = before.map_or(p1 - p0, |(time, before)| (p1 - before) / (one - time));
+    let m1 = after.map_or(p1 - p0, |(time, after)| (after - p0) / time);
+    (m0, m1)
+}
+
+/// Catmull-Rom spline between `p0` and `p1`, going through the neighbouring samples `before` and `after`
+pub fn catmull_rom<V, S>(before: Option<(S, V)>, p0: V, p1: V, after: Option<(S, V)>, t: S) -> V
+where
+    V: Copy + Add<Output = V> + Sub<Output = V> + Mul<S, Output = V> + Div<S, Output = V>,
+    S: Copy + From<f32> + Add<Output = S> + Sub<Output = S> + Mul<Output = S>,
+{
+    let (m0, m1) = catmull_rom_tangents(before, p0, p1, after);
+    cubic_hermite(p0, m0, p1, m1, t)
+}
+
+/// Keeps track of the sample before the start of the current interpolation interval
+#[derive(Component, PartialEq, Debug)]
+pub struct CurveStatus<C: Component> {
+    /// sample before `start`
+    pub previous: Option<(Tick, C)>,
+    /// start of the current interpolation interval
+    pub start: Option<(Tick, C)>,
+}
+
+impl<C: Component> Default for CurveStatus<C> {
+    fn default() -> Self {
+        Self {
+            previous: None,
+            start: None,
+        }
+    }
+}
+
+/// Add the [`CurveStatus`] to the entities that are interpolated
+pub(crate) fn add_curve_status<C: SyncComponent>(
+    mut commands: Commands,
+    query: Query<Entity, (With<InterpolateStatus<C>>, Without<CurveStatus<C>>)>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).insert(CurveStatus::<C>::default());
+    }
+}
+
+/// Keep track of the sample before the start of the interpolation interval
+pub(crate) fn update_curve_status<C: SyncComponent>(
+    mut query: Query<(&InterpolateStatus<C>, &mut CurveStatus<C>)>,
+) {
+    for (status, mut curve_status) in query.iter_mut() {
+        let Some((start_tick, start_value)) = &status.start else {
+            continue;
+        };
+        if curve_status
+            .start
+            .as_ref()
+            .map_or(true, |(tick, _)| tick < start_tick)
+        {
+            let previous = curve_status
+                .start
+                .replace((*start_tick, start_value.clone()));
+            curve_status.previous = previous;
+        }
+    }
+}
+
+/// Build the samples used to interpolate between `status.start` and `status.end`, and return them along with `t`
+fn curve_samples<'a, C: SyncComponent>(
+    status: &'a InterpolateStatus<C>,
+    curve_status: &'a CurveStatus<C>,
+    history: &'a ConfirmedHistory<C>,
+    tick_duration: f32,
+) -> Option<(CurveSamples<'a, C>, f32)> {
+    let (start_tick, start) = status.start.as_ref()?;
+    let (end_tick, end) = status.end.as_ref()?;
+    let interval = (*end_tick - *start_tick) as f32;
+    if interval <= 0.0 {
+        return None;
+    }
+    let relative_time = |tick: Tick| (tick - *start_tick) as f32 / interval;
+    let samples = CurveSamples {
+        before: curve_status
+            .previous
+            .as_ref()
+            .filter(|(tick, _)| tick < start_tick)
+            .map(|(tick, value)| (relative_time(*tick), value)),
+        start,
+        end,
+        after: history
+            .buffer
+            .heap
+            .peek()
+            .filter(|item| item.key > *end_tick)
+            .map(|item| (relative_time(item.key), &item.item)),
+        duration: interval * tick_duration,
+    };
+    Some((samples, status.interpolation_fraction()?))
+}
+
+/// The value of the derivative at `tick`: the most recent value of the derivative that is not newer than `tick`
+fn derivative_at<D: Component>(status: &InterpolateStatus<D>, tick: Tick) -> Option<&D> {
+    [status.end.as_ref(), status.start.as_ref()]
+        .into_iter()
+        .flatten()
+        .find(|(derivative_tick, _)| *derivative_tick <= tick)
+        .map(|(_, derivative)| derivative)
+}
+
+/// Update the component value on the Interpolated entity, using the registered [`CurveFn`].
+///
+/// Runs after [`interpolate`](super::interpolate::interpolate), so the linear interpolation is kept
+/// when there are not enough samples.
+pub(crate) fn interpolate_curve<C: SyncComponent>(
+    tick_manager: Res<TickManager>,
+    component_registry: Res<ComponentRegistry>,
+    mut query: Query<(
+        &mut C,
+        &InterpolateStatus<C>,
+        &CurveStatus<C>,
+        &ConfirmedHistory<C>,
+    )>,
+) {
+    // the hermite interpolation system falls back to the curve function itself
+    if component_registry.has_hermite_interpolation::<C>() {
+        return;
+    }
+    let tick_duration = tick_manager.config.tick_duration.as_secs_f32();
+    for (mut component, status, curve_status, history) in query.iter_mut() {
+        if let Some((samples, t)) = curve_samples(status, curve_status, history, tick_duration) {
+            trace!(?t, "curve interpolation");
+            *component = component_registry.interpolate_curve(&samples, t);
+        }
+    }
+}
+
+/// Update the component value on the Interpolated entity, using the registered [`HermiteFn`] with the
+/// values of the derivative component `D`.
+///
+/// Runs after [`interpolate`](super::interpolate::interpolate), so the linear interpolation is kept
+/// when there are not enough samples.
+pub(crate) fn interpolate_hermite<C: SyncComponent, D: SyncComponent>(
+    tick_manager: Res<TickManager>,
+    component_registry: Res<ComponentRegistry>,
+    mut query: Query<(
+        &mut C,
+        &InterpolateStatus<C>,
+        &CurveStatus<C>,
+        &ConfirmedHistory<C>,
+        Option<&InterpolateStatus<D>>,
+        Option<&D>,
+    )>,
+) {
+    let tick_duration = tick_manager.config.tick_duration.as_secs_f32();
+    let has_curve_fn = component_registry.has_curve_interpolation::<C>();
+    for (mut component, status, curve_status, history, derivative_status, derivative) in
+        query.iter_mut()
+    {
+        let (Some((start_tick, _)), Some((end_tick, _))) = (&status.start, &status.end) else {
+            continue;
+        };
+        let Some((samples, t)) = curve_samples(status, curve_status, history, tick_duration) else {
+            continue;
+        };
+        // use the current value of the derivative if it has no interpolation history
+        // (for example if it is not interpolated)
+        let derivative_at = |tick: Tick| {
+            derivative_status
+                .and_then(|status| derivative_at(status, tick))
+                .or(derivative)
+        };
+        match (derivative_at(*start_tick), derivative_at(*end_tick)) {
+            (Some(start_derivative), Some(end_derivative)) => {
+                trace!(?t, "hermite interpolation");
+                *component = component_registry.interpolate_hermite::<C, D>(
+                    &samples,
+                    start_derivative,
+                    end_derivative,
+                    t,
+                );
+            }
+            _ if has_curve_fn => {
+                *component = component_registry.interpolate_curve(&samples, t);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{App, IntoSystemConfigs, Update};
+    use bevy::utils::Duration;
+
+    use super::*;
+    use crate::client::components::ComponentSyncMode;
+    use crate::client::interpolation::interpolate::interpolate;
+    use crate::prelude::TickConfig;
+    use crate::tests::protocol::{ComponentSyncModeFull, ComponentSyncModeFull2};
+
+    #[test]
+    fn test_cubic_hermite() {
+        // the spline goes through the points with the given tangents
+        assert_eq!(cubic_hermite(0.0, 1.0, 1.0, 1.0, 0.0), 0.0);
+        assert_eq!(cubic_hermite(0.0, 1.0, 1.0, 1.0, 1.0), 1.0);
+        assert_eq!(cubic_hermite(0.0, 1.0, 1.0, 1.0, 0.5), 0.5);
+        // with zero tangents, the spline eases in and out
+        assert_eq!(cubic_hermite(0.0, 0.0, 1.0, 0.0, 0.25), 0.15625);
+
+        // the Catmull-Rom spline follows the curve of y = x^2
+        let value = catmull_rom(Some((-1.0, 0.0)), 1.0, 4.0, Some((2.0, 9.0)), 0.5);
+        assert_eq!(value, 2.25);
+        // without neighbours, it is a straight line
+        assert_eq!(catmull_rom(None, 1.0, 4.0, None, 0.5), 2.5);
+    }
+
+    /// The derivative component is used at the start and at the end of the interval
+    #[test]
+    fn test_hermite_interpolation() {
+        let mut app = App::new();
+        app.insert_resource(TickManager::from_config(TickConfig::new(
+            Duration::from_millis(100),
+        )));
+        let mut registry = ComponentRegistry::default();
+        registry.set_interpolation_mode::<ComponentSyncModeFull>(ComponentSyncMode::Full);
+        registry.set_interpolation::<ComponentSyncModeFull>(|start, end, t| {
+            ComponentSyncModeFull(start.0 * (1.0 - t) + end.0 * t)
+        });
+        // ComponentSyncModeFull2 is the velocity of ComponentSyncModeFull
+        registry.set_hermite_interpolation::<ComponentSyncModeFull, ComponentSyncModeFull2>(
+            |samples, start_velocity, end_velocity, t| {
+                ComponentSyncModeFull(cubic_hermite(
+                    samples.start.0,
+                    start_velocity.0 * samples.duration,
+                    samples.end.0,
+                    end_velocity.0 * samples.duration,
+                    t,
+                ))
+            },
+        );
+        app.insert_resource(registry);
+        app.add_systems(
+            Update,
+            (
+                update_curve_status::<ComponentSyncModeFull>,
+                interpolate::<ComponentSyncModeFull>,
+                interpolate_hermite::<ComponentSyncModeFull, ComponentSyncModeFull2>,
+            )
+                .chain(),
+        );
+        let status = |current_tick| InterpolateStatus::<ComponentSyncModeFull> {
+            start: Some((Tick(0), ComponentSyncModeFull(0.0))),
+            end: Some((Tick(10), ComponentSyncModeFull(1.0))),
+            current_tick: Tick(current_tick),
+            current_overstep: 0.0,
+        };
+        let entity = app
+            .world_mut()
+            .spawn((
+                ComponentSyncModeFull(0.0),
+                status(2),
+                CurveStatus::<ComponentSyncModeFull>::default(),
+                ConfirmedHistory::<ComponentSyncModeFull>::new(),
+                // the velocity is 0.0, so the motion eases in and out
+                ComponentSyncModeFull2(0.0),
+            ))
+            .id();
+        app.update();
+        let value = app.world().get::<ComponentSyncModeFull>(entity).unwrap().0;
+        assert!((value - 0.104).abs() < 1e-5, "{value}");
+
+        // without the derivative, we keep the linear interpolation
+        app.world_mut()
+            .entity_mut(entity)
+            .remove::<ComponentSyncModeFull2>()
+            .insert(status(5));
+        app.update();
+        assert_eq!(
+            app.world().get::<ComponentSyncModeFull>(entity),
+            Some(&ComponentSyncModeFull(0.5))
+        );
+    }
+}
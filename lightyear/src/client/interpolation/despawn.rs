@@ -1,9 +1,12 @@
-use bevy::prelude::{Commands, DespawnRecursiveExt, OnRemove, Query, ResMut, Trigger};
+use bevy::prelude::{
+    BuildChildren, Children, Commands, DespawnRecursiveExt, OnRemove, Query, ResMut, Trigger, With,
+};
 
 use crate::client::components::{Confirmed, SyncComponent};
 use crate::client::interpolation::interpolate::InterpolateStatus;
 use crate::client::interpolation::interpolation_history::ConfirmedHistory;
 use crate::client::interpolation::resource::InterpolationManager;
+use crate::client::interpolation::Interpolated;
 
 /// Remove the component from interpolated entities when it gets removed from confirmed
 pub(crate) fn removed_components<C: SyncComponent>(
@@ -28,6 +31,8 @@ pub(crate) fn despawn_interpolated(
     trigger: Trigger<OnRemove, Confirmed>,
     mut manager: ResMut<InterpolationManager>,
     mut commands: Commands,
+    children_query: Query<&Children>,
+    interpolated_query: Query<(), With<Interpolated>>,
 ) {
     if let Some(interpolated) = manager
         .interpolated_entity_map
@@ -35,6 +40,12 @@ pub(crate) fn despawn_interpolated(
         .confirmed_to_interpolated
         .remove(&trigger.entity())
     {
+        // interpolated children are despawned when their own confirmed entity is despawned
+        if let Ok(children) = children_query.get(interpolated) {
+            for child in children.iter().filter(|c| interpolated_query.contains(**c)) {
+                commands.entity(*child).remove_parent();
+            }
+        }
         if let Some(entity_mut) = commands.get_entity(interpolated) {
             entity_mut.despawn_recursive();
         }
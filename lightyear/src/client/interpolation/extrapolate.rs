@@ -0,0 +1,301 @@
+//! Extrapolation (dead reckoning) of interpolated components when no server update is available
+//!
+//! An interpolated component stays on its last confirmed value when the interpolation tick goes beyond the most
+//! recent server update, for example during a burst of packet loss or a server hitch.
+//! Components registered with [`add_extrapolation`](crate::prelude::AppComponentExt::add_extrapolation)
+//! are instead projected forward from their last two confirmed values, for at most
+//! [`ExtrapolationConfig::max_duration`]. When interpolation resumes, the component is blended from its
+//! extrapolated value back to the interpolated value over [`ExtrapolationConfig::blend_duration`].
+use bevy::prelude::{Commands, Component, Entity, Query, Res, With, Without};
+use bevy::reflect::Reflect;
+use bevy::utils::Duration;
+use tracing::trace;
+
+use crate::client::components::SyncComponent;
+use crate::client::config::ClientConfig;
+use crate::client::interpolation::interpolate::InterpolateStatus;
+use crate::prelude::ComponentRegistry;
+use crate::shared::tick_manager::Tick;
+
+/// Function used to extrapolate a component from its last two confirmed values (`previous` and `last`).
+/// `t` is the time elapsed since `last`, as a fraction of the time between `previous` and `last`.
+///
+/// The default extrapolation uses the interpolation function with `t > 1.0`, which keeps the velocity
+/// between `previous` and `last` constant.
+pub type ExtrapolationFn<C> = fn(previous: &C, last: &C, t: f32) -> C;
+
+/// Config to specify how the extrapolation of a component should behave
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct ExtrapolationConfig {
+    /// How long we keep extrapolating after the last confirmed value. After that the component
+    /// stays on the last extrapolated value
+    pub max_duration: Duration,
+    /// How long it takes to blend back from the extrapolated value to the interpolated value
+    /// once server updates are received again
+    pub blend_duration: Duration,
+}
+
+impl Default for ExtrapolationConfig {
+    fn default() -> Self {
+        Self {
+            max_duration: Duration::from_millis(250),
+            blend_duration: Duration::from_millis(100),
+        }
+    }
+}
+
+impl ExtrapolationConfig {
+    pub fn with_max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = max_duration;
+        self
+    }
+
+    pub fn with_blend_duration(mut self, blend_duration: Duration) -> Self {
+        self.blend_duration = blend_duration;
+        self
+    }
+}
+
+/// Component that keeps track of the values used to extrapolate the component `C`
+#[derive(Component, PartialEq, Debug)]
+pub struct ExtrapolateStatus<C: Component> {
+    /// confirmed value before `last`, used to estimate the velocity
+    pub previous: Option<(Tick, C)>,
+    /// most recent confirmed value that the interpolation reached
+    pub last: Option<(Tick, C)>,
+    /// value of the component the last time it was extrapolated
+    pub extrapolated: Option<C>,
+    /// value that we are blending from, along with the interpolation time at which the blend started
+    pub blend: Option<(C, Tick, f32)>,
+}
+
+impl<C: Component> Default for ExtrapolateStatus<C> {
+    fn default() -> Self {
+        Self {
+            previous: None,
+            last: None,
+            extrapolated: None,
+            blend: None,
+        }
+    }
+}
+
+impl<C: Component> ExtrapolateStatus<C> {
+    /// Returns true if the component is currently extrapolated instead of interpolated
+    pub fn is_extrapolating(&self) -> bool {
+        self.extrapolated.is_some()
+    }
+}
+
+/// Number of ticks (with overstep) elapsed between `from` and the current interpolation time of the status
+fn elapsed_ticks<C: Component>(
+    status: &InterpolateStatus<C>,
+    from: Tick,
+    from_overstep: f32,
+) -> f32 {
+    (status.current_tick - from) as f32 + status.current_overstep - from_overstep
+}
+
+/// Add the [`ExtrapolateStatus`] to the entities that are interpolated
+pub(crate) fn add_extrapolate_status<C: SyncComponent>(
+    mut commands: Commands,
+    query: Query<Entity, (With<InterpolateStatus<C>>, Without<ExtrapolateStatus<C>>)>,
+) {
+    for entity in query.iter() {
+        commands
+            .entity(entity)
+            .insert(ExtrapolateStatus::<C>::default());
+    }
+}
+
+/// Keep track of the last two confirmed values reached by the interpolation
+pub(crate) fn update_extrapolate_status<C: SyncComponent>(
+    mut query: Query<(&InterpolateStatus<C>, &mut ExtrapolateStatus<C>)>,
+) {
+    for (status, mut extrapolate_status) in query.iter_mut() {
+        let Some((start_tick, start_value)) = &status.start else {
+            continue;
+        };
+        if extrapolate_status
+            .last
+            .as_ref()
+            .map_or(true, |(last_tick, _)| last_tick < start_tick)
+        {
+            let last = extrapolate_status
+                .last
+                .replace((*start_tick, start_value.clone()));
+            extrapolate_status.previous = last;
+        }
+    }
+}
+
+/// Extrapolate the component when there is no server update to interpolate towards,
+/// and blend back to the interpolated value when interpolation resumes
+pub(crate) fn extrapolate<C: SyncComponent>(
+    config: Res<ClientConfig>,
+    component_registry: Res<ComponentRegistry>,
+    mut query: Query<(&mut C, &InterpolateStatus<C>, &mut ExtrapolateStatus<C>)>,
+) {
+    let Some(extrapolation_config) = component_registry.extrapolation_config::<C>() else {
+        return;
+    };
+    let tick_duration = config.shared.tick.tick_duration.as_secs_f32();
+    let max_ticks = extrapolation_config.max_duration.as_secs_f32() / tick_duration;
+    let blend_ticks = extrapolation_config.blend_duration.as_secs_f32() / tick_duration;
+    for (mut component, status, mut extrapolate_status) in query.iter_mut() {
+        let extrapolate_status = extrapolate_status.as_mut();
+        let is_interpolating = status.start.is_some() && status.end.is_some();
+        if !is_interpolating {
+            if let (Some((previous_tick, previous)), Some((last_tick, last))) =
+                (&extrapolate_status.previous, &extrapolate_status.last)
+            {
+                let interval = (*last_tick - *previous_tick) as f32;
+                let elapsed = elapsed_ticks(status, *last_tick, 0.0);
+                if interval > 0.0 && elapsed > 0.0 {
+                    let t = elapsed.min(max_ticks) / interval;
+                    trace!(?last_tick, ?t, "extrapolating component");
+                    let value = component_registry.extrapolate(previous, last, t);
+                    *component = value.clone();
+                    extrapolate_status.extrapolated = Some(value);
+                    extrapolate_status.blend = None;
+                }
+            }
+            continue;
+        }
+        // the interpolation just resumed: blend from the last extrapolated value
+        if let Some(extrapolated) = extrapolate_status.extrapolated.take() {
+            trace!("interpolation resumed, blending from the extrapolated value");
+            extrapolate_status.blend =
+                Some((extrapolated, status.current_tick, status.current_overstep));
+        }
+        if let Some((from, blend_tick, blend_overstep)) = &extrapolate_status.blend {
+            let t = elapsed_ticks(status, *blend_tick, *blend_overstep) / blend_ticks;
+            if t >= 1.0 || !t.is_finite() {
+                extrapolate_status.blend = None;
+            } else {
+                *component = component_registry.interpolate(from, component.as_ref(), t);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{default, App, IntoSystemConfigs, Update};
+
+    use super::*;
+    use crate::prelude::client::Interpolated;
+    use crate::prelude::{ComponentRegistry, SharedConfig, TickConfig};
+    use crate::tests::protocol::ComponentSyncModeFull;
+
+    fn setup() -> (App, Entity) {
+        let mut app = App::new();
+        let tick_duration = Duration::from_millis(10);
+        app.insert_resource(ClientConfig {
+            shared: SharedConfig {
+                tick: TickConfig::new(tick_duration),
+                ..default()
+            },
+            ..default()
+        });
+        let mut registry = ComponentRegistry::default();
+        registry.set_interpolation_mode::<ComponentSyncModeFull>(
+            crate::client::components::ComponentSyncMode::Full,
+        );
+        registry.set_interpolation::<ComponentSyncModeFull>(|start, end, t| {
+            ComponentSyncModeFull(start.0 * (1.0 - t) + end.0 * t)
+        });
+        registry.set_extrapolation_config::<ComponentSyncModeFull>(ExtrapolationConfig {
+            max_duration: Duration::from_millis(50),
+            blend_duration: Duration::from_millis(40),
+        });
+        app.insert_resource(registry);
+        app.add_systems(
+            Update,
+            (
+                update_extrapolate_status::<ComponentSyncModeFull>,
+                extrapolate::<ComponentSyncModeFull>,
+            )
+                .chain(),
+        );
+        let entity = app
+            .world_mut()
+            .spawn((
+                Interpolated {
+                    confirmed_entity: Entity::PLACEHOLDER,
+                },
+                ComponentSyncModeFull(0.0),
+                ExtrapolateStatus::<ComponentSyncModeFull>::default(),
+            ))
+            .id();
+        (app, entity)
+    }
+
+    fn set_status(
+        app: &mut App,
+        entity: Entity,
+        start: Option<(u16, f32)>,
+        end: Option<(u16, f32)>,
+        current_tick: u16,
+    ) {
+        app.world_mut()
+            .entity_mut(entity)
+            .insert(InterpolateStatus::<ComponentSyncModeFull> {
+                start: start.map(|(t, v)| (Tick(t), ComponentSyncModeFull(v))),
+                end: end.map(|(t, v)| (Tick(t), ComponentSyncModeFull(v))),
+                current_tick: Tick(current_tick),
+                current_overstep: 0.0,
+            });
+    }
+
+    fn value(app: &App, entity: Entity) -> f32 {
+        app.world().get::<ComponentSyncModeFull>(entity).unwrap().0
+    }
+
+    #[test]
+    fn test_extrapolation() {
+        let (mut app, entity) = setup();
+        // the value increases by 1.0 per tick
+        set_status(&mut app, entity, Some((0, 0.0)), Some((2, 2.0)), 1);
+        app.update();
+        set_status(&mut app, entity, Some((2, 2.0)), Some((4, 4.0)), 3);
+        app.update();
+        set_status(&mut app, entity, Some((4, 4.0)), None, 4);
+        app.update();
+
+        // no server update: extrapolate with the same velocity
+        set_status(&mut app, entity, Some((4, 4.0)), None, 6);
+        app.update();
+        assert_eq!(value(&app, entity), 6.0);
+        assert!(app
+            .world()
+            .get::<ExtrapolateStatus<ComponentSyncModeFull>>(entity)
+            .unwrap()
+            .is_extrapolating());
+
+        // the extrapolation is capped to max_duration (5 ticks)
+        set_status(&mut app, entity, None, None, 20);
+        app.update();
+        assert_eq!(value(&app, entity), 9.0);
+
+        // a server update is received: blend back to the interpolated value over 4 ticks
+        app.world_mut()
+            .entity_mut(entity)
+            .insert(ComponentSyncModeFull(5.0));
+        set_status(&mut app, entity, Some((20, 5.0)), Some((22, 7.0)), 20);
+        app.update();
+        assert_eq!(value(&app, entity), 9.0);
+        app.world_mut()
+            .entity_mut(entity)
+            .insert(ComponentSyncModeFull(6.0));
+        set_status(&mut app, entity, Some((20, 5.0)), Some((22, 7.0)), 21);
+        app.update();
+        assert_eq!(value(&app, entity), 9.0 * 0.75 + 6.0 * 0.25);
+        app.world_mut()
+            .entity_mut(entity)
+            .insert(ComponentSyncModeFull(8.0));
+        set_status(&mut app, entity, Some((22, 7.0)), Some((24, 9.0)), 24);
+        app.update();
+        assert_eq!(value(&app, entity), 8.0);
+    }
+}
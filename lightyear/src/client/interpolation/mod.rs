@@ -3,14 +3,22 @@ use std::ops::{Add, Mul};
 
 use bevy::prelude::{Component, Entity, Reflect, ReflectComponent};
 
+pub use curve::{CurveFn, CurveSamples, CurveStatus, HermiteFn};
+pub use extrapolate::{ExtrapolateStatus, ExtrapolationConfig, ExtrapolationFn};
 pub use interpolate::InterpolateStatus;
 pub use interpolation_history::ConfirmedHistory;
-pub use plugin::{add_interpolation_systems, add_prepare_interpolation_systems};
+pub use plugin::{
+    add_curve_interpolation_systems, add_curve_status_systems, add_extrapolation_systems,
+    add_hermite_interpolation_systems, add_interpolation_systems,
+    add_prepare_interpolation_systems,
+};
 pub use visual_interpolation::{VisualInterpolateStatus, VisualInterpolationPlugin};
 
 use crate::client::components::LerpFn;
 
+pub mod curve;
 mod despawn;
+pub mod extrapolate;
 pub mod interpolate;
 pub mod interpolation_history;
 pub mod plugin;
@@ -2,7 +2,13 @@ use bevy::prelude::*;
 use bevy::utils::Duration;
 
 use crate::client::components::{ComponentSyncMode, SyncComponent};
+use crate::client::interpolation::curve::{
+    add_curve_status, interpolate_curve, interpolate_hermite, update_curve_status,
+};
 use crate::client::interpolation::despawn::{despawn_interpolated, removed_components};
+use crate::client::interpolation::extrapolate::{
+    add_extrapolate_status, extrapolate, update_extrapolate_status,
+};
 use crate::client::interpolation::interpolate::{
     insert_interpolated_component, interpolate, update_interpolate_status,
 };
@@ -11,6 +17,7 @@ use crate::client::interpolation::spawn::spawn_interpolated_entity;
 use crate::client::interpolation::Interpolated;
 use crate::client::run_conditions::is_synced;
 use crate::prelude::is_host_server;
+use crate::shared::replication::hierarchy::sync_copy_hierarchy;
 
 use super::interpolation_history::{
     add_component_history, apply_confirmed_update_mode_full, apply_confirmed_update_mode_simple,
@@ -28,6 +35,9 @@ pub struct InterpolationDelay {
     /// The higher the server update_rate (i.e. smaller send_interval), the smaller the interpolation delay
     /// Set to 0.0 if you want to only use the Delay
     pub send_interval_ratio: f32,
+    /// If set, the delay adapts to the measured jitter and to the gaps between server updates
+    /// instead of using `send_interval_ratio`. `min_delay` is still used as a lower bound.
+    pub adaptive: Option<AdaptiveInterpolationDelay>,
 }
 
 impl Default for InterpolationDelay {
@@ -35,6 +45,32 @@ impl Default for InterpolationDelay {
         Self {
             min_delay: Duration::from_millis(0),
             send_interval_ratio: 2.0,
+            adaptive: None,
+        }
+    }
+}
+
+/// Settings for an interpolation delay that adapts to the connection quality.
+///
+/// The objective delay is the largest recent gap between two server updates, plus a multiple of the jitter.
+/// The delay is widened as fast as the interpolation timeline can slow down (see [`SyncConfig::speedup_factor`](crate::prelude::client::SyncConfig::speedup_factor))
+/// when the objective increases, and shrunk slowly when it decreases, so that the interpolation time never jumps.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct AdaptiveInterpolationDelay {
+    /// How many multiples of the jitter to add as margin to the delay
+    pub jitter_multiple: f32,
+    /// The maximum delay that we will apply for interpolation
+    pub max_delay: Duration,
+    /// How fast the delay decreases when the connection improves, in seconds of delay per second
+    pub shrink_rate: f32,
+}
+
+impl Default for AdaptiveInterpolationDelay {
+    fn default() -> Self {
+        Self {
+            jitter_multiple: 3.0,
+            max_delay: Duration::from_millis(500),
+            shrink_rate: 0.01,
         }
     }
 }
@@ -50,6 +86,11 @@ impl InterpolationDelay {
         self
     }
 
+    pub fn with_adaptive(mut self, adaptive: AdaptiveInterpolationDelay) -> Self {
+        self.adaptive = Some(adaptive);
+        self
+    }
+
     /// How much behind the latest server update we want the interpolation time to be
     pub(crate) fn to_duration(&self, server_send_interval: Duration) -> Duration {
         // TODO: deal with server_send_interval = 0 (set to frame rate)
@@ -112,6 +153,8 @@ pub enum InterpolationSet {
     /// Interpolate between last 2 server states. Has to be overriden if
     /// `InterpolationConfig.custom_interpolation_logic` is set to true
     Interpolate,
+    /// Extrapolate the components that have no server state to interpolate towards
+    Extrapolate,
     // PostUpdate sets
     /// Interpolate the visual state of the game with 1 tick of delay
     VisualInterpolation,
@@ -167,6 +210,52 @@ pub fn add_interpolation_systems<C: SyncComponent>(app: &mut App) {
     );
 }
 
+/// Add the systems that extrapolate the component when there is no server update to interpolate towards
+pub fn add_extrapolation_systems<C: SyncComponent>(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            add_extrapolate_status::<C>,
+            update_extrapolate_status::<C>,
+            extrapolate::<C>,
+        )
+            .chain()
+            .in_set(InterpolationSet::Extrapolate),
+    );
+}
+
+/// Add the systems that keep track of the samples used by the curve interpolation functions
+pub fn add_curve_status_systems<C: SyncComponent>(app: &mut App) {
+    app.add_systems(
+        Update,
+        (add_curve_status::<C>, update_curve_status::<C>)
+            .chain()
+            .after(update_interpolate_status::<C>)
+            .in_set(InterpolationSet::PrepareInterpolation),
+    );
+}
+
+/// Add the systems that interpolate the component with its [`CurveFn`](super::curve::CurveFn)
+pub fn add_curve_interpolation_systems<C: SyncComponent>(app: &mut App) {
+    app.add_systems(
+        Update,
+        interpolate_curve::<C>
+            .after(interpolate::<C>)
+            .in_set(InterpolationSet::Interpolate),
+    );
+}
+
+/// Add the systems that interpolate the component `C` with its [`HermiteFn`](super::curve::HermiteFn),
+/// using the derivative component `D`
+pub fn add_hermite_interpolation_systems<C: SyncComponent, D: SyncComponent>(app: &mut App) {
+    app.add_systems(
+        Update,
+        interpolate_hermite::<C, D>
+            .after(interpolate::<C>)
+            .in_set(InterpolationSet::Interpolate),
+    );
+}
+
 impl Plugin for InterpolationPlugin {
     fn build(&self, app: &mut App) {
         let should_run_interpolation = not(is_host_server).and_then(is_synced);
@@ -174,6 +263,7 @@ impl Plugin for InterpolationPlugin {
         // REFLECT
         app.register_type::<InterpolationConfig>()
             .register_type::<InterpolationDelay>()
+            .register_type::<AdaptiveInterpolationDelay>()
             .register_type::<Interpolated>();
 
         // RESOURCES
@@ -186,6 +276,7 @@ impl Plugin for InterpolationPlugin {
                 InterpolationSet::SpawnHistory,
                 InterpolationSet::PrepareInterpolation,
                 InterpolationSet::Interpolate,
+                InterpolationSet::Extrapolate,
             )
                 .in_set(InterpolationSet::All)
                 .chain(),
@@ -197,7 +288,13 @@ impl Plugin for InterpolationPlugin {
         // SYSTEMS
         app.add_systems(
             Update,
-            spawn_interpolated_entity.in_set(InterpolationSet::SpawnInterpolation),
+            (
+                spawn_interpolated_entity.in_set(InterpolationSet::SpawnInterpolation),
+                sync_copy_hierarchy::<Interpolated>
+                    .after(InterpolationSet::SpawnInterpolation)
+                    .before(InterpolationSet::SpawnHistory)
+                    .in_set(InterpolationSet::All),
+            ),
         );
         app.observe(despawn_interpolated);
     }
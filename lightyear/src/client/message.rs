@@ -196,6 +196,7 @@ mod tests {
     use crate::serialize::writer::Writer;
     use crate::tests::host_server_stepper::HostServerStepper;
     use crate::tests::protocol::{Channel1, StringMessage};
+    use crate::tests::stepper::BevyStepper;
     use bevy::prelude::{EventReader, Resource, Update};
 
     #[test]
@@ -250,4 +251,50 @@ mod tests {
         // verify that the server received the message
         assert_eq!(stepper.server_app.world().resource::<Counter>().0, 1);
     }
+
+    #[derive(Resource, Default)]
+    struct ReceivedMessages(Vec<String>);
+
+    fn collect_messages(
+        mut received: ResMut<ReceivedMessages>,
+        mut events: EventReader<crate::server::events::MessageEvent<StringMessage>>,
+    ) {
+        received
+            .0
+            .extend(events.read().map(|event| event.message().0.clone()));
+    }
+
+    #[test]
+    fn client_send_message_coalesced() {
+        let mut stepper = BevyStepper::default();
+        stepper.server_app.init_resource::<ReceivedMessages>();
+        stepper.server_app.add_systems(Update, collect_messages);
+
+        let mut manager = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<crate::prelude::client::ConnectionManager>();
+        for message in ["a", "b", "c"] {
+            manager
+                .send_message_coalesced::<Channel1, StringMessage>(
+                    &mut StringMessage(message.to_string()),
+                    0,
+                )
+                .unwrap();
+        }
+        manager
+            .send_message_coalesced::<Channel1, StringMessage>(
+                &mut StringMessage("d".to_string()),
+                1,
+            )
+            .unwrap();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        // only the latest message for each key was sent
+        assert_eq!(
+            stepper.server_app.world().resource::<ReceivedMessages>().0,
+            vec!["c".to_string(), "d".to_string()]
+        );
+    }
 }
@@ -202,6 +202,15 @@ pub(crate) fn receive(world: &mut World) {
             tick_manager,
         )
         .inspect_err(|e| error!("Error receiving packets: {}", e));
+
+    // the server rejected our connection during the handshake
+    if let Some(reason) = connection_manager.denied_reason.take() {
+        world.resource_mut::<ClientConnection>().disconnect_reason =
+            Some(DisconnectReason::Denied(reason));
+        world
+            .resource_mut::<NextState<NetworkingState>>()
+            .set(NetworkingState::Disconnected);
+    }
 }
 
 pub(crate) fn send(
@@ -285,6 +294,15 @@ pub(crate) fn sync_update(
         }
         let relative_speed = time_manager.get_relative_speed();
         virtual_time.set_relative_speed(relative_speed);
+
+        // let the server know how far behind the server we are displaying the interpolated entities
+        let interpolation_delay = connection.sync_manager.interpolation_delay(
+            &config.interpolation.delay,
+            config.shared.server_replication_send_interval,
+        );
+        let _ = connection
+            .send_interpolation_delay(interpolation_delay)
+            .inspect_err(|e| error!("Error sending interpolation delay: {e:?}"));
     }
 }
 
@@ -356,8 +374,13 @@ fn on_connect(
     mut connect_event_writer: EventWriter<ConnectEvent>,
     mut commands: Commands,
     netcode: Res<ClientConnection>,
+    mut connection_manager: ResMut<ConnectionManager>,
     mut query: Query<&mut ReplicateToServer>,
 ) {
+    // send our protocol to the server, which will disconnect us if it does not match its own protocol
+    let _ = connection_manager
+        .send_protocol_fingerprint()
+        .inspect_err(|e| error!("Error sending the protocol fingerprint: {}", e));
     // Set all the ReplicateToServer ticks to changed, so that we replicate existing entities to the server
     for mut replicate in query.iter_mut() {
         // TODO: ideally set is_added instead of simply changed
@@ -383,11 +406,7 @@ fn on_connect_host_server(
     // spawn an entity for the client
     let client_entity = commands.spawn(ControlledEntities::default()).id();
     // start a server connection for that client (which will also send a ConnectEvent on the server)
-    server_manager.add(netcode.id(), client_entity);
-    server_manager
-        .connection_mut(netcode.id())
-        .unwrap()
-        .set_local_client();
+    server_manager.add_local_client(netcode.id(), client_entity);
     metadata.client_entity = Some(client_entity);
     connect_event_writer.send(ConnectEvent::new(netcode.id()));
     // also trigger the event
@@ -558,12 +577,25 @@ mod tests {
 
     use std::time::Duration;
 
+    use bevy::ecs::system::RunSystemOnce;
     use bevy::prelude::*;
 
+    use serde::{Deserialize, Serialize};
+
+    use super::NetworkingState;
     use crate::{
         client::config::ClientConfig,
-        prelude::{client::ClientCommands, server::*, SharedConfig, TickConfig},
-        tests::host_server_stepper::HostServerStepper,
+        connection::client::DisconnectReason,
+        connection::server::DeniedReason,
+        prelude::{
+            client::ClientCommands, server::*, AppComponentExt, AppMessageExt, ChannelDirection,
+            ChannelKind, ClientId, NetworkTarget, SharedConfig, TickConfig,
+        },
+        tests::{
+            host_server_stepper::HostServerStepper,
+            protocol::{Channel1, ComponentSyncModeFull, StringMessage},
+            stepper::{BevyStepper, TEST_CLIENT_ID},
+        },
     };
 
     #[derive(Resource, Default)]
@@ -584,6 +616,401 @@ mod tests {
         }
     }
 
+    #[derive(Resource, Default)]
+    struct DeniedReasons(Vec<DeniedReason>);
+
+    fn receive_denied_event(
+        mut reader: EventReader<crate::client::events::DisconnectEvent>,
+        mut res: ResMut<DeniedReasons>,
+    ) {
+        for event in reader.read() {
+            if let Some(DisconnectReason::Denied(reason)) = &event.reason {
+                res.0.push(reason.clone());
+            }
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct ServerMessageCount(usize);
+
+    fn send_string_message(mut manager: ResMut<crate::prelude::client::ConnectionManager>) {
+        let _ = manager.send_message::<Channel1, StringMessage>(&mut StringMessage("a".into()));
+    }
+
+    fn count_server_messages(
+        mut reader: EventReader<crate::server::events::MessageEvent<StringMessage>>,
+        mut res: ResMut<ServerMessageCount>,
+    ) {
+        res.0 += reader.read().count();
+    }
+
+    /// The server should deny the connection of a client whose protocol does not match its own,
+    /// without processing any of its messages
+    #[test]
+    fn test_protocol_mismatch() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+        struct ExtraMessage;
+
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        stepper
+            .client_app
+            .register_message::<ExtraMessage>(ChannelDirection::ClientToServer);
+        stepper
+            .client_app
+            .init_resource::<DeniedReasons>()
+            .add_systems(Update, (receive_denied_event, send_string_message));
+        stepper
+            .server_app
+            .init_resource::<ServerMessageCount>()
+            .add_systems(Update, count_server_messages);
+        stepper.init();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Disconnected
+        );
+        let reasons = &stepper.client_app.world().resource::<DeniedReasons>().0;
+        assert_eq!(reasons.len(), 1);
+        let DeniedReason::ProtocolMismatch(difference) = &reasons[0] else {
+            panic!("unexpected denied reason: {:?}", reasons[0]);
+        };
+        assert!(difference.contains("ExtraMessage"), "{difference}");
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<crate::server::connection::ConnectionManager>()
+            .connections
+            .is_empty());
+        // the messages sent by the client before it got denied were never processed
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<ServerMessageCount>()
+                .0,
+            0
+        );
+    }
+
+    /// The server should deny the connection of a client that does not send its protocol fingerprint in time
+    #[test]
+    fn test_handshake_timeout() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        // the fingerprint can only arrive after the server has registered the connection
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .packet
+            .handshake_timeout = Duration::default();
+        stepper
+            .client_app
+            .init_resource::<DeniedReasons>()
+            .add_systems(Update, receive_denied_event);
+        stepper.init();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        let reasons = &stepper.client_app.world().resource::<DeniedReasons>().0;
+        assert_eq!(reasons.len(), 1);
+        let DeniedReason::ProtocolMismatch(difference) = &reasons[0] else {
+            panic!("unexpected denied reason: {:?}", reasons[0]);
+        };
+        assert!(
+            difference.contains("no protocol fingerprint"),
+            "{difference}"
+        );
+    }
+
+    /// The server should keep the connection of a denied client open until the client has
+    /// received the reason of the denial
+    #[test]
+    fn test_denial_kept_until_acked() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+        struct ExtraMessage;
+
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        stepper
+            .client_app
+            .register_message::<ExtraMessage>(ChannelDirection::ClientToServer);
+        stepper
+            .client_app
+            .init_resource::<DeniedReasons>()
+            .add_systems(Update, receive_denied_event);
+        // connect without waiting for the client to be synced
+        stepper.build();
+        stepper
+            .server_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.start_server());
+        stepper
+            .client_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.connect_client());
+        let denied = |stepper: &BevyStepper| {
+            stepper
+                .server_app
+                .world()
+                .resource::<crate::server::connection::ConnectionManager>()
+                .connections
+                .get(&ClientId::Netcode(TEST_CLIENT_ID))
+                .is_some_and(|connection| {
+                    matches!(
+                        connection.handshake,
+                        crate::server::connection::HandshakeState::Denied(_)
+                    )
+                })
+        };
+        for _ in 0..100 {
+            if denied(&stepper) {
+                break;
+            }
+            stepper.frame_step();
+        }
+        assert!(denied(&stepper));
+
+        // the client does not receive any packet, so the denial is not acked:
+        // the server keeps the connection open to resend the denial
+        for _ in 0..10 {
+            stepper.advance_time(frame_duration);
+            stepper.server_app.update();
+        }
+        assert!(denied(&stepper));
+
+        // once the client receives the denial, it gets disconnected
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<crate::server::connection::ConnectionManager>()
+            .connections
+            .is_empty());
+        let reasons = &stepper.client_app.world().resource::<DeniedReasons>().0;
+        assert_eq!(reasons.len(), 1);
+        assert!(matches!(reasons[0], DeniedReason::ProtocolMismatch(_)));
+    }
+
+    /// A client using an older version of the protocol can connect to the server, and the server
+    /// does not send it the messages that were added in a newer version
+    #[test]
+    fn test_older_protocol_version() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+        struct NewMessage;
+
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            protocol_version: 1,
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        stepper
+            .server_app
+            .register_message::<NewMessage>(ChannelDirection::ServerToClient)
+            // use an explicit net id so that the net ids of the other messages are not shifted
+            .with_net_id(100)
+            .since_version(1);
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .shared
+            .protocol_version = 0;
+        stepper.init();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Connected
+        );
+        let server_manager = stepper
+            .server_app
+            .world()
+            .resource::<crate::server::connection::ConnectionManager>();
+        assert_eq!(
+            server_manager
+                .connection(ClientId::Netcode(TEST_CLIENT_ID))
+                .unwrap()
+                .protocol_version,
+            0
+        );
+
+        // only the message that is part of the client's protocol version is sent
+        let mut server_manager = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<crate::server::connection::ConnectionManager>();
+        server_manager
+            .send_message_to_target::<Channel1, _>(&mut NewMessage, NetworkTarget::All)
+            .unwrap();
+        server_manager
+            .send_message_to_target::<Channel1, _>(
+                &mut StringMessage("a".to_string()),
+                NetworkTarget::All,
+            )
+            .unwrap();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<crate::client::connection::ConnectionManager>()
+                .message_manager
+                .channels
+                .get(&ChannelKind::of::<Channel1>())
+                .unwrap()
+                .stats
+                .receive
+                .messages_received(),
+            1
+        );
+    }
+
+    /// A client using an older version of the protocol can connect to a server that is already
+    /// replicating components added in a newer version: nothing is sent to the client before
+    /// the handshake, so the initial replication and the messages sent on connection only contain
+    /// the types that the client knows
+    #[test]
+    fn test_older_protocol_version_initial_replication() {
+        #[derive(Component, Serialize, Deserialize, Debug, PartialEq, Clone)]
+        struct NewComponent;
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+        struct NewMessage;
+
+        fn send_on_connect(
+            mut events: EventReader<ConnectEvent>,
+            mut manager: ResMut<crate::server::connection::ConnectionManager>,
+        ) {
+            for event in events.read() {
+                let _ = manager.send_message::<Channel1, _>(event.client_id, &mut NewMessage);
+                let _ = manager.send_message::<Channel1, _>(
+                    event.client_id,
+                    &mut StringMessage("a".to_string()),
+                );
+            }
+        }
+
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            protocol_version: 1,
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        stepper
+            .server_app
+            .register_component::<NewComponent>(ChannelDirection::ServerToClient)
+            // use an explicit net id so that the net ids of the other components are not shifted
+            .with_net_id(100)
+            .since_version(1);
+        stepper
+            .server_app
+            .register_message::<NewMessage>(ChannelDirection::ServerToClient)
+            .with_net_id(100)
+            .since_version(1);
+        stepper.server_app.add_systems(Update, send_on_connect);
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .shared
+            .protocol_version = 0;
+        stepper.build();
+        stepper
+            .server_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.start_server());
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate::default(),
+                ComponentSyncModeFull(1.0),
+                NewComponent,
+            ))
+            .id();
+        stepper.frame_step();
+        stepper
+            .client_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.connect_client());
+        for _ in 0..50 {
+            stepper.frame_step();
+        }
+
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Connected
+        );
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<crate::client::connection::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+        // only the message that is part of the client's protocol version was sent on connection
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<crate::client::connection::ConnectionManager>()
+                .message_manager
+                .channels
+                .get(&ChannelKind::of::<Channel1>())
+                .unwrap()
+                .stats
+                .receive
+                .messages_received(),
+            1
+        );
+    }
+
     #[test]
     fn test_host_server_connect_event() {
         let frame_duration = Duration::from_millis(10);
@@ -10,9 +10,102 @@ use bevy::prelude::{Commands, Component, DetectChangesMut, Entity, Query, Res};
 use tracing::debug;
 
 use crate::client::components::{LerpFn, SyncComponent};
-use crate::client::easings::ease_out_quad;
+use crate::client::easings::{ease_out_cubic, ease_out_expo, ease_out_quad, ease_out_quart};
 use crate::prelude::{ComponentRegistry, Tick, TickManager};
 
+/// Function that returns the size of the error between the predicted value and the corrected value
+/// (for example the distance between two positions)
+pub type CorrectionErrorFn<C> = fn(predicted: &C, corrected: &C) -> f32;
+
+/// Easing curve applied to the correction progress `t` (between 0.0 and 1.0)
+#[derive(Clone, Copy, Debug, Default)]
+pub enum CorrectionEasing {
+    Linear,
+    #[default]
+    EaseOutQuad,
+    EaseOutCubic,
+    EaseOutQuart,
+    EaseOutExpo,
+    /// Custom easing function. It should return 0.0 for `t = 0.0` and 1.0 for `t = 1.0`
+    Custom(fn(f32) -> f32),
+}
+
+// function pointers cannot be compared reliably, so two custom easings are considered equal
+impl PartialEq for CorrectionEasing {
+    fn eq(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+impl CorrectionEasing {
+    pub fn apply(&self, t: f32) -> f32 {
+        match self {
+            CorrectionEasing::Linear => t,
+            CorrectionEasing::EaseOutQuad => ease_out_quad(t),
+            CorrectionEasing::EaseOutCubic => ease_out_cubic(t),
+            CorrectionEasing::EaseOutQuart => ease_out_quart(t),
+            CorrectionEasing::EaseOutExpo => ease_out_expo(t),
+            CorrectionEasing::Custom(easing) => easing(t),
+        }
+    }
+}
+
+/// Per-component policy that controls how the visual correction behaves depending on the size of the error
+/// between the original prediction and the corrected value.
+///
+/// The size of the error is computed with the [`CorrectionErrorFn`] provided in
+/// [`add_correction_policy`](crate::prelude::ComponentRegistration::add_correction_policy).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CorrectionPolicy {
+    /// Errors strictly smaller than this are not visually corrected: the component snaps to the corrected value
+    pub epsilon: f32,
+    /// Errors bigger than this are not visually corrected: the component snaps to the corrected value (for example for teleports)
+    pub snap_distance: Option<f32>,
+    /// Easing curve used for the visual correction
+    pub easing: CorrectionEasing,
+    /// If set, the duration of the correction depends on the size of the error instead of the number of rollback ticks:
+    /// the error is corrected at `correction_speed` units per tick
+    pub correction_speed: Option<f32>,
+}
+
+impl Default for CorrectionPolicy {
+    fn default() -> Self {
+        Self {
+            epsilon: 0.0,
+            snap_distance: None,
+            easing: CorrectionEasing::default(),
+            correction_speed: None,
+        }
+    }
+}
+
+impl CorrectionPolicy {
+    pub fn with_epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn with_snap_distance(mut self, snap_distance: f32) -> Self {
+        self.snap_distance = Some(snap_distance);
+        self
+    }
+
+    pub fn with_easing(mut self, easing: CorrectionEasing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn with_correction_speed(mut self, correction_speed: f32) -> Self {
+        self.correction_speed = Some(correction_speed);
+        self
+    }
+
+    /// Returns true if an error of this size should be snapped instead of visually corrected
+    fn should_snap(&self, error: f32) -> bool {
+        error < self.epsilon || self.snap_distance.is_some_and(|snap| error > snap)
+    }
+}
+
 // TODO: instead of requiring the component to implement the correction, we could have a separate
 //  'type registry' that stores the correction function for each component type.
 //  or we register in the protocol (that is user defined), the correction function for each component type
@@ -87,12 +180,32 @@ pub(crate) fn get_visually_corrected_state<C: SyncComponent>(
     mut query: Query<(Entity, &mut C, &mut Correction<C>)>,
 ) {
     let kind = std::any::type_name::<C>();
+    let policy = component_registry.correction_policy::<C>();
+    let easing = policy.map_or(CorrectionEasing::default(), |(policy, _)| policy.easing);
     for (entity, mut component, mut correction) in query.iter_mut() {
         let current_tick = tick_manager.tick();
+        // the correction just started: apply the correction policy now that the corrected value is known
+        if let Some((policy, error_fn)) = policy.filter(|_| correction.current_visual.is_none()) {
+            let error = error_fn(&correction.original_prediction, component.as_ref());
+            if policy.should_snap(error) {
+                debug!(
+                    ?error,
+                    ?entity,
+                    "Snapping to the corrected value for: {:?}",
+                    kind
+                );
+                commands.entity(entity).remove::<Correction<C>>();
+                continue;
+            }
+            if let Some(speed) = policy.correction_speed {
+                let correction_ticks = (error / speed).ceil().max(1.0) as i16;
+                correction.final_correction_tick = correction.original_tick + correction_ticks;
+            }
+        }
         let mut t = (current_tick - correction.original_tick) as f32
             / (correction.final_correction_tick - correction.original_tick) as f32;
         t = t.clamp(0.0, 1.0);
-        let t = ease_out_quad(t);
+        let t = easing.apply(t);
         if t == 1.0 || &correction.original_prediction == component.as_ref() {
             debug!(
                 ?t,
@@ -138,3 +251,97 @@ pub(crate) fn restore_corrected_state<C: SyncComponent>(
 // - we compute the final_correction_tick = current_tick + correction_ticks
 // - during rollback, the Predicted entity will take the Corrected position.
 // - in PostUpdate, during the correction_ticks, we will interpolated between the old
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{App, Update};
+
+    use super::*;
+    use crate::client::components::ComponentSyncMode;
+    use crate::prelude::TickConfig;
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use bevy::utils::Duration;
+
+    fn setup(policy: CorrectionPolicy, original: f32, corrected: f32) -> (App, Entity) {
+        let mut app = App::new();
+        let mut registry = ComponentRegistry::default();
+        registry.set_prediction_mode::<ComponentSyncModeFull>(ComponentSyncMode::Full);
+        registry.set_correction::<ComponentSyncModeFull>(|start, end, t| {
+            ComponentSyncModeFull(start.0 * (1.0 - t) + end.0 * t)
+        });
+        registry.set_correction_policy::<ComponentSyncModeFull>(policy, |predicted, corrected| {
+            (predicted.0 - corrected.0).abs()
+        });
+        app.insert_resource(registry);
+        let mut tick_manager = TickManager::from_config(TickConfig::new(Duration::from_millis(10)));
+        tick_manager.increment_tick();
+        app.insert_resource(tick_manager);
+        app.add_systems(
+            Update,
+            get_visually_corrected_state::<ComponentSyncModeFull>,
+        );
+        let entity = app
+            .world_mut()
+            .spawn((
+                ComponentSyncModeFull(corrected),
+                Correction {
+                    original_prediction: ComponentSyncModeFull(original),
+                    original_tick: Tick(0),
+                    final_correction_tick: Tick(10),
+                    current_visual: None,
+                    current_correction: None,
+                },
+            ))
+            .id();
+        (app, entity)
+    }
+
+    /// Errors below the epsilon or above the snap distance are not visually corrected
+    #[test]
+    fn test_correction_policy_snap() {
+        let policy = CorrectionPolicy::default()
+            .with_epsilon(0.5)
+            .with_snap_distance(10.0);
+        for corrected in [0.1, 20.0] {
+            let (mut app, entity) = setup(policy, 0.0, corrected);
+            app.update();
+            assert!(app
+                .world()
+                .get::<Correction<ComponentSyncModeFull>>(entity)
+                .is_none());
+            assert_eq!(
+                app.world().get::<ComponentSyncModeFull>(entity).unwrap().0,
+                corrected
+            );
+        }
+
+        // errors in between are visually corrected
+        let (mut app, entity) = setup(policy, 0.0, 5.0);
+        app.update();
+        assert!(app
+            .world()
+            .get::<Correction<ComponentSyncModeFull>>(entity)
+            .is_some());
+    }
+
+    /// The correction duration depends on the error size, and uses the custom easing
+    #[test]
+    fn test_correction_policy_speed_and_easing() {
+        let policy = CorrectionPolicy::default()
+            .with_correction_speed(2.0)
+            .with_easing(CorrectionEasing::Custom(|t| t * t));
+        let (mut app, entity) = setup(policy, 0.0, 8.0);
+        app.update();
+        let correction = app
+            .world()
+            .get::<Correction<ComponentSyncModeFull>>(entity)
+            .unwrap();
+        // the error is corrected at 2.0 per tick, so the correction takes 4 ticks
+        assert_eq!(correction.final_correction_tick, Tick(4));
+        // t = 0.25, eased to 0.0625
+        assert_eq!(
+            app.world().get::<ComponentSyncModeFull>(entity).unwrap().0,
+            0.5
+        );
+    }
+}
@@ -1,8 +1,8 @@
 use bevy::ecs::system::EntityCommands;
 use bevy::ecs::world::Command;
 use bevy::prelude::{
-    Commands, Component, DespawnRecursiveExt, Entity, OnRemove, Query, Reflect, ReflectComponent,
-    Res, ResMut, Trigger, With, Without, World,
+    BuildChildren, Children, Commands, Component, DespawnRecursiveExt, Entity, OnRemove, Query,
+    Reflect, ReflectComponent, Res, ResMut, Trigger, With, Without, World,
 };
 use tracing::{debug, error, trace};
 
@@ -87,10 +87,15 @@ impl PredictionDespawnCommandsExt for EntityCommands<'_> {
 }
 
 /// Despawn predicted entities when the confirmed entity gets despawned
+///
+/// Children that are themselves predicted entities are detached first: their lifetime is tied to
+/// their own confirmed entity.
 pub(crate) fn despawn_confirmed(
     trigger: Trigger<OnRemove, Confirmed>,
     mut manager: ResMut<PredictionManager>,
     mut commands: Commands,
+    children_query: Query<&Children>,
+    predicted_query: Query<(), With<Predicted>>,
 ) {
     if let Some(predicted) = manager
         .predicted_entity_map
@@ -98,6 +103,11 @@ pub(crate) fn despawn_confirmed(
         .confirmed_to_predicted
         .remove(&trigger.entity())
     {
+        if let Ok(children) = children_query.get(predicted) {
+            for child in children.iter().filter(|c| predicted_query.contains(**c)) {
+                commands.entity(*child).remove_parent();
+            }
+        }
         if let Some(entity_mut) = commands.get_entity(predicted) {
             entity_mut.despawn_recursive();
         }
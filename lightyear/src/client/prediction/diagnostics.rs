@@ -57,6 +57,9 @@ pub struct PredictionMetrics {
     pub rollbacks: u32,
     /// Per rollback, incremented by the number of ticks the rollback window contains
     pub rollback_ticks: u32,
+    /// Incremented when a rollback (or part of a partial rollback) is skipped or deferred
+    /// because it exceeds the rollback budget
+    pub skipped_rollbacks: u32,
 }
 
 impl Plugin for PredictionDiagnosticsPlugin {
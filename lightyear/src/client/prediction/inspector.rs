@@ -0,0 +1,223 @@
+//! Record debug information about each rollback, so that tools can find out what triggers rollbacks.
+//!
+//! Add the [`RollbackInspectorPlugin`] to the client app to keep the most recent rollbacks in the
+//! [`RollbackInspector`] resource. Each [`RollbackRecord`] contains the entities and components that
+//! triggered the rollback, the tick distance and the time spent resimulating.
+//!
+//! The predicted and confirmed values are only recorded for the components registered with
+//! [`add_rollback_debug`](crate::prelude::ComponentRegistration::add_rollback_debug).
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+use bevy::prelude::{App, Entity, Event, Plugin, Resource};
+use bevy::utils::{Duration, HashMap};
+use parking_lot::RwLock;
+
+use crate::prelude::Tick;
+
+/// Plugin that records debug information about every rollback in the [`RollbackInspector`]
+#[derive(Debug, Clone, Copy)]
+pub struct RollbackInspectorPlugin {
+    /// Number of rollbacks that are kept in the [`RollbackInspector`]
+    pub capacity: usize,
+    /// If true, a [`RollbackRecord`] event is also emitted for every rollback
+    pub emit_events: bool,
+}
+
+impl Default for RollbackInspectorPlugin {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            emit_events: false,
+        }
+    }
+}
+
+impl Plugin for RollbackInspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RollbackInspector::new(self.capacity, self.emit_events));
+        app.add_event::<RollbackRecord>();
+    }
+}
+
+/// A mismatch between a predicted component and its confirmed value that triggered a rollback
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollbackTrigger {
+    /// The predicted entity
+    pub entity: Entity,
+    /// Name of the component that had a mismatch
+    pub component: &'static str,
+    /// Debug representation of the predicted value (None if the component was not predicted,
+    /// or if the component is not registered with `add_rollback_debug`)
+    pub predicted: Option<String>,
+    /// Debug representation of the confirmed value (None if the component does not exist on the
+    /// confirmed entity, or if the component is not registered with `add_rollback_debug`)
+    pub confirmed: Option<String>,
+    /// Tick of the confirmed value
+    pub confirmed_tick: Tick,
+    /// Number of ticks between the confirmed tick and the current tick
+    pub tick_distance: u16,
+}
+
+/// Debug information about one rollback
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct RollbackRecord {
+    /// Tick at which the rollback happened
+    pub tick: Tick,
+    /// First tick that was resimulated
+    pub rollback_tick: Tick,
+    /// Number of ticks that were resimulated
+    pub resimulated_ticks: u16,
+    /// Time spent resimulating the ticks
+    pub resimulation_time: Duration,
+    /// The mismatches that triggered the rollback. Can be empty if the rollback was not triggered
+    /// by a predicted component (for example in a P2P session)
+    pub triggers: Vec<RollbackTrigger>,
+}
+
+/// Resource that keeps the most recent [`RollbackRecord`]s in a ring buffer
+#[derive(Resource, Debug)]
+pub struct RollbackInspector {
+    capacity: usize,
+    pub(crate) emit_events: bool,
+    records: VecDeque<RollbackRecord>,
+    /// Mismatches found during the rollback check of the current frame.
+    /// We use a RwLock because the rollback check systems for each component run in parallel
+    pending: RwLock<Vec<RollbackTrigger>>,
+}
+
+impl RollbackInspector {
+    pub fn new(capacity: usize, emit_events: bool) -> Self {
+        Self {
+            capacity,
+            emit_events,
+            records: VecDeque::with_capacity(capacity),
+            pending: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// The recorded rollbacks, from the oldest to the most recent
+    pub fn records(&self) -> impl Iterator<Item = &RollbackRecord> {
+        self.records.iter()
+    }
+
+    /// The most recent rollback
+    pub fn last(&self) -> Option<&RollbackRecord> {
+        self.records.back()
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    /// Number of recorded rollbacks triggered by each component, sorted from the most frequent to the least frequent
+    pub fn triggers_per_component(&self) -> Vec<(&'static str, usize)> {
+        let mut counts: HashMap<&'static str, usize> = HashMap::default();
+        for record in self.records.iter() {
+            // count each component once per rollback
+            let mut components: Vec<_> = record.triggers.iter().map(|t| t.component).collect();
+            components.sort_unstable();
+            components.dedup();
+            for component in components {
+                *counts.entry(component).or_default() += 1;
+            }
+        }
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts
+    }
+
+    /// Text dump of the recorded rollbacks, one line per rollback followed by one line per trigger
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        for record in self.records.iter() {
+            let _ = writeln!(
+                out,
+                "tick {:?}: rolled back {} ticks from {:?} in {:?}",
+                record.tick,
+                record.resimulated_ticks,
+                record.rollback_tick,
+                record.resimulation_time
+            );
+            for trigger in record.triggers.iter() {
+                let _ = writeln!(
+                    out,
+                    "  {:?} {} (confirmed tick {:?}, {} ticks ago): predicted {} / confirmed {}",
+                    trigger.entity,
+                    trigger.component,
+                    trigger.confirmed_tick,
+                    trigger.tick_distance,
+                    trigger.predicted.as_deref().unwrap_or("-"),
+                    trigger.confirmed.as_deref().unwrap_or("-"),
+                );
+            }
+        }
+        out
+    }
+
+    /// Record a mismatch found during the rollback check
+    pub(crate) fn add_trigger(&self, trigger: RollbackTrigger) {
+        self.pending.write().push(trigger);
+    }
+
+    /// Discard the mismatches of a rollback that did not run
+    pub(crate) fn discard_triggers(&self) {
+        self.pending.write().clear();
+    }
+
+    /// Store the record of a rollback that just ran, along with the mismatches that triggered it
+    pub(crate) fn record(
+        &mut self,
+        tick: Tick,
+        rollback_tick: Tick,
+        resimulated_ticks: u16,
+        resimulation_time: Duration,
+    ) {
+        if self.records.len() >= self.capacity {
+            self.records.pop_front();
+        }
+        let triggers = std::mem::take(self.pending.get_mut());
+        self.records.push_back(RollbackRecord {
+            tick,
+            rollback_tick,
+            resimulated_ticks,
+            resimulation_time,
+            triggers,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trigger(component: &'static str) -> RollbackTrigger {
+        RollbackTrigger {
+            entity: Entity::PLACEHOLDER,
+            component,
+            predicted: Some("1.0".to_string()),
+            confirmed: None,
+            confirmed_tick: Tick(8),
+            tick_distance: 2,
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let mut inspector = RollbackInspector::new(2, false);
+        for i in 0..3 {
+            inspector.add_trigger(trigger("A"));
+            if i == 0 {
+                inspector.add_trigger(trigger("B"));
+            }
+            inspector.record(Tick(10 + i), Tick(9), 2, Duration::from_micros(50));
+        }
+        // only the 2 most recent rollbacks are kept
+        assert_eq!(
+            inspector.records().map(|r| r.tick).collect::<Vec<_>>(),
+            vec![Tick(11), Tick(12)]
+        );
+        assert_eq!(inspector.triggers_per_component(), vec![("A", 2)]);
+        assert!(inspector.dump().contains("predicted 1.0 / confirmed -"));
+    }
+}
@@ -5,6 +5,7 @@ use std::fmt::Debug;
 pub mod correction;
 pub mod despawn;
 pub mod diagnostics;
+pub mod inspector;
 pub mod plugin;
 pub mod pre_prediction;
 pub mod predicted_history;
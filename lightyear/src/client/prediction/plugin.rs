@@ -24,7 +24,9 @@ use crate::client::prediction::prespawn::{
 };
 use crate::client::prediction::resource::PredictionManager;
 use crate::client::prediction::Predicted;
+use crate::prelude::MainSet;
 use crate::prelude::{client::is_synced, is_host_server, PreSpawnedPlayerObject};
+use crate::shared::replication::hierarchy::sync_copy_hierarchy;
 use crate::shared::sets::{ClientMarker, InternalMainSet};
 
 use super::pre_prediction::PrePredictionPlugin;
@@ -32,10 +34,27 @@ use super::predicted_history::{add_component_history, apply_confirmed_update};
 use super::resource_history::{update_resource_history, ResourceHistory};
 use super::rollback::{
     check_rollback, increment_rollback_tick, prepare_rollback, prepare_rollback_non_networked,
-    prepare_rollback_prespawn, prepare_rollback_resource, run_rollback, Rollback, RollbackState,
+    prepare_rollback_prespawn, prepare_rollback_resource, restore_frozen_components, run_rollback,
+    select_rollback_entities, unfreeze_rollback_entities, Rollback, RollbackFrozen, RollbackGroup,
+    RollbackState,
 };
 use super::spawn::spawn_predicted_entity;
 
+/// Which predicted entities are rolled back when a mismatch is detected
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum RollbackMode {
+    /// All predicted entities are rolled back whenever any predicted entity has a mismatch
+    #[default]
+    Full,
+    /// Only the entities that are in the same rollback group as a mismatched entity are rolled back.
+    ///
+    /// Entities are grouped by their [`RollbackGroup`](super::rollback::RollbackGroup) if they have one,
+    /// and by the replication group of their confirmed entity otherwise.
+    /// The other predicted entities are marked with [`RollbackFrozen`](super::rollback::RollbackFrozen)
+    /// during the rollback and keep their current state.
+    Partial,
+}
+
 /// Configuration to specify how the prediction plugin should behave
 #[derive(Debug, Clone, Copy, Reflect)]
 pub struct PredictionConfig {
@@ -76,6 +95,16 @@ pub struct PredictionConfig {
     /// (i.e. if the client is 10 ticks head and correction_ticks is 1.0, then the correction will be done over 10 ticks)
     // Number of ticks it will take to visually update the Predicted state to the new Corrected state
     pub correction_ticks_factor: f32,
+    /// Which predicted entities are rolled back when a mismatch is detected
+    pub rollback_mode: RollbackMode,
+    /// Maximum number of entity-ticks (number of rolled back entities multiplied by the number of resimulated ticks)
+    /// that can be resimulated in a single frame.
+    ///
+    /// With [`RollbackMode::Full`], rollbacks that exceed the budget are skipped.
+    /// With [`RollbackMode::Partial`], the rollback groups that don't fit in the budget are deferred to the next frames.
+    ///
+    /// Should not be used for deterministic simulations, where every rollback is necessary.
+    pub rollback_budget: Option<u32>,
 }
 
 impl Default for PredictionConfig {
@@ -94,6 +123,8 @@ impl Default for PredictionConfig {
             maximum_input_delay_before_prediction: 0,
             maximum_predicted_ticks: 100,
             correction_ticks_factor: 1.0,
+            rollback_mode: RollbackMode::Full,
+            rollback_budget: None,
         }
     }
 }
@@ -116,6 +147,18 @@ impl PredictionConfig {
         self
     }
 
+    /// Update which predicted entities are rolled back when a mismatch is detected
+    pub fn with_rollback_mode(mut self, rollback_mode: RollbackMode) -> Self {
+        self.rollback_mode = rollback_mode;
+        self
+    }
+
+    /// Update the maximum number of entity-ticks that can be resimulated in a single frame
+    pub fn with_rollback_budget(mut self, rollback_budget: u32) -> Self {
+        self.rollback_budget = Some(rollback_budget);
+        self
+    }
+
     /// Compute the amount of input delay that should be applied, considering the current RTT
     pub fn input_delay_ticks(&self, rtt: Duration, tick_interval: Duration) -> u16 {
         let rtt_ticks = rtt.as_nanos() as f32 / tick_interval.as_nanos() as f32;
@@ -152,6 +195,9 @@ pub enum PredictionSet {
     /// Add component history for all predicted entities' predicted components
     SpawnHistory,
     RestoreVisualCorrection,
+    /// Compare the predicted history with the checksums sent by the server.
+    /// This runs before the rollback check, which clears the history older than the confirmed tick
+    CheckDesync,
     /// Check if rollback is needed
     CheckRollback,
     /// Prepare rollback by snapping the current state to the confirmed state and clearing histories
@@ -192,6 +238,10 @@ pub fn add_non_networked_rollback_systems<C: Component + PartialEq + Clone>(app:
         (
             add_non_networked_component_history::<C>.in_set(PredictionSet::SpawnHistory),
             prepare_rollback_non_networked::<C>.in_set(PredictionSet::PrepareRollback),
+            restore_frozen_components::<C>
+                .after(PredictionSet::Rollback)
+                .before(unfreeze_rollback_entities)
+                .in_set(PredictionSet::All),
         ),
     );
     app.add_systems(
@@ -247,6 +297,10 @@ pub fn add_prediction_systems<C: SyncComponent>(app: &mut App, prediction_mode:
                     check_rollback::<C>.in_set(PredictionSet::CheckRollback),
                     (prepare_rollback::<C>, prepare_rollback_prespawn::<C>)
                         .in_set(PredictionSet::PrepareRollback),
+                    restore_frozen_components::<C>
+                        .after(PredictionSet::Rollback)
+                        .before(unfreeze_rollback_entities)
+                        .in_set(PredictionSet::All),
                 ),
             );
             app.add_systems(
@@ -306,6 +360,8 @@ impl Plugin for PredictionPlugin {
             .register_type::<PreSpawnedPlayerObject>()
             .register_type::<Rollback>()
             .register_type::<RollbackState>()
+            .register_type::<RollbackGroup>()
+            .register_type::<RollbackFrozen>()
             .register_type::<PredictionDespawnMarker>()
             .register_type::<PredictionConfig>();
 
@@ -326,6 +382,7 @@ impl Plugin for PredictionPlugin {
                     PredictionSet::SpawnPrediction,
                     PredictionSet::SpawnHistory,
                     PredictionSet::RestoreVisualCorrection,
+                    PredictionSet::CheckDesync,
                     PredictionSet::CheckRollback,
                     PredictionSet::PrepareRollback.run_if(is_in_rollback),
                     PredictionSet::Rollback.run_if(is_in_rollback),
@@ -348,7 +405,20 @@ impl Plugin for PredictionPlugin {
                 spawn_predicted_entity
                     .after(PreSpawnedPlayerObjectSet::Spawn)
                     .in_set(PredictionSet::SpawnPrediction),
+                // mirror the confirmed hierarchy (updated in MainSet::Receive) on the predicted entities
+                sync_copy_hierarchy::<Predicted>
+                    .after(MainSet::Receive)
+                    .after(PredictionSet::SpawnPrediction)
+                    .before(PredictionSet::CheckRollback)
+                    .in_set(PredictionSet::All),
+                select_rollback_entities
+                    .after(PredictionSet::CheckRollback)
+                    .before(PredictionSet::PrepareRollback)
+                    .in_set(PredictionSet::All),
                 run_rollback.in_set(PredictionSet::Rollback),
+                unfreeze_rollback_entities
+                    .after(PredictionSet::Rollback)
+                    .in_set(PredictionSet::All),
             ),
         );
         app.observe(despawn_confirmed);
@@ -410,6 +480,7 @@ mod tests {
             maximum_input_delay_before_prediction: 3,
             maximum_predicted_ticks: 7,
             correction_ticks_factor: 0.0,
+            ..Default::default()
         };
         // 1. Test the minimum input delay
         assert_eq!(
@@ -10,7 +10,7 @@ use tracing::{debug, trace};
 
 use crate::client::components::{ComponentSyncMode, Confirmed, SyncComponent};
 use crate::client::prediction::resource::PredictionManager;
-use crate::client::prediction::rollback::Rollback;
+use crate::client::prediction::rollback::{Rollback, RollbackFrozen};
 use crate::client::prediction::Predicted;
 use crate::prelude::{ComponentRegistry, PreSpawnedPlayerObject, ShouldBePredicted, TickManager};
 use crate::shared::tick_manager::Tick;
@@ -71,6 +71,17 @@ impl<C: PartialEq + Clone> PredictionHistory<C> {
         self.buffer.push(tick, ComponentState::Removed);
     }
 
+    /// Return the most recent value that is older or equal to the specified tick, without
+    /// modifying the history
+    pub(crate) fn peek_at_tick(&self, tick: Tick) -> Option<&ComponentState<C>> {
+        self.buffer
+            .heap
+            .iter()
+            .filter(|item| item.key <= tick)
+            .max_by_key(|item| item.key)
+            .map(|item| &item.item)
+    }
+
     // TODO: check if this logic is necessary/correct?
     /// Clear the history of values strictly older than the specified tick,
     /// and return the most recent value that is older or equal to the specified tick.
@@ -248,7 +259,8 @@ fn add_history<C: SyncComponent>(
 ///
 /// This system only handles changes, removals are handled in `apply_component_removal`
 pub(crate) fn update_prediction_history<T: Component + PartialEq + Clone>(
-    mut query: Query<(Ref<T>, &mut PredictionHistory<T>)>,
+    // the history of entities that are frozen during a partial rollback must not be modified
+    mut query: Query<(Ref<T>, &mut PredictionHistory<T>), Without<RollbackFrozen>>,
     tick_manager: Res<TickManager>,
     rollback: Res<Rollback>,
 ) {
@@ -270,7 +282,7 @@ pub(crate) fn apply_component_removal_predicted<C: Component + PartialEq + Clone
     trigger: Trigger<OnRemove, C>,
     tick_manager: Res<TickManager>,
     rollback: Res<Rollback>,
-    mut predicted_query: Query<&mut PredictionHistory<C>>,
+    mut predicted_query: Query<&mut PredictionHistory<C>, Without<RollbackFrozen>>,
 ) {
     // TODO: do not run this if component-sync-mode != FULL
     // if the component was removed from the Predicted entity, add the Removal to the history
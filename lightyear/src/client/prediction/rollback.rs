@@ -10,6 +10,7 @@ use bevy::prelude::{
 };
 use bevy::reflect::Reflect;
 use bevy::time::{Fixed, Time};
+use bevy::utils::{HashMap, Instant};
 use parking_lot::RwLock;
 use tracing::{debug, error, trace, trace_span};
 
@@ -18,6 +19,8 @@ use crate::client::config::ClientConfig;
 use crate::client::connection::ConnectionManager;
 use crate::client::prediction::correction::Correction;
 use crate::client::prediction::diagnostics::PredictionMetrics;
+use crate::client::prediction::inspector::{RollbackInspector, RollbackTrigger};
+use crate::client::prediction::plugin::RollbackMode;
 use crate::client::prediction::predicted_history::ComponentState;
 use crate::client::prediction::resource::PredictionManager;
 use crate::prelude::{ComponentRegistry, PreSpawnedPlayerObject, Tick, TickManager};
@@ -35,7 +38,42 @@ pub struct Rollback {
     /// We use a RwLock because we want to be able to update this value from multiple systems
     /// in parallel.
     pub state: RwLock<RollbackState>,
-    // pub rollback_groups: EntityHashMap<ReplicationGroupId, RollbackState>,
+    /// Predicted entities that had a mismatch during the rollback check.
+    /// Only used with [`RollbackMode::Partial`]
+    #[reflect(ignore)]
+    pub(crate) mismatched: RwLock<EntityHashSet>,
+    /// Predicted entities that are not part of the current partial rollback
+    #[reflect(ignore)]
+    pub(crate) frozen: EntityHashSet,
+    /// Mismatched predicted entities whose rollback was postponed to a later frame because
+    /// the rollback budget was exceeded
+    #[reflect(ignore)]
+    pub(crate) deferred: EntityHashSet,
+}
+
+/// Component that can be added to predicted entities to specify which entities must be rolled back together
+/// when using [`RollbackMode::Partial`].
+///
+/// If any entity of the group has a mismatch, all the entities of the group are rolled back.
+/// Entities without this component are grouped by the [`ReplicationGroup`](crate::prelude::ReplicationGroup)
+/// of their confirmed entity.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+pub struct RollbackGroup(pub u64);
+
+/// Marker component added on predicted entities that are not part of the current partial rollback.
+///
+/// Systems that run in the `FixedMain` schedule can use `Without<RollbackFrozen>` to avoid simulating these entities
+/// during the rollback. In any case, their predicted components are restored to their pre-rollback values
+/// once the rollback is over.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+pub struct RollbackFrozen;
+
+/// Key used to find the rollback group of a predicted entity
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum RollbackGroupKey {
+    User(u64),
+    Replication(u64),
+    Entity(Entity),
 }
 
 /// Resource that will track whether we should do rollback or not
@@ -58,6 +96,7 @@ impl Rollback {
     pub(crate) fn new(state: RollbackState) -> Self {
         Self {
             state: RwLock::new(state),
+            ..Default::default()
         }
     }
 
@@ -87,9 +126,32 @@ impl Rollback {
         }
     }
 
+    /// Returns true if the predicted entity is not part of the current partial rollback
+    pub fn is_frozen(&self, entity: Entity) -> bool {
+        self.frozen.contains(&entity)
+    }
+
     /// Set the rollback state back to non-rollback
     pub(crate) fn set_non_rollback(&self) {
         *self.state.write().deref_mut() = RollbackState::Default;
+        self.mismatched.write().clear();
+    }
+
+    /// Record that the predicted entity had a mismatch, and make sure that the rollback
+    /// starts at `tick` at the latest
+    pub(crate) fn add_mismatch(&self, entity: Entity, tick: Tick) {
+        self.mismatched.write().insert(entity);
+        let mut state = self.state.write();
+        match state.deref_mut() {
+            RollbackState::ShouldRollback { current_tick } => {
+                if tick < *current_tick {
+                    *current_tick = tick;
+                }
+            }
+            RollbackState::Default => {
+                *state = RollbackState::ShouldRollback { current_tick: tick };
+            }
+        }
     }
 
     /// Set the rollback state to `ShouldRollback` with the given tick
@@ -105,6 +167,7 @@ impl Rollback {
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn check_rollback<C: SyncComponent>(
     component_registry: Res<ComponentRegistry>,
+    config: Res<ClientConfig>,
     // TODO: have a way to only get the updates of entities that are predicted?
     tick_manager: Res<TickManager>,
     connection: Res<ConnectionManager>,
@@ -113,6 +176,7 @@ pub(crate) fn check_rollback<C: SyncComponent>(
     // We use Option<> because the predicted component could have been removed while it still exists in Confirmed
     confirmed_query: Query<(Entity, Option<&C>, Ref<Confirmed>)>,
     rollback: Res<Rollback>,
+    inspector: Option<Res<RollbackInspector>>,
 ) {
     // TODO: can just enable bevy spans?
     let _span = trace_span!("client rollback check");
@@ -126,6 +190,8 @@ pub(crate) fn check_rollback<C: SyncComponent>(
         return;
     }
 
+    // with partial rollbacks, we need to find every predicted entity that has a mismatch
+    let partial = config.prediction.rollback_mode == RollbackMode::Partial;
     let current_tick = tick_manager.tick();
     for (confirmed_entity, confirmed_component, confirmed) in confirmed_query.iter() {
         // NOTE: it is not enough to check if we received any ComponentRemoveEvent<C>, ComponentUpdateEvent<C> and ComponentInsertEvent<C>
@@ -175,23 +241,27 @@ pub(crate) fn check_rollback<C: SyncComponent>(
 
         // 3.a We are still not sure if we should do rollback. Compare history against confirmed
         // We rollback if there's no history (newly added predicted entity, or if there is a mismatch)
-        if !rollback.is_rollback() {
+        if partial || !rollback.is_rollback() {
             let history_value = predicted_history.pop_until_tick(tick);
             let predicted_exist = history_value.is_some();
             let confirmed_exist = confirmed_component.is_some();
             let should_rollback = match confirmed_component {
                 // TODO: history-value should not be empty here; should we panic if it is?
                 // confirm does not exist. rollback if history value is not Removed
-                None => history_value.map_or(false, |history_value| {
-                    history_value != ComponentState::Removed
+                None => history_value.as_ref().map_or(false, |history_value| {
+                    *history_value != ComponentState::Removed
                 }),
                 // confirm exist. rollback if history value is different
-                Some(c) => history_value.map_or(true, |history_value| match history_value {
-                    ComponentState::Updated(history_value) => {
-                        component_registry.should_rollback(&history_value, c)
-                    }
-                    ComponentState::Removed => true,
-                }),
+                Some(c) => {
+                    history_value
+                        .as_ref()
+                        .map_or(true, |history_value| match history_value {
+                            ComponentState::Updated(history_value) => {
+                                component_registry.should_rollback(history_value, c)
+                            }
+                            ComponentState::Removed => true,
+                        })
+                }
             };
             if should_rollback {
                 debug!(
@@ -201,7 +271,28 @@ pub(crate) fn check_rollback<C: SyncComponent>(
                    );
                 // we already rolled-back the state for the entity's latest_tick
                 // after this we will start right away with a physics update, so we need to start taking the inputs from the next tick
-                rollback.set_rollback_tick(tick + 1);
+                if partial {
+                    rollback.add_mismatch(p, tick + 1);
+                } else {
+                    rollback.set_rollback_tick(tick + 1);
+                }
+                if let Some(inspector) = inspector.as_ref() {
+                    let predicted = match &history_value {
+                        Some(ComponentState::Updated(value)) => {
+                            component_registry.rollback_debug(value)
+                        }
+                        _ => None,
+                    };
+                    inspector.add_trigger(RollbackTrigger {
+                        entity: p,
+                        component: kind,
+                        predicted,
+                        confirmed: confirmed_component
+                            .and_then(|c| component_registry.rollback_debug(c)),
+                        confirmed_tick: tick,
+                        tick_distance: (current_tick - tick) as u16,
+                    });
+                }
             }
         } else {
             // 3.b We already know we should do rollback (because of another entity/component), start the rollback
@@ -261,6 +352,10 @@ pub(crate) fn prepare_rollback<C: SyncComponent>(
         let Some(p) = confirmed.predicted else {
             continue;
         };
+        // the entity is not part of the partial rollback, keep its current state
+        if rollback.is_frozen(p) {
+            continue;
+        }
 
         // 1. Get the predicted entity, and it's history
         let Ok((predicted_entity, predicted_component, mut predicted_history, mut correction)) =
@@ -488,6 +583,9 @@ pub(crate) fn prepare_rollback_non_networked<C: Component + PartialEq + Clone>(
     // TODO? or is it handled for us?
 
     for (entity, component, mut history) in predicted_query.iter_mut() {
+        if rollback.is_frozen(entity) {
+            continue;
+        }
         // 1. restore the component to the historical value
         match history.pop_until_tick(rollback_tick) {
             None | Some(ComponentState::Removed) => {
@@ -569,6 +667,183 @@ pub(crate) fn prepare_rollback_resource<R: Resource + Clone>(
     history.clear();
 }
 
+/// Select the predicted entities that will be part of the rollback, and enforce the rollback budget.
+///
+/// With [`RollbackMode::Partial`], only the rollback groups that contain a mismatched entity are rolled back.
+/// The other predicted entities are marked with [`RollbackFrozen`] and keep their current state.
+///
+/// If the rollback exceeds [`PredictionConfig::rollback_budget`](crate::prelude::client::PredictionConfig::rollback_budget):
+/// - with [`RollbackMode::Full`], the rollback is skipped
+/// - with [`RollbackMode::Partial`], the groups that don't fit in the budget are deferred to the next frames
+#[allow(clippy::type_complexity)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn select_rollback_entities(
+    mut commands: Commands,
+    config: Res<ClientConfig>,
+    tick_manager: Res<TickManager>,
+    connection: Res<ConnectionManager>,
+    mut rollback: ResMut<Rollback>,
+    mut metrics: ResMut<PredictionMetrics>,
+    predicted_query: Query<(Entity, &Predicted, Option<&RollbackGroup>), Without<Confirmed>>,
+    confirmed_query: Query<&Confirmed>,
+    inspector: Option<Res<RollbackInspector>>,
+) {
+    let partial = config.prediction.rollback_mode == RollbackMode::Partial;
+    let budget = config.prediction.rollback_budget;
+
+    // 0. resume the rollbacks that were deferred during the previous frames
+    if partial && !rollback.deferred.is_empty() {
+        for entity in std::mem::take(&mut rollback.deferred) {
+            let Some(confirmed) = predicted_query
+                .get(entity)
+                .ok()
+                .and_then(|(_, predicted, _)| predicted.confirmed_entity)
+                .and_then(|confirmed_entity| confirmed_query.get(confirmed_entity).ok())
+            else {
+                continue;
+            };
+            rollback.add_mismatch(entity, confirmed.tick + 1);
+        }
+    }
+    let Some(rollback_tick) = rollback.get_rollback_tick() else {
+        return;
+    };
+    let num_rollback_ticks = (tick_manager.tick() + 1 - rollback_tick).max(0) as u32;
+    let mismatched = std::mem::take(rollback.mismatched.get_mut());
+
+    // 1. full rollback: all predicted entities are rolled back
+    if !partial || mismatched.is_empty() {
+        if let Some(budget) = budget {
+            let cost = predicted_query.iter().count() as u32 * num_rollback_ticks;
+            if cost > budget {
+                debug!(
+                    ?cost,
+                    ?budget,
+                    "Skipping rollback because it exceeds the rollback budget"
+                );
+                metrics.skipped_rollbacks += 1;
+                rollback.set_non_rollback();
+                if let Some(inspector) = inspector.as_ref() {
+                    inspector.discard_triggers();
+                }
+            }
+        }
+        return;
+    }
+
+    // 2. partial rollback: find the rollback group of each predicted entity
+    let group_key = |entity: Entity, predicted: &Predicted, group: Option<&RollbackGroup>| {
+        if let Some(group) = group {
+            return RollbackGroupKey::User(group.0);
+        }
+        predicted
+            .confirmed_entity
+            .and_then(|confirmed_entity| {
+                connection
+                    .replication_receiver
+                    .local_entity_to_group
+                    .get(&confirmed_entity)
+            })
+            .map_or(RollbackGroupKey::Entity(entity), |group_id| {
+                RollbackGroupKey::Replication(group_id.0)
+            })
+    };
+    let mut groups: HashMap<RollbackGroupKey, Vec<Entity>> = HashMap::default();
+    for (entity, predicted, group) in predicted_query.iter() {
+        groups
+            .entry(group_key(entity, predicted, group))
+            .or_default()
+            .push(entity);
+    }
+    // iterate through the mismatched groups in a deterministic order
+    let mut mismatched_groups: Vec<RollbackGroupKey> = mismatched
+        .iter()
+        .filter_map(|entity| predicted_query.get(*entity).ok())
+        .map(|(entity, predicted, group)| group_key(entity, predicted, group))
+        .collect();
+    mismatched_groups.sort();
+    mismatched_groups.dedup();
+
+    // 3. select the groups to rollback, within the budget.
+    // We always rollback at least one group so that rollbacks cannot be deferred forever
+    let mut rolled_back = EntityHashSet::default();
+    let mut cost = 0;
+    for key in mismatched_groups {
+        let Some(members) = groups.get(&key) else {
+            continue;
+        };
+        let group_cost = members.len() as u32 * num_rollback_ticks;
+        if budget.is_some_and(|budget| !rolled_back.is_empty() && cost + group_cost > budget) {
+            debug!(
+                ?key,
+                "Deferring the rollback of a group because it exceeds the rollback budget"
+            );
+            metrics.skipped_rollbacks += 1;
+            rollback.deferred.extend(
+                members
+                    .iter()
+                    .filter(|entity| mismatched.contains(*entity))
+                    .copied(),
+            );
+            continue;
+        }
+        cost += group_cost;
+        rolled_back.extend(members.iter().copied());
+    }
+    if rolled_back.is_empty() {
+        return;
+    }
+
+    // 4. freeze the other predicted entities
+    for (entity, _, _) in predicted_query.iter() {
+        if !rolled_back.contains(&entity) {
+            rollback.frozen.insert(entity);
+            commands.entity(entity).insert(RollbackFrozen);
+        }
+    }
+    debug!(
+        rolled_back = ?rolled_back.len(),
+        frozen = ?rollback.frozen.len(),
+        "Partial rollback"
+    );
+}
+
+/// Restore the components of the entities that were frozen during a partial rollback to their
+/// pre-rollback value (the most recent value in their history)
+pub(crate) fn restore_frozen_components<C: Component + PartialEq + Clone>(
+    mut commands: Commands,
+    tick_manager: Res<TickManager>,
+    mut query: Query<(Entity, Option<&mut C>, &PredictionHistory<C>), With<RollbackFrozen>>,
+) {
+    let tick = tick_manager.tick();
+    for (entity, component, history) in query.iter_mut() {
+        match (history.peek_at_tick(tick), component) {
+            (Some(ComponentState::Removed), Some(_)) => {
+                commands.entity(entity).remove::<C>();
+            }
+            (Some(ComponentState::Updated(c)), Some(mut component)) if component.as_ref() != c => {
+                *component = c.clone();
+            }
+            (Some(ComponentState::Updated(c)), None) => {
+                commands.entity(entity).insert(c.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Remove the [`RollbackFrozen`] marker once the partial rollback is over
+pub(crate) fn unfreeze_rollback_entities(mut commands: Commands, mut rollback: ResMut<Rollback>) {
+    if rollback.frozen.is_empty() {
+        return;
+    }
+    for entity in rollback.frozen.drain() {
+        if let Some(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.remove::<RollbackFrozen>();
+        }
+    }
+}
+
 /// Return a fixed time that represents rollbacking `current_fixed_time` by
 /// `num_rollback_ticks` ticks. The returned fixed time's overstep is zero.
 ///
@@ -629,6 +904,7 @@ pub(crate) fn run_rollback(world: &mut World) {
     // Keep track of the generic time resource so it can be restored after the
     // rollback.
     let time_resource = *world.resource::<Time>();
+    let resimulation_start = Instant::now();
 
     // Rollback the fixed time resource in preparation for the rollback.
     let current_fixed_time = *world.resource::<Time<Fixed>>();
@@ -670,6 +946,18 @@ pub(crate) fn run_rollback(world: &mut World) {
     metrics.rollbacks += 1;
     metrics.rollback_ticks += num_rollback_ticks as u32;
 
+    if let Some(mut inspector) = world.get_resource_mut::<RollbackInspector>() {
+        inspector.record(
+            current_tick,
+            current_rollback_tick,
+            num_rollback_ticks as u16,
+            resimulation_start.elapsed(),
+        );
+        if let Some(record) = inspector.last().filter(|_| inspector.emit_events).cloned() {
+            world.send_event(record);
+        }
+    }
+
     // revert the state of Rollback for the next frame
     let rollback = world.get_resource_mut::<Rollback>().unwrap();
     rollback.set_non_rollback();
@@ -879,6 +1167,8 @@ mod integration_tests {
     use std::time::Duration;
 
     use super::test_utils::*;
+    use crate::client::prediction::diagnostics::PredictionMetrics;
+    use crate::prelude::ComponentRegistry;
 
     use crate::prelude::client::*;
     use crate::tests::protocol::*;
@@ -1237,4 +1527,231 @@ mod integration_tests {
             .unwrap()
             .0 = 4.0;
     }
+
+    /// Test that the rollback inspector records what triggered the rollback
+    #[test]
+    fn test_rollback_inspector() {
+        let (mut stepper, confirmed, predicted) = setup(false);
+        stepper
+            .client_app
+            .insert_resource(RollbackInspector::new(8, true))
+            .add_event::<RollbackRecord>();
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ComponentRegistry>()
+            .set_rollback_debug::<ComponentSyncModeFull>();
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .insert(ComponentSyncModeFull(0.0));
+        for _ in 0..3 {
+            stepper.frame_step();
+        }
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<RollbackInspector>()
+            .clear();
+
+        let tick = stepper.client_tick();
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(confirmed)
+            .unwrap()
+            .0 = 1.0;
+        received_confirmed_update(&mut stepper, confirmed, tick - 2);
+        stepper.frame_step();
+
+        let inspector = stepper.client_app.world().resource::<RollbackInspector>();
+        let record = inspector.last().unwrap();
+        assert_eq!(record.rollback_tick, tick - 1);
+        assert_eq!(record.resimulated_ticks, 2);
+        assert_eq!(
+            record.triggers,
+            vec![RollbackTrigger {
+                entity: predicted,
+                component: std::any::type_name::<ComponentSyncModeFull>(),
+                predicted: Some("ComponentSyncModeFull(0.0)".to_string()),
+                confirmed: Some("ComponentSyncModeFull(1.0)".to_string()),
+                confirmed_tick: tick - 2,
+                tick_distance: 2,
+            }]
+        );
+        assert_eq!(
+            inspector.triggers_per_component(),
+            vec![(std::any::type_name::<ComponentSyncModeFull>(), 1)]
+        );
+        assert!(!stepper
+            .client_app
+            .world()
+            .resource::<Events<RollbackRecord>>()
+            .is_empty());
+    }
+
+    fn spawn_predicted(stepper: &mut BevyStepper) -> (Entity, Entity) {
+        let tick = stepper.client_tick();
+        let confirmed = stepper
+            .client_app
+            .world_mut()
+            .spawn(Confirmed {
+                tick,
+                ..Default::default()
+            })
+            .id();
+        let predicted = stepper
+            .client_app
+            .world_mut()
+            .spawn(Predicted {
+                confirmed_entity: Some(confirmed),
+            })
+            .id();
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .get_mut::<Confirmed>()
+            .unwrap()
+            .predicted = Some(predicted);
+        (confirmed, predicted)
+    }
+
+    fn value(stepper: &BevyStepper, entity: Entity) -> f32 {
+        stepper
+            .client_app
+            .world()
+            .get::<ComponentSyncModeFull>(entity)
+            .unwrap()
+            .0
+    }
+
+    /// Test that with `RollbackMode::Partial`:
+    /// - only the entity that has a mismatch is rolled back
+    /// - the other predicted entities keep their current state
+    /// - rollback groups that exceed the rollback budget are deferred to the next frame
+    #[test]
+    fn test_partial_rollback() {
+        let (mut stepper, confirmed_a, predicted_a) = setup(true);
+        let (confirmed_b, predicted_b) = spawn_predicted(&mut stepper);
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .prediction = PredictionConfig::default().with_rollback_mode(RollbackMode::Partial);
+        for confirmed in [confirmed_a, confirmed_b] {
+            stepper
+                .client_app
+                .world_mut()
+                .entity_mut(confirmed)
+                .insert(ComponentSyncModeFull(0.5));
+        }
+        stepper.frame_step();
+        stepper.frame_step();
+
+        // 1. mismatch on entity A only
+        let value_b = value(&stepper, predicted_b);
+        let tick = stepper.client_tick();
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(confirmed_a)
+            .unwrap()
+            .0 = 10.5;
+        received_confirmed_update(&mut stepper, confirmed_a, tick - 2);
+        stepper.frame_step();
+        let new_ticks = (stepper.client_tick() - tick) as f32;
+        // A was rolled back to the confirmed state
+        assert_eq!(value(&stepper, predicted_a), 10.5 + 2.0 + new_ticks);
+        // B was frozen during the rollback
+        assert_eq!(value(&stepper, predicted_b), value_b + new_ticks);
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<RollbackFrozen>(predicted_b)
+            .is_none());
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<Rollback>()
+            .frozen
+            .is_empty());
+
+        // 2. mismatch on both entities, but the budget only allows rolling back one entity
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .prediction
+            .rollback_budget = Some(1);
+        let value_b = value(&stepper, predicted_b);
+        let tick = stepper.client_tick();
+        for (confirmed, v) in [(confirmed_a, 20.5), (confirmed_b, 30.5)] {
+            stepper
+                .client_app
+                .world_mut()
+                .get_mut::<ComponentSyncModeFull>(confirmed)
+                .unwrap()
+                .0 = v;
+            received_confirmed_update(&mut stepper, confirmed, tick - 1);
+        }
+        stepper.frame_step();
+        let new_ticks = (stepper.client_tick() - tick) as f32;
+        assert_eq!(value(&stepper, predicted_a), 20.5 + 1.0 + new_ticks);
+        // the rollback of B was deferred
+        assert_eq!(value(&stepper, predicted_b), value_b + new_ticks);
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<PredictionMetrics>()
+                .skipped_rollbacks,
+            1
+        );
+
+        // B is rolled back on the next frame
+        stepper.frame_step();
+        let new_ticks = (stepper.client_tick() - tick) as f32;
+        assert_eq!(value(&stepper, predicted_b), 30.5 + 1.0 + new_ticks);
+    }
+
+    /// Test that a full rollback that exceeds the rollback budget is skipped
+    #[test]
+    fn test_rollback_budget() {
+        let (mut stepper, confirmed, predicted) = setup(true);
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .prediction = PredictionConfig::default().with_rollback_budget(1);
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .insert(ComponentSyncModeFull(0.5));
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let value_before = value(&stepper, predicted);
+        let tick = stepper.client_tick();
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(confirmed)
+            .unwrap()
+            .0 = 10.5;
+        received_confirmed_update(&mut stepper, confirmed, tick - 2);
+        stepper.frame_step();
+        let new_ticks = (stepper.client_tick() - tick) as f32;
+        assert_eq!(value(&stepper, predicted), value_before + new_ticks);
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<PredictionMetrics>()
+                .skipped_rollbacks,
+            1
+        );
+    }
 }
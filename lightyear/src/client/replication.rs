@@ -78,6 +78,9 @@ pub(crate) mod receive {
 pub(crate) mod send {
     use super::*;
     use bevy::ecs::component::ComponentTicks;
+    use bevy::ecs::event::ManualEventReader;
+    use bevy::ecs::removal_detection::RemovedComponentEntity;
+    use bevy::utils::HashMap;
 
     use crate::connection::client::ClientConnection;
 
@@ -85,10 +88,10 @@ pub(crate) mod send {
 
     use crate::prelude::{
         client::{is_connected, is_synced},
-        is_host_server, ComponentRegistry, DisabledComponent, ReplicateHierarchy, Replicated,
-        ReplicationGroup, TargetEntity, Tick, TickManager, TimeManager,
+        is_host_server, ChannelDirection, ComponentRegistry, DisabledComponent, ReplicateHierarchy,
+        Replicated, ReplicationGroup, TargetEntity, Tick, TickManager, TimeManager,
     };
-    use crate::protocol::component::ComponentKind;
+    use crate::protocol::component::{ComponentKind, ComponentNetId};
 
     use crate::shared::replication::components::{
         InitialReplicated, Replicating, ReplicationGroupId,
@@ -144,6 +147,9 @@ pub(crate) mod send {
                         replicate
                             .in_set(InternalReplicationSet::<ClientMarker>::BufferEntityUpdates)
                             .in_set(InternalReplicationSet::<ClientMarker>::BufferComponentUpdates),
+                        send_reflect_component_removed.in_set(
+                            InternalReplicationSet::<ClientMarker>::BufferDespawnsAndRemovals,
+                        ),
                         buffer_replication_messages
                             .in_set(InternalReplicationSet::<ClientMarker>::AfterBuffer),
                         add_replicated_component_host_server.run_if(is_host_server),
@@ -629,6 +635,56 @@ pub(crate) mod send {
         }
     }
 
+    /// Send the removals of the components registered with
+    /// [`register_component_reflect`](crate::prelude::AppComponentExt::register_component_reflect).
+    ///
+    /// The component types are only known at runtime, so the removals are read from the World
+    /// with the [`ComponentId`](bevy::ecs::component::ComponentId) of each component.
+    pub(crate) fn send_reflect_component_removed(
+        world: &mut World,
+        mut readers: Local<HashMap<ComponentNetId, ManualEventReader<RemovedComponentEntity>>>,
+    ) {
+        world.resource_scope(|world, registry: Mut<ComponentRegistry>| {
+            world.resource_scope(|world, mut sender: Mut<ConnectionManager>| {
+                for (kind, metadata) in
+                    registry.reflect_replication(ChannelDirection::ClientToServer)
+                {
+                    let Some(events) = world.removed_components().get(metadata.component_id) else {
+                        continue;
+                    };
+                    for entity in readers.entry(kind).or_default().read(events) {
+                        let entity = Entity::from(entity.clone());
+                        // only remove the component for entities that are being actively replicated
+                        let Some(entity_ref) = world.get_entity(entity) else {
+                            continue;
+                        };
+                        let (Some(group), true, true) = (
+                            entity_ref.get::<ReplicationGroup>(),
+                            entity_ref.contains::<Replicating>(),
+                            entity_ref.contains::<ReplicateToServer>(),
+                        ) else {
+                            continue;
+                        };
+                        // do not replicate components that are disabled
+                        if entity_ref.contains_id(metadata.disabled_id) {
+                            continue;
+                        }
+                        let group_id = group.group_id(Some(entity));
+                        // convert the entity to a network entity (possibly mapped)
+                        let entity = sender
+                            .replication_receiver
+                            .remote_entity_map
+                            .to_remote(entity);
+                        trace!(?entity, ?kind, "Sending RemoveComponent");
+                        sender
+                            .replication_sender
+                            .prepare_component_remove(entity, group_id, kind);
+                    }
+                }
+            });
+        });
+    }
+
     pub(crate) fn register_replicate_component_send<C: Component>(app: &mut App) {
         // TODO: what if we remove and add within one replication_interval?
         app.observe(send_component_removed::<C>);
@@ -5,7 +5,7 @@ use bevy::utils::Duration;
 use chrono::Duration as ChronoDuration;
 use tracing::{debug, trace};
 
-use crate::client::interpolation::plugin::InterpolationDelay;
+use crate::client::interpolation::plugin::{AdaptiveInterpolationDelay, InterpolationDelay};
 use crate::packet::packet::PacketId;
 use crate::prelude::client::PredictionConfig;
 use crate::shared::ping::manager::PingManager;
@@ -98,6 +98,11 @@ pub struct SyncManager {
     server_time_estimate: WrappedTime,
     pub(crate) interpolation_time: WrappedTime,
     interpolation_speed_ratio: f32,
+    /// Current interpolation delay, when using an [`AdaptiveInterpolationDelay`]
+    adaptive_interpolation_delay: Option<Duration>,
+    /// Largest recent duration between two packets received from the server.
+    /// It decreases over time so that the interpolation delay can shrink again.
+    max_server_update_gap: Duration,
 
     // ticks
     // TODO: see if this is correct; should we instead attach the tick on every update message?
@@ -124,6 +129,8 @@ impl SyncManager {
             server_time_estimate: WrappedTime::default(),
             interpolation_time: WrappedTime::default(),
             interpolation_speed_ratio: 1.0,
+            adaptive_interpolation_delay: None,
+            max_server_update_gap: Duration::default(),
             // server tick
             latest_received_server_tick: None,
             duration_since_latest_received_server_tick: Duration::default(),
@@ -149,6 +156,15 @@ impl SyncManager {
         self.duration_since_latest_received_server_tick += time_manager.delta();
         self.server_time_estimate += time_manager.delta();
         self.interpolation_time += time_manager.delta().mul_f32(self.interpolation_speed_ratio);
+        if let Some(adaptive) = interpolation_delay.adaptive {
+            self.update_adaptive_interpolation_delay(
+                &adaptive,
+                interpolation_delay.min_delay,
+                server_send_interval,
+                time_manager.delta(),
+                ping_manager.jitter(),
+            );
+        }
 
         // check if we are ready to finalize the handshake
         if !self.synced && ping_manager.sync_stats.len() >= self.config.handshake_pings as usize {
@@ -339,13 +355,63 @@ impl SyncManager {
         // let objective_time = self.server_time_estimate();
         // how much we want interpolation time to be behind the latest received server tick?
         // TODO: use a specified config margin + add std of time_between_server_updates?
-        let objective_delta =
-            chrono::Duration::from_std(interpolation_delay.to_duration(server_send_interval))
-                .unwrap();
+        let objective_delta = chrono::Duration::from_std(
+            self.interpolation_delay(interpolation_delay, server_send_interval),
+        )
+        .unwrap();
         // info!("objective_delta: {:?}", objective_delta);
         self.server_time_estimate() - objective_delta
     }
 
+    /// How much behind the latest server update the interpolation timeline should be
+    pub(crate) fn interpolation_delay(
+        &self,
+        interpolation_delay: &InterpolationDelay,
+        server_send_interval: Duration,
+    ) -> Duration {
+        self.adaptive_interpolation_delay
+            .unwrap_or_else(|| interpolation_delay.to_duration(server_send_interval))
+    }
+
+    /// Keep track of the duration between two packets received from the server
+    pub(crate) fn record_server_update_gap(&mut self) {
+        self.max_server_update_gap = self
+            .max_server_update_gap
+            .max(self.duration_since_latest_received_server_tick);
+    }
+
+    /// Move the adaptive interpolation delay towards its objective: `max_server_update_gap + jitter_multiple * jitter`.
+    ///
+    /// The delay is increased by at most what the interpolation timeline can absorb by slowing down
+    /// (`delta * (speedup_factor - 1)`), so that the interpolation time never jumps.
+    fn update_adaptive_interpolation_delay(
+        &mut self,
+        adaptive: &AdaptiveInterpolationDelay,
+        min_delay: Duration,
+        server_send_interval: Duration,
+        delta: Duration,
+        jitter: Duration,
+    ) {
+        let shrink = delta.mul_f32(adaptive.shrink_rate);
+        self.max_server_update_gap = self.max_server_update_gap.saturating_sub(shrink);
+        let objective = (self.max_server_update_gap.max(server_send_interval)
+            + jitter.mul_f32(adaptive.jitter_multiple))
+        .clamp(min_delay, adaptive.max_delay.max(min_delay));
+        let max_step = delta.mul_f32((self.config.speedup_factor - 1.0).max(0.0));
+        let new_delay = match self.adaptive_interpolation_delay {
+            // start directly with the objective, since the interpolation time is set when we sync
+            None => objective,
+            // if the interpolation timeline cannot slow down, there is no point in increasing the delay gradually
+            Some(current) if objective > current && max_step.is_zero() => objective,
+            Some(current) if objective > current => objective.min(current + max_step),
+            Some(current) => objective.max(current.saturating_sub(shrink)),
+        };
+        if self.adaptive_interpolation_delay != Some(new_delay) {
+            trace!(?new_delay, ?objective, ?jitter, max_update_gap = ?self.max_server_update_gap, "update adaptive interpolation delay");
+        }
+        self.adaptive_interpolation_delay = Some(new_delay);
+    }
+
     pub(crate) fn interpolation_tick(&self, tick_manager: &TickManager) -> Tick {
         self.interpolation_time
             .to_tick(tick_manager.config.tick_duration)
@@ -653,4 +719,56 @@ mod tests {
             &ComponentSyncModeFull(1.0)
         );
     }
+
+    /// The adaptive interpolation delay grows with the update gaps and the jitter, and shrinks slowly
+    #[test]
+    fn test_adaptive_interpolation_delay() {
+        let mut sync_manager = SyncManager::new(SyncConfig::default(), PredictionConfig::default());
+        let adaptive = AdaptiveInterpolationDelay {
+            jitter_multiple: 2.0,
+            max_delay: Duration::from_millis(300),
+            shrink_rate: 0.1,
+        };
+        let send_interval = Duration::from_millis(50);
+        let delta = Duration::from_millis(100);
+        let update = |sync_manager: &mut SyncManager, jitter: Duration| {
+            sync_manager.update_adaptive_interpolation_delay(
+                &adaptive,
+                Duration::default(),
+                send_interval,
+                delta,
+                jitter,
+            );
+            sync_manager.interpolation_delay(&InterpolationDelay::default(), send_interval)
+        };
+        let assert_delay = |delay: Duration, expected_millis: u64| {
+            assert!(
+                (delay.as_secs_f64() * 1000.0 - expected_millis as f64).abs() < 0.01,
+                "{delay:?} != {expected_millis}ms"
+            );
+        };
+
+        // good connection: send_interval + 2 * jitter
+        assert_delay(update(&mut sync_manager, Duration::from_millis(5)), 60);
+
+        // the server updates stop for 200ms: the delay grows by at most 5ms (delta * (speedup_factor - 1)) per update
+        sync_manager.duration_since_latest_received_server_tick = Duration::from_millis(200);
+        sync_manager.record_server_update_gap();
+        assert_delay(update(&mut sync_manager, Duration::from_millis(5)), 65);
+        for _ in 0..10 {
+            update(&mut sync_manager, Duration::from_millis(5));
+        }
+        // the gap decreases by 10ms per update
+        assert_delay(
+            update(&mut sync_manager, Duration::from_millis(5)),
+            200 - 120 + 10,
+        );
+
+        // the delay is capped
+        assert_delay(update(&mut sync_manager, Duration::from_millis(200)), 95);
+        for _ in 0..50 {
+            update(&mut sync_manager, Duration::from_millis(200));
+        }
+        assert_delay(update(&mut sync_manager, Duration::from_millis(200)), 300);
+    }
 }
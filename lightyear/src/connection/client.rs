@@ -91,6 +91,8 @@ pub struct ClientConnection {
 pub enum DisconnectReason {
     Transport(crate::transport::error::Error),
     Netcode(super::netcode::ClientState),
+    /// The server denied the connection during the handshake
+    Denied(super::server::DeniedReason),
     #[cfg(all(feature = "steam", not(target_family = "wasm")))]
     Steam(steamworks::networking_types::NetConnectionEnd),
 }
@@ -192,8 +192,13 @@ impl Bytes for DeniedReason {
             DeniedReason::InvalidToken => {
                 writer.write_u8(5)?;
             }
-            DeniedReason::Custom(reason) => {
-                writer.write_u8(6)?;
+            DeniedReason::Custom(reason) | DeniedReason::ProtocolMismatch(reason) => {
+                let variant = if matches!(self, DeniedReason::Custom(_)) {
+                    6
+                } else {
+                    7
+                };
+                writer.write_u8(variant)?;
                 // the reason cannot exceed u8::MAX in size
                 if reason.len() > u8::MAX as usize {
                     return Err(io::Error::new(
@@ -228,13 +233,17 @@ impl Bytes for DeniedReason {
             Ok(DeniedReason::TokenAlreadyUsed)
         } else if variant == 5 {
             Ok(DeniedReason::InvalidToken)
-        } else if variant == 6 {
+        } else if variant == 6 || variant == 7 {
             let len = reader.read_u8()? as usize;
             let mut string_buf = vec![0; len];
             reader.read_exact(&mut string_buf)?;
             let reason_str = String::from_utf8(string_buf)
                 .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid denied reason"))?;
-            Ok(DeniedReason::Custom(reason_str))
+            if variant == 6 {
+                Ok(DeniedReason::Custom(reason_str))
+            } else {
+                Ok(DeniedReason::ProtocolMismatch(reason_str))
+            }
         } else {
             Err(io::Error::new(
                 io::ErrorKind::InvalidData,
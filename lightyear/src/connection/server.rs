@@ -28,6 +28,9 @@ pub enum DeniedReason {
     TokenAlreadyUsed,
     InvalidToken,
     Custom(String),
+    /// The client's protocol does not match the server's protocol.
+    /// Contains a description of the first entry of the [`ProtocolFingerprint`](crate::protocol::fingerprint::ProtocolFingerprint) that differs
+    ProtocolMismatch(String),
 }
 
 /// Trait for handling connection requests from clients.
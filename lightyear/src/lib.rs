@@ -181,7 +181,7 @@ pub(crate) mod _internal {
 
 /// Prelude containing commonly used types
 pub mod prelude {
-    pub use lightyear_macros::Channel;
+    pub use lightyear_macros::{BitSerialize, Channel};
     pub use serde::{Deserialize, Serialize};
 
     pub use crate::channel::builder::{
@@ -197,26 +197,37 @@ pub mod prelude {
     pub use crate::packet::error::PacketError;
     pub use crate::packet::message::Message;
     pub use crate::protocol::channel::{AppChannelExt, ChannelKind, ChannelRegistry};
-    pub use crate::protocol::component::{AppComponentExt, ComponentRegistry, Linear};
+    pub use crate::protocol::component::{
+        AppComponentExt, ComponentRegistry, Linear, ReflectComponentRegistration,
+    };
     pub use crate::protocol::message::{AppMessageExt, MessageRegistry};
-    pub use crate::protocol::serialize::AppSerializeExt;
+    pub use crate::protocol::registry::ProtocolVersion;
+    pub use crate::protocol::schema::ProtocolSchema;
+    pub use crate::protocol::serialize::{AppSerializeExt, SerializationBackend};
+    pub use crate::serialize::bits::BitSerialize;
     pub use crate::shared::config::{Mode, SharedConfig};
     #[cfg(feature = "leafwing")]
     pub use crate::shared::input::leafwing::LeafwingInputPlugin;
     pub use crate::shared::input::native::InputPlugin;
+    pub use crate::shared::p2p::{
+        ChecksumFn, P2PConfig, P2PPlugin, P2PSession, PeerDesyncEvent, PeerInputEvent,
+        SessionDesyncEvent,
+    };
     pub use crate::shared::ping::manager::PingConfig;
     pub use crate::shared::plugin::{NetworkIdentity, SharedPlugin};
     pub use crate::shared::replication::authority::HasAuthority;
+    pub use crate::shared::replication::checksum::{ChecksumConfig, DesyncDetected};
     pub use crate::shared::replication::components::{
         DeltaCompression, DisabledComponent, NetworkRelevanceMode, OverrideTargetComponent,
         PrePredicted, ReplicateHierarchy, ReplicateOnceComponent, Replicated, Replicating,
-        ReplicationGroup, ReplicationTarget, ShouldBePredicted, TargetEntity,
+        ReplicationFrequency, ReplicationGroup, ReplicationTarget, ShouldBePredicted, TargetEntity,
     };
     pub use crate::shared::replication::entity_map::RemoteEntityMap;
     pub use crate::shared::replication::hierarchy::ParentSync;
     pub use crate::shared::replication::network_target::NetworkTarget;
     pub use crate::shared::replication::plugin::ReplicationConfig;
     pub use crate::shared::replication::plugin::SendUpdatesMode;
+    pub use crate::shared::replication::reflect::ReflectReplicationCommandsExt;
     pub use crate::shared::replication::resources::{
         ReplicateResourceExt, ReplicateResourceMetadata, StopReplicateResourceExt,
     };
@@ -269,20 +280,31 @@ pub mod prelude {
         pub use crate::client::input::native::{InputConfig, InputManager};
         pub use crate::client::interpolation::interpolation_history::ConfirmedHistory;
         pub use crate::client::interpolation::plugin::{
-            InterpolationConfig, InterpolationDelay, InterpolationSet,
+            AdaptiveInterpolationDelay, InterpolationConfig, InterpolationDelay, InterpolationSet,
         };
         pub use crate::client::interpolation::{
-            InterpolateStatus, Interpolated, VisualInterpolateStatus, VisualInterpolationPlugin,
+            CurveFn, CurveSamples, ExtrapolateStatus, ExtrapolationConfig, ExtrapolationFn,
+            HermiteFn, InterpolateStatus, Interpolated, VisualInterpolateStatus,
+            VisualInterpolationPlugin,
         };
         pub use crate::client::io::config::ClientTransport;
         pub use crate::client::io::Io;
         pub use crate::client::networking::{ClientCommands, NetworkingState};
         pub use crate::client::plugin::ClientPlugins;
-        pub use crate::client::prediction::correction::Correction;
+        pub use crate::client::prediction::correction::{
+            Correction, CorrectionEasing, CorrectionErrorFn, CorrectionPolicy,
+        };
         pub use crate::client::prediction::despawn::PredictionDespawnCommandsExt;
+        pub use crate::client::prediction::inspector::{
+            RollbackInspector, RollbackInspectorPlugin, RollbackRecord, RollbackTrigger,
+        };
         pub use crate::client::prediction::plugin::is_in_rollback;
-        pub use crate::client::prediction::plugin::{PredictionConfig, PredictionSet};
-        pub use crate::client::prediction::rollback::{Rollback, RollbackState};
+        pub use crate::client::prediction::plugin::{
+            PredictionConfig, PredictionSet, RollbackMode,
+        };
+        pub use crate::client::prediction::rollback::{
+            Rollback, RollbackFrozen, RollbackGroup, RollbackState,
+        };
         pub use crate::client::prediction::Predicted;
         pub use crate::client::replication::commands::DespawnReplicationCommandExt;
         pub use crate::client::replication::send::{Replicate, ReplicateToServer};
@@ -313,10 +335,19 @@ pub mod prelude {
         };
         pub use crate::server::io::config::ServerTransport;
         pub use crate::server::io::Io;
+        pub use crate::server::lag_compensation::{
+            AppLagCompensationExt, LagCompensation, LagCompensationConfig, LagCompensationHistory,
+            LagCompensationPlugin, LagCompensationSet, LagCompensationTime,
+        };
         pub use crate::server::networking::{NetworkingState, ServerCommands};
         pub use crate::server::plugin::ServerPlugins;
+        pub use crate::server::priority::ReplicationPriorityFn;
         pub use crate::server::relevance::immediate::RelevanceManager;
         pub use crate::server::relevance::room::{RoomId, RoomManager};
+        pub use crate::server::relevance::spatial::{
+            GridDimension, RelevanceViewer, SpatialGrid, SpatialPosition, SpatialRelevanceConfig,
+            SpatialRelevancePlugin,
+        };
         pub use crate::server::replication::commands::AuthorityCommandExt;
         pub use crate::server::replication::commands::DespawnReplicationCommandExt;
         pub use crate::server::replication::{
@@ -192,8 +192,12 @@ impl PacketHeaderManager {
 
     /// Process the header of a received packet (update ack metadata)
     ///
-    /// Returns the list of packets that have been newly acked by the remote
-    pub(crate) fn process_recv_packet_header(&mut self, header: &PacketHeader) -> Vec<PacketId> {
+    /// Returns the list of packets that have been newly acked by the remote,
+    /// along with the time at which they were sent
+    pub(crate) fn process_recv_packet_header(
+        &mut self,
+        header: &PacketHeader,
+    ) -> Vec<(PacketId, WrappedTime)> {
         // update the receive buffer
         self.stats_manager.received_packet();
         self.recv_buffer.recv_packet(header.packet_id);
@@ -222,17 +226,22 @@ impl PacketHeaderManager {
     /// when we receive confirmation that packet_id was delivered
     ///
     /// Also potentially notify the channels/etc. that the packet was delivered.
-    fn update_sent_packets_not_acked(&mut self, packet_id: &PacketId) -> Option<PacketId> {
-        if self.sent_packets_not_acked.contains_key(packet_id) {
-            // TODO: make this non-blocking, but keep trying until it works?
-            // notify that one of the packets we sent got acked
-            // TODO: important to compute RTT
-            // self.ack_notification_sender.send(*packet_id)?;
-
-            self.sent_packets_not_acked.remove(packet_id);
-            return Some(*packet_id);
-        }
-        None
+    fn update_sent_packets_not_acked(
+        &mut self,
+        packet_id: &PacketId,
+    ) -> Option<(PacketId, WrappedTime)> {
+        // TODO: make this non-blocking, but keep trying until it works?
+        // notify that one of the packets we sent got acked
+        // TODO: important to compute RTT
+        // self.ack_notification_sender.send(*packet_id)?;
+        self.sent_packets_not_acked
+            .remove(packet_id)
+            .map(|sent_time| (*packet_id, sent_time))
+    }
+
+    /// Latest time at which the header manager was updated
+    pub(crate) fn current_time(&self) -> WrappedTime {
+        self.current_time
     }
 
     /// Prepare the header of the next packet to send
@@ -2,21 +2,22 @@ use byteorder::ReadBytesExt;
 use bytes::Bytes;
 use crossbeam_channel::{Receiver, Sender};
 use std::collections::{HashMap, VecDeque};
-use tracing::trace;
 #[cfg(feature = "trace")]
 use tracing::{instrument, Level};
+use tracing::{trace, warn};
 
 use crate::channel::builder::ChannelContainer;
 use crate::channel::receivers::ChannelReceive;
-use crate::channel::senders::ChannelSend;
-#[cfg(feature = "trace")]
-use crate::channel::stats::send::ChannelSendStats;
+use crate::channel::senders::{ChannelSend, CoalescingKey};
+use crate::channel::stats::ChannelStats;
+use crate::connection::netcode::MAX_PACKET_SIZE;
 use crate::packet::error::PacketError;
 use crate::packet::header::PacketHeader;
 use crate::packet::message::{
     FragmentData, MessageAck, MessageId, ReceiveMessage, SendMessage, SingleData,
 };
-use crate::packet::packet::PacketId;
+use crate::packet::mtu::{MtuConfig, MtuProber};
+use crate::packet::packet::{fragment_size, PacketId};
 use crate::packet::packet_builder::{PacketBuilder, Payload, RecvPayload};
 use crate::packet::packet_type::PacketType;
 use crate::packet::priority_manager::{PriorityConfig, PriorityManager};
@@ -50,6 +51,19 @@ pub struct MessageManager {
     /// reliable senders can stop trying to send a message that has already been received
     packet_to_message_ack_map: HashMap<PacketId, Vec<(ChannelKind, MessageAck)>>,
     nack_senders: Vec<Sender<MessageId>>,
+    /// Discovers the maximum packet size that can reach the remote peer, if MTU discovery is enabled
+    mtu_prober: Option<MtuProber>,
+    /// Labels of the metrics of each channel, built once so that recording the metrics doesn't allocate
+    #[cfg(feature = "metrics")]
+    channel_metrics: HashMap<ChannelKind, ChannelMetrics>,
+}
+
+/// Labels of the metrics of a channel, and the send stats at the time the metrics were last recorded
+#[cfg(feature = "metrics")]
+#[derive(Debug)]
+struct ChannelMetrics {
+    labels: Vec<metrics::Label>,
+    last_send: crate::channel::stats::send::ChannelSendStats,
 }
 
 impl MessageManager {
@@ -57,14 +71,64 @@ impl MessageManager {
         channel_registry: &ChannelRegistry,
         nack_rtt_multiple: f32,
         priority_config: PriorityConfig,
+        mtu_config: MtuConfig,
     ) -> Self {
-        Self {
-            packet_manager: PacketBuilder::new(nack_rtt_multiple),
+        let mut message_manager = Self {
+            packet_manager: PacketBuilder::new(nack_rtt_multiple, MAX_PACKET_SIZE),
             priority_manager: PriorityManager::new(priority_config),
             channels: channel_registry.channels(),
             channel_registry: channel_registry.clone(),
             packet_to_message_ack_map: HashMap::new(),
             nack_senders: vec![],
+            mtu_prober: MtuProber::new(&mtu_config),
+            #[cfg(feature = "metrics")]
+            channel_metrics: HashMap::new(),
+        };
+        message_manager.set_max_packet_size(mtu_config.initial_packet_size());
+        #[cfg(feature = "metrics")]
+        message_manager.set_metrics_connection("unknown");
+        message_manager
+    }
+
+    /// Set the value of the `connection` label of the channel metrics, to tell the connections apart
+    #[cfg(feature = "metrics")]
+    pub(crate) fn set_metrics_connection(&mut self, connection: &str) {
+        let connection = metrics::SharedString::from_shared(std::sync::Arc::from(connection));
+        self.channel_metrics = self
+            .channels
+            .keys()
+            .map(|kind| {
+                let name = self.channel_registry.name(kind).unwrap_or("unknown");
+                let labels = vec![
+                    metrics::Label::new(
+                        "channel",
+                        metrics::SharedString::from_shared(std::sync::Arc::from(name)),
+                    ),
+                    metrics::Label::new("connection", connection.clone()),
+                ];
+                (
+                    *kind,
+                    ChannelMetrics {
+                        labels,
+                        last_send: Default::default(),
+                    },
+                )
+            })
+            .collect();
+    }
+
+    /// Maximum number of bytes in the packets sent on this connection
+    pub fn max_packet_size(&self) -> usize {
+        self.packet_manager.max_packet_size()
+    }
+
+    /// Update the maximum size of the packets sent on this connection, and the size of the
+    /// fragments for messages that are too big to fit in a single packet
+    pub(crate) fn set_max_packet_size(&mut self, max_packet_size: usize) {
+        self.packet_manager.set_max_packet_size(max_packet_size);
+        let fragment_size = fragment_size(self.packet_manager.max_packet_size());
+        for channel in self.channels.values_mut() {
+            channel.sender.set_fragment_size(fragment_size);
         }
     }
 
@@ -86,8 +150,14 @@ impl MessageManager {
             .packet_manager
             .header_manager
             .update(time_manager, ping_manager);
+        if let Some(mtu_prober) = &mut self.mtu_prober {
+            mtu_prober.update(time_manager.delta());
+        }
         // notify that some messages have been lost
         for lost_packet in lost_packets {
+            if let Some(mtu_prober) = &mut self.mtu_prober {
+                mtu_prober.receive_nack(lost_packet);
+            }
             if let Some(message_map) = self.packet_to_message_ack_map.remove(&lost_packet) {
                 for (channel_kind, message_ack) in message_map {
                     let channel = self
@@ -148,6 +218,25 @@ impl MessageManager {
         Ok(channel.sender.buffer_send(message, priority)?)
     }
 
+    /// Buffer a message to be sent on this connection; on unreliable channels, the message replaces
+    /// any message with the same [`CoalescingKey`] that has not been sent yet.
+    ///
+    /// Returns the message id associated with the message, if there is one
+    pub(crate) fn buffer_send_coalesced(
+        &mut self,
+        message: Bytes,
+        channel_kind: ChannelKind,
+        key: CoalescingKey,
+    ) -> Result<Option<MessageId>, PacketError> {
+        let channel = self
+            .channels
+            .get_mut(&channel_kind)
+            .ok_or(PacketError::ChannelNotFound)?;
+        Ok(channel
+            .sender
+            .buffer_send_coalesced(message, DEFAULT_MESSAGE_PRIORITY, key)?)
+    }
+
     /// Prepare buckets from the internal send buffers, and return the bytes to send
     // TODO: maybe pass TickManager instead of Tick? Find a more elegant way to pass extra data that might not be used?
     //  (ticks are not purely necessary without client prediction)
@@ -164,7 +253,15 @@ impl MessageManager {
                 .channel_registry
                 .get_net_from_kind(channel_kind)
                 .ok_or(PacketError::ChannelNotFound)?;
+            channel
+                .stats
+                .send
+                .set_queue_depth(channel.sender.num_buffered_messages());
             let (single_data, fragment_data) = channel.sender.send_packet();
+            channel
+                .stats
+                .send
+                .set_messages_resent(channel.sender.num_resent_messages());
 
             if !single_data.is_empty() || !fragment_data.is_empty() {
                 trace!(?channel_id, "send message with channel_id");
@@ -174,46 +271,54 @@ impl MessageManager {
         }
         // return early if there are no messages to send
         if !has_data_to_send {
-            return Ok(vec![]);
+            let mut bytes = vec![];
+            self.send_mtu_probe(current_tick, &mut bytes)?;
+            return Ok(bytes);
         }
 
+        // keep track of how many messages each channel wanted to send, to know how many
+        // were dropped by the priority filter
+        let mut num_messages_to_send: HashMap<ChannelId, usize> = data_to_send
+            .iter()
+            .map(|(channel_id, (single, fragment))| (*channel_id, single.len() + fragment.len()))
+            .collect();
+
         // priority manager: get the list of messages we can send according to the rate limiter
         //  (the other messages are stored in an internal buffer)
         let (single_data, fragment_data, num_bytes_added_to_limiter) = self
             .priority_manager
             .priority_filter(data_to_send, &self.channel_registry, current_tick);
 
-        #[cfg(feature = "trace")]
-        {
-            // NOTE: we don't know the actual exact amount of bytes sent (because we don't take into account the ids, etc.),
-            // but we could during build_packet?
-            for (channel_id, data) in &single_data {
-                let channel_stats = &mut self
-                    .channels
-                    .get_mut(
-                        self.channel_registry
-                            .get_kind_from_net_id(*channel_id)
-                            .ok_or(PacketError::ChannelNotFound)?,
-                    )
-                    .ok_or(PacketError::ChannelNotFound)?
-                    .sender_stats;
-                channel_stats.add_bytes_sent(data.iter().fold(0, |acc, d| acc + d.bytes.len()));
-                channel_stats.add_single_message_sent(data.len());
+        // NOTE: we don't know the actual exact amount of bytes sent (because we don't take into account the ids, etc.),
+        // but we could during build_packet?
+        for (channel_id, data) in &single_data {
+            let num_bytes = data.iter().fold(0, |acc, d| acc + d.bytes.len());
+            let stats = &mut self.get_channel_mut(*channel_id)?.stats.send;
+            stats.add_bytes_sent(num_bytes);
+            stats.add_single_message_sent(data.len());
+            if let Some(num) = num_messages_to_send.get_mut(channel_id) {
+                *num -= data.len();
+            }
+        }
+        for (channel_id, data) in &fragment_data {
+            let num_bytes = data.iter().fold(0, |acc, d| acc + d.bytes.len());
+            let stats = &mut self.get_channel_mut(*channel_id)?.stats.send;
+            stats.add_bytes_sent(num_bytes);
+            stats.add_fragment_message_sent(data.len());
+            if let Some(num) = num_messages_to_send.get_mut(channel_id) {
+                *num -= data.len();
             }
-            for (channel_id, data) in &fragment_data {
-                let channel_stats = &mut self
-                    .channels
-                    .get_mut(
-                        self.channel_registry
-                            .get_kind_from_net_id(*channel_id)
-                            .ok_or(PacketError::ChannelNotFound)?,
-                    )
-                    .ok_or(PacketError::ChannelNotFound)?
-                    .sender_stats;
-                channel_stats.add_bytes_sent(data.iter().fold(0, |acc, d| acc + d.bytes.len()));
-                channel_stats.add_fragment_message_sent(data.len());
+        }
+        for (channel_id, num_dropped) in num_messages_to_send {
+            if num_dropped > 0 {
+                self.get_channel_mut(channel_id)?
+                    .stats
+                    .send
+                    .add_messages_dropped(num_dropped);
             }
         }
+        #[cfg(feature = "metrics")]
+        self.record_send_metrics(&single_data, &fragment_data);
 
         let packets =
             self.packet_manager
@@ -256,6 +361,7 @@ impl MessageManager {
             // Step 3. Get the packets to send over the network
             bytes.push(packet.payload);
         }
+        self.send_mtu_probe(current_tick, &mut bytes)?;
 
         // adjust the real amount of bytes that we sent through the limiter (to account for the actual packet size)
         if self.priority_manager.config.enabled {
@@ -273,6 +379,27 @@ impl MessageManager {
         Ok(bytes)
     }
 
+    /// Add a probe packet to the packets to send, if MTU discovery is enabled and it is time
+    /// to send a new probe
+    fn send_mtu_probe(
+        &mut self,
+        current_tick: Tick,
+        bytes: &mut Vec<Payload>,
+    ) -> Result<(), PacketError> {
+        let Some(mtu_prober) = &mut self.mtu_prober else {
+            return Ok(());
+        };
+        let Some(size) = mtu_prober.next_probe_size() else {
+            return Ok(());
+        };
+        let packet = self
+            .packet_manager
+            .build_mtu_probe_packet(size, current_tick)?;
+        mtu_prober.probe_sent(packet.packet_id, size);
+        bytes.push(packet.payload);
+        Ok(())
+    }
+
     /// Process packet received over the network as raw bytes
     /// Update the acks, and put the messages from the packets in internal buffers
     /// Returns the tick of the packet
@@ -300,8 +427,17 @@ impl MessageManager {
             .process_recv_packet_header(&header);
 
         // Step 3. Update the list of messages that have been acked
-        for acked_packet in acked_packets {
+        let current_time = self.packet_manager.header_manager.current_time();
+        for (acked_packet, sent_time) in acked_packets {
             trace!("Acked packet {:?}", acked_packet);
+            if let Some(max_packet_size) = self
+                .mtu_prober
+                .as_mut()
+                .and_then(|mtu_prober| mtu_prober.receive_ack(acked_packet))
+            {
+                self.set_max_packet_size(max_packet_size);
+            }
+            let ack_latency = (current_time - sent_time).to_std().unwrap_or_default();
             if let Some(message_acks) = self.packet_to_message_ack_map.remove(&acked_packet) {
                 for (channel_kind, message_ack) in message_acks {
                     let channel_name = self
@@ -318,10 +454,16 @@ impl MessageManager {
                         .get_mut(&channel_kind)
                         .ok_or(PacketError::ChannelNotFound)?;
                     channel.sender.receive_ack(&message_ack);
+                    channel.stats.send.add_message_acked(ack_latency);
                 }
             }
         }
 
+        // probe packets only contain padding after the header
+        if header.get_packet_type() == PacketType::MtuProbe {
+            return Ok(tick);
+        }
+
         // Step 4. Parse the payload into messages, put them in the internal buffers for each channel
         // we read directly from the packet and don't create intermediary datastructures to avoid allocations
         // TODO: maybe do this in a helper function?
@@ -329,12 +471,24 @@ impl MessageManager {
             // read the fragment data
             let channel_id = ChannelId::from_bytes(&mut cursor)?;
             let fragment_data = FragmentData::from_bytes(&mut cursor)?;
-            self.get_channel_mut(channel_id)?
-                .receiver
-                .buffer_recv(ReceiveMessage {
+            #[cfg(feature = "metrics")]
+            self.record_receive_metrics(channel_id, fragment_data.bytes.len());
+            // the remote peer can use a newer version of the protocol, with channels that we don't know
+            if let Ok(channel) = self.get_channel_mut(channel_id) {
+                channel
+                    .stats
+                    .receive
+                    .add_fragment_message_received(fragment_data.bytes.len());
+                channel.receiver.buffer_recv(ReceiveMessage {
                     data: fragment_data.into(),
                     remote_sent_tick: tick,
                 })?;
+            } else {
+                warn!(
+                    ?channel_id,
+                    "Received a fragment on an unknown channel, ignoring it"
+                );
+            }
         }
         // read single message data
         while cursor.has_remaining() {
@@ -343,12 +497,23 @@ impl MessageManager {
             trace!(?channel_id, ?num_messages);
             for i in 0..num_messages {
                 let single_data = SingleData::from_bytes(&mut cursor)?;
-                self.get_channel_mut(channel_id)?
-                    .receiver
-                    .buffer_recv(ReceiveMessage {
-                        data: single_data.into(),
-                        remote_sent_tick: tick,
-                    })?;
+                #[cfg(feature = "metrics")]
+                self.record_receive_metrics(channel_id, single_data.bytes.len());
+                let Ok(channel) = self.get_channel_mut(channel_id) else {
+                    warn!(
+                        ?channel_id,
+                        "Received a message on an unknown channel, ignoring it"
+                    );
+                    continue;
+                };
+                channel
+                    .stats
+                    .receive
+                    .add_single_message_received(single_data.bytes.len());
+                channel.receiver.buffer_recv(ReceiveMessage {
+                    data: single_data.into(),
+                    remote_sent_tick: tick,
+                })?;
             }
         }
         // trace!(
@@ -418,12 +583,80 @@ impl MessageManager {
             .ok_or(PacketError::ChannelNotFound)
     }
 
-    /// Get the ChannelSendStats of a given channel
-    #[cfg(feature = "trace")]
-    pub fn channel_send_stats<C: crate::prelude::Channel>(&self) -> Option<&ChannelSendStats> {
+    /// Get the [`ChannelStats`] of a given channel
+    pub fn channel_stats<C: crate::prelude::Channel>(&self) -> Option<&ChannelStats> {
         self.channels
             .get(&ChannelKind::of::<C>())
-            .map(|channel| &channel.sender_stats)
+            .map(|channel| &channel.stats)
+    }
+
+    /// Iterate through the [`ChannelStats`] of all channels
+    pub fn all_channel_stats(&self) -> impl Iterator<Item = (&ChannelKind, &ChannelStats)> {
+        self.channels
+            .iter()
+            .map(|(kind, channel)| (kind, &channel.stats))
+    }
+
+    #[cfg(feature = "metrics")]
+    fn record_send_metrics(
+        &mut self,
+        single_data: &[(ChannelId, VecDeque<SingleData>)],
+        fragment_data: &[(ChannelId, VecDeque<FragmentData>)],
+    ) {
+        for (channel_id, data) in single_data {
+            let Some(labels) = self.metrics_labels(*channel_id) else {
+                continue;
+            };
+            let num_bytes = data.iter().fold(0, |acc, d| acc + d.bytes.len());
+            metrics::counter!("channel.messages_sent", labels.iter()).increment(data.len() as u64);
+            metrics::counter!("channel.bytes_sent", labels.iter()).increment(num_bytes as u64);
+        }
+        for (channel_id, data) in fragment_data {
+            let Some(labels) = self.metrics_labels(*channel_id) else {
+                continue;
+            };
+            let num_bytes = data.iter().fold(0, |acc, d| acc + d.bytes.len());
+            metrics::counter!("channel.fragments_sent", labels.iter()).increment(data.len() as u64);
+            metrics::counter!("channel.bytes_sent", labels.iter()).increment(num_bytes as u64);
+        }
+        for (kind, channel) in self.channels.iter() {
+            let Some(channel_metrics) = self.channel_metrics.get_mut(kind) else {
+                continue;
+            };
+            let stats = channel.stats.send;
+            // only record the channels whose stats changed since the last time
+            if stats == channel_metrics.last_send {
+                continue;
+            }
+            channel_metrics.last_send = stats;
+            let labels = &channel_metrics.labels;
+            metrics::gauge!("channel.queue_depth", labels.iter()).set(stats.queue_depth() as f64);
+            // the stats are cumulative since the connection was established
+            metrics::counter!("channel.messages_resent", labels.iter())
+                .absolute(stats.messages_resent() as u64);
+            metrics::counter!("channel.messages_dropped", labels.iter())
+                .absolute(stats.messages_dropped() as u64);
+            metrics::gauge!("channel.ack_latency_ms", labels.iter())
+                .set(stats.ack_latency().as_secs_f64() * 1000.0);
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    fn record_receive_metrics(&self, channel_id: ChannelId, num_bytes: usize) {
+        let Some(labels) = self.metrics_labels(channel_id) else {
+            return;
+        };
+        metrics::counter!("channel.messages_received", labels.iter()).increment(1);
+        metrics::counter!("channel.bytes_received", labels.iter()).increment(num_bytes as u64);
+    }
+
+    /// Labels of the metrics of the channel with net id `channel_id`
+    #[cfg(feature = "metrics")]
+    fn metrics_labels(&self, channel_id: ChannelId) -> Option<&Vec<metrics::Label>> {
+        self.channel_registry
+            .get_kind_from_net_id(channel_id)
+            .and_then(|kind| self.channel_metrics.get(kind))
+            .map(|channel_metrics| &channel_metrics.labels)
     }
 }
 
@@ -440,7 +673,9 @@ mod tests {
     use crate::packet::priority_manager::PriorityConfig;
     use crate::prelude::*;
 
+    use crate::packet::mtu::MtuDiscoveryConfig;
     use crate::tests::protocol::*;
+    use bevy::utils::Duration;
 
     use super::*;
 
@@ -456,10 +691,18 @@ mod tests {
         });
 
         // Create message managers
-        let client_message_manager =
-            MessageManager::new(&channel_registry, 1.5, PriorityConfig::default());
-        let server_message_manager =
-            MessageManager::new(&channel_registry, 1.5, PriorityConfig::default());
+        let client_message_manager = MessageManager::new(
+            &channel_registry,
+            1.5,
+            PriorityConfig::default(),
+            MtuConfig::default(),
+        );
+        let server_message_manager = MessageManager::new(
+            &channel_registry,
+            1.5,
+            PriorityConfig::default(),
+            MtuConfig::default(),
+        );
         (client_message_manager, server_message_manager)
     }
 
@@ -686,4 +929,104 @@ mod tests {
         assert_eq!(update_acks_tracker.try_recv().unwrap(), message_id);
         Ok(())
     }
+
+    #[test]
+    fn test_channel_stats() -> Result<(), PacketError> {
+        let (mut client_message_manager, mut server_message_manager) = setup();
+
+        let message: Bytes = vec![0, 1].into();
+        client_message_manager.buffer_send(message.clone(), Channel1::kind())?;
+        client_message_manager.buffer_send(message.clone(), Channel2::kind())?;
+        client_message_manager.buffer_send(message.clone(), Channel2::kind())?;
+        let payloads = client_message_manager.send_packets(Tick(0))?;
+
+        let stats = client_message_manager.channel_stats::<Channel2>().unwrap();
+        assert_eq!(stats.send.messages_sent(), 2);
+        assert_eq!(stats.send.bytes_sent(), 4);
+        assert_eq!(stats.send.queue_depth(), 2);
+
+        // server: receive the messages
+        for payload in payloads {
+            server_message_manager.recv_packet(payload.into())?;
+        }
+        let stats = server_message_manager.channel_stats::<Channel1>().unwrap();
+        assert_eq!(stats.receive.messages_received(), 1);
+        assert_eq!(stats.receive.bytes_received(), 2);
+        let stats = server_message_manager.channel_stats::<Channel2>().unwrap();
+        assert_eq!(stats.receive.messages_received(), 2);
+
+        // server sends back a message to ack the client's messages
+        server_message_manager.buffer_send(message.clone(), Channel1::kind())?;
+        for payload in server_message_manager.send_packets(Tick(0))? {
+            client_message_manager.recv_packet(payload.into())?;
+        }
+        let stats = client_message_manager.channel_stats::<Channel2>().unwrap();
+        assert_eq!(stats.send.messages_acked(), 2);
+        // Channel1 does not track acks
+        let stats = client_message_manager.channel_stats::<Channel1>().unwrap();
+        assert_eq!(stats.send.messages_acked(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mtu_discovery() -> Result<(), PacketError> {
+        let (_, mut server_message_manager) = setup();
+        let mut client_message_manager = MessageManager::new(
+            &server_message_manager.channel_registry,
+            1.5,
+            PriorityConfig::default(),
+            MtuConfig {
+                max_packet_size: 1200,
+                discovery: Some(MtuDiscoveryConfig {
+                    initial_packet_size: 400,
+                    probe_interval: Duration::default(),
+                    ..default()
+                }),
+            },
+        );
+        assert_eq!(client_message_manager.max_packet_size(), 400);
+
+        // the big message is fragmented according to the initial packet size,
+        // and a probe is sent along with the data packets
+        let message = Bytes::from(vec![1; 1000]);
+        client_message_manager.buffer_send(message.clone(), Channel1::kind())?;
+        let payloads = client_message_manager.send_packets(Tick(0))?;
+        assert_eq!(payloads.len(), 4);
+        assert!(payloads[..3].iter().all(|p| p.len() <= 400));
+        assert_eq!(payloads[3].len(), 800);
+        for payload in payloads {
+            server_message_manager.recv_packet(payload.into())?;
+        }
+        assert_eq!(
+            server_message_manager
+                .read_messages()
+                .map(|(_, (_, bytes))| bytes)
+                .collect::<Vec<_>>(),
+            vec![message.clone()]
+        );
+
+        // the server acks the probe
+        server_message_manager.buffer_send(vec![0].into(), Channel1::kind())?;
+        for payload in server_message_manager.send_packets(Tick(0))? {
+            client_message_manager.recv_packet(payload.into())?;
+        }
+        assert_eq!(client_message_manager.max_packet_size(), 800);
+
+        // the message now only needs 2 fragments
+        client_message_manager.buffer_send(message.clone(), Channel1::kind())?;
+        let payloads = client_message_manager.send_packets(Tick(0))?;
+        assert_eq!(payloads.len(), 3);
+        assert_eq!(payloads[2].len(), 1000);
+        for payload in payloads {
+            server_message_manager.recv_packet(payload.into())?;
+        }
+        assert_eq!(
+            server_message_manager
+                .read_messages()
+                .map(|(_, (_, bytes))| bytes)
+                .collect::<Vec<_>>(),
+            vec![message]
+        );
+        Ok(())
+    }
 }
@@ -27,6 +27,9 @@ pub(crate) mod message;
 /// Manages sending and receiving [`Packets`](packet::Packet) over the network
 pub mod message_manager;
 
+/// Configure the maximum packet size and discover the path MTU
+pub mod mtu;
+
 pub mod packet;
 
 pub(crate) mod error;
@@ -0,0 +1,249 @@
+//! Configure the maximum size of the packets sent on a connection, and optionally discover
+//! the largest packet size that can reach the remote peer (path MTU discovery).
+//!
+//! Some networks (VPNs, tunnels, etc.) silently drop UDP datagrams that are too big.
+//! With [`MtuDiscoveryConfig`], a connection starts by sending small packets, and periodically sends
+//! probe packets of increasing size. Once a probe is acked, the probed size becomes the new maximum
+//! packet size of the connection; if a probe is lost multiple times, we stop probing above that size.
+use bevy::reflect::Reflect;
+use bevy::utils::Duration;
+use tracing::{debug, trace};
+
+use crate::connection::netcode::MAX_PACKET_SIZE;
+use crate::packet::packet::{PacketId, MIN_PACKET_SIZE};
+
+/// Configuration of the path MTU discovery
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct MtuDiscoveryConfig {
+    /// Maximum packet size used when the connection is established, before any probe has been acked.
+    ///
+    /// This should be small enough to go through any network.
+    pub initial_packet_size: usize,
+    /// Duration between two probes
+    pub probe_interval: Duration,
+    /// Number of times a probe of a given size must be lost before we consider that packets of that
+    /// size cannot reach the remote peer
+    pub max_probe_attempts: u8,
+    /// We stop probing once the difference between the largest size that is known to work and the
+    /// smallest size that is known to fail is lower than this number of bytes
+    pub precision: usize,
+}
+
+impl Default for MtuDiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            initial_packet_size: 512,
+            probe_interval: Duration::from_millis(500),
+            max_probe_attempts: 3,
+            precision: 16,
+        }
+    }
+}
+
+/// Packet size configuration of a connection
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MtuConfig {
+    /// Maximum number of bytes in a packet
+    pub max_packet_size: usize,
+    /// If set, the connection starts with small packets and probes for larger packet sizes,
+    /// up to `max_packet_size`
+    pub discovery: Option<MtuDiscoveryConfig>,
+}
+
+impl Default for MtuConfig {
+    fn default() -> Self {
+        Self {
+            max_packet_size: MAX_PACKET_SIZE,
+            discovery: None,
+        }
+    }
+}
+
+impl MtuConfig {
+    /// Maximum packet size to use for the connection, clamped to the sizes supported by the netcode protocol
+    pub(crate) fn max_packet_size(&self) -> usize {
+        self.max_packet_size.clamp(MIN_PACKET_SIZE, MAX_PACKET_SIZE)
+    }
+
+    /// Maximum packet size to use when the connection is established
+    pub(crate) fn initial_packet_size(&self) -> usize {
+        match &self.discovery {
+            Some(discovery) => discovery
+                .initial_packet_size
+                .clamp(MIN_PACKET_SIZE, self.max_packet_size()),
+            None => self.max_packet_size(),
+        }
+    }
+}
+
+impl From<crate::client::config::PacketConfig> for MtuConfig {
+    fn from(value: crate::client::config::PacketConfig) -> Self {
+        Self {
+            max_packet_size: value.max_packet_size,
+            discovery: value.mtu_discovery,
+        }
+    }
+}
+
+impl From<crate::server::config::PacketConfig> for MtuConfig {
+    fn from(value: crate::server::config::PacketConfig) -> Self {
+        Self {
+            max_packet_size: value.max_packet_size,
+            discovery: value.mtu_discovery,
+        }
+    }
+}
+
+/// Runs a binary search between the largest packet size that is known to reach the remote peer,
+/// and the largest packet size that could still reach it
+#[derive(Debug)]
+pub(crate) struct MtuProber {
+    config: MtuDiscoveryConfig,
+    /// Largest packet size that is known to reach the remote peer
+    confirmed_size: usize,
+    /// Largest packet size that could still reach the remote peer
+    max_size: usize,
+    /// Number of probes of the current size that have been lost
+    num_lost_probes: u8,
+    /// Time elapsed since the last probe was sent
+    elapsed: Duration,
+    /// Probe that is waiting for an ack
+    in_flight: Option<(PacketId, usize)>,
+}
+
+impl MtuProber {
+    pub(crate) fn new(config: &MtuConfig) -> Option<Self> {
+        config.discovery.map(|discovery| Self {
+            config: discovery,
+            confirmed_size: config.initial_packet_size(),
+            max_size: config.max_packet_size(),
+            num_lost_probes: 0,
+            elapsed: Duration::default(),
+            in_flight: None,
+        })
+    }
+
+    /// Returns true if we have found the largest packet size that can reach the remote peer
+    pub(crate) fn is_done(&self) -> bool {
+        self.max_size.saturating_sub(self.confirmed_size) < self.config.precision.max(1)
+    }
+
+    pub(crate) fn update(&mut self, delta: Duration) {
+        self.elapsed += delta;
+    }
+
+    /// Returns the size of the next probe to send, if it is time to send one
+    pub(crate) fn next_probe_size(&mut self) -> Option<usize> {
+        if self.in_flight.is_some() || self.is_done() || self.elapsed < self.config.probe_interval {
+            return None;
+        }
+        self.elapsed = Duration::default();
+        Some((self.confirmed_size + self.max_size).div_ceil(2))
+    }
+
+    pub(crate) fn probe_sent(&mut self, packet_id: PacketId, size: usize) {
+        trace!(?packet_id, ?size, "sent mtu probe");
+        self.in_flight = Some((packet_id, size));
+    }
+
+    /// Returns the size of the probe in flight if it was sent in the packet `packet_id`
+    fn take_in_flight(&mut self, packet_id: PacketId) -> Option<usize> {
+        match self.in_flight {
+            Some((id, size)) if id == packet_id => {
+                self.in_flight = None;
+                Some(size)
+            }
+            _ => None,
+        }
+    }
+
+    /// The packet `packet_id` has been acked by the remote peer.
+    ///
+    /// Returns the new maximum packet size if the packet was a probe.
+    pub(crate) fn receive_ack(&mut self, packet_id: PacketId) -> Option<usize> {
+        let size = self.take_in_flight(packet_id)?;
+        debug!(?size, "mtu probe acked");
+        self.confirmed_size = size;
+        self.num_lost_probes = 0;
+        Some(size)
+    }
+
+    /// The packet `packet_id` has been lost
+    pub(crate) fn receive_nack(&mut self, packet_id: PacketId) {
+        let Some(size) = self.take_in_flight(packet_id) else {
+            return;
+        };
+        self.num_lost_probes += 1;
+        trace!(?size, num_lost_probes = ?self.num_lost_probes, "mtu probe lost");
+        if self.num_lost_probes >= self.config.max_probe_attempts {
+            debug!(?size, "packets of this size cannot reach the remote peer");
+            self.max_size = size - 1;
+            self.num_lost_probes = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prober() -> MtuProber {
+        MtuProber::new(&MtuConfig {
+            max_packet_size: 1200,
+            discovery: Some(MtuDiscoveryConfig {
+                initial_packet_size: 400,
+                probe_interval: Duration::from_millis(100),
+                max_probe_attempts: 2,
+                precision: 10,
+            }),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_probe_interval() {
+        let mut prober = prober();
+        assert_eq!(prober.next_probe_size(), None);
+        prober.update(Duration::from_millis(100));
+        assert_eq!(prober.next_probe_size(), Some(800));
+        prober.probe_sent(PacketId(0), 800);
+
+        // only one probe in flight at a time
+        prober.update(Duration::from_millis(100));
+        assert_eq!(prober.next_probe_size(), None);
+    }
+
+    #[test]
+    fn test_binary_search() {
+        // packets bigger than 1000 bytes are dropped
+        let mtu = 1000;
+        let mut prober = prober();
+        let mut packet_id = PacketId(0);
+        let mut confirmed = 400;
+        while !prober.is_done() {
+            prober.update(Duration::from_millis(100));
+            let size = prober.next_probe_size().unwrap();
+            prober.probe_sent(packet_id, size);
+            if size <= mtu {
+                assert_eq!(prober.receive_ack(packet_id), Some(size));
+                confirmed = size;
+            } else {
+                prober.receive_nack(packet_id);
+            }
+            packet_id += 1;
+        }
+        assert!(confirmed <= mtu);
+        assert!(mtu - confirmed < 10);
+    }
+
+    #[test]
+    fn test_ignore_other_packets() {
+        let mut prober = prober();
+        prober.update(Duration::from_millis(100));
+        let size = prober.next_probe_size().unwrap();
+        prober.probe_sent(PacketId(3), size);
+        assert_eq!(prober.receive_ack(PacketId(2)), None);
+        prober.receive_nack(PacketId(1));
+        assert_eq!(prober.receive_ack(PacketId(3)), Some(size));
+    }
+}
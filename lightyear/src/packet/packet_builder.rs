@@ -2,7 +2,7 @@
 use crate::connection::netcode::MAX_PACKET_SIZE;
 use crate::packet::header::PacketHeaderManager;
 use crate::packet::message::{FragmentData, MessageAck, SingleData};
-use crate::packet::packet::{Packet, FRAGMENT_SIZE};
+use crate::packet::packet::{fragment_size, Packet};
 use crate::packet::packet_type::PacketType;
 use crate::prelude::Tick;
 use crate::protocol::channel::ChannelId;
@@ -31,6 +31,8 @@ pub type RecvPayload = Bytes;
 pub(crate) struct PacketBuilder {
     pub(crate) header_manager: PacketHeaderManager,
     current_packet: Option<Packet>,
+    /// Maximum number of bytes in a packet for this connection
+    max_packet_size: usize,
     // Pre-allocated buffer to encode/decode without allocation.
     // TODO: should this be associated with Packet?
     // cursor: Vec<u8>,
@@ -41,10 +43,11 @@ pub(crate) struct PacketBuilder {
 }
 
 impl PacketBuilder {
-    pub fn new(nack_rtt_multiple: f32) -> Self {
+    pub fn new(nack_rtt_multiple: f32, max_packet_size: usize) -> Self {
         Self {
             header_manager: PacketHeaderManager::new(nack_rtt_multiple),
             current_packet: None,
+            max_packet_size,
             // cursor: Vec::with_capacity(PACKET_BUFFER_CAPACITY),
             // acks: Vec::new(),
 
@@ -55,9 +58,22 @@ impl PacketBuilder {
         }
     }
 
+    /// Maximum number of bytes in a packet
+    pub(crate) fn max_packet_size(&self) -> usize {
+        self.max_packet_size
+    }
+
+    /// Update the maximum number of bytes in a packet.
+    ///
+    /// The size cannot exceed [`MAX_PACKET_SIZE`], which is the maximum payload size of the
+    /// netcode protocol.
+    pub(crate) fn set_max_packet_size(&mut self, max_packet_size: usize) {
+        self.max_packet_size = max_packet_size.min(MAX_PACKET_SIZE);
+    }
+
     // TODO: get the vec from a pool of preallocated buffers
     fn get_new_buffer(&self) -> Payload {
-        Vec::with_capacity(MAX_PACKET_SIZE)
+        Vec::with_capacity(self.max_packet_size)
     }
 
     /// Start building new packet, we start with an empty packet
@@ -80,6 +96,7 @@ impl PacketBuilder {
             message_acks: vec![],
             packet_id: header.packet_id,
             prewritten_size: 0,
+            max_size: self.max_packet_size,
         });
         Ok(())
     }
@@ -112,6 +129,7 @@ impl PacketBuilder {
             )],
             packet_id: header.packet_id,
             prewritten_size: 0,
+            max_size: self.max_packet_size,
         });
         Ok(())
 
@@ -137,6 +155,30 @@ impl PacketBuilder {
         // }
     }
 
+    /// Build a packet used to probe whether packets of `size` bytes can reach the remote peer.
+    ///
+    /// The packet only contains a header, followed by padding.
+    pub(crate) fn build_mtu_probe_packet(
+        &mut self,
+        size: usize,
+        current_tick: Tick,
+    ) -> Result<Packet, SerializationError> {
+        let mut cursor = Vec::with_capacity(size);
+        let mut header = self
+            .header_manager
+            .prepare_send_packet_header(PacketType::MtuProbe);
+        header.tick = current_tick;
+        header.to_bytes(&mut cursor)?;
+        cursor.resize(size.max(cursor.len()), 0);
+        Ok(Packet {
+            payload: cursor,
+            message_acks: vec![],
+            packet_id: header.packet_id,
+            prewritten_size: 0,
+            max_size: size,
+        })
+    }
+
     pub fn finish_packet(&mut self) -> Packet {
         let mut packet = self.current_packet.take().unwrap();
         packet.payload.shrink_to_fit();
@@ -172,7 +214,7 @@ impl PacketBuilder {
         // try to fill the packet with fragment messages first
         for (channel_id, mut fragment_messages) in fragment_data.into_iter() {
             while let Some(fragment_data) = fragment_messages.pop_front() {
-                debug_assert!(fragment_data.bytes.len() <= FRAGMENT_SIZE);
+                debug_assert!(fragment_data.bytes.len() <= fragment_size(self.max_packet_size));
                 self.build_new_fragment_packet(channel_id, &fragment_data, current_tick)?;
                 if !fragment_data.is_last_fragment() {
                     // big fragment, write packet immediately
@@ -427,6 +469,7 @@ mod tests {
 
     use crate::channel::senders::fragment_sender::FragmentSender;
     use crate::packet::message::MessageId;
+    use crate::packet::packet::FRAGMENT_SIZE;
     use crate::prelude::*;
 
     use super::*;
@@ -456,7 +499,7 @@ mod tests {
     #[test]
     fn test_pack_small_messages() -> Result<(), PacketError> {
         let channel_registry = get_channel_registry();
-        let mut manager = PacketBuilder::new(1.5);
+        let mut manager = PacketBuilder::new(1.5, MAX_PACKET_SIZE);
         let channel_kind1 = ChannelKind::of::<Channel1>();
         let channel_id1 = channel_registry.get_net_from_kind(&channel_kind1).unwrap();
         let channel_kind2 = ChannelKind::of::<Channel2>();
@@ -504,7 +547,7 @@ mod tests {
     #[test]
     fn test_pack_cannot_write_channel_id() -> Result<(), PacketError> {
         let channel_registry = get_channel_registry();
-        let mut manager = PacketBuilder::new(1.5);
+        let mut manager = PacketBuilder::new(1.5, MAX_PACKET_SIZE);
         let channel_kind1 = ChannelKind::of::<Channel1>();
         let channel_id1 = channel_registry.get_net_from_kind(&channel_kind1).unwrap();
         let channel_kind2 = ChannelKind::of::<Channel2>();
@@ -538,7 +581,7 @@ mod tests {
     #[test]
     fn test_pack_many_small_messages() -> Result<(), PacketError> {
         let channel_registry = get_channel_registry();
-        let mut manager = PacketBuilder::new(1.5);
+        let mut manager = PacketBuilder::new(1.5, MAX_PACKET_SIZE);
         let channel_kind1 = ChannelKind::of::<Channel1>();
         let channel_id1 = channel_registry.get_net_from_kind(&channel_kind1).unwrap();
         let channel_kind2 = ChannelKind::of::<Channel2>();
@@ -573,7 +616,7 @@ mod tests {
     #[test]
     fn test_pack_single_data_multiple_packets() -> Result<(), PacketError> {
         let channel_registry = get_channel_registry();
-        let mut manager = PacketBuilder::new(1.5);
+        let mut manager = PacketBuilder::new(1.5, MAX_PACKET_SIZE);
         let channel_kind1 = ChannelKind::of::<Channel1>();
         let channel_id1 = channel_registry.get_net_from_kind(&channel_kind1).unwrap();
         let channel_kind2 = ChannelKind::of::<Channel2>();
@@ -606,7 +649,7 @@ mod tests {
     #[test]
     fn test_pack_big_messages() -> Result<(), PacketError> {
         let channel_registry = get_channel_registry();
-        let mut manager = PacketBuilder::new(1.5);
+        let mut manager = PacketBuilder::new(1.5, MAX_PACKET_SIZE);
         let channel_kind1 = ChannelKind::of::<Channel1>();
         let channel_id1 = channel_registry.get_net_from_kind(&channel_kind1).unwrap();
         let channel_kind2 = ChannelKind::of::<Channel2>();
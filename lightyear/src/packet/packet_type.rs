@@ -15,6 +15,9 @@ pub enum PacketType {
     /// - channel_id = 0 = indication of end of packet
     Data = 0,
     DataFragment = 1,
+    /// A packet that only contains a header followed by padding, used to discover the maximum
+    /// packet size that can reach the remote peer
+    MtuProbe = 2,
 }
 
 impl From<PacketType> for u8 {
@@ -30,6 +33,7 @@ impl TryFrom<u8> for PacketType {
         match value {
             0 => Ok(PacketType::Data),
             1 => Ok(PacketType::DataFragment),
+            2 => Ok(PacketType::MtuProbe),
             _ => Err(crate::serialize::SerializationError::InvalidPacketType),
         }
     }
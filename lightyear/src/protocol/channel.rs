@@ -5,13 +5,15 @@ use std::any::TypeId;
 use std::collections::HashMap;
 
 use crate::channel::builder::{
-    AuthorityChannel, Channel, ChannelBuilder, ChannelSettings, PongChannel,
+    AuthorityChannel, Channel, ChannelBuilder, ChannelSettings, ChecksumChannel, HandshakeChannel,
+    InterpolationDelayChannel, PongChannel, SnapshotAckChannel, SnapshotChannel,
 };
 use crate::channel::builder::{
     ChannelContainer, EntityActionsChannel, EntityUpdatesChannel, InputChannel, PingChannel,
 };
-use crate::prelude::{ChannelMode, ReliableSettings};
-use crate::protocol::registry::{NetId, TypeKind, TypeMapper};
+use crate::prelude::{ChannelDirection, ChannelMode, ReliableSettings};
+use crate::protocol::registry::{NetId, ProtocolVersion, TypeKind, TypeMapper};
+use crate::protocol::schema::{channel_mode_name, ChannelSchema};
 
 // TODO: derive Reflect once we reach bevy 0.14
 /// ChannelKind - internal wrapper around the type of the channel
@@ -77,6 +79,7 @@ impl ChannelRegistry {
         };
         registry.add_channel::<EntityUpdatesChannel>(ChannelSettings {
             mode: ChannelMode::UnorderedUnreliableWithAcks,
+            direction: ChannelDirection::Bidirectional,
             // we do not send the send_frequency to `replication_interval` here
             // because we want to make sure that the entity updates for tick T
             // are sent on tick T, so we will set the `replication_interval`
@@ -86,6 +89,7 @@ impl ChannelRegistry {
         });
         registry.add_channel::<EntityActionsChannel>(ChannelSettings {
             mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
+            direction: ChannelDirection::Bidirectional,
             // we do not send the send_frequency to `replication_interval` here
             // because we want to make sure that the entity updates for tick T
             // are sent on tick T, so we will set the `replication_interval`
@@ -96,28 +100,65 @@ impl ChannelRegistry {
         });
         registry.add_channel::<PingChannel>(ChannelSettings {
             mode: ChannelMode::SequencedUnreliable,
+            direction: ChannelDirection::Bidirectional,
             send_frequency: Duration::default(),
             // we always want to include the ping in the packet
             priority: f32::INFINITY,
         });
         registry.add_channel::<PongChannel>(ChannelSettings {
             mode: ChannelMode::SequencedUnreliable,
+            direction: ChannelDirection::Bidirectional,
             send_frequency: Duration::default(),
             // we always want to include the pong in the packet
             priority: f32::INFINITY,
         });
         registry.add_channel::<InputChannel>(ChannelSettings {
             mode: ChannelMode::UnorderedUnreliable,
+            direction: ChannelDirection::Bidirectional,
             send_frequency: input_send_interval,
             // we always want to include the inputs in the packet
             priority: f32::INFINITY,
         });
         registry.add_channel::<AuthorityChannel>(ChannelSettings {
             mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            direction: ChannelDirection::ServerToClient,
             send_frequency: Duration::default(),
             // we want to send the authority transfers as soon as possible
             priority: 10.0,
         });
+        registry.add_channel::<HandshakeChannel>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            direction: ChannelDirection::Bidirectional,
+            send_frequency: Duration::default(),
+            // the handshake must be done before anything else
+            priority: f32::INFINITY,
+        });
+        registry.add_channel::<SnapshotChannel>(ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliable,
+            direction: ChannelDirection::ServerToClient,
+            // the snapshots are buffered every `replication_interval`
+            send_frequency: Duration::default(),
+            priority: 1.0,
+        });
+        registry.add_channel::<SnapshotAckChannel>(ChannelSettings {
+            mode: ChannelMode::SequencedUnreliable,
+            direction: ChannelDirection::ClientToServer,
+            send_frequency: Duration::default(),
+            // the server cannot use a snapshot as baseline until it is acked
+            priority: 10.0,
+        });
+        registry.add_channel::<ChecksumChannel>(ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliable,
+            direction: ChannelDirection::ServerToClient,
+            send_frequency: Duration::default(),
+            priority: 1.0,
+        });
+        registry.add_channel::<InterpolationDelayChannel>(ChannelSettings {
+            mode: ChannelMode::SequencedReliable(ReliableSettings::default()),
+            direction: ChannelDirection::ClientToServer,
+            send_frequency: Duration::default(),
+            priority: 1.0,
+        });
         registry
     }
 
@@ -136,6 +177,11 @@ impl ChannelRegistry {
         })
     }
 
+    /// Version of the protocol in which the channel was added
+    pub(crate) fn version(&self, kind: &ChannelKind) -> ProtocolVersion {
+        self.kind_map.version(kind)
+    }
+
     /// Build all the channels in the registry
     pub fn channels(&self) -> HashMap<ChannelKind, ChannelContainer> {
         let mut channels = HashMap::new();
@@ -170,10 +216,66 @@ impl ChannelRegistry {
         self.kind_map.net_id(kind)
     }
 
+    /// Direction in which messages can be sent on the channel
+    pub fn direction(&self, kind: &ChannelKind) -> Option<ChannelDirection> {
+        self.builder_map
+            .get(kind)
+            .map(|builder| builder.settings.direction)
+    }
+
     pub fn name(&self, kind: &ChannelKind) -> Option<&str> {
         self.name_map.get(kind).map(|s| s.as_str())
     }
 
+    /// Iterate through the names of all the registered channels
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+        self.name_map.values().map(|name| name.as_str())
+    }
+
+    /// Description of every channel known in the protocol `version`, ordered by net id, used to build the
+    /// [`ProtocolFingerprint`](crate::protocol::fingerprint::ProtocolFingerprint)
+    pub(crate) fn fingerprint_entries(&self, version: ProtocolVersion) -> Vec<String> {
+        self.kind_map
+            .net_ids()
+            .into_iter()
+            .filter_map(|net_id| {
+                let kind = self.kind_map.kind(net_id)?;
+                if !self.kind_map.is_known(kind, version) {
+                    return None;
+                }
+                let since = self.kind_map.version(kind);
+                let name = self.name(kind).unwrap_or("unknown");
+                let settings = &self.builder_map.get(kind)?.settings;
+                Some(format!(
+                    "channel {net_id}: {name} since={since} mode={:?} direction={:?}",
+                    settings.mode, settings.direction
+                ))
+            })
+            .collect()
+    }
+
+    /// Description of every channel, ordered by net id, used to build the [`ProtocolSchema`](crate::protocol::schema::ProtocolSchema)
+    pub(crate) fn schema(&self) -> Vec<ChannelSchema> {
+        self.kind_map
+            .net_ids()
+            .into_iter()
+            .filter_map(|net_id| {
+                let kind = self.kind_map.kind(net_id)?;
+                let settings = &self.builder_map.get(kind)?.settings;
+                Some(ChannelSchema {
+                    net_id,
+                    name: self.name(kind).unwrap_or("unknown").to_string(),
+                    mode: channel_mode_name(&settings.mode).to_string(),
+                    reliable: settings.mode.is_reliable(),
+                    direction: format!("{:?}", settings.direction),
+                    priority: settings.priority.is_finite().then_some(settings.priority),
+                    send_frequency_ms: settings.send_frequency.as_millis(),
+                    since: self.kind_map.version(kind),
+                })
+            })
+            .collect()
+    }
+
     pub fn get_builder_from_net_id(&self, channel_id: ChannelId) -> Option<&ChannelBuilder> {
         let channel_kind = self.get_kind_from_net_id(channel_id)?;
         self.get_builder_from_kind(channel_kind)
@@ -185,15 +287,44 @@ impl ChannelRegistry {
     }
 }
 
+pub struct ChannelRegistration<'a, C> {
+    app: &'a mut App,
+    _marker: std::marker::PhantomData<C>,
+}
+
+impl<C: Channel> ChannelRegistration<'_, C> {
+    /// Use an explicit network id for the channel, instead of an id derived from the registration order.
+    ///
+    /// This keeps the id stable across versions of the protocol, even if channels are added or removed.
+    pub fn with_net_id(self, net_id: u16) -> Self {
+        let mut registry = self.app.world_mut().resource_mut::<ChannelRegistry>();
+        registry.kind_map.set_net_id::<C>(net_id);
+        self
+    }
+
+    /// Specify that the channel was added in the given [`ProtocolVersion`].
+    ///
+    /// Nothing will be sent on this channel to peers that use an older version of the protocol.
+    pub fn since_version(self, version: ProtocolVersion) -> Self {
+        let mut registry = self.app.world_mut().resource_mut::<ChannelRegistry>();
+        registry.kind_map.set_version::<C>(version);
+        self
+    }
+}
+
 /// Add a message to the list of messages that can be sent
 pub trait AppChannelExt {
-    fn add_channel<C: Channel>(&mut self, settings: ChannelSettings);
+    fn add_channel<C: Channel>(&mut self, settings: ChannelSettings) -> ChannelRegistration<'_, C>;
 }
 
 impl AppChannelExt for App {
-    fn add_channel<C: Channel>(&mut self, settings: ChannelSettings) {
+    fn add_channel<C: Channel>(&mut self, settings: ChannelSettings) -> ChannelRegistration<'_, C> {
         let mut registry = self.world_mut().resource_mut::<ChannelRegistry>();
         registry.add_channel::<C>(settings);
+        ChannelRegistration {
+            app: self,
+            _marker: std::marker::PhantomData,
+        }
     }
 }
 
@@ -5,29 +5,43 @@ use std::fmt::Debug;
 use std::hash::Hash;
 use std::ops::{Add, Mul};
 
-use bevy::prelude::{App, Component, EntityWorldMut, Mut, Resource, TypePath, World};
+use bevy::prelude::{
+    App, AppTypeRegistry, Component, EntityWorldMut, Mut, Resource, TypePath, World,
+};
 use bevy::ptr::Ptr;
+use bevy::reflect::TypeRegistration;
 use bevy::utils::HashMap;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
-use tracing::{debug, error, trace};
+use tracing::{debug, error, trace, warn};
 
+use crate::channel::builder::{EntityActionsChannel, EntityUpdatesChannel};
 use crate::client::components::ComponentSyncMode;
 use crate::client::config::ClientConfig;
-use crate::client::interpolation::{add_interpolation_systems, add_prepare_interpolation_systems};
+use crate::client::interpolation::curve::{CurveFn, CurveSamples, HermiteFn};
+use crate::client::interpolation::extrapolate::{ExtrapolationConfig, ExtrapolationFn};
+use crate::client::interpolation::{
+    add_curve_interpolation_systems, add_curve_status_systems, add_extrapolation_systems,
+    add_hermite_interpolation_systems, add_interpolation_systems,
+    add_prepare_interpolation_systems,
+};
+use crate::client::prediction::correction::{CorrectionErrorFn, CorrectionPolicy};
 use crate::client::prediction::plugin::{
     add_non_networked_rollback_systems, add_prediction_systems, add_resource_rollback_systems,
 };
 use crate::prelude::client::SyncComponent;
 use crate::prelude::server::ServerConfig;
-use crate::prelude::{ChannelDirection, Message, Tick};
+use crate::prelude::{ChannelDirection, ChannelKind, ChannelRegistry, Message, Tick};
 use crate::protocol::delta::ErasedDeltaFns;
-use crate::protocol::registry::{NetId, TypeKind, TypeMapper};
-use crate::protocol::serialize::{ErasedSerializeFns, SerializeFns};
+use crate::protocol::registry::{NetId, ProtocolVersion, TypeKind, TypeMapper};
+use crate::protocol::schema::ComponentSchema;
+use crate::protocol::serialize::{ErasedSerializeFns, SerializationBackend, SerializeFns};
+use crate::serialize::bits::BitSerialize;
 use crate::serialize::reader::Reader;
 use crate::serialize::SerializationError;
 use crate::shared::events::connection::ConnectionEvents;
+use crate::shared::replication::checksum;
 use crate::shared::replication::delta::{DeltaMessage, Diffable};
 use crate::shared::replication::entity_map::{EntityMap, ReceiveEntityMap};
 
@@ -101,6 +115,9 @@ pub enum ComponentError {
 /// If your component implements the [`Linear`] trait, you can use the [`add_linear_correction_fn`](ComponentRegistration::add_linear_correction_fn) method,
 /// which provides linear interpolation.
 ///
+/// The [`add_correction_policy`](ComponentRegistration::add_correction_policy) method lets you ignore small errors,
+/// snap instantly on large errors, choose the easing curve of the correction, or make the correction duration depend on the size of the error.
+///
 /// #### Interpolation
 /// Similarly to client-prediction, we create two distinct entities on the client when the server replicates an entity: a Confirmed entity and an Interpolated entity.
 /// The Confirmed entity will just get updated when the client receives the server updates, while the Interpolated entity will be updated by the client's interpolation system,
@@ -144,6 +161,12 @@ pub struct ComponentRegistry {
     serialize_fns_map: HashMap<ComponentKind, ErasedSerializeFns>,
     delta_fns_map: HashMap<ComponentKind, ErasedDeltaFns>,
     pub(crate) kind_map: TypeMapper<ComponentKind>,
+    /// Serialization used for components registered without custom [`SerializeFns`]
+    pub(crate) serialization_backend: SerializationBackend,
+    /// Direction in which each component can be replicated
+    direction_map: HashMap<ComponentKind, ChannelDirection>,
+    /// Components that are only known through reflection
+    pub(crate) reflect_fns_map: HashMap<ComponentKind, reflect::ReflectFns>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -153,6 +176,8 @@ pub struct ReplicationMetadata {
     pub replicate_once_id: ComponentId,
     pub override_target_id: ComponentId,
     pub disabled_id: ComponentId,
+    /// `None` for the components registered with reflection, which do not support [`ReplicationFrequency`](crate::prelude::ReplicationFrequency)
+    pub frequency_id: Option<ComponentId>,
     pub write: RawWriteFn,
     pub remove: Option<RawRemoveFn>,
 }
@@ -161,6 +186,10 @@ pub struct ReplicationMetadata {
 pub struct PredictionMetadata {
     pub prediction_mode: ComponentSyncMode,
     pub correction: Option<unsafe fn()>,
+    /// Policy that controls the visual correction depending on the size of the error
+    pub correction_policy: Option<CorrectionPolicyMetadata>,
+    /// Function used to format the component in the [`RollbackInspector`](crate::client::prediction::inspector::RollbackInspector)
+    pub debug: Option<ErasedDebugFn>,
     /// Function used to compare the confirmed component with the predicted component's history
     /// to determine if a rollback is needed. Returns true if we should do a rollback.
     /// Will default to a PartialEq::ne implementation, but can be overriden.
@@ -173,6 +202,8 @@ impl PredictionMetadata {
         Self {
             prediction_mode: mode,
             correction: None,
+            correction_policy: None,
+            debug: None,
             should_rollback: unsafe {
                 std::mem::transmute::<for<'a, 'b> fn(&'a C, &'b C) -> bool, unsafe fn()>(
                     should_rollback,
@@ -182,14 +213,70 @@ impl PredictionMetadata {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Type-erased function used to format a component in the rollback inspector
+#[derive(Debug, Clone, Copy)]
+pub struct ErasedDebugFn(unsafe fn());
+
+impl PartialEq for ErasedDebugFn {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 as usize == other.0 as usize
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CorrectionPolicyMetadata {
+    pub policy: CorrectionPolicy,
+    /// Function used to compute the size of the error between the prediction and the corrected value
+    pub error: unsafe fn(),
+}
+
+// function pointers cannot be compared reliably, so we only compare the policies
+impl PartialEq for CorrectionPolicyMetadata {
+    fn eq(&self, other: &Self) -> bool {
+        self.policy == other.policy
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct InterpolationMetadata {
     pub interpolation_mode: ComponentSyncMode,
     pub interpolation: Option<unsafe fn()>,
     pub custom_interpolation: bool,
+    /// Set if the component is extrapolated when there is no server update to interpolate towards
+    pub extrapolation: Option<ExtrapolationMetadata>,
+    /// Function used to interpolate along a curve that goes through the neighbouring server samples
+    pub curve_interpolation: Option<unsafe fn()>,
+    /// Function used to interpolate with the values of a derivative component
+    pub hermite_interpolation: Option<unsafe fn()>,
 }
 
-type RawRemoveFn = fn(&ComponentRegistry, &mut EntityWorldMut);
+#[derive(Debug, Clone)]
+pub struct ExtrapolationMetadata {
+    pub config: ExtrapolationConfig,
+    /// Custom extrapolation function. If `None`, the interpolation function is used
+    pub extrapolation: Option<unsafe fn()>,
+}
+
+// function pointers cannot be compared reliably, so we only check if the functions are set
+impl PartialEq for InterpolationMetadata {
+    fn eq(&self, other: &Self) -> bool {
+        self.interpolation_mode == other.interpolation_mode
+            && self.interpolation.is_some() == other.interpolation.is_some()
+            && self.custom_interpolation == other.custom_interpolation
+            && self.extrapolation == other.extrapolation
+            && self.curve_interpolation.is_some() == other.curve_interpolation.is_some()
+            && self.hermite_interpolation.is_some() == other.hermite_interpolation.is_some()
+    }
+}
+
+// function pointers cannot be compared reliably, so we only check if a custom function is set
+impl PartialEq for ExtrapolationMetadata {
+    fn eq(&self, other: &Self) -> bool {
+        self.config == other.config && self.extrapolation.is_some() == other.extrapolation.is_some()
+    }
+}
+
+type RawRemoveFn = fn(&ComponentRegistry, ComponentNetId, &mut EntityWorldMut);
 type RawWriteFn = fn(
     &ComponentRegistry,
     &mut Reader,
@@ -235,7 +322,15 @@ impl ComponentRegistry {
 
     /// Return the name of the component from the [`ComponentKind`]
     pub fn name(&self, kind: ComponentKind) -> &'static str {
-        self.serialize_fns_map.get(&kind).unwrap().type_name
+        self.type_name(&kind).unwrap()
+    }
+
+    /// Name of the component, or None if it is not registered
+    fn type_name(&self, kind: &ComponentKind) -> Option<&'static str> {
+        self.serialize_fns_map
+            .get(kind)
+            .map(|fns| fns.type_name)
+            .or_else(|| self.reflect_fns_map.get(kind).map(|fns| fns.type_path))
     }
 
     pub fn is_registered<C: 'static>(&self) -> bool {
@@ -244,7 +339,22 @@ impl ComponentRegistry {
 
     /// Check that the protocol is correct:
     /// - emits warnings for every component that has prediction/interpolation metadata but wasn't registered
-    pub fn check(&self) {
+    pub fn check(&self, channel_registry: &ChannelRegistry) {
+        for (component_kind, direction) in &self.direction_map {
+            for channel_kind in [
+                ChannelKind::of::<EntityActionsChannel>(),
+                ChannelKind::of::<EntityUpdatesChannel>(),
+            ] {
+                let channel_direction = channel_registry
+                    .direction(&channel_kind)
+                    .expect("the replication channels must be registered");
+                if !channel_direction.contains(*direction) {
+                    let name = self.type_name(component_kind).unwrap_or("unknown");
+                    let channel_name = channel_registry.name(&channel_kind).unwrap_or("unknown");
+                    panic!("The Component {name:?} is registered with direction {direction:?} but the replication channel {channel_name:?} only allows {channel_direction:?}");
+                }
+            }
+        }
         for component_kind in self.prediction_map.keys() {
             if !self.serialize_fns_map.contains_key(component_kind) {
                 panic!(
@@ -269,10 +379,82 @@ impl ComponentRegistry {
         }
     }
 
+    pub(crate) fn set_direction<C: 'static>(&mut self, direction: ChannelDirection) {
+        self.direction_map
+            .insert(ComponentKind::of::<C>(), direction);
+    }
+
+    /// Version of the protocol in which the component was added.
+    ///
+    /// The message used to send delta-compressed updates of a component has the same version as the component.
+    pub(crate) fn version(&self, kind: &ComponentKind) -> ProtocolVersion {
+        let kind = self
+            .delta_fns_map
+            .iter()
+            .find(|(_, delta_fns)| delta_fns.delta_kind == *kind)
+            .map_or(kind, |(component_kind, _)| component_kind);
+        self.kind_map.version(kind)
+    }
+
+    /// Description of every component known in the protocol `version`, ordered by net id, used to build the
+    /// [`ProtocolFingerprint`](crate::protocol::fingerprint::ProtocolFingerprint)
+    pub(crate) fn fingerprint_entries(&self, version: ProtocolVersion) -> Vec<String> {
+        self.kind_map
+            .net_ids()
+            .into_iter()
+            .filter_map(|net_id| {
+                let kind = self.kind_map.kind(net_id)?;
+                let since = self.version(kind);
+                if since > version {
+                    return None;
+                }
+                let name = self.type_name(kind).unwrap_or("unknown");
+                let prediction = self.prediction_map.get(kind).map(|p| p.prediction_mode);
+                let interpolation = self
+                    .interpolation_map
+                    .get(kind)
+                    .map(|i| i.interpolation_mode);
+                let delta = self.delta_fns_map.contains_key(kind);
+                Some(format!(
+                    "component {net_id}: {name} since={since} prediction={prediction:?} interpolation={interpolation:?} delta={delta}"
+                ))
+            })
+            .collect()
+    }
+
+    /// Description of every component, ordered by net id, used to build the [`ProtocolSchema`](crate::protocol::schema::ProtocolSchema)
+    pub(crate) fn schema(&self) -> Vec<ComponentSchema> {
+        self.kind_map
+            .net_ids()
+            .into_iter()
+            .filter_map(|net_id| {
+                let kind = self.kind_map.kind(net_id)?;
+                let name = self.type_name(kind).unwrap_or("unknown");
+                Some(ComponentSchema {
+                    net_id,
+                    name: name.to_string(),
+                    direction: self.direction_map.get(kind).map(|d| format!("{d:?}")),
+                    prediction: self
+                        .prediction_map
+                        .get(kind)
+                        .map(|p| format!("{:?}", p.prediction_mode)),
+                    interpolation: self
+                        .interpolation_map
+                        .get(kind)
+                        .map(|i| format!("{:?}", i.interpolation_mode)),
+                    delta_compression: self.delta_fns_map.contains_key(kind),
+                    since: self.version(kind),
+                })
+            })
+            .collect()
+    }
+
     pub(crate) fn register_component<C: Message + Serialize + DeserializeOwned>(&mut self) {
         let component_kind = self.kind_map.add::<C>();
-        self.serialize_fns_map
-            .insert(component_kind, ErasedSerializeFns::new::<C>());
+        self.serialize_fns_map.insert(
+            component_kind,
+            ErasedSerializeFns::new::<C>(self.serialization_backend),
+        );
     }
 
     pub(crate) fn register_component_custom_serde<C: Message>(
@@ -325,6 +507,10 @@ mod serialize {
 
         /// Returns true if we have a registered `map_entities` function for this component type
         pub(crate) fn erased_is_map_entities(&self, kind: ComponentKind) -> bool {
+            // entity mapping is not supported for components registered with reflection
+            if self.reflect_fns_map.contains_key(&kind) {
+                return false;
+            }
             let erased_fns = self
                 .serialize_fns_map
                 .get(&kind)
@@ -361,6 +547,12 @@ mod serialize {
             kind: ComponentKind,
             entity_map: Option<&mut SendEntityMap>,
         ) -> Result<(), ComponentError> {
+            if let Some(reflect_fns) = self.reflect_fns_map.get(&kind) {
+                // SAFETY: the Ptr corresponds to the component registered with reflection
+                return unsafe {
+                    self.erased_serialize_reflect(component, writer, kind, reflect_fns)
+                };
+            }
             let erased_fns = self
                 .serialize_fns_map
                 .get(&kind)
@@ -463,6 +655,62 @@ mod prediction {
                 )
             });
         }
+        pub(crate) fn set_correction_policy<C: Component + PartialEq>(
+            &mut self,
+            policy: CorrectionPolicy,
+            error_fn: CorrectionErrorFn<C>,
+        ) {
+            let kind = ComponentKind::of::<C>();
+            self.prediction_map
+                .entry(kind)
+                .or_insert_with(|| PredictionMetadata::default_from::<C>(ComponentSyncMode::Full))
+                .correction_policy = Some(CorrectionPolicyMetadata {
+                policy,
+                error: unsafe {
+                    std::mem::transmute::<for<'a, 'b> fn(&'a C, &'b C) -> f32, unsafe fn()>(
+                        error_fn,
+                    )
+                },
+            });
+        }
+
+        pub(crate) fn correction_policy<C: Component>(
+            &self,
+        ) -> Option<(CorrectionPolicy, CorrectionErrorFn<C>)> {
+            let kind = ComponentKind::of::<C>();
+            self.prediction_map
+                .get(&kind)
+                .and_then(|metadata| metadata.correction_policy.as_ref())
+                .map(|metadata| {
+                    let error_fn: CorrectionErrorFn<C> =
+                        unsafe { std::mem::transmute(metadata.error) };
+                    (metadata.policy, error_fn)
+                })
+        }
+
+        pub(crate) fn set_rollback_debug<C: Component + PartialEq + Debug>(&mut self) {
+            let kind = ComponentKind::of::<C>();
+            let debug_fn: fn(&C) -> String = |component| format!("{:?}", component);
+            self.prediction_map
+                .entry(kind)
+                .or_insert_with(|| PredictionMetadata::default_from::<C>(ComponentSyncMode::Full))
+                .debug = Some(ErasedDebugFn(unsafe {
+                std::mem::transmute::<for<'a> fn(&'a C) -> String, unsafe fn()>(debug_fn)
+            }));
+        }
+
+        /// Debug representation of the component, if it was registered with `add_rollback_debug`
+        pub(crate) fn rollback_debug<C: Component>(&self, component: &C) -> Option<String> {
+            let kind = ComponentKind::of::<C>();
+            self.prediction_map
+                .get(&kind)
+                .and_then(|metadata| metadata.debug)
+                .map(|debug_fn| {
+                    let debug_fn: fn(&C) -> String = unsafe { std::mem::transmute(debug_fn.0) };
+                    debug_fn(component)
+                })
+        }
+
         pub(crate) fn prediction_mode<C: Component>(&self) -> ComponentSyncMode {
             let kind = ComponentKind::of::<C>();
             self.prediction_map
@@ -514,6 +762,9 @@ mod interpolation {
                     interpolation_mode: mode,
                     interpolation: None,
                     custom_interpolation: false,
+                    extrapolation: None,
+                    curve_interpolation: None,
+                    hermite_interpolation: None,
                 })
                 .interpolation_mode = mode;
         }
@@ -530,6 +781,9 @@ mod interpolation {
                     interpolation_mode: ComponentSyncMode::Full,
                     interpolation: None,
                     custom_interpolation: false,
+                    extrapolation: None,
+                    curve_interpolation: None,
+                    hermite_interpolation: None,
                 })
                 .interpolation = Some(unsafe {
                 std::mem::transmute::<for<'a, 'b> fn(&'a C, &'b C, f32) -> C, unsafe fn()>(
@@ -555,6 +809,152 @@ mod interpolation {
                 unsafe { std::mem::transmute(interpolation_metadata.interpolation.unwrap()) };
             interpolation_fn(start, end, t)
         }
+
+        pub(crate) fn set_extrapolation_config<C: Component>(
+            &mut self,
+            config: ExtrapolationConfig,
+        ) {
+            let kind = ComponentKind::of::<C>();
+            let metadata = self.interpolation_map.get_mut(&kind).expect(
+                "the component must be registered for interpolation before enabling extrapolation",
+            );
+            match &mut metadata.extrapolation {
+                Some(extrapolation) => extrapolation.config = config,
+                None => {
+                    metadata.extrapolation = Some(ExtrapolationMetadata {
+                        config,
+                        extrapolation: None,
+                    })
+                }
+            }
+        }
+
+        pub(crate) fn set_extrapolation<C: Component>(
+            &mut self,
+            extrapolation_fn: ExtrapolationFn<C>,
+        ) {
+            let kind = ComponentKind::of::<C>();
+            let metadata = self.interpolation_map.get_mut(&kind).expect(
+                "the component must be registered for interpolation before enabling extrapolation",
+            );
+            metadata
+                .extrapolation
+                .get_or_insert_with(|| ExtrapolationMetadata {
+                    config: ExtrapolationConfig::default(),
+                    extrapolation: None,
+                })
+                .extrapolation = Some(unsafe {
+                std::mem::transmute::<for<'a, 'b> fn(&'a C, &'b C, f32) -> C, unsafe fn()>(
+                    extrapolation_fn,
+                )
+            });
+        }
+
+        pub(crate) fn extrapolation_config<C: Component>(&self) -> Option<ExtrapolationConfig> {
+            let kind = ComponentKind::of::<C>();
+            self.interpolation_map
+                .get(&kind)
+                .and_then(|metadata| metadata.extrapolation.as_ref())
+                .map(|extrapolation| extrapolation.config)
+        }
+
+        pub(crate) fn extrapolate<C: Component>(&self, previous: &C, last: &C, t: f32) -> C {
+            let kind = ComponentKind::of::<C>();
+            let interpolation_metadata = self
+                .interpolation_map
+                .get(&kind)
+                .expect("the component is not part of the protocol");
+            match interpolation_metadata
+                .extrapolation
+                .as_ref()
+                .and_then(|e| e.extrapolation)
+            {
+                Some(extrapolation_fn) => {
+                    let extrapolation_fn: ExtrapolationFn<C> =
+                        unsafe { std::mem::transmute(extrapolation_fn) };
+                    extrapolation_fn(previous, last, t)
+                }
+                // keep the velocity between the last two values
+                None => self.interpolate(previous, last, 1.0 + t),
+            }
+        }
+
+        pub(crate) fn set_curve_interpolation<C: Component>(&mut self, curve_fn: CurveFn<C>) {
+            let kind = ComponentKind::of::<C>();
+            let metadata = self.interpolation_map.get_mut(&kind).expect(
+                "the component must be registered for interpolation before adding a curve interpolation function",
+            );
+            metadata.curve_interpolation = Some(unsafe {
+                std::mem::transmute::<for<'a, 'b> fn(&'a CurveSamples<'b, C>, f32) -> C, unsafe fn()>(
+                    curve_fn,
+                )
+            });
+        }
+
+        pub(crate) fn set_hermite_interpolation<C: Component, D: Component>(
+            &mut self,
+            hermite_fn: HermiteFn<C, D>,
+        ) {
+            let kind = ComponentKind::of::<C>();
+            let metadata = self.interpolation_map.get_mut(&kind).expect(
+                "the component must be registered for interpolation before adding a hermite interpolation function",
+            );
+            metadata.hermite_interpolation = Some(unsafe {
+                std::mem::transmute::<
+                    for<'a, 'b, 'c, 'd> fn(&'a CurveSamples<'b, C>, &'c D, &'d D, f32) -> C,
+                    unsafe fn(),
+                >(hermite_fn)
+            });
+        }
+
+        pub(crate) fn has_curve_interpolation<C: Component>(&self) -> bool {
+            let kind = ComponentKind::of::<C>();
+            self.interpolation_map
+                .get(&kind)
+                .is_some_and(|metadata| metadata.curve_interpolation.is_some())
+        }
+
+        pub(crate) fn has_hermite_interpolation<C: Component>(&self) -> bool {
+            let kind = ComponentKind::of::<C>();
+            self.interpolation_map
+                .get(&kind)
+                .is_some_and(|metadata| metadata.hermite_interpolation.is_some())
+        }
+
+        pub(crate) fn interpolate_curve<C: Component>(
+            &self,
+            samples: &CurveSamples<C>,
+            t: f32,
+        ) -> C {
+            let kind = ComponentKind::of::<C>();
+            let interpolation_metadata = self
+                .interpolation_map
+                .get(&kind)
+                .expect("the component is not part of the protocol");
+            let curve_fn: CurveFn<C> =
+                unsafe { std::mem::transmute(interpolation_metadata.curve_interpolation.unwrap()) };
+            curve_fn(samples, t)
+        }
+
+        /// Interpolate the component `C` using the values of its derivative `D`.
+        /// `D` must be the derivative component that the hermite function was registered with.
+        pub(crate) fn interpolate_hermite<C: Component, D: Component>(
+            &self,
+            samples: &CurveSamples<C>,
+            start_derivative: &D,
+            end_derivative: &D,
+            t: f32,
+        ) -> C {
+            let kind = ComponentKind::of::<C>();
+            let interpolation_metadata = self
+                .interpolation_map
+                .get(&kind)
+                .expect("the component is not part of the protocol");
+            let hermite_fn: HermiteFn<C, D> = unsafe {
+                std::mem::transmute(interpolation_metadata.hermite_interpolation.unwrap())
+            };
+            hermite_fn(samples, start_derivative, end_derivative, t)
+        }
     }
 }
 
@@ -562,6 +962,7 @@ mod replication {
     use super::*;
     use crate::prelude::{
         DeltaCompression, DisabledComponent, OverrideTargetComponent, ReplicateOnceComponent,
+        ReplicationFrequency,
     };
     use crate::serialize::reader::Reader;
     use crate::serialize::ToBytes;
@@ -580,6 +981,7 @@ mod replication {
                     replicate_once_id: world.init_component::<ReplicateOnceComponent<C>>(),
                     override_target_id: world.init_component::<OverrideTargetComponent<C>>(),
                     disabled_id: world.init_component::<DisabledComponent<C>>(),
+                    frequency_id: Some(world.init_component::<ReplicationFrequency<C>>()),
                     write,
                     remove: Some(remove),
                 },
@@ -646,7 +1048,14 @@ mod replication {
             net_id: ComponentNetId,
             entity_world_mut: &mut EntityWorldMut,
         ) {
-            let kind = self.kind_map.kind(net_id).expect("unknown component kind");
+            let Some(kind) = self.kind_map.kind(net_id) else {
+                // the remote peer can use a newer version of the protocol, with components that we don't know
+                warn!(
+                    ?net_id,
+                    "Received a removal for an unknown component, ignoring it"
+                );
+                return;
+            };
             let replication_metadata = self
                 .replication_map
                 .get(kind)
@@ -654,10 +1063,14 @@ mod replication {
             let f = replication_metadata
                 .remove
                 .expect("the component does not have a remove function");
-            f(self, entity_world_mut);
+            f(self, net_id, entity_world_mut);
         }
 
-        pub(crate) fn remove<C: Component>(&self, entity_world_mut: &mut EntityWorldMut) {
+        pub(crate) fn remove<C: Component>(
+            &self,
+            _net_id: ComponentNetId,
+            entity_world_mut: &mut EntityWorldMut,
+        ) {
             entity_world_mut.remove::<C>();
         }
     }
@@ -697,6 +1110,7 @@ mod delta {
                     replicate_once_id: ComponentId::new(0),
                     override_target_id: ComponentId::new(0),
                     disabled_id: ComponentId::new(0),
+                    frequency_id: None,
                     write,
                     remove: None,
                 },
@@ -857,6 +1271,258 @@ mod delta {
     }
 }
 
+mod reflect {
+    use super::*;
+    use crate::prelude::{
+        DeltaCompression, DisabledComponent, OverrideTargetComponent, ReplicateOnceComponent,
+    };
+    use crate::serialize::bits::{BitReader, BitWriter};
+    use crate::serialize::writer::Writer;
+    use crate::serialize::ToBytes;
+    use bevy::ecs::component::ComponentDescriptor;
+    use bevy::prelude::ReflectComponent;
+    use bevy::reflect::serde::{TypedReflectDeserializer, TypedReflectSerializer};
+    use bevy::reflect::{Reflect, ReflectFromPtr, TypeRegistration};
+    use serde::de::DeserializeSeed;
+
+    /// Functions used to replicate a component that is only known through reflection
+    #[derive(Clone)]
+    pub(crate) struct ReflectFns {
+        pub(crate) type_path: &'static str,
+        registration: TypeRegistration,
+        type_registry: AppTypeRegistry,
+        from_ptr: ReflectFromPtr,
+        component: ReflectComponent,
+    }
+
+    impl Debug for ReflectFns {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("ReflectFns")
+                .field("type_path", &self.type_path)
+                .finish()
+        }
+    }
+
+    impl PartialEq for ReflectFns {
+        fn eq(&self, other: &Self) -> bool {
+            self.registration.type_id() == other.registration.type_id()
+        }
+    }
+
+    impl ReflectFns {
+        fn serialize(
+            &self,
+            value: &dyn Reflect,
+            writer: &mut Writer,
+            backend: SerializationBackend,
+        ) -> Result<(), SerializationError> {
+            let type_registry = self.type_registry.read();
+            let serializer = TypedReflectSerializer::new(value, &type_registry);
+            match backend {
+                SerializationBackend::Bincode => {
+                    bincode::serde::encode_into_std_write(
+                        &serializer,
+                        writer,
+                        bincode::config::standard(),
+                    )?;
+                }
+                SerializationBackend::BitPacked => {
+                    let mut bit_writer = BitWriter::new(writer);
+                    serializer.serialize(&mut bit_writer)?;
+                    bit_writer.finish()?;
+                }
+            }
+            Ok(())
+        }
+
+        fn deserialize(
+            &self,
+            reader: &mut Reader,
+            backend: SerializationBackend,
+        ) -> Result<Box<dyn Reflect>, SerializationError> {
+            let type_registry = self.type_registry.read();
+            let seed = TypedReflectDeserializer::new(&self.registration, &type_registry);
+            match backend {
+                SerializationBackend::Bincode => {
+                    let (value, len) = bincode::serde::seed_decode_from_slice(
+                        seed,
+                        reader.remaining_slice(),
+                        bincode::config::standard(),
+                    )?;
+                    reader.advance(len);
+                    Ok(value)
+                }
+                SerializationBackend::BitPacked => {
+                    Ok(seed.deserialize(&mut BitReader::new(reader))?)
+                }
+            }
+        }
+    }
+
+    impl ComponentRegistry {
+        pub(crate) fn register_component_reflect(
+            &mut self,
+            registration: &TypeRegistration,
+            type_registry: AppTypeRegistry,
+        ) -> ComponentKind {
+            let type_path = registration.type_info().type_path();
+            let component = registration
+                .data::<ReflectComponent>()
+                .unwrap_or_else(|| {
+                    panic!("Component {type_path:?} must reflect `Component` to be registered with reflection")
+                })
+                .clone();
+            let from_ptr = registration
+                .data::<ReflectFromPtr>()
+                .unwrap_or_else(|| {
+                    panic!("Component {type_path:?} must have `ReflectFromPtr` type data to be registered with reflection")
+                })
+                .clone();
+            let kind = self
+                .kind_map
+                .add_kind(ComponentKind(registration.type_id()), type_path);
+            self.reflect_fns_map.insert(
+                kind,
+                ReflectFns {
+                    type_path,
+                    registration: registration.clone(),
+                    type_registry,
+                    from_ptr,
+                    component,
+                },
+            );
+            kind
+        }
+
+        /// Returns true if the component was registered with reflection
+        pub(crate) fn is_reflect(&self, kind: &ComponentKind) -> bool {
+            self.reflect_fns_map.contains_key(kind)
+        }
+
+        pub(crate) fn set_reflect_direction(
+            &mut self,
+            kind: ComponentKind,
+            direction: ChannelDirection,
+        ) {
+            self.direction_map.insert(kind, direction);
+        }
+
+        /// Components registered with reflection that can be replicated in the given direction
+        pub(crate) fn reflect_replication(
+            &self,
+            direction: ChannelDirection,
+        ) -> impl Iterator<Item = (ComponentNetId, &ReplicationMetadata)> {
+            self.reflect_fns_map.keys().filter_map(move |kind| {
+                if !self.direction_map.get(kind)?.contains(direction) {
+                    return None;
+                }
+                Some((
+                    *self.kind_map.net_id(kind)?,
+                    self.replication_map.get(kind)?,
+                ))
+            })
+        }
+
+        pub(crate) fn set_reflect_replication_fns(
+            &mut self,
+            kind: ComponentKind,
+            world: &mut World,
+        ) {
+            let type_path = self.reflect_fns_map.get(&kind).unwrap().type_path;
+            let component_id = world.components().get_id(kind.0).unwrap_or_else(|| {
+                panic!("Component {type_path:?} must be initialized in the World (with `World::init_component`) before being registered with reflection")
+            });
+            let write: RawWriteFn = Self::write_reflect;
+            let remove: RawRemoveFn = Self::remove_reflect;
+            // the marker components are generic over the component type, which is not known here:
+            // we create dedicated ComponentIds for them instead
+            self.replication_map.insert(
+                kind,
+                ReplicationMetadata {
+                    component_id,
+                    delta_compression_id: world.init_component_with_descriptor(
+                        ComponentDescriptor::new::<DeltaCompression<()>>(),
+                    ),
+                    replicate_once_id: world.init_component_with_descriptor(
+                        ComponentDescriptor::new::<ReplicateOnceComponent<()>>(),
+                    ),
+                    override_target_id: world.init_component_with_descriptor(
+                        ComponentDescriptor::new::<OverrideTargetComponent<()>>(),
+                    ),
+                    disabled_id: world.init_component_with_descriptor(ComponentDescriptor::new::<
+                        DisabledComponent<()>,
+                    >()),
+                    // the ReplicationFrequency timers are ticked by systems that are generic over the component type
+                    frequency_id: None,
+                    write,
+                    remove: Some(remove),
+                },
+            );
+        }
+
+        /// SAFETY: the Ptr must correspond to the component registered with reflection
+        pub(super) unsafe fn erased_serialize_reflect(
+            &self,
+            component: Ptr,
+            writer: &mut Writer,
+            kind: ComponentKind,
+            reflect_fns: &ReflectFns,
+        ) -> Result<(), ComponentError> {
+            let net_id = self.kind_map.net_id(&kind).unwrap();
+            net_id.to_bytes(writer)?;
+            let value = reflect_fns.from_ptr.as_reflect(component);
+            reflect_fns.serialize(value, writer, self.serialization_backend)?;
+            Ok(())
+        }
+
+        pub(crate) fn write_reflect(
+            &self,
+            reader: &mut Reader,
+            net_id: ComponentNetId,
+            tick: Tick,
+            entity_world_mut: &mut EntityWorldMut,
+            _entity_map: &mut ReceiveEntityMap,
+            events: &mut ConnectionEvents,
+        ) -> Result<(), ComponentError> {
+            let reflect_fns = self
+                .kind_map
+                .kind(net_id)
+                .and_then(|kind| self.reflect_fns_map.get(kind))
+                .ok_or(ComponentError::MissingSerializationFns)?;
+            trace!("Writing component {} to entity", reflect_fns.type_path);
+            let component = reflect_fns.deserialize(reader, self.serialization_backend)?;
+            let entity = entity_world_mut.id();
+            match reflect_fns.component.reflect(&*entity_world_mut) {
+                // only apply the update if the component is different, to not trigger change detection
+                Some(c) if c.reflect_partial_eq(component.as_ref()) == Some(true) => return Ok(()),
+                Some(_) => events.push_update_component(entity, net_id, tick),
+                None => events.push_insert_component(entity, net_id, tick),
+            }
+            let type_registry = reflect_fns.type_registry.read();
+            reflect_fns.component.apply_or_insert(
+                entity_world_mut,
+                component.as_ref(),
+                &type_registry,
+            );
+            Ok(())
+        }
+
+        pub(crate) fn remove_reflect(
+            &self,
+            net_id: ComponentNetId,
+            entity_world_mut: &mut EntityWorldMut,
+        ) {
+            if let Some(reflect_fns) = self
+                .kind_map
+                .kind(net_id)
+                .and_then(|kind| self.reflect_fns_map.get(kind))
+            {
+                reflect_fns.component.remove(entity_world_mut);
+            }
+        }
+    }
+}
+
 fn register_component_send<C: Component>(app: &mut App, direction: ChannelDirection) {
     let is_client = app.world().get_resource::<ClientConfig>().is_some();
     let is_server = app.world().get_resource::<ServerConfig>().is_some();
@@ -910,6 +1576,34 @@ pub trait AppComponentExt {
         serialize_fns: SerializeFns<C>,
     ) -> ComponentRegistration<'_, C>;
 
+    /// Registers the component in the Registry: this component can now be sent over the network.
+    ///
+    /// The component will be serialized with its bit-packed [`BitSerialize`] implementation.
+    fn register_component_bitpacked<C: Component + Message + PartialEq + BitSerialize>(
+        &mut self,
+        direction: ChannelDirection,
+    ) -> ComponentRegistration<'_, C>;
+
+    /// Registers a component that is only known through reflection (for example a component defined by a mod)
+    /// from its [`TypeRegistration`]: this component can now be sent over the network.
+    ///
+    /// The component is serialized with its reflected representation, using the [`SerializationBackend`] of the registry.
+    /// It needs the [`ReflectComponent`](bevy::prelude::ReflectComponent) and [`ReflectFromPtr`](bevy::reflect::ReflectFromPtr) type data,
+    /// and must already be initialized in the [`World`].
+    ///
+    /// Compared to [`register_component`](AppComponentExt::register_component), there are some limitations:
+    /// - the typed replication events (e.g. `ComponentInsertEvent<C>`) are not emitted
+    /// - prediction, interpolation, entity mapping, delta compression and [`ReplicationFrequency`](crate::prelude::ReplicationFrequency)
+    ///   are not supported
+    /// - [`DisabledComponent`](crate::prelude::DisabledComponent), [`ReplicateOnceComponent`](crate::prelude::ReplicateOnceComponent)
+    ///   and [`OverrideTargetComponent`](crate::prelude::OverrideTargetComponent) cannot be named for the component, use
+    ///   [`ReflectReplicationCommandsExt`](crate::prelude::ReflectReplicationCommandsExt) instead
+    fn register_component_reflect(
+        &mut self,
+        registration: &TypeRegistration,
+        direction: ChannelDirection,
+    ) -> ReflectComponentRegistration<'_>;
+
     /// Enable rollbacks for a component even if the component is not networked
     fn add_rollback<C: Component + PartialEq + Clone>(&mut self);
 
@@ -926,12 +1620,31 @@ pub trait AppComponentExt {
     /// Add a `Correction` behaviour to this component.
     fn add_correction_fn<C: SyncComponent>(&mut self, correction_fn: LerpFn<C>);
 
+    /// Control the visual correction of this component depending on the size of the error
+    /// between the prediction and the corrected value, computed with `error_fn`.
+    fn add_correction_policy<C: SyncComponent>(
+        &mut self,
+        policy: CorrectionPolicy,
+        error_fn: CorrectionErrorFn<C>,
+    );
+
+    /// Record the predicted and confirmed values of this component (using their `Debug` representation)
+    /// in the [`RollbackInspector`](crate::client::prediction::inspector::RollbackInspector)
+    /// when they trigger a rollback.
+    fn add_rollback_debug<C: SyncComponent + Debug>(&mut self);
+
     /// Add a custom function to use for checking if a rollback is needed.
     ///
     /// (By default we use the PartialEq::ne function, but you can use this to override the
     ///  equality check. For example, you might want to add a threshold for floating point numbers)
     fn add_should_rollback_fn<C: SyncComponent>(&mut self, should_rollback: ShouldRollbackFn<C>);
 
+    /// Include this component in the state checksums sent by the server, so that clients
+    /// can detect when their predicted value diverges from the server's value.
+    ///
+    /// See [`checksum`](crate::shared::replication::checksum).
+    fn add_checksum<C: SyncComponent + Debug>(&mut self);
+
     /// Register helper systems to perform interpolation for the component; but the user has to define the interpolation logic
     /// themselves (the interpolation_fn will not be used)
     fn add_custom_interpolation<C: SyncComponent>(&mut self, interpolation_mode: ComponentSyncMode);
@@ -946,6 +1659,26 @@ pub trait AppComponentExt {
     /// Add a `Interpolation` behaviour to this component.
     fn add_interpolation_fn<C: SyncComponent>(&mut self, interpolation_fn: LerpFn<C>);
 
+    /// Extrapolate this component when there is no server update to interpolate towards.
+    /// The component must be registered for [`ComponentSyncMode::Full`] interpolation.
+    fn add_extrapolation<C: SyncComponent>(&mut self, config: ExtrapolationConfig);
+
+    /// Use a custom function to extrapolate this component (instead of the interpolation function).
+    fn add_extrapolation_fn<C: SyncComponent>(&mut self, extrapolation_fn: ExtrapolationFn<C>);
+
+    /// Interpolate this component along a curve that goes through the neighbouring server samples,
+    /// instead of a straight line between the two surrounding samples.
+    /// The component must be registered for [`ComponentSyncMode::Full`] interpolation.
+    fn add_curve_interpolation_fn<C: SyncComponent>(&mut self, curve_fn: CurveFn<C>);
+
+    /// Interpolate this component with a cubic Hermite spline, using the values of the derivative component `D`
+    /// (for example the velocity of a position) at the start and at the end of the interpolation interval.
+    /// The component must be registered for [`ComponentSyncMode::Full`] interpolation.
+    fn add_hermite_interpolation_fn<C: SyncComponent, D: SyncComponent>(
+        &mut self,
+        hermite_fn: HermiteFn<C, D>,
+    );
+
     /// Enable delta compression when serializing this component
     fn add_delta_compression<C: Component + PartialEq + Diffable>(&mut self)
     where
@@ -958,6 +1691,30 @@ pub struct ComponentRegistration<'a, C> {
 }
 
 impl<C> ComponentRegistration<'_, C> {
+    /// Use an explicit network id for the component, instead of an id derived from the registration order.
+    ///
+    /// This keeps the id stable across versions of the protocol, even if components are added or removed.
+    pub fn with_net_id(self, net_id: u16) -> Self
+    where
+        C: 'static,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<ComponentRegistry>();
+        registry.kind_map.set_net_id::<C>(net_id);
+        self
+    }
+
+    /// Specify that the component was added in the given [`ProtocolVersion`].
+    ///
+    /// The component won't be replicated to peers that use an older version of the protocol.
+    pub fn since_version(self, version: ProtocolVersion) -> Self
+    where
+        C: 'static,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<ComponentRegistry>();
+        registry.kind_map.set_version::<C>(version);
+        self
+    }
+
     /// Specify that the component contains entities which should be mapped from the remote world to the local world
     /// upon deserialization
     pub fn add_map_entities(self) -> Self
@@ -997,6 +1754,43 @@ impl<C> ComponentRegistration<'_, C> {
         self
     }
 
+    /// Control the visual correction of this component depending on the size of the error
+    /// between the prediction and the corrected value, computed with `error_fn`.
+    ///
+    /// The component must also have a correction function (see [`add_correction_fn`](Self::add_correction_fn)).
+    pub fn add_correction_policy(
+        self,
+        policy: CorrectionPolicy,
+        error_fn: CorrectionErrorFn<C>,
+    ) -> Self
+    where
+        C: SyncComponent,
+    {
+        self.app.add_correction_policy::<C>(policy, error_fn);
+        self
+    }
+
+    /// Record the predicted and confirmed values of this component (using their `Debug` representation)
+    /// in the [`RollbackInspector`](crate::client::prediction::inspector::RollbackInspector)
+    /// when they trigger a rollback.
+    pub fn add_rollback_debug(self) -> Self
+    where
+        C: SyncComponent + Debug,
+    {
+        self.app.add_rollback_debug::<C>();
+        self
+    }
+
+    /// Include this component in the state checksums sent by the server, so that clients
+    /// can detect when their predicted value diverges from the server's value.
+    pub fn add_checksum(self) -> Self
+    where
+        C: SyncComponent + Debug,
+    {
+        self.app.add_checksum::<C>();
+        self
+    }
+
     /// Add a custom function to use for checking if a rollback is needed.
     ///
     /// (By default we use the PartialEq::ne function, but you can use this to override the
@@ -1047,6 +1841,47 @@ impl<C> ComponentRegistration<'_, C> {
         self
     }
 
+    /// Extrapolate this component when there is no server update to interpolate towards.
+    /// The component must be registered for [`ComponentSyncMode::Full`] interpolation.
+    pub fn add_extrapolation(self, config: ExtrapolationConfig) -> Self
+    where
+        C: SyncComponent,
+    {
+        self.app.add_extrapolation::<C>(config);
+        self
+    }
+
+    /// Use a custom function to extrapolate this component (instead of the interpolation function).
+    pub fn add_extrapolation_fn(self, extrapolation_fn: ExtrapolationFn<C>) -> Self
+    where
+        C: SyncComponent,
+    {
+        self.app.add_extrapolation_fn::<C>(extrapolation_fn);
+        self
+    }
+
+    /// Interpolate this component along a curve that goes through the neighbouring server samples,
+    /// instead of a straight line between the two surrounding samples.
+    /// The component must be registered for [`ComponentSyncMode::Full`] interpolation.
+    pub fn add_curve_interpolation_fn(self, curve_fn: CurveFn<C>) -> Self
+    where
+        C: SyncComponent,
+    {
+        self.app.add_curve_interpolation_fn::<C>(curve_fn);
+        self
+    }
+
+    /// Interpolate this component with a cubic Hermite spline, using the values of the derivative component `D`
+    /// (for example the velocity of a position) at the start and at the end of the interpolation interval.
+    /// The component must be registered for [`ComponentSyncMode::Full`] interpolation.
+    pub fn add_hermite_interpolation_fn<D: SyncComponent>(self, hermite_fn: HermiteFn<C, D>) -> Self
+    where
+        C: SyncComponent,
+    {
+        self.app.add_hermite_interpolation_fn::<C, D>(hermite_fn);
+        self
+    }
+
     /// Enable delta compression when serializing this component
     pub fn add_delta_compression(self) -> Self
     where
@@ -1058,6 +1893,33 @@ impl<C> ComponentRegistration<'_, C> {
     }
 }
 
+/// Returned by [`register_component_reflect`](AppComponentExt::register_component_reflect)
+pub struct ReflectComponentRegistration<'a> {
+    app: &'a mut App,
+    kind: ComponentKind,
+}
+
+impl ReflectComponentRegistration<'_> {
+    /// Use an explicit network id for the component, instead of an id derived from the registration order.
+    ///
+    /// This keeps the id stable across versions of the protocol, even if components are added or removed.
+    pub fn with_net_id(self, net_id: u16) -> Self {
+        let mut registry = self.app.world_mut().resource_mut::<ComponentRegistry>();
+        let name = registry.name(self.kind);
+        registry.kind_map.set_kind_net_id(self.kind, name, net_id);
+        self
+    }
+
+    /// Specify that the component was added in the given [`ProtocolVersion`].
+    ///
+    /// The component won't be replicated to peers that use an older version of the protocol.
+    pub fn since_version(self, version: ProtocolVersion) -> Self {
+        let mut registry = self.app.world_mut().resource_mut::<ComponentRegistry>();
+        registry.kind_map.set_kind_version(self.kind, version);
+        self
+    }
+}
+
 impl AppComponentExt for App {
     fn register_component<C: Component + Message + PartialEq + Serialize + DeserializeOwned>(
         &mut self,
@@ -1068,6 +1930,7 @@ impl AppComponentExt for App {
                 if !registry.is_registered::<C>() {
                     registry.register_component::<C>();
                 }
+                registry.set_direction::<C>(direction);
                 registry.set_replication_fns::<C>(world);
                 debug!("register component {}", std::any::type_name::<C>());
             });
@@ -1088,6 +1951,7 @@ impl AppComponentExt for App {
                 if !registry.is_registered::<C>() {
                     registry.register_component_custom_serde::<C>(serialize_fns);
                 }
+                registry.set_direction::<C>(direction);
                 registry.set_replication_fns::<C>(world);
                 debug!("register component {}", std::any::type_name::<C>());
             });
@@ -1098,6 +1962,33 @@ impl AppComponentExt for App {
         }
     }
 
+    fn register_component_bitpacked<C: Component + Message + PartialEq + BitSerialize>(
+        &mut self,
+        direction: ChannelDirection,
+    ) -> ComponentRegistration<'_, C> {
+        self.register_component_custom_serde(direction, SerializeFns::bitpacked())
+    }
+
+    fn register_component_reflect(
+        &mut self,
+        registration: &TypeRegistration,
+        direction: ChannelDirection,
+    ) -> ReflectComponentRegistration<'_> {
+        let type_registry = self.world().resource::<AppTypeRegistry>().clone();
+        let kind =
+            self.world_mut()
+                .resource_scope(|world, mut registry: Mut<ComponentRegistry>| {
+                    let kind = registry.register_component_reflect(registration, type_registry);
+                    registry.set_reflect_direction(kind, direction);
+                    registry.set_reflect_replication_fns(kind, world);
+                    debug!("register component {}", registry.name(kind));
+                    kind
+                });
+        // the inserts and updates are sent by the `replicate` systems, and the removals by the
+        // `send_reflect_component_removed` systems, which handle every component registered with reflection
+        ReflectComponentRegistration { app: self, kind }
+    }
+
     // TODO: move this away from protocol? since it doesn't even use the registry at all
     //  maybe put this in the PredictionPlugin?
     fn add_rollback<C: Component + PartialEq + Clone>(&mut self) {
@@ -1137,11 +2028,36 @@ impl AppComponentExt for App {
         registry.set_correction::<C>(correction_fn);
     }
 
+    fn add_correction_policy<C: SyncComponent>(
+        &mut self,
+        policy: CorrectionPolicy,
+        error_fn: CorrectionErrorFn<C>,
+    ) {
+        let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
+        registry.set_correction_policy::<C>(policy, error_fn);
+    }
+
+    fn add_rollback_debug<C: SyncComponent + Debug>(&mut self) {
+        let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
+        registry.set_rollback_debug::<C>();
+    }
+
     fn add_should_rollback_fn<C: SyncComponent>(&mut self, rollback_check: ShouldRollbackFn<C>) {
         let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
         registry.set_should_rollback::<C>(rollback_check);
     }
 
+    fn add_checksum<C: SyncComponent + Debug>(&mut self) {
+        let is_client = self.world().get_resource::<ClientConfig>().is_some();
+        let is_server = self.world().get_resource::<ServerConfig>().is_some();
+        if is_client {
+            checksum::receive::add_checksum_receive_systems::<C>(self);
+        }
+        if is_server {
+            checksum::send::add_checksum_send_systems::<C>(self);
+        }
+    }
+
     fn add_custom_interpolation<C: SyncComponent>(
         &mut self,
         interpolation_mode: ComponentSyncMode,
@@ -1186,6 +2102,57 @@ impl AppComponentExt for App {
         registry.set_interpolation::<C>(interpolation_fn);
     }
 
+    fn add_extrapolation<C: SyncComponent>(&mut self, config: ExtrapolationConfig) {
+        let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
+        let already_enabled = registry.extrapolation_config::<C>().is_some();
+        registry.set_extrapolation_config::<C>(config);
+        let is_client = self.world().get_resource::<ClientConfig>().is_some();
+        if is_client && !already_enabled {
+            add_extrapolation_systems::<C>(self);
+        }
+    }
+
+    fn add_extrapolation_fn<C: SyncComponent>(&mut self, extrapolation_fn: ExtrapolationFn<C>) {
+        let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
+        let already_enabled = registry.extrapolation_config::<C>().is_some();
+        registry.set_extrapolation::<C>(extrapolation_fn);
+        let is_client = self.world().get_resource::<ClientConfig>().is_some();
+        if is_client && !already_enabled {
+            add_extrapolation_systems::<C>(self);
+        }
+    }
+
+    fn add_curve_interpolation_fn<C: SyncComponent>(&mut self, curve_fn: CurveFn<C>) {
+        let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
+        let has_curve = registry.has_curve_interpolation::<C>();
+        let has_hermite = registry.has_hermite_interpolation::<C>();
+        registry.set_curve_interpolation::<C>(curve_fn);
+        let is_client = self.world().get_resource::<ClientConfig>().is_some();
+        if is_client && !has_curve {
+            if !has_hermite {
+                add_curve_status_systems::<C>(self);
+            }
+            add_curve_interpolation_systems::<C>(self);
+        }
+    }
+
+    fn add_hermite_interpolation_fn<C: SyncComponent, D: SyncComponent>(
+        &mut self,
+        hermite_fn: HermiteFn<C, D>,
+    ) {
+        let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
+        let has_curve = registry.has_curve_interpolation::<C>();
+        let has_hermite = registry.has_hermite_interpolation::<C>();
+        registry.set_hermite_interpolation::<C, D>(hermite_fn);
+        let is_client = self.world().get_resource::<ClientConfig>().is_some();
+        if is_client && !has_hermite {
+            if !has_curve {
+                add_curve_status_systems::<C>(self);
+            }
+            add_hermite_interpolation_systems::<C, D>(self);
+        }
+    }
+
     fn add_delta_compression<C: Component + PartialEq + Diffable>(&mut self)
     where
         C::Delta: Serialize + DeserializeOwned,
@@ -0,0 +1,272 @@
+//! Fingerprint of the protocol, used to check during the connection handshake that the client and
+//! the server registered the same channels, components and messages.
+//!
+//! When a client connects, it sends its [`ProtocolFingerprint`] to the server on the
+//! [`HandshakeChannel`](crate::channel::builder::HandshakeChannel). If the fingerprint does not match
+//! the server's, the server replies with [`DeniedReason::ProtocolMismatch`] (naming the first entry that
+//! differs) and disconnects the client.
+//!
+//! Until the fingerprint has been accepted, the server does not process the other messages of the client
+//! (they stay buffered in their channels), and it denies clients that do not send their fingerprint within
+//! [`PacketConfig::handshake_timeout`](crate::server::config::PacketConfig::handshake_timeout).
+//!
+//! The fingerprint only contains the types that are part of the client's [`ProtocolVersion`], so that
+//! a client using an older version of the protocol can still connect to a newer server.
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use bytes::Bytes;
+
+use crate::connection::server::DeniedReason;
+use crate::protocol::channel::ChannelRegistry;
+use crate::protocol::component::ComponentRegistry;
+use crate::protocol::message::MessageRegistry;
+use crate::protocol::registry::ProtocolVersion;
+use crate::serialize::reader::Reader;
+use crate::serialize::varint::{varint_len, VarIntWriteExt};
+use crate::serialize::{SerializationError, ToBytes};
+
+/// Stable description of a version of a protocol: one entry per channel, component and message
+/// that is part of that version, ordered by net id.
+///
+/// Each entry contains the type name and the settings that need to be identical on both peers
+/// (channel mode and direction, prediction/interpolation modes, delta compression, etc.)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtocolFingerprint {
+    version: ProtocolVersion,
+    entries: Vec<String>,
+}
+
+impl ProtocolFingerprint {
+    /// Fingerprint of the protocol, restricted to the types that are known in `version`
+    pub(crate) fn new(
+        channel_registry: &ChannelRegistry,
+        component_registry: &ComponentRegistry,
+        message_registry: &MessageRegistry,
+        version: ProtocolVersion,
+    ) -> Self {
+        let mut entries = channel_registry.fingerprint_entries(version);
+        entries.extend(component_registry.fingerprint_entries(version));
+        entries.extend(message_registry.fingerprint_entries(version));
+        Self { version, entries }
+    }
+
+    /// Version of the protocol
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Entries that make up the fingerprint
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Stable 64-bit hash of the fingerprint (FNV-1a), identical across platforms and builds
+    pub fn hash(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const PRIME: u64 = 0x100000001b3;
+        self.entries
+            .iter()
+            // separate the entries so that ["ab", "c"] and ["a", "bc"] have different hashes
+            .flat_map(|entry| entry.as_bytes().iter().chain(std::iter::once(&0)))
+            .fold(OFFSET_BASIS, |hash, byte| {
+                (hash ^ *byte as u64).wrapping_mul(PRIME)
+            })
+    }
+
+    /// Returns a description of the first entry that differs between the two fingerprints,
+    /// or None if they are identical
+    pub fn first_difference(&self, other: &Self) -> Option<String> {
+        let len = self.entries.len().max(other.entries.len());
+        (0..len).find_map(|i| match (self.entries.get(i), other.entries.get(i)) {
+            (Some(a), Some(b)) if a == b => None,
+            (Some(a), Some(b)) => Some(format!("expected `{a}`, got `{b}`")),
+            (Some(a), None) => Some(format!("expected `{a}`, got nothing")),
+            (None, Some(b)) => Some(format!("unexpected `{b}`")),
+            (None, None) => None,
+        })
+    }
+}
+
+fn string_len(value: &str) -> usize {
+    varint_len(value.len() as u64) + value.len()
+}
+
+fn write_string<T: WriteBytesExt>(value: &str, buffer: &mut T) -> Result<(), SerializationError> {
+    buffer.write_varint(value.len() as u64)?;
+    buffer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+fn read_string(buffer: &mut Reader) -> Result<String, SerializationError> {
+    let bytes = Bytes::from_bytes(buffer)?;
+    String::from_utf8(bytes.to_vec()).map_err(|_| SerializationError::InvalidValue)
+}
+
+impl ToBytes for ProtocolFingerprint {
+    fn len(&self) -> usize {
+        4 + 8
+            + self
+                .entries
+                .iter()
+                .map(|entry| string_len(entry))
+                .sum::<usize>()
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        buffer.write_u32::<byteorder::NetworkEndian>(self.version)?;
+        buffer.write_u64::<byteorder::NetworkEndian>(self.entries.len() as u64)?;
+        self.entries
+            .iter()
+            .try_for_each(|entry| write_string(entry, buffer))
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        let version = buffer.read_u32::<byteorder::NetworkEndian>()?;
+        let len = buffer.read_u64::<byteorder::NetworkEndian>()? as usize;
+        let entries = (0..len)
+            .map(|_| read_string(buffer))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { version, entries })
+    }
+}
+
+/// Messages exchanged on the [`HandshakeChannel`](crate::channel::builder::HandshakeChannel)
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum HandshakeMessage {
+    /// Sent by the client when it connects
+    Protocol(ProtocolFingerprint),
+    /// Sent by the server right before it disconnects the client
+    Denied(DeniedReason),
+}
+
+impl ToBytes for HandshakeMessage {
+    fn len(&self) -> usize {
+        1 + match self {
+            HandshakeMessage::Protocol(fingerprint) => fingerprint.len(),
+            HandshakeMessage::Denied(DeniedReason::Custom(reason))
+            | HandshakeMessage::Denied(DeniedReason::ProtocolMismatch(reason)) => {
+                1 + string_len(reason)
+            }
+            HandshakeMessage::Denied(_) => 1,
+        }
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        match self {
+            HandshakeMessage::Protocol(fingerprint) => {
+                buffer.write_u8(0)?;
+                fingerprint.to_bytes(buffer)
+            }
+            HandshakeMessage::Denied(reason) => {
+                buffer.write_u8(1)?;
+                let tag = match reason {
+                    DeniedReason::ServerFull => 0,
+                    DeniedReason::Banned => 1,
+                    DeniedReason::InternalError => 2,
+                    DeniedReason::AlreadyConnected => 3,
+                    DeniedReason::TokenAlreadyUsed => 4,
+                    DeniedReason::InvalidToken => 5,
+                    DeniedReason::Custom(_) => 6,
+                    DeniedReason::ProtocolMismatch(_) => 7,
+                };
+                buffer.write_u8(tag)?;
+                match reason {
+                    DeniedReason::Custom(reason) | DeniedReason::ProtocolMismatch(reason) => {
+                        write_string(reason, buffer)
+                    }
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        match buffer.read_u8()? {
+            0 => Ok(HandshakeMessage::Protocol(ProtocolFingerprint::from_bytes(
+                buffer,
+            )?)),
+            1 => {
+                let reason = match buffer.read_u8()? {
+                    0 => DeniedReason::ServerFull,
+                    1 => DeniedReason::Banned,
+                    2 => DeniedReason::InternalError,
+                    3 => DeniedReason::AlreadyConnected,
+                    4 => DeniedReason::TokenAlreadyUsed,
+                    5 => DeniedReason::InvalidToken,
+                    6 => DeniedReason::Custom(read_string(buffer)?),
+                    7 => DeniedReason::ProtocolMismatch(read_string(buffer)?),
+                    _ => return Err(SerializationError::InvalidValue),
+                };
+                Ok(HandshakeMessage::Denied(reason))
+            }
+            _ => Err(SerializationError::InvalidPacketType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialize::writer::Writer;
+
+    fn fingerprint(entries: &[&str]) -> ProtocolFingerprint {
+        ProtocolFingerprint {
+            version: 0,
+            entries: entries.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_hash() {
+        let a = fingerprint(&["channel 0: A", "message 0: M"]);
+        assert_eq!(a.hash(), a.clone().hash());
+        assert_ne!(a.hash(), fingerprint(&["channel 0: A"]).hash());
+        assert_ne!(
+            fingerprint(&["ab", "c"]).hash(),
+            fingerprint(&["a", "bc"]).hash()
+        );
+    }
+
+    #[test]
+    fn test_first_difference() {
+        let server = fingerprint(&["channel 0: A", "message 0: M"]);
+        assert_eq!(server.first_difference(&server.clone()), None);
+        assert_eq!(
+            server.first_difference(&fingerprint(&["channel 0: A", "message 0: N"])),
+            Some("expected `message 0: M`, got `message 0: N`".to_string())
+        );
+        assert_eq!(
+            server.first_difference(&fingerprint(&["channel 0: A"])),
+            Some("expected `message 0: M`, got nothing".to_string())
+        );
+        assert_eq!(
+            server.first_difference(&fingerprint(&[
+                "channel 0: A",
+                "message 0: M",
+                "message 1: N"
+            ])),
+            Some("unexpected `message 1: N`".to_string())
+        );
+    }
+
+    #[test]
+    fn test_serde_handshake_message() {
+        for message in [
+            HandshakeMessage::Protocol(fingerprint(&["channel 0: A", "message 0: M"])),
+            HandshakeMessage::Denied(DeniedReason::Banned),
+            HandshakeMessage::Denied(DeniedReason::ProtocolMismatch("mismatch".to_string())),
+        ] {
+            let mut writer = Writer::default();
+            message.to_bytes(&mut writer).unwrap();
+            let bytes = writer.to_bytes();
+            assert_eq!(bytes.len(), message.len());
+            let mut reader = Reader::from(bytes);
+            assert_eq!(HandshakeMessage::from_bytes(&mut reader).unwrap(), message);
+        }
+    }
+}
@@ -5,7 +5,7 @@ use std::fmt::Debug;
 use crate::client::config::ClientConfig;
 use crate::client::message::add_client_receive_message_from_server;
 use crate::prelude::{client, server};
-use bevy::prelude::{App, Resource, TypePath};
+use bevy::prelude::{App, Event, Resource, TypePath};
 use bevy::utils::HashMap;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -13,15 +13,20 @@ use tracing::{debug, error};
 
 use crate::packet::message::Message;
 use crate::prelude::server::ServerConfig;
-use crate::prelude::ChannelDirection;
-use crate::protocol::registry::{NetId, TypeKind, TypeMapper};
-use crate::protocol::serialize::{ErasedSerializeFns, SerializeFns};
+use crate::prelude::{Channel, ChannelDirection, ChannelKind, ChannelRegistry};
+use crate::protocol::registry::{NetId, ProtocolVersion, TypeKind, TypeMapper};
+use crate::protocol::schema::MessageSchema;
+use crate::protocol::serialize::{ErasedSerializeFns, SerializationBackend, SerializeFns};
+use crate::serialize::bits::BitSerialize;
 use crate::serialize::reader::Reader;
 use crate::serialize::writer::Writer;
 use crate::serialize::ToBytes;
 use crate::server::message::add_server_receive_message_from_client;
 use crate::shared::replication::entity_map::{ReceiveEntityMap, SendEntityMap};
 use crate::shared::replication::resources::DespawnResource;
+use crate::shared::replication::triggers::{
+    self, TriggerMessage, TriggerMetadata, TriggerRegistration,
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum MessageError {
@@ -31,6 +36,21 @@ pub enum MessageError {
     MissingSerializationFns,
     #[error(transparent)]
     Serialization(#[from] crate::serialize::SerializationError),
+    #[error("message {message} was not registered to be sent in the {direction:?} direction")]
+    InvalidDirection {
+        message: &'static str,
+        direction: ChannelDirection,
+    },
+    #[error("channel {channel} does not allow sending messages in the {direction:?} direction")]
+    InvalidChannelDirection {
+        channel: String,
+        direction: ChannelDirection,
+    },
+    #[error("message {message} was not registered to be sent on channel {channel}")]
+    InvalidChannel {
+        message: &'static str,
+        channel: String,
+    },
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -100,6 +120,12 @@ pub struct MessageRegistry {
     typed_map: HashMap<MessageKind, MessageType>,
     serialize_fns_map: HashMap<MessageKind, ErasedSerializeFns>,
     pub(crate) kind_map: TypeMapper<MessageKind>,
+    /// Serialization used for messages registered without custom [`SerializeFns`]
+    pub(crate) serialization_backend: SerializationBackend,
+    /// Direction in which each message can be sent
+    direction_map: HashMap<MessageKind, ChannelDirection>,
+    /// If present, the message can only be sent on these channels
+    channel_map: HashMap<MessageKind, Vec<ChannelKind>>,
 }
 
 fn register_message_send<M: Message>(app: &mut App, direction: ChannelDirection) {
@@ -188,6 +214,19 @@ pub struct MessageRegistration<'a, M> {
 }
 
 impl<M> MessageRegistration<'_, M> {
+    /// Only allow the message to be sent on the channel `C`.
+    ///
+    /// Can be called multiple times to allow multiple channels. By default, a message can be sent
+    /// on any channel whose direction is compatible with the direction of the message.
+    pub fn add_channel<C: Channel>(self) -> Self
+    where
+        M: 'static,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<MessageRegistry>();
+        registry.add_channel::<M>(ChannelKind::of::<C>());
+        self
+    }
+
     /// Specify that the message contains entities which should be mapped from the remote world to the local world
     /// upon deserialization
     pub fn add_map_entities(self) -> Self
@@ -198,6 +237,30 @@ impl<M> MessageRegistration<'_, M> {
         registry.add_map_entities::<M>();
         self
     }
+
+    /// Use an explicit network id for the message, instead of an id derived from the registration order.
+    ///
+    /// This keeps the id stable across versions of the protocol, even if messages are added or removed.
+    pub fn with_net_id(self, net_id: u16) -> Self
+    where
+        M: 'static,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<MessageRegistry>();
+        registry.kind_map.set_net_id::<M>(net_id);
+        self
+    }
+
+    /// Specify that the message was added in the given [`ProtocolVersion`].
+    ///
+    /// The message won't be sent to peers that use an older version of the protocol.
+    pub fn since_version(self, version: ProtocolVersion) -> Self
+    where
+        M: 'static,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<MessageRegistry>();
+        registry.kind_map.set_version::<M>(version);
+        self
+    }
 }
 
 pub(crate) trait AppMessageInternalExt {
@@ -228,6 +291,7 @@ impl AppMessageInternalExt for App {
         if !registry.is_registered::<M>() {
             registry.add_message::<M>(message_type);
         }
+        registry.set_direction::<M>(direction);
         debug!("register message {}", std::any::type_name::<M>());
         register_message_send::<M>(self, direction);
         MessageRegistration {
@@ -246,6 +310,7 @@ impl AppMessageInternalExt for App {
         if !registry.is_registered::<M>() {
             registry.add_message_custom_serde::<M>(message_type, serialize_fns);
         }
+        registry.set_direction::<M>(direction);
         debug!("register message {}", std::any::type_name::<M>());
         register_message_send::<M>(self, direction);
         MessageRegistration {
@@ -274,6 +339,15 @@ pub trait AppMessageExt {
         serialize_fns: SerializeFns<M>,
     ) -> MessageRegistration<'_, M>;
 
+    /// Registers the message in the Registry
+    ///
+    /// This message can now be sent over the network.
+    /// The message will be serialized with its bit-packed [`BitSerialize`] implementation.
+    fn register_message_bitpacked<M: Message + BitSerialize>(
+        &mut self,
+        direction: ChannelDirection,
+    ) -> MessageRegistration<'_, M>;
+
     /// Registers the resource in the Registry
     /// This resource can now be sent over the network.
     fn register_resource<R: Resource + Message + Serialize + DeserializeOwned>(
@@ -290,6 +364,16 @@ pub trait AppMessageExt {
         direction: ChannelDirection,
         serialize_fns: SerializeFns<R>,
     );
+
+    /// Registers the trigger event `E`, so that it is replicated from the server to the clients
+    /// on the channel `C`.
+    ///
+    /// Calling `commands.trigger_targets(event, entity)` on the server will fire the trigger on the clients
+    /// that the entity is replicated to, with the entity mapped to the client entity.
+    /// See [`triggers`](crate::shared::replication::triggers) for more details.
+    fn register_trigger<E: Event + Message + Clone + Serialize + DeserializeOwned, C: Channel>(
+        &mut self,
+    ) -> TriggerRegistration<'_, E>;
 }
 
 impl AppMessageExt for App {
@@ -308,6 +392,13 @@ impl AppMessageExt for App {
         self.register_message_internal_custom_serde(direction, MessageType::Normal, serialize_fns)
     }
 
+    fn register_message_bitpacked<M: Message + BitSerialize>(
+        &mut self,
+        direction: ChannelDirection,
+    ) -> MessageRegistration<'_, M> {
+        self.register_message_custom_serde(direction, SerializeFns::bitpacked())
+    }
+
     /// Register a resource to be automatically replicated over the network
     fn register_resource<R: Resource + Message + Serialize + DeserializeOwned>(
         &mut self,
@@ -328,14 +419,35 @@ impl AppMessageExt for App {
         self.register_message::<DespawnResource<R>>(direction);
         register_resource_send::<R>(self, direction)
     }
+
+    fn register_trigger<E: Event + Message + Clone + Serialize + DeserializeOwned, C: Channel>(
+        &mut self,
+    ) -> TriggerRegistration<'_, E> {
+        self.register_message::<TriggerMessage<E>>(ChannelDirection::ServerToClient);
+        self.insert_resource(TriggerMetadata::<E>::new(ChannelKind::of::<C>()));
+        if self.world().get_resource::<ServerConfig>().is_some() {
+            triggers::send::add_trigger_send_observer::<E>(self);
+        }
+        if self.world().get_resource::<ClientConfig>().is_some() {
+            triggers::receive::add_trigger_receive_systems::<E>(self);
+        }
+        TriggerRegistration {
+            app: self,
+            _marker: std::marker::PhantomData,
+        }
+    }
 }
 
 impl MessageRegistry {
-    pub(crate) fn message_type(&self, net_id: NetId) -> MessageType {
-        let kind = self.kind_map.kind(net_id).unwrap();
-        self.typed_map
-            .get(kind)
-            .map_or(MessageType::Normal, |message_type| *message_type)
+    /// Returns the type of the message, or None if the message is not part of the protocol
+    /// (for example because it was added in a newer version of the protocol than the one we use)
+    pub(crate) fn message_type(&self, net_id: NetId) -> Option<MessageType> {
+        let kind = self.kind_map.kind(net_id)?;
+        Some(
+            self.typed_map
+                .get(kind)
+                .map_or(MessageType::Normal, |message_type| *message_type),
+        )
     }
 
     pub fn is_registered<M: 'static>(&self) -> bool {
@@ -347,8 +459,10 @@ impl MessageRegistry {
         message_type: MessageType,
     ) {
         let message_kind = self.kind_map.add::<M>();
-        self.serialize_fns_map
-            .insert(message_kind, ErasedSerializeFns::new::<M>());
+        self.serialize_fns_map.insert(
+            message_kind,
+            ErasedSerializeFns::new::<M>(self.serialization_backend),
+        );
         self.typed_map.insert(message_kind, message_type);
     }
 
@@ -365,6 +479,157 @@ impl MessageRegistry {
         self.typed_map.insert(message_kind, message_type);
     }
 
+    pub(crate) fn set_direction<M: 'static>(&mut self, direction: ChannelDirection) {
+        self.direction_map.insert(MessageKind::of::<M>(), direction);
+    }
+
+    pub(crate) fn add_channel<M: 'static>(&mut self, channel_kind: ChannelKind) {
+        let channels = self.channel_map.entry(MessageKind::of::<M>()).or_default();
+        if !channels.contains(&channel_kind) {
+            channels.push(channel_kind);
+        }
+    }
+
+    /// Check that the message `M` can be sent on the channel `channel_kind` in the `direction` direction
+    /// (`ClientToServer` or `ServerToClient`)
+    pub(crate) fn check_send<M: 'static>(
+        &self,
+        channel_kind: ChannelKind,
+        channel_registry: &ChannelRegistry,
+        direction: ChannelDirection,
+    ) -> Result<(), MessageError> {
+        let kind = MessageKind::of::<M>();
+        let channel_name = || {
+            channel_registry
+                .name(&channel_kind)
+                .unwrap_or("unknown")
+                .to_string()
+        };
+        if self
+            .direction_map
+            .get(&kind)
+            .is_some_and(|d| !d.contains(direction))
+        {
+            return Err(MessageError::InvalidDirection {
+                message: std::any::type_name::<M>(),
+                direction,
+            });
+        }
+        if channel_registry
+            .direction(&channel_kind)
+            .is_some_and(|d| !d.contains(direction))
+        {
+            return Err(MessageError::InvalidChannelDirection {
+                channel: channel_name(),
+                direction,
+            });
+        }
+        if self
+            .channel_map
+            .get(&kind)
+            .is_some_and(|channels| !channels.contains(&channel_kind))
+        {
+            return Err(MessageError::InvalidChannel {
+                message: std::any::type_name::<M>(),
+                channel: channel_name(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Description of every message known in the protocol `version`, ordered by net id, used to build the
+    /// [`ProtocolFingerprint`](crate::protocol::fingerprint::ProtocolFingerprint)
+    pub(crate) fn fingerprint_entries(&self, version: ProtocolVersion) -> Vec<String> {
+        self.kind_map
+            .net_ids()
+            .into_iter()
+            .filter_map(|net_id| {
+                let kind = self.kind_map.kind(net_id)?;
+                if !self.kind_map.is_known(kind, version) {
+                    return None;
+                }
+                let since = self.kind_map.version(kind);
+                let name = self
+                    .serialize_fns_map
+                    .get(kind)
+                    .map_or("unknown", |fns| fns.type_name);
+                let direction = self.direction_map.get(kind);
+                Some(format!(
+                    "message {net_id}: {name} since={since} direction={direction:?}"
+                ))
+            })
+            .collect()
+    }
+
+    /// Description of every message, ordered by net id, used to build the [`ProtocolSchema`](crate::protocol::schema::ProtocolSchema)
+    pub(crate) fn schema(&self, channel_registry: &ChannelRegistry) -> Vec<MessageSchema> {
+        self.kind_map
+            .net_ids()
+            .into_iter()
+            .filter_map(|net_id| {
+                let kind = self.kind_map.kind(net_id)?;
+                let name = self
+                    .serialize_fns_map
+                    .get(kind)
+                    .map_or("unknown", |fns| fns.type_name);
+                let channels = self.channel_map.get(kind).map_or(vec![], |channels| {
+                    channels
+                        .iter()
+                        .map(|channel| {
+                            channel_registry
+                                .name(channel)
+                                .unwrap_or("unknown")
+                                .to_string()
+                        })
+                        .collect()
+                });
+                Some(MessageSchema {
+                    net_id,
+                    name: name.to_string(),
+                    direction: self.direction_map.get(kind).map(|d| format!("{d:?}")),
+                    channels,
+                    since: self.kind_map.version(kind),
+                })
+            })
+            .collect()
+    }
+
+    /// Check that the messages were registered correctly:
+    /// - the channels that a message is restricted to must be registered
+    /// - these channels must allow sending the message in each of its directions
+    pub fn check(&self, channel_registry: &ChannelRegistry) {
+        for (message_kind, channels) in &self.channel_map {
+            let name = self
+                .serialize_fns_map
+                .get(message_kind)
+                .map_or("unknown", |fns| fns.type_name);
+            let direction = self
+                .direction_map
+                .get(message_kind)
+                .copied()
+                .unwrap_or(ChannelDirection::Bidirectional);
+            let mut allowed = vec![];
+            for channel_kind in channels {
+                let Some(channel_direction) = channel_registry.direction(channel_kind) else {
+                    panic!("The message {name:?} can only be sent on a channel that is not registered in the protocol");
+                };
+                let channel_name = channel_registry.name(channel_kind).unwrap_or("unknown");
+                if !channel_direction.overlaps(direction) {
+                    panic!("The message {name:?} is registered with direction {direction:?} but the channel {channel_name:?} only allows {channel_direction:?}");
+                }
+                allowed.push(channel_direction);
+            }
+            for required in [
+                ChannelDirection::ClientToServer,
+                ChannelDirection::ServerToClient,
+            ] {
+                if direction.contains(required) && !allowed.iter().any(|d| d.contains(required)) {
+                    panic!("The message {name:?} is registered with direction {direction:?} but none of its channels allow {required:?}");
+                }
+            }
+        }
+    }
+
     pub(crate) fn try_add_map_entities<M: Clone + MapEntities + 'static>(&mut self) {
         let kind = MessageKind::of::<M>();
         if let Some(erased_fns) = self.serialize_fns_map.get_mut(&kind) {
@@ -451,10 +716,13 @@ impl From<TypeId> for MessageKind {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::prelude::ChannelSettings;
     use crate::tests::protocol::{
-        deserialize_resource2, serialize_resource2, ComponentMapEntities, Resource1, Resource2,
+        deserialize_resource2, serialize_resource2, Channel1, Channel2, ComponentMapEntities,
+        Resource1, Resource2, StringMessage,
     };
-    use bevy::prelude::Entity;
+    use bevy::prelude::{default, Entity};
+    use lightyear_macros::BitSerializeInternal;
 
     #[test]
     fn test_serde() {
@@ -518,4 +786,145 @@ mod tests {
             .unwrap();
         assert_eq!(message, read);
     }
+
+    #[test]
+    fn test_serde_bitpacked_backend() {
+        let mut registry = MessageRegistry {
+            serialization_backend: SerializationBackend::BitPacked,
+            ..default()
+        };
+        registry.add_message::<ComponentMapEntities>(MessageType::Normal);
+        registry.add_map_entities::<ComponentMapEntities>();
+
+        let message = ComponentMapEntities(Entity::from_raw(0));
+        let mut writer = Writer::default();
+        let mut map = SendEntityMap::default();
+        map.insert(Entity::from_raw(0), Entity::from_raw(1));
+        registry
+            .serialize(&message, &mut writer, Some(&mut map))
+            .unwrap();
+        let data = writer.to_bytes();
+
+        let mut reader = Reader::from(data);
+        let read = registry
+            .deserialize::<ComponentMapEntities>(&mut reader, &mut ReceiveEntityMap::default())
+            .unwrap();
+        assert_eq!(read, ComponentMapEntities(Entity::from_raw(1)));
+    }
+
+    #[derive(BitSerializeInternal, Debug, Clone, PartialEq)]
+    struct Position {
+        #[bits(quantize(min = -100.0, max = 100.0, bits = 16))]
+        x: f32,
+        #[bits(range(min = 0, max = 3))]
+        team: u8,
+    }
+
+    #[test]
+    fn test_bitpacked() {
+        let mut registry = MessageRegistry::default();
+        registry
+            .add_message_custom_serde::<Position>(MessageType::Normal, SerializeFns::bitpacked());
+
+        let message = Position { x: 0.5, team: 2 };
+        let mut writer = Writer::default();
+        registry.serialize(&message, &mut writer, None).unwrap();
+        let data = writer.to_bytes();
+        // 2 bytes for the message id, 16 + 2 bits for the message
+        assert_eq!(data.len(), 4);
+
+        let mut reader = Reader::from(data);
+        let read = registry
+            .deserialize::<Position>(&mut reader, &mut ReceiveEntityMap::default())
+            .unwrap();
+        assert!((read.x - message.x).abs() < 0.01);
+        assert_eq!(read.team, message.team);
+    }
+
+    fn channel_registry() -> ChannelRegistry {
+        let mut channel_registry = ChannelRegistry::default();
+        channel_registry.add_channel::<Channel1>(ChannelSettings {
+            direction: ChannelDirection::ServerToClient,
+            ..default()
+        });
+        channel_registry.add_channel::<Channel2>(ChannelSettings::default());
+        channel_registry
+    }
+
+    #[test]
+    fn test_check_send() {
+        let channel_registry = channel_registry();
+        let mut registry = MessageRegistry::default();
+        registry.add_message::<Resource1>(MessageType::Normal);
+        registry.set_direction::<Resource1>(ChannelDirection::ClientToServer);
+        registry.add_message::<StringMessage>(MessageType::Normal);
+        registry.set_direction::<StringMessage>(ChannelDirection::Bidirectional);
+
+        assert!(registry
+            .check_send::<Resource1>(
+                Channel2::kind(),
+                &channel_registry,
+                ChannelDirection::ClientToServer
+            )
+            .is_ok());
+        // the message cannot be sent from the server
+        assert!(matches!(
+            registry.check_send::<Resource1>(
+                Channel2::kind(),
+                &channel_registry,
+                ChannelDirection::ServerToClient
+            ),
+            Err(MessageError::InvalidDirection { .. })
+        ));
+        // the channel does not allow sending from the client
+        assert!(matches!(
+            registry.check_send::<StringMessage>(
+                Channel1::kind(),
+                &channel_registry,
+                ChannelDirection::ClientToServer
+            ),
+            Err(MessageError::InvalidChannelDirection { .. })
+        ));
+
+        // the message is restricted to Channel1
+        registry.add_channel::<StringMessage>(Channel1::kind());
+        assert!(registry
+            .check_send::<StringMessage>(
+                Channel1::kind(),
+                &channel_registry,
+                ChannelDirection::ServerToClient
+            )
+            .is_ok());
+        assert!(matches!(
+            registry.check_send::<StringMessage>(
+                Channel2::kind(),
+                &channel_registry,
+                ChannelDirection::ServerToClient
+            ),
+            Err(MessageError::InvalidChannel { .. })
+        ));
+    }
+
+    #[test]
+    fn test_check() {
+        let channel_registry = channel_registry();
+        let mut registry = MessageRegistry::default();
+        registry.add_message::<Resource1>(MessageType::Normal);
+        registry.set_direction::<Resource1>(ChannelDirection::Bidirectional);
+        registry.add_channel::<Resource1>(Channel1::kind());
+        registry.add_channel::<Resource1>(Channel2::kind());
+        registry.check(&channel_registry);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_check_invalid_channel_direction() {
+        let channel_registry = channel_registry();
+        let mut registry = MessageRegistry::default();
+        registry.add_message::<Resource1>(MessageType::Normal);
+        registry.set_direction::<Resource1>(ChannelDirection::Bidirectional);
+        // Channel1 does not allow sending the message from the client
+        registry.add_channel::<Resource1>(Channel1::kind());
+        registry.check(&channel_registry);
+    }
 }
@@ -20,10 +20,14 @@ pub(crate) mod component;
 pub(crate) mod message;
 
 pub(crate) mod delta;
+/// Fingerprint of the protocol, checked during the connection handshake
+pub mod fingerprint;
 /// Provides a mapping from a type to a unique identifier that can be serialized
 pub(crate) mod registry;
+/// Machine-readable description of the protocol, that can be exported for external tools
+pub mod schema;
 pub(crate) mod serialize;
-pub use serialize::SerializeFns;
+pub use serialize::{SerializationBackend, SerializeFns};
 
 /// Data that can be used in an Event
 /// Same as `Event`, but we implement it automatically for all compatible types
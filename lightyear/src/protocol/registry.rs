@@ -9,6 +9,12 @@ use std::hash::Hash;
 /// ID used to serialize IDs over the network efficiently
 pub(crate) type NetId = u16;
 
+/// Version of the protocol.
+///
+/// Types (channels, components, messages) can be registered as being added in a given version of the protocol.
+/// A peer using an older version of the protocol does not know these types, so they won't be sent to it.
+pub type ProtocolVersion = u32;
+
 impl ToBytes for NetId {
     fn len(&self) -> usize {
         varint_len(*self as u64)
@@ -35,6 +41,8 @@ pub struct TypeMapper<K: TypeKind> {
     pub(crate) next_net_id: NetId,
     pub(crate) kind_map: HashMap<K, NetId>,
     pub(crate) id_map: HashMap<NetId, K>,
+    /// Version of the protocol in which each type was added (if not present, the type is part of every version)
+    pub(crate) version_map: HashMap<K, ProtocolVersion>,
 }
 
 impl<K: TypeKind> Default for TypeMapper<K> {
@@ -49,14 +57,23 @@ impl<K: TypeKind> TypeMapper<K> {
             next_net_id: 0,
             kind_map: HashMap::new(),
             id_map: HashMap::new(),
+            version_map: HashMap::new(),
         }
     }
 
     /// Register a new type
     pub fn add<T: 'static>(&mut self) -> K {
-        let kind = K::from(TypeId::of::<T>());
+        self.add_kind(K::from(TypeId::of::<T>()), std::any::type_name::<T>())
+    }
+
+    /// Register a new type from its kind, for types that are only known at runtime
+    pub(crate) fn add_kind(&mut self, kind: K, name: &str) -> K {
         if self.kind_map.contains_key(&kind) {
-            panic!("Type {:?} already registered", std::any::type_name::<T>());
+            panic!("Type {name:?} already registered");
+        }
+        // skip the net ids that were assigned explicitly
+        while self.id_map.contains_key(&self.next_net_id) {
+            self.next_net_id += 1;
         }
         let net_id = self.next_net_id;
         self.kind_map.insert(kind, net_id);
@@ -65,6 +82,67 @@ impl<K: TypeKind> TypeMapper<K> {
         kind
     }
 
+    /// Use an explicit net id for an already registered type, instead of the id derived from the registration order.
+    ///
+    /// This keeps the net id stable even if types are added or removed in other versions of the protocol.
+    pub fn set_net_id<T: 'static>(&mut self, net_id: NetId) {
+        self.set_kind_net_id(
+            K::from(TypeId::of::<T>()),
+            std::any::type_name::<T>(),
+            net_id,
+        );
+    }
+
+    /// Use an explicit net id for an already registered type, for types that are only known at runtime
+    pub(crate) fn set_kind_net_id(&mut self, kind: K, name: &str, net_id: NetId) {
+        let Some(previous) = self.kind_map.get(&kind).copied() else {
+            panic!("Type {name:?} is not registered");
+        };
+        match self.id_map.get(&net_id) {
+            Some(other) if *other != kind => {
+                panic!(
+                    "Cannot use net id {net_id} for type {name:?}: it is already used by another type"
+                );
+            }
+            _ => {}
+        }
+        self.id_map.remove(&previous);
+        self.id_map.insert(net_id, kind);
+        self.kind_map.insert(kind, net_id);
+        // free the positional id if it was just assigned, so that the types registered afterwards
+        // keep the same net ids as if this type had not been registered
+        if previous + 1 == self.next_net_id {
+            self.next_net_id = previous;
+        }
+    }
+
+    /// Mark a registered type as being added in the given version of the protocol
+    pub fn set_version<T: 'static>(&mut self, version: ProtocolVersion) {
+        self.set_kind_version(K::from(TypeId::of::<T>()), version);
+    }
+
+    /// Mark a registered type as being added in the given version of the protocol, for types that are only known at runtime
+    pub(crate) fn set_kind_version(&mut self, kind: K, version: ProtocolVersion) {
+        self.version_map.insert(kind, version);
+    }
+
+    /// Version of the protocol in which the type was added
+    pub fn version(&self, kind: &K) -> ProtocolVersion {
+        self.version_map.get(kind).copied().unwrap_or_default()
+    }
+
+    /// Returns true if a peer using the given version of the protocol knows the type
+    pub fn is_known(&self, kind: &K, version: ProtocolVersion) -> bool {
+        self.version(kind) <= version
+    }
+
+    /// All the registered net ids, in increasing order
+    pub(crate) fn net_ids(&self) -> Vec<NetId> {
+        let mut net_ids = self.id_map.keys().copied().collect::<Vec<_>>();
+        net_ids.sort_unstable();
+        net_ids
+    }
+
     pub fn kind(&self, net_id: NetId) -> Option<&K> {
         self.id_map.get(&net_id)
     }
@@ -78,3 +156,58 @@ impl<K: TypeKind> TypeMapper<K> {
         self.kind_map.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::message::MessageKind;
+
+    struct A;
+    struct B;
+    struct C;
+
+    #[test]
+    fn test_explicit_net_id() {
+        let mut mapper = TypeMapper::<MessageKind>::new();
+        mapper.add::<A>();
+        mapper.add::<B>();
+        mapper.set_net_id::<B>(10);
+        mapper.add::<C>();
+        // C gets the positional id that B would have had
+        assert_eq!(mapper.net_id(&MessageKind::of::<A>()), Some(&0));
+        assert_eq!(mapper.net_id(&MessageKind::of::<B>()), Some(&10));
+        assert_eq!(mapper.net_id(&MessageKind::of::<C>()), Some(&1));
+        assert_eq!(mapper.kind(10), Some(&MessageKind::of::<B>()));
+        assert_eq!(mapper.net_ids(), vec![0, 1, 10]);
+
+        // positional ids skip the ids that were assigned explicitly
+        let mut mapper = TypeMapper::<MessageKind>::new();
+        mapper.add::<A>();
+        mapper.set_net_id::<A>(1);
+        mapper.add::<B>();
+        mapper.add::<C>();
+        assert_eq!(mapper.net_id(&MessageKind::of::<B>()), Some(&0));
+        assert_eq!(mapper.net_id(&MessageKind::of::<C>()), Some(&2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_explicit_net_id_conflict() {
+        let mut mapper = TypeMapper::<MessageKind>::new();
+        mapper.add::<A>();
+        mapper.add::<B>();
+        mapper.set_net_id::<B>(0);
+    }
+
+    #[test]
+    fn test_version() {
+        let mut mapper = TypeMapper::<MessageKind>::new();
+        mapper.add::<A>();
+        mapper.add::<B>();
+        mapper.set_version::<B>(2);
+        assert_eq!(mapper.version(&MessageKind::of::<A>()), 0);
+        assert!(mapper.is_known(&MessageKind::of::<A>(), 0));
+        assert!(!mapper.is_known(&MessageKind::of::<B>(), 1));
+        assert!(mapper.is_known(&MessageKind::of::<B>(), 2));
+    }
+}
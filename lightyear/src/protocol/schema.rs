@@ -0,0 +1,306 @@
+//! Machine-readable description of the protocol, for tools that need to decode the traffic outside of the Rust app
+//! (packet inspectors, bots written in another language, etc.)
+//!
+//! The [`ProtocolSchema`] lists the channels, components and messages with their net ids and settings,
+//! as well as the layout of the packets. It can be exported to JSON with `ProtocolSchema::to_json` (requires the `json`
+//! feature), or to any other format with [`serde`].
+//!
+//! ```rust,ignore
+//! let schema = ProtocolSchema::from_world(app.world());
+//! std::fs::write("protocol.json", schema.to_json()).unwrap();
+//! ```
+use bevy::prelude::World;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::{ChannelMode, ChannelRegistry, MessageRegistry};
+use crate::protocol::component::ComponentRegistry;
+use crate::protocol::registry::{NetId, ProtocolVersion};
+use crate::protocol::serialize::SerializationBackend;
+
+/// Description of the registered channels, components and messages, and of the packet layout
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProtocolSchema {
+    /// Serialization used for the components and messages that don't use custom serialization functions
+    pub serialization_backend: String,
+    pub packet: PacketSchema,
+    /// Channels, ordered by net id
+    pub channels: Vec<ChannelSchema>,
+    /// Components, ordered by net id
+    pub components: Vec<ComponentSchema>,
+    /// Messages, ordered by net id
+    pub messages: Vec<MessageSchema>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChannelSchema {
+    pub net_id: NetId,
+    pub name: String,
+    /// Name of the [`ChannelMode`] variant
+    pub mode: String,
+    pub reliable: bool,
+    pub direction: String,
+    /// None if the priority is infinite (the channel is always sent first)
+    pub priority: Option<f32>,
+    pub send_frequency_ms: u128,
+    /// Version of the protocol in which the channel was added
+    pub since: ProtocolVersion,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ComponentSchema {
+    pub net_id: NetId,
+    pub name: String,
+    /// Direction in which the component is replicated, if it was registered with a direction
+    pub direction: Option<String>,
+    /// [`ComponentSyncMode`](crate::prelude::client::ComponentSyncMode) used for prediction, if any
+    pub prediction: Option<String>,
+    /// [`ComponentSyncMode`](crate::prelude::client::ComponentSyncMode) used for interpolation, if any
+    pub interpolation: Option<String>,
+    pub delta_compression: bool,
+    /// Version of the protocol in which the component was added
+    pub since: ProtocolVersion,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MessageSchema {
+    pub net_id: NetId,
+    pub name: String,
+    /// Direction in which the message can be sent, if it was registered with a direction
+    pub direction: Option<String>,
+    /// Names of the channels the message is restricted to. If empty, the message can be sent on any channel
+    pub channels: Vec<String>,
+    /// Version of the protocol in which the message was added
+    pub since: ProtocolVersion,
+}
+
+/// One field of a binary layout
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FieldSchema {
+    pub name: String,
+    /// One of `u8`, `u16`, `u32` (big-endian), `varint` or `bytes` (a `varint` length followed by the bytes).
+    ///
+    /// `varint`s use the QUIC encoding: the 2 most significant bits of the first byte give the length (1, 2, 4 or 8 bytes)
+    pub encoding: String,
+    pub description: String,
+}
+
+impl FieldSchema {
+    fn new(name: &str, encoding: &str, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            encoding: encoding.to_string(),
+            description: description.to_string(),
+        }
+    }
+}
+
+/// Layout of the packets exchanged by lightyear (inside the transport layer, i.e. after netcode decryption)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PacketSchema {
+    /// Header at the start of every packet
+    pub header: Vec<FieldSchema>,
+    /// Values of the `packet_type` field of the header
+    pub packet_types: Vec<(u8, String)>,
+    /// Start of the payload of a `DataFragment` packet, followed by messages like a `Data` packet
+    pub fragment: Vec<FieldSchema>,
+    /// Block of messages for one channel. The payload of a `Data` packet is a sequence of these blocks
+    pub channel_block: Vec<FieldSchema>,
+    /// Message inside a channel block, repeated `num_messages` times
+    pub message: Vec<FieldSchema>,
+    /// Content of the `bytes` of a message sent on a channel that is not used for replication or internal messages
+    pub message_bytes: Vec<FieldSchema>,
+}
+
+impl Default for PacketSchema {
+    fn default() -> Self {
+        #[cfg(not(feature = "big_messages"))]
+        let fragment_encoding = "u8";
+        #[cfg(feature = "big_messages")]
+        let fragment_encoding = "u16";
+        Self {
+            header: vec![
+                FieldSchema::new("packet_type", "u8", "type of the packet"),
+                FieldSchema::new("packet_id", "u16", "id of the packet from the sender's perspective"),
+                FieldSchema::new(
+                    "last_ack_packet_id",
+                    "u16",
+                    "id of the last packet received by the sender",
+                ),
+                FieldSchema::new(
+                    "ack_bitfield",
+                    "u32",
+                    "bit i is set if the sender received the packet `last_ack_packet_id - i - 1`",
+                ),
+                FieldSchema::new("tick", "u16", "tick of the sender when the packet was sent"),
+            ],
+            packet_types: vec![
+                (0, "Data".to_string()),
+                (1, "DataFragment".to_string()),
+                (2, "MtuProbe".to_string()),
+            ],
+            fragment: vec![
+                FieldSchema::new("channel_id", "varint", "net id of the channel"),
+                FieldSchema::new("message_id", "u16", "id of the fragmented message"),
+                FieldSchema::new("fragment_id", fragment_encoding, "index of the fragment"),
+                FieldSchema::new(
+                    "num_fragments",
+                    fragment_encoding,
+                    "number of fragments of the message",
+                ),
+                FieldSchema::new("bytes", "bytes", "content of the fragment"),
+            ],
+            channel_block: vec![
+                FieldSchema::new("channel_id", "varint", "net id of the channel"),
+                FieldSchema::new("num_messages", "u8", "number of messages in the block"),
+            ],
+            message: vec![
+                FieldSchema::new(
+                    "has_message_id",
+                    "u8",
+                    "1 if the message has an id (reliable or sequenced channels), 0 otherwise",
+                ),
+                FieldSchema::new(
+                    "message_id",
+                    "u16",
+                    "id of the message, only present if `has_message_id` is 1",
+                ),
+                FieldSchema::new("bytes", "bytes", "content of the message"),
+            ],
+            message_bytes: vec![
+                FieldSchema::new("message_net_id", "varint", "net id of the message"),
+                FieldSchema::new(
+                    "payload",
+                    "bytes",
+                    "the message serialized with the serialization backend, until the end of the message bytes (no length prefix)",
+                ),
+            ],
+        }
+    }
+}
+
+impl ProtocolSchema {
+    pub fn new(
+        channel_registry: &ChannelRegistry,
+        component_registry: &ComponentRegistry,
+        message_registry: &MessageRegistry,
+    ) -> Self {
+        let serialization_backend = match message_registry.serialization_backend {
+            SerializationBackend::Bincode => "Bincode",
+            SerializationBackend::BitPacked => "BitPacked",
+        };
+        Self {
+            serialization_backend: serialization_backend.to_string(),
+            packet: PacketSchema::default(),
+            channels: channel_registry.schema(),
+            components: component_registry.schema(),
+            messages: message_registry.schema(channel_registry),
+        }
+    }
+
+    /// Schema of the protocol registered in the [`World`]
+    pub fn from_world(world: &World) -> Self {
+        Self::new(
+            world.resource::<ChannelRegistry>(),
+            world.resource::<ComponentRegistry>(),
+            world.resource::<MessageRegistry>(),
+        )
+    }
+
+    /// Export the schema as pretty-printed JSON
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("the protocol schema can always be serialized")
+    }
+
+    /// Read a schema that was exported with [`ProtocolSchema::to_json`]
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Name of the [`ChannelMode`] variant, without its settings
+pub(crate) fn channel_mode_name(mode: &ChannelMode) -> &'static str {
+    match mode {
+        ChannelMode::UnorderedUnreliableWithAcks => "UnorderedUnreliableWithAcks",
+        ChannelMode::UnorderedUnreliable => "UnorderedUnreliable",
+        ChannelMode::SequencedUnreliable => "SequencedUnreliable",
+        ChannelMode::UnorderedReliable(_) => "UnorderedReliable",
+        ChannelMode::SequencedReliable(_) => "SequencedReliable",
+        ChannelMode::OrderedReliable(_) => "OrderedReliable",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::builder::EntityActionsChannel;
+    use crate::prelude::ChannelKind;
+    use crate::protocol::message::MessageKind;
+    use crate::tests::protocol::{Channel1, StringMessage};
+    use crate::tests::stepper::BevyStepper;
+
+    #[test]
+    fn test_schema() {
+        let stepper = BevyStepper::default();
+        let world = stepper.server_app.world();
+        let channel_registry = world.resource::<ChannelRegistry>();
+        let message_registry = world.resource::<MessageRegistry>();
+        let schema = ProtocolSchema::from_world(world);
+
+        let channel = |name: &str| schema.channels.iter().find(|c| c.name == name).unwrap();
+        assert_eq!(
+            channel("Channel1").net_id,
+            *channel_registry
+                .get_net_from_kind(&ChannelKind::of::<Channel1>())
+                .unwrap()
+        );
+        let actions = channel("EntityActionsChannel");
+        assert_eq!(
+            actions.net_id,
+            *channel_registry
+                .get_net_from_kind(&ChannelKind::of::<EntityActionsChannel>())
+                .unwrap()
+        );
+        assert_eq!(actions.mode, "UnorderedReliable");
+        assert!(actions.reliable);
+
+        let string_message = schema
+            .messages
+            .iter()
+            .find(|m| m.name.ends_with("StringMessage"))
+            .unwrap();
+        assert_eq!(
+            string_message.net_id,
+            *message_registry
+                .kind_map
+                .net_id(&MessageKind::of::<StringMessage>())
+                .unwrap()
+        );
+        assert_eq!(string_message.direction.as_deref(), Some("Bidirectional"));
+
+        let component = schema
+            .components
+            .iter()
+            .find(|c| c.name.ends_with("ComponentSyncModeFull"))
+            .unwrap();
+        assert_eq!(component.prediction.as_deref(), Some("Full"));
+        // entries are ordered by net id
+        assert!(schema
+            .components
+            .windows(2)
+            .all(|w| w[0].net_id < w[1].net_id));
+    }
+
+    /// The schema can be read back from JSON
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_schema_json() {
+        let stepper = BevyStepper::default();
+        let schema = ProtocolSchema::from_world(stepper.server_app.world());
+        assert_eq!(
+            ProtocolSchema::from_json(&schema.to_json()).unwrap(),
+            schema
+        );
+    }
+}
@@ -1,4 +1,6 @@
 use crate::prelude::{ComponentRegistry, Message, MessageRegistry};
+use crate::serialize::bits::serializer::{serde_bitpacked_deserialize, serde_bitpacked_serialize};
+use crate::serialize::bits::{bitpacked_deserialize, bitpacked_serialize, BitSerialize};
 use crate::serialize::{reader::Reader, writer::Writer, SerializationError};
 use crate::shared::replication::entity_map::{EntityMap, ReceiveEntityMap, SendEntityMap};
 use bevy::app::App;
@@ -36,6 +38,49 @@ pub struct SerializeFns<M> {
     pub serialize_map_entities: Option<SerializeMapEntitiesFn<M>>,
 }
 
+/// Serialization used for the types that are registered without a custom [`SerializeFns`]
+/// (i.e. with `register_message` or `register_component`).
+///
+/// The client and the server must use the same backend.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SerializationBackend {
+    /// Serialize with [`bincode`]
+    #[default]
+    Bincode,
+    /// Serialize with the bit-packed [`serde`] backend (see [`bits`](crate::serialize::bits))
+    BitPacked,
+}
+
+impl<M: Message + Serialize + DeserializeOwned> SerializeFns<M> {
+    /// Serialize the type with its [`Serialize`] and [`DeserializeOwned`] implementations,
+    /// using the provided [`SerializationBackend`]
+    pub fn serde(backend: SerializationBackend) -> Self {
+        match backend {
+            SerializationBackend::Bincode => Self {
+                serialize: default_serialize::<M>,
+                deserialize: default_deserialize::<M>,
+                serialize_map_entities: None,
+            },
+            SerializationBackend::BitPacked => Self {
+                serialize: serde_bitpacked_serialize::<M>,
+                deserialize: serde_bitpacked_deserialize::<M>,
+                serialize_map_entities: None,
+            },
+        }
+    }
+}
+
+impl<M: Message + BitSerialize> SerializeFns<M> {
+    /// Serialize the type with its [`BitSerialize`] implementation
+    pub fn bitpacked() -> Self {
+        Self {
+            serialize: bitpacked_serialize::<M>,
+            deserialize: bitpacked_deserialize::<M>,
+            serialize_map_entities: None,
+        }
+    }
+}
+
 type ErasedSerializeFn = unsafe fn(
     erased_serialize_fn: &ErasedSerializeFns,
     message: Ptr,
@@ -157,23 +202,10 @@ unsafe fn erased_receive_map_entities<M: MapEntities + 'static>(
 }
 
 impl ErasedSerializeFns {
-    pub(crate) fn new<M: Message + Serialize + DeserializeOwned>() -> Self {
-        let serialize_fns = SerializeFns {
-            serialize: default_serialize::<M>,
-            deserialize: default_deserialize::<M>,
-            serialize_map_entities: None,
-        };
-        Self {
-            type_id: TypeId::of::<M>(),
-            type_name: std::any::type_name::<M>(),
-            erased_serialize: erased_serialize_fn::<M>,
-            serialize: unsafe { std::mem::transmute(serialize_fns.serialize) },
-            deserialize: unsafe { std::mem::transmute(serialize_fns.deserialize) },
-            erased_clone: None,
-            map_entities: None,
-            send_map_entities: None,
-            receive_map_entities: None,
-        }
+    pub(crate) fn new<M: Message + Serialize + DeserializeOwned>(
+        backend: SerializationBackend,
+    ) -> Self {
+        Self::new_custom_serde(SerializeFns::<M>::serde(backend))
     }
 
     pub(crate) fn new_custom_serde<M: Message>(serialize_fns: SerializeFns<M>) -> Self {
@@ -284,6 +316,12 @@ pub trait AppSerializeExt {
     /// Indicate that the type `M` contains Entity references, and that the entities
     /// should be mapped during deserialization
     fn add_map_entities<M: Clone + MapEntities + 'static>(&mut self);
+
+    /// Set the [`SerializationBackend`] used for the messages and components registered
+    /// without a custom [`SerializeFns`].
+    ///
+    /// This must be called before registering the types, and with the same backend on the client and the server.
+    fn set_serialization_backend(&mut self, backend: SerializationBackend);
 }
 
 impl AppSerializeExt for App {
@@ -297,11 +335,22 @@ impl AppSerializeExt for App {
         let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
         registry.try_add_map_entities::<M>();
     }
+
+    fn set_serialization_backend(&mut self, backend: SerializationBackend) {
+        self.world_mut()
+            .resource_mut::<MessageRegistry>()
+            .serialization_backend = backend;
+        self.world_mut()
+            .resource_mut::<ComponentRegistry>()
+            .serialization_backend = backend;
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::protocol::serialize::{erased_serialize_fn, ErasedSerializeFns};
+    use crate::protocol::serialize::{
+        erased_serialize_fn, ErasedSerializeFns, SerializationBackend,
+    };
     use crate::serialize::reader::Reader;
     use crate::serialize::writer::Writer;
     use crate::shared::replication::authority::AuthorityChange;
@@ -311,7 +360,8 @@ mod tests {
 
     #[test]
     fn test_erased_serde() {
-        let mut registry = ErasedSerializeFns::new::<AuthorityChange>();
+        let mut registry =
+            ErasedSerializeFns::new::<AuthorityChange>(SerializationBackend::Bincode);
         registry.add_map_entities::<AuthorityChange>();
 
         let message = AuthorityChange {
@@ -339,7 +389,8 @@ mod tests {
 
     #[test]
     fn test_erased_serde_map_entities() {
-        let mut registry = ErasedSerializeFns::new::<AuthorityChange>();
+        let mut registry =
+            ErasedSerializeFns::new::<AuthorityChange>(SerializationBackend::Bincode);
         registry.add_map_entities::<AuthorityChange>();
 
         let message = AuthorityChange {
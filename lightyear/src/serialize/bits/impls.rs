@@ -0,0 +1,352 @@
+//! [`BitSerialize`] implementations for common types
+use std::io::{Read, Write};
+
+use bevy::math::{Quat, Vec2, Vec3};
+use bevy::prelude::{Entity, Transform};
+
+use crate::serialize::bits::{BitReader, BitSerialize, BitWriter};
+use crate::serialize::SerializationError;
+
+impl BitSerialize for bool {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        writer.write_bool(*self)
+    }
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        reader.read_bool()
+    }
+}
+
+/// Small integers are written on their full number of bits
+macro_rules! impl_fixed_int {
+    ($($ty:ty),*) => {
+        $(
+            impl BitSerialize for $ty {
+                fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+                    writer.write_bits(*self as u64, <$ty>::BITS)
+                }
+
+                fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+                    Ok(reader.read_bits(<$ty>::BITS)? as $ty)
+                }
+            }
+        )*
+    };
+}
+impl_fixed_int!(u8, i8);
+
+/// Bigger integers are written as varints, since they usually hold small values
+macro_rules! impl_varint {
+    ($($ty:ty),*) => {
+        $(
+            impl BitSerialize for $ty {
+                fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+                    writer.write_varint(*self as u64)
+                }
+
+                fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+                    <$ty>::try_from(reader.read_varint()?).map_err(|_| SerializationError::InvalidValue)
+                }
+            }
+        )*
+    };
+}
+impl_varint!(u16, u32, u64, usize);
+
+macro_rules! impl_signed_varint {
+    ($($ty:ty),*) => {
+        $(
+            impl BitSerialize for $ty {
+                fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+                    writer.write_signed_varint(*self as i64)
+                }
+
+                fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+                    <$ty>::try_from(reader.read_signed_varint()?).map_err(|_| SerializationError::InvalidValue)
+                }
+            }
+        )*
+    };
+}
+impl_signed_varint!(i16, i32, i64, isize);
+
+impl BitSerialize for f32 {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        writer.write_bits(self.to_bits() as u64, 32)
+    }
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        Ok(f32::from_bits(reader.read_bits(32)? as u32))
+    }
+}
+
+impl BitSerialize for f64 {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        writer.write_bits(self.to_bits(), 64)
+    }
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        Ok(f64::from_bits(reader.read_bits(64)?))
+    }
+}
+
+impl BitSerialize for char {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        writer.write_varint(*self as u64)
+    }
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        u32::try_from(reader.read_varint()?)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(SerializationError::InvalidValue)
+    }
+}
+
+impl BitSerialize for String {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        writer.write_varint(self.len() as u64)?;
+        self.bytes()
+            .try_for_each(|b| writer.write_bits(b as u64, 8))
+    }
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        let bytes = Vec::<u8>::decode(reader)?;
+        String::from_utf8(bytes).map_err(|_| SerializationError::InvalidValue)
+    }
+}
+
+impl BitSerialize for () {
+    fn encode<W: Write>(&self, _: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        Ok(())
+    }
+
+    fn decode<R: Read>(_: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        Ok(())
+    }
+}
+
+impl<T: BitSerialize> BitSerialize for Option<T> {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        writer.write_bool(self.is_some())?;
+        if let Some(value) = self {
+            value.encode(writer)?;
+        }
+        Ok(())
+    }
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        if reader.read_bool()? {
+            Ok(Some(T::decode(reader)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<T: BitSerialize> BitSerialize for Box<T> {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        self.as_ref().encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        Ok(Box::new(T::decode(reader)?))
+    }
+}
+
+impl<T: BitSerialize> BitSerialize for Vec<T> {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        writer.write_varint(self.len() as u64)?;
+        self.iter().try_for_each(|value| value.encode(writer))
+    }
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        let len = reader.read_varint()? as usize;
+        // do not trust the length for the allocation, in case the data is corrupted
+        let mut values = Vec::with_capacity(len.min(1024));
+        for _ in 0..len {
+            values.push(T::decode(reader)?);
+        }
+        Ok(values)
+    }
+}
+
+impl<T: BitSerialize, const N: usize> BitSerialize for [T; N] {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        self.iter().try_for_each(|value| value.encode(writer))
+    }
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        let mut values = Vec::with_capacity(N);
+        for _ in 0..N {
+            values.push(T::decode(reader)?);
+        }
+        values
+            .try_into()
+            .map_err(|_| SerializationError::InvalidValue)
+    }
+}
+
+macro_rules! impl_tuple {
+    ($($name:ident),*) => {
+        impl<$($name: BitSerialize),*> BitSerialize for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+                let ($($name,)*) = self;
+                $($name.encode(writer)?;)*
+                Ok(())
+            }
+
+            fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+                Ok(($($name::decode(reader)?,)*))
+            }
+        }
+    };
+}
+impl_tuple!(A);
+impl_tuple!(A, B);
+impl_tuple!(A, B, C);
+impl_tuple!(A, B, C, D);
+
+impl BitSerialize for Entity {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        writer.write_varint(self.index() as u64)?;
+        writer.write_varint(self.generation() as u64)
+    }
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        let index = u32::decode(reader)?;
+        let generation = u32::decode(reader)?;
+        Entity::try_from_bits(((generation as u64) << 32) | index as u64)
+            .map_err(|_| SerializationError::InvalidValue)
+    }
+}
+
+impl BitSerialize for Vec2 {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        self.x.encode(writer)?;
+        self.y.encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        Ok(Vec2::new(f32::decode(reader)?, f32::decode(reader)?))
+    }
+}
+
+impl BitSerialize for Vec3 {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        self.x.encode(writer)?;
+        self.y.encode(writer)?;
+        self.z.encode(writer)
+    }
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        Ok(Vec3::new(
+            f32::decode(reader)?,
+            f32::decode(reader)?,
+            f32::decode(reader)?,
+        ))
+    }
+}
+
+/// Quaternions are normalized, so each component is in `[-1, 1]`: we quantize them on 16 bits.
+impl BitSerialize for Quat {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        for v in self.to_array() {
+            writer.write_quantized(v as f64, -1.0, 1.0, 16)?;
+        }
+        Ok(())
+    }
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        let mut values = [0.0; 4];
+        for v in values.iter_mut() {
+            *v = reader.read_quantized(-1.0, 1.0, 16)? as f32;
+        }
+        Ok(Quat::from_array(values).normalize())
+    }
+}
+
+impl BitSerialize for Transform {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError> {
+        self.translation.encode(writer)?;
+        self.rotation.encode(writer)?;
+        // most transforms are not scaled
+        if self.scale == Vec3::ONE {
+            writer.write_bool(false)
+        } else {
+            writer.write_bool(true)?;
+            self.scale.encode(writer)
+        }
+    }
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError> {
+        let translation = Vec3::decode(reader)?;
+        let rotation = Quat::decode(reader)?;
+        let scale = if reader.read_bool()? {
+            Vec3::decode(reader)?
+        } else {
+            Vec3::ONE
+        };
+        Ok(Transform {
+            translation,
+            rotation,
+            scale,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use super::*;
+
+    fn roundtrip<T: BitSerialize + PartialEq + Debug>(value: T) -> usize {
+        let mut writer = BitWriter::new(vec![]);
+        value.encode(&mut writer).unwrap();
+        let bytes = writer.finish().unwrap();
+        let mut reader = BitReader::new(bytes.as_slice());
+        assert_eq!(T::decode(&mut reader).unwrap(), value);
+        bytes.len()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip(true);
+        roundtrip(-3i8);
+        roundtrip(u64::MAX);
+        roundtrip(i64::MIN);
+        roundtrip(-1.5f32);
+        roundtrip('é');
+        roundtrip("lightyear".to_string());
+        roundtrip(Some(vec![1u16, 2, 300]));
+        roundtrip::<Option<u8>>(None);
+        roundtrip([1u32, 2, 3]);
+        roundtrip((true, 2u8, Vec2::new(1.0, -1.0)));
+        roundtrip(Entity::from_raw(12));
+    }
+
+    #[test]
+    fn test_packing() {
+        // 8 booleans fit in a single byte
+        assert_eq!(roundtrip([true; 8]), 1);
+        // small integers use fewer bytes
+        assert_eq!(roundtrip(3u32), 1);
+    }
+
+    #[test]
+    fn test_transform() {
+        let transform = Transform::from_xyz(1.0, 2.0, 3.0)
+            .with_rotation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_3));
+        let mut writer = BitWriter::new(vec![]);
+        transform.encode(&mut writer).unwrap();
+        let bytes = writer.finish().unwrap();
+        // 3 * 32 bits for the translation, 4 * 16 bits for the rotation, 1 bit for the scale
+        assert_eq!(bytes.len(), 21);
+        let decoded = Transform::decode(&mut BitReader::new(bytes.as_slice())).unwrap();
+        assert_eq!(decoded.translation, transform.translation);
+        assert_eq!(decoded.scale, transform.scale);
+        assert!(decoded.rotation.abs_diff_eq(transform.rotation, 1e-4));
+    }
+}
@@ -0,0 +1,409 @@
+/*!
+Bit-packed serialization.
+
+The default serialization uses [`bincode`], which writes every value on a whole number of bytes.
+Game-state payloads are usually made of many small values (booleans, small enums, bounded integers,
+floats that don't need full precision) that can be packed much more tightly.
+
+This module provides:
+- a [`BitWriter`] and [`BitReader`] that can write/read values on an arbitrary number of bits
+- the [`BitSerialize`] trait, which can be derived with `#[derive(BitSerialize)]`. The derive supports
+  the field attributes:
+  - `#[bits(quantize(min = -100.0, max = 100.0, bits = 12))]` to write a float on `bits` bits, with a
+    precision of `(max - min) / (2^bits - 1)`
+  - `#[bits(range(min = 0, max = 15))]` to write an integer using only the number of bits required to
+    represent the range
+- a [`serde`] backend, so that any `Serialize + DeserializeOwned` type can be bit-packed without
+  implementing [`BitSerialize`]
+
+A type can use the bit-packed serialization with `register_message_bitpacked` or `register_component_bitpacked`,
+or all the types registered with `register_message`/`register_component` can use the bit-packed [`serde`]
+backend via [`AppSerializeExt::set_serialization_backend`](crate::protocol::serialize::AppSerializeExt::set_serialization_backend).
+
+```rust,ignore
+#[derive(BitSerialize, Clone, PartialEq, Debug)]
+struct PlayerPosition {
+    #[bits(quantize(min = -1000.0, max = 1000.0, bits = 16))]
+    x: f32,
+    #[bits(quantize(min = -1000.0, max = 1000.0, bits = 16))]
+    y: f32,
+    #[bits(range(min = 0, max = 3))]
+    team: u8,
+}
+```
+*/
+use std::io::{Read, Write};
+
+use byteorder::{ReadBytesExt, WriteBytesExt};
+
+use crate::serialize::reader::Reader;
+use crate::serialize::writer::Writer;
+use crate::serialize::SerializationError;
+
+mod impls;
+pub(crate) mod serializer;
+
+/// Number of bits needed to represent every integer in `0..=max`
+pub const fn bits_needed(max: u64) -> u32 {
+    u64::BITS - max.leading_zeros()
+}
+
+/// Writes values on an arbitrary number of bits into an underlying byte writer.
+///
+/// Bits are accumulated and written to the underlying writer one byte at a time;
+/// [`BitWriter::finish`] must be called to write the last partial byte.
+pub struct BitWriter<W: Write> {
+    inner: W,
+    scratch: u128,
+    num_bits: u32,
+}
+
+impl<W: Write> BitWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            scratch: 0,
+            num_bits: 0,
+        }
+    }
+
+    /// Write the `num_bits` least significant bits of `value`
+    pub fn write_bits(&mut self, value: u64, num_bits: u32) -> Result<(), SerializationError> {
+        debug_assert!(num_bits <= u64::BITS);
+        if num_bits == 0 {
+            return Ok(());
+        }
+        let mask = u64::MAX >> (u64::BITS - num_bits);
+        self.scratch |= ((value & mask) as u128) << self.num_bits;
+        self.num_bits += num_bits;
+        while self.num_bits >= 8 {
+            self.inner.write_u8(self.scratch as u8)?;
+            self.scratch >>= 8;
+            self.num_bits -= 8;
+        }
+        Ok(())
+    }
+
+    pub fn write_bool(&mut self, value: bool) -> Result<(), SerializationError> {
+        self.write_bits(value as u64, 1)
+    }
+
+    /// Write an unsigned integer using groups of 7 bits, each followed by a continuation bit.
+    /// Small values use fewer bits.
+    pub fn write_varint(&mut self, mut value: u64) -> Result<(), SerializationError> {
+        loop {
+            let group = value & 0x7f;
+            value >>= 7;
+            self.write_bits(group, 7)?;
+            self.write_bool(value != 0)?;
+            if value == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Write a signed integer with zigzag encoding, so that small negative values use few bits
+    pub fn write_signed_varint(&mut self, value: i64) -> Result<(), SerializationError> {
+        self.write_varint(((value << 1) ^ (value >> 63)) as u64)
+    }
+
+    /// Write an integer in the range `min..=max`, using only the number of bits needed to represent the range
+    pub fn write_bounded(
+        &mut self,
+        value: i64,
+        min: i64,
+        max: i64,
+    ) -> Result<(), SerializationError> {
+        if value < min || value > max {
+            return Err(SerializationError::InvalidValue);
+        }
+        let range = (max as i128 - min as i128) as u64;
+        self.write_bits((value as i128 - min as i128) as u64, bits_needed(range))
+    }
+
+    /// Write a float in the range `min..=max` on `num_bits` bits.
+    ///
+    /// Values outside of the range are clamped.
+    pub fn write_quantized(
+        &mut self,
+        value: f64,
+        min: f64,
+        max: f64,
+        num_bits: u32,
+    ) -> Result<(), SerializationError> {
+        debug_assert!(min < max);
+        debug_assert!(num_bits > 0 && num_bits <= u64::BITS);
+        let steps = (u64::MAX >> (u64::BITS - num_bits)) as f64;
+        let normalized = ((value - min) / (max - min)).clamp(0.0, 1.0);
+        self.write_bits((normalized * steps).round() as u64, num_bits)
+    }
+
+    /// Write the remaining bits (padded with zeros to a full byte) and return the underlying writer
+    pub fn finish(mut self) -> Result<W, SerializationError> {
+        if self.num_bits > 0 {
+            self.inner.write_u8(self.scratch as u8)?;
+        }
+        Ok(self.inner)
+    }
+}
+
+/// Reads values written by a [`BitWriter`].
+///
+/// Bytes are only read from the underlying reader when they are needed, so that once a value
+/// has been read, the underlying reader is positioned right after the last byte used by the value.
+pub struct BitReader<R: Read> {
+    inner: R,
+    scratch: u128,
+    num_bits: u32,
+}
+
+impl<R: Read> BitReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            scratch: 0,
+            num_bits: 0,
+        }
+    }
+
+    pub fn read_bits(&mut self, num_bits: u32) -> Result<u64, SerializationError> {
+        debug_assert!(num_bits <= u64::BITS);
+        if num_bits == 0 {
+            return Ok(0);
+        }
+        while self.num_bits < num_bits {
+            self.scratch |= (self.inner.read_u8()? as u128) << self.num_bits;
+            self.num_bits += 8;
+        }
+        let mask = u64::MAX >> (u64::BITS - num_bits);
+        let value = self.scratch as u64 & mask;
+        self.scratch >>= num_bits;
+        self.num_bits -= num_bits;
+        Ok(value)
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, SerializationError> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    pub fn read_varint(&mut self) -> Result<u64, SerializationError> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let group = self.read_bits(7)?;
+            // the varint overflows a u64 if any bit of the group would be shifted out
+            if shift >= u64::BITS || (group << shift) >> shift != group {
+                return Err(SerializationError::InvalidValue);
+            }
+            value |= group << shift;
+            shift += 7;
+            if !self.read_bool()? {
+                return Ok(value);
+            }
+        }
+    }
+
+    pub fn read_signed_varint(&mut self) -> Result<i64, SerializationError> {
+        let value = self.read_varint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    pub fn read_bounded(&mut self, min: i64, max: i64) -> Result<i64, SerializationError> {
+        let range = (max as i128 - min as i128) as u64;
+        let value = self.read_bits(bits_needed(range))?;
+        if value > range {
+            return Err(SerializationError::InvalidValue);
+        }
+        Ok((min as i128 + value as i128) as i64)
+    }
+
+    pub fn read_quantized(
+        &mut self,
+        min: f64,
+        max: f64,
+        num_bits: u32,
+    ) -> Result<f64, SerializationError> {
+        debug_assert!(num_bits > 0 && num_bits <= u64::BITS);
+        let steps = (u64::MAX >> (u64::BITS - num_bits)) as f64;
+        let value = self.read_bits(num_bits)? as f64;
+        Ok(min + (value / steps) * (max - min))
+    }
+
+    /// Discard the bits remaining in the current byte and return the underlying reader
+    pub fn finish(self) -> R {
+        self.inner
+    }
+}
+
+/// A type that can be serialized with the bit-packed format of [`BitWriter`] and [`BitReader`].
+///
+/// Can be derived with `#[derive(BitSerialize)]`.
+pub trait BitSerialize: Sized {
+    fn encode<W: Write>(&self, writer: &mut BitWriter<W>) -> Result<(), SerializationError>;
+
+    fn decode<R: Read>(reader: &mut BitReader<R>) -> Result<Self, SerializationError>;
+}
+
+/// Serialize function that uses the [`BitSerialize`] implementation of the type
+pub(crate) fn bitpacked_serialize<M: BitSerialize>(
+    message: &M,
+    writer: &mut Writer,
+) -> Result<(), SerializationError> {
+    let mut bit_writer = BitWriter::new(writer);
+    message.encode(&mut bit_writer)?;
+    bit_writer.finish()?;
+    Ok(())
+}
+
+/// Deserialize function that uses the [`BitSerialize`] implementation of the type
+pub(crate) fn bitpacked_deserialize<M: BitSerialize>(
+    reader: &mut Reader,
+) -> Result<M, SerializationError> {
+    M::decode(&mut BitReader::new(reader))
+}
+
+#[cfg(test)]
+mod tests {
+    use lightyear_macros::BitSerializeInternal;
+
+    use super::*;
+
+    #[test]
+    fn test_bits_needed() {
+        assert_eq!(bits_needed(0), 0);
+        assert_eq!(bits_needed(1), 1);
+        assert_eq!(bits_needed(3), 2);
+        assert_eq!(bits_needed(4), 3);
+        assert_eq!(bits_needed(u64::MAX), 64);
+    }
+
+    #[test]
+    fn test_write_read_bits() {
+        let mut writer = BitWriter::new(vec![]);
+        writer.write_bool(true).unwrap();
+        writer.write_bits(5, 3).unwrap();
+        writer.write_varint(300).unwrap();
+        writer.write_signed_varint(-2).unwrap();
+        writer.write_bounded(-3, -5, 5).unwrap();
+        writer.write_quantized(0.25, -1.0, 1.0, 10).unwrap();
+        writer.write_bits(u64::MAX, 64).unwrap();
+        let bytes = writer.finish().unwrap();
+        // 1 + 3 + 16 + 8 + 4 + 10 + 64 bits
+        assert_eq!(bytes.len(), 14);
+
+        let mut reader = BitReader::new(bytes.as_slice());
+        assert!(reader.read_bool().unwrap());
+        assert_eq!(reader.read_bits(3).unwrap(), 5);
+        assert_eq!(reader.read_varint().unwrap(), 300);
+        assert_eq!(reader.read_signed_varint().unwrap(), -2);
+        assert_eq!(reader.read_bounded(-5, 5).unwrap(), -3);
+        let quantized = reader.read_quantized(-1.0, 1.0, 10).unwrap();
+        assert!((quantized - 0.25).abs() <= 2.0 / 1023.0);
+        assert_eq!(reader.read_bits(64).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn test_varint_overflow() {
+        let mut writer = BitWriter::new(vec![]);
+        writer.write_varint(u64::MAX).unwrap();
+        // 9 full groups, then a last group with 2 bits set: only 1 bit fits in a u64
+        for _ in 0..9 {
+            writer.write_bits(0x7f, 7).unwrap();
+            writer.write_bool(true).unwrap();
+        }
+        writer.write_bits(0b11, 7).unwrap();
+        writer.write_bool(false).unwrap();
+        let bytes = writer.finish().unwrap();
+
+        let mut reader = BitReader::new(bytes.as_slice());
+        assert_eq!(reader.read_varint().unwrap(), u64::MAX);
+        assert!(matches!(
+            reader.read_varint(),
+            Err(SerializationError::InvalidValue)
+        ));
+    }
+
+    #[test]
+    fn test_bounded_out_of_range() {
+        let mut writer = BitWriter::new(vec![]);
+        assert!(matches!(
+            writer.write_bounded(6, 0, 5),
+            Err(SerializationError::InvalidValue)
+        ));
+    }
+
+    /// The reader only consumes the bytes used by the value
+    #[test]
+    fn test_reader_position() {
+        let mut writer = Writer::default();
+        bitpacked_serialize(&true, &mut writer).unwrap();
+        bitpacked_serialize(&7u8, &mut writer).unwrap();
+        let mut reader = Reader::from(writer.to_bytes());
+        assert!(bitpacked_deserialize::<bool>(&mut reader).unwrap());
+        assert_eq!(bitpacked_deserialize::<u8>(&mut reader).unwrap(), 7);
+        assert!(!reader.has_remaining());
+    }
+
+    #[derive(BitSerializeInternal, Debug, PartialEq)]
+    enum Action {
+        Idle,
+        Move(#[bits(quantize(min = -1.0, max = 1.0, bits = 8))] f32),
+        Attack {
+            #[bits(range(min = 1, max = 10))]
+            damage: u32,
+            target: Option<u16>,
+        },
+    }
+
+    #[derive(BitSerializeInternal, Debug, PartialEq)]
+    struct Wrapper<T>(Vec<T>);
+
+    /// Fields can have the same name as the arguments of `encode` and `decode`
+    #[derive(BitSerializeInternal, Debug, PartialEq)]
+    struct Io {
+        writer: u8,
+        reader: bool,
+    }
+
+    #[test]
+    fn test_derive() {
+        let value = Wrapper(vec![
+            Action::Idle,
+            Action::Move(-1.0),
+            Action::Attack {
+                damage: 7,
+                target: None,
+            },
+        ]);
+        let mut writer = BitWriter::new(vec![]);
+        value.encode(&mut writer).unwrap();
+        let bytes = writer.finish().unwrap();
+        // 8 bits for the length, 2 bits for each variant, 8 bits for Move, 4 + 1 bits for Attack
+        assert_eq!(bytes.len(), 4);
+        let decoded = Wrapper::<Action>::decode(&mut BitReader::new(bytes.as_slice())).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn test_derive_field_names() {
+        let value = Io {
+            writer: 3,
+            reader: true,
+        };
+        let mut writer = BitWriter::new(vec![]);
+        value.encode(&mut writer).unwrap();
+        let bytes = writer.finish().unwrap();
+        let decoded = Io::decode(&mut BitReader::new(bytes.as_slice())).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn test_derive_invalid_variant() {
+        // variant index 3 does not exist
+        let bytes = [0b11u8];
+        assert!(matches!(
+            Action::decode(&mut BitReader::new(bytes.as_slice())),
+            Err(SerializationError::InvalidValue)
+        ));
+    }
+}
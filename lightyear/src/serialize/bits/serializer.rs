@@ -0,0 +1,643 @@
+//! [`serde`] backend for the bit-packed format, so that any `Serialize + DeserializeOwned` type
+//! can be bit-packed without implementing [`BitSerialize`](super::BitSerialize).
+//!
+//! The format is not self-describing: primitives are written like their [`BitSerialize`](super::BitSerialize)
+//! implementation, enum variants are written as a varint index, and sequences and maps are prefixed
+//! by their length.
+use std::fmt::Display;
+use std::io::{Read, Write};
+
+use serde::de::{
+    DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::ser::{
+    SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+    SerializeTupleStruct, SerializeTupleVariant,
+};
+use serde::{Deserializer, Serialize, Serializer};
+
+use crate::serialize::bits::{BitReader, BitWriter};
+use crate::serialize::reader::Reader;
+use crate::serialize::writer::Writer;
+use crate::serialize::SerializationError;
+
+impl serde::ser::Error for SerializationError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerializationError::Serde(msg.to_string())
+    }
+}
+
+impl serde::de::Error for SerializationError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerializationError::Serde(msg.to_string())
+    }
+}
+
+/// Serialize function that uses the bit-packed [`serde`] backend
+pub(crate) fn serde_bitpacked_serialize<M: Serialize>(
+    message: &M,
+    writer: &mut Writer,
+) -> Result<(), SerializationError> {
+    let mut bit_writer = BitWriter::new(writer);
+    message.serialize(&mut bit_writer)?;
+    bit_writer.finish()?;
+    Ok(())
+}
+
+/// Deserialize function that uses the bit-packed [`serde`] backend
+pub(crate) fn serde_bitpacked_deserialize<M: DeserializeOwned>(
+    reader: &mut Reader,
+) -> Result<M, SerializationError> {
+    M::deserialize(&mut BitReader::new(reader))
+}
+
+impl<W: Write> Serializer for &mut BitWriter<W> {
+    type Ok = ();
+    type Error = SerializationError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<(), SerializationError> {
+        self.write_bool(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), SerializationError> {
+        self.write_bits(v as u64, 8)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), SerializationError> {
+        self.write_signed_varint(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), SerializationError> {
+        self.write_signed_varint(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), SerializationError> {
+        self.write_signed_varint(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), SerializationError> {
+        self.write_bits(v as u64, 8)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), SerializationError> {
+        self.write_varint(v as u64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), SerializationError> {
+        self.write_varint(v as u64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), SerializationError> {
+        self.write_varint(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), SerializationError> {
+        self.write_bits(v.to_bits() as u64, 32)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), SerializationError> {
+        self.write_bits(v.to_bits(), 64)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), SerializationError> {
+        self.write_varint(v as u64)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), SerializationError> {
+        self.serialize_bytes(v.as_bytes())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), SerializationError> {
+        self.write_varint(v.len() as u64)?;
+        v.iter().try_for_each(|b| self.write_bits(*b as u64, 8))
+    }
+
+    fn serialize_none(self) -> Result<(), SerializationError> {
+        self.write_bool(false)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), SerializationError> {
+        self.write_bool(true)?;
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), SerializationError> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        variant_index: u32,
+        _: &'static str,
+    ) -> Result<(), SerializationError> {
+        self.write_varint(variant_index as u64)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), SerializationError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        variant_index: u32,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), SerializationError> {
+        self.write_varint(variant_index as u64)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self, SerializationError> {
+        let len = len.ok_or(SerializationError::Serde(
+            "the length of sequences must be known".to_string(),
+        ))?;
+        self.write_varint(len as u64)?;
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self, SerializationError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self, SerializationError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        variant_index: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self, SerializationError> {
+        self.write_varint(variant_index as u64)?;
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self, SerializationError> {
+        let len = len.ok_or(SerializationError::Serde(
+            "the length of maps must be known".to_string(),
+        ))?;
+        self.write_varint(len as u64)?;
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, SerializationError> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        variant_index: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self, SerializationError> {
+        self.write_varint(variant_index as u64)?;
+        Ok(self)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// Implement the serde `SerializeX` traits that simply serialize each element in order
+macro_rules! impl_serialize_compound {
+    ($trait:ident, $method:ident $(, $key:ty)?) => {
+        impl<W: Write> $trait for &mut BitWriter<W> {
+            type Ok = ();
+            type Error = SerializationError;
+
+            fn $method<T: ?Sized + Serialize>(
+                &mut self,
+                $(_: $key,)?
+                value: &T,
+            ) -> Result<(), SerializationError> {
+                value.serialize(&mut **self)
+            }
+
+            fn end(self) -> Result<(), SerializationError> {
+                Ok(())
+            }
+        }
+    };
+}
+impl_serialize_compound!(SerializeSeq, serialize_element);
+impl_serialize_compound!(SerializeTuple, serialize_element);
+impl_serialize_compound!(SerializeTupleStruct, serialize_field);
+impl_serialize_compound!(SerializeTupleVariant, serialize_field);
+impl_serialize_compound!(SerializeStruct, serialize_field, &'static str);
+impl_serialize_compound!(SerializeStructVariant, serialize_field, &'static str);
+
+impl<W: Write> SerializeMap for &mut BitWriter<W> {
+    type Ok = ();
+    type Error = SerializationError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), SerializationError> {
+        key.serialize(&mut **self)
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), SerializationError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl<'de, R: Read> Deserializer<'de> for &mut BitReader<R> {
+    type Error = SerializationError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, SerializationError> {
+        Err(SerializationError::Serde(
+            "the bit-packed format is not self-describing".to_string(),
+        ))
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        visitor.visit_bool(self.read_bool()?)
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        visitor.visit_i8(self.read_bits(8)? as i8)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        let value = self.read_signed_varint()?;
+        visitor.visit_i16(
+            value
+                .try_into()
+                .map_err(|_| SerializationError::InvalidValue)?,
+        )
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        let value = self.read_signed_varint()?;
+        visitor.visit_i32(
+            value
+                .try_into()
+                .map_err(|_| SerializationError::InvalidValue)?,
+        )
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        visitor.visit_i64(self.read_signed_varint()?)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        visitor.visit_u8(self.read_bits(8)? as u8)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        let value = self.read_varint()?;
+        visitor.visit_u16(
+            value
+                .try_into()
+                .map_err(|_| SerializationError::InvalidValue)?,
+        )
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        let value = self.read_varint()?;
+        visitor.visit_u32(
+            value
+                .try_into()
+                .map_err(|_| SerializationError::InvalidValue)?,
+        )
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        visitor.visit_u64(self.read_varint()?)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        visitor.visit_f32(f32::from_bits(self.read_bits(32)? as u32))
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        visitor.visit_f64(f64::from_bits(self.read_bits(64)?))
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        let value = u32::try_from(self.read_varint()?)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(SerializationError::InvalidValue)?;
+        visitor.visit_char(value)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, SerializationError> {
+        let bytes = self.read_byte_buf()?;
+        visitor
+            .visit_string(String::from_utf8(bytes).map_err(|_| SerializationError::InvalidValue)?)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, SerializationError> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, SerializationError> {
+        visitor.visit_byte_buf(self.read_byte_buf()?)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, SerializationError> {
+        if self.read_bool()? {
+            visitor.visit_some(self)
+        } else {
+            visitor.visit_none()
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerializationError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerializationError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        let len = self.read_varint()? as usize;
+        visitor.visit_seq(Access { reader: self, len })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerializationError> {
+        visitor.visit_seq(Access { reader: self, len })
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerializationError> {
+        visitor.visit_seq(Access { reader: self, len })
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerializationError> {
+        let len = self.read_varint()? as usize;
+        visitor.visit_map(Access { reader: self, len })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerializationError> {
+        visitor.visit_seq(Access {
+            reader: self,
+            len: fields.len(),
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerializationError> {
+        visitor.visit_enum(self)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, SerializationError> {
+        self.deserialize_u32(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, SerializationError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+impl<R: Read> BitReader<R> {
+    fn read_byte_buf(&mut self) -> Result<Vec<u8>, SerializationError> {
+        let len = self.read_varint()? as usize;
+        // do not trust the length for the allocation, in case the data is corrupted
+        let mut bytes = Vec::with_capacity(len.min(1024));
+        for _ in 0..len {
+            bytes.push(self.read_bits(8)? as u8);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Gives access to the `len` elements of a sequence or map
+struct Access<'a, R: Read> {
+    reader: &'a mut BitReader<R>,
+    len: usize,
+}
+
+impl<'de, 'a, R: Read> SeqAccess<'de> for Access<'a, R> {
+    type Error = SerializationError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SerializationError> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        seed.deserialize(&mut *self.reader).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+impl<'de, 'a, R: Read> MapAccess<'de> for Access<'a, R> {
+    type Error = SerializationError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerializationError> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        seed.deserialize(&mut *self.reader).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerializationError> {
+        seed.deserialize(&mut *self.reader)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+impl<'de, R: Read> EnumAccess<'de> for &mut BitReader<R> {
+    type Error = SerializationError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), SerializationError> {
+        let variant_index =
+            u32::try_from(self.read_varint()?).map_err(|_| SerializationError::InvalidValue)?;
+        let value = seed.deserialize(IntoDeserializer::<SerializationError>::into_deserializer(
+            variant_index,
+        ))?;
+        Ok((value, self))
+    }
+}
+
+impl<'de, R: Read> VariantAccess<'de> for &mut BitReader<R> {
+    type Error = SerializationError;
+
+    fn unit_variant(self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerializationError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerializationError> {
+        visitor.visit_seq(Access { reader: self, len })
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerializationError> {
+        visitor.visit_seq(Access {
+            reader: self,
+            len: fields.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{Entity, Transform};
+    use bevy::utils::HashMap;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Action {
+        Jump,
+        Move { x: i16, y: i16 },
+        Say(String),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Message {
+        entity: Entity,
+        actions: Vec<Action>,
+        flags: (bool, bool, bool),
+        target: Option<u32>,
+        scores: HashMap<u8, f32>,
+        transform: Transform,
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let message = Message {
+            entity: Entity::from_raw(3),
+            actions: vec![
+                Action::Jump,
+                Action::Move { x: -3, y: 400 },
+                Action::Say("hi".to_string()),
+            ],
+            flags: (true, false, true),
+            target: None,
+            scores: HashMap::from_iter([(1, 0.5)]),
+            transform: Transform::from_xyz(1.0, 2.0, 3.0),
+        };
+        let mut writer = Writer::default();
+        serde_bitpacked_serialize(&message, &mut writer).unwrap();
+        let bytes = writer.to_bytes();
+        let mut reader = Reader::from(bytes.clone());
+        let decoded = serde_bitpacked_deserialize::<Message>(&mut reader).unwrap();
+        assert_eq!(decoded, message);
+        assert!(!reader.has_remaining());
+
+        // the bit-packed format is smaller than bincode
+        let bincode_bytes =
+            bincode::serde::encode_to_vec(&message, bincode::config::standard()).unwrap();
+        assert!(bytes.len() < bincode_bytes.len());
+    }
+}
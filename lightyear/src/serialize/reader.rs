@@ -52,6 +52,16 @@ impl Reader {
         bytes
     }
 
+    /// Returns the bytes that have not been read yet, without consuming them
+    pub(crate) fn remaining_slice(&self) -> &[u8] {
+        self.0.chunk()
+    }
+
+    /// Skip the next `len` bytes
+    pub(crate) fn advance(&mut self, len: usize) {
+        self.0.advance(len);
+    }
+
     pub(crate) fn has_remaining(&self) -> bool {
         self.0.has_remaining()
     }
@@ -1,13 +1,15 @@
 //! Defines server-specific configuration options
 use bevy::prelude::Resource;
+use bevy::utils::Duration;
 use governor::Quota;
 use nonzero_ext::nonzero;
 use std::sync::Arc;
 
-use crate::connection::netcode::{Key, PRIVATE_KEY_BYTES};
+use crate::connection::netcode::{Key, MAX_PACKET_SIZE, PRIVATE_KEY_BYTES};
 use crate::connection::server::{
     ConnectionRequestHandler, DefaultConnectionRequestHandler, NetConfig,
 };
+use crate::packet::mtu::MtuDiscoveryConfig;
 use crate::prelude::ReplicationConfig;
 use crate::shared::config::SharedConfig;
 use crate::shared::ping::manager::PingConfig;
@@ -67,6 +69,19 @@ pub struct PacketConfig {
     pub per_client_send_bandwidth_cap: Quota,
     /// If false, there is no bandwidth cap and all messages are sent as soon as possible
     pub bandwidth_cap_enabled: bool,
+    /// Maximum number of bytes in a packet. Messages that are bigger will be fragmented.
+    ///
+    /// Cannot exceed [`MAX_PACKET_SIZE`], which is the maximum payload size of the netcode protocol.
+    /// Lower it if some networks drop packets that are too big.
+    pub max_packet_size: usize,
+    /// If set, the connection will start with small packets and probe for the largest packet size
+    /// (up to `max_packet_size`) that can reach the remote peer
+    pub mtu_discovery: Option<MtuDiscoveryConfig>,
+    /// Duration after which a client that hasn't sent its protocol fingerprint is denied.
+    ///
+    /// Until the fingerprint has been accepted, the server only processes the ping messages of the client.
+    /// A denied client is disconnected once it has acked the reason of the denial, or after this same timeout.
+    pub handshake_timeout: Duration,
 }
 
 impl Default for PacketConfig {
@@ -76,6 +91,9 @@ impl Default for PacketConfig {
             // 56 KB/s bandwidth cap
             per_client_send_bandwidth_cap: Quota::per_second(nonzero!(56000u32)),
             bandwidth_cap_enabled: false,
+            max_packet_size: MAX_PACKET_SIZE,
+            mtu_discovery: None,
+            handshake_timeout: Duration::from_secs(5),
         }
     }
 }
@@ -96,6 +114,21 @@ impl PacketConfig {
         self.bandwidth_cap_enabled = true;
         self
     }
+
+    pub fn with_max_packet_size(mut self, max_packet_size: usize) -> Self {
+        self.max_packet_size = max_packet_size;
+        self
+    }
+
+    pub fn with_mtu_discovery(mut self, mtu_discovery: MtuDiscoveryConfig) -> Self {
+        self.mtu_discovery = Some(mtu_discovery);
+        self
+    }
+
+    pub fn with_handshake_timeout(mut self, handshake_timeout: Duration) -> Self {
+        self.handshake_timeout = handshake_timeout;
+        self
+    }
 }
 
 /// Configuration for the server plugin.
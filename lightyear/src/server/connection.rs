@@ -5,33 +5,39 @@ use bevy::prelude::{Component, Entity, Resource, World};
 use bevy::ptr::Ptr;
 use bevy::utils::{Duration, HashMap};
 use bytes::Bytes;
+use crossbeam_channel::Receiver;
 use hashbrown::hash_map::Entry;
-use tracing::{debug, info, info_span, trace, trace_span};
+use tracing::{debug, error, info, info_span, trace, trace_span, warn};
 #[cfg(feature = "trace")]
 use tracing::{instrument, Level};
 
 use crate::channel::builder::{
-    EntityActionsChannel, EntityUpdatesChannel, PingChannel, PongChannel,
+    EntityActionsChannel, EntityUpdatesChannel, HandshakeChannel, InterpolationDelayChannel,
+    PingChannel, PongChannel, SnapshotAckChannel, SnapshotChannel,
 };
 
 use crate::channel::receivers::ChannelReceive;
-use crate::channel::senders::ChannelSend;
+use crate::channel::senders::{ChannelSend, CoalescingKey};
+use crate::channel::stats::ChannelStats;
 use crate::client::message::ClientMessage;
 use crate::connection::id::ClientId;
 use crate::connection::netcode::MAX_PACKET_SIZE;
+use crate::connection::server::DeniedReason;
+use crate::packet::message::MessageId;
 use crate::packet::message_manager::MessageManager;
 use crate::packet::packet_builder::{Payload, RecvPayload};
 use crate::prelude::server::{DisconnectEvent, RoomId, RoomManager};
 use crate::prelude::{
-    Channel, ChannelKind, Message, PreSpawnedPlayerObject, ReplicationConfig, ReplicationGroup,
-    ShouldBePredicted,
+    Channel, ChannelDirection, ChannelKind, Message, PreSpawnedPlayerObject, ReplicationConfig,
+    ReplicationGroup, ShouldBePredicted,
 };
 use crate::protocol::channel::ChannelRegistry;
 use crate::protocol::component::{
     ComponentError, ComponentKind, ComponentNetId, ComponentRegistry,
 };
-use crate::protocol::message::{MessageError, MessageRegistry, MessageType};
-use crate::protocol::registry::NetId;
+use crate::protocol::fingerprint::{HandshakeMessage, ProtocolFingerprint};
+use crate::protocol::message::{MessageError, MessageKind, MessageRegistry, MessageType};
+use crate::protocol::registry::{NetId, ProtocolVersion};
 use crate::serialize::reader::Reader;
 use crate::serialize::writer::Writer;
 use crate::serialize::{SerializationError, ToBytes};
@@ -40,6 +46,7 @@ use crate::server::error::ServerError;
 use crate::server::events::{ConnectEvent, ServerEvents};
 use crate::server::relevance::error::RelevanceError;
 use crate::shared::events::connection::ConnectionEvents;
+use crate::shared::message::InterpolationDelayMessage;
 use crate::shared::message::MessageSend;
 use crate::shared::ping::manager::{PingConfig, PingManager};
 use crate::shared::ping::message::{Ping, Pong};
@@ -48,6 +55,7 @@ use crate::shared::replication::delta::DeltaManager;
 use crate::shared::replication::network_target::NetworkTarget;
 use crate::shared::replication::receive::ReplicationReceiver;
 use crate::shared::replication::send::ReplicationSender;
+use crate::shared::replication::snapshot::{Snapshot, SnapshotAckMessage, SnapshotSender};
 use crate::shared::replication::{EntityActionsMessage, EntityUpdatesMessage, ReplicationPeer};
 use crate::shared::replication::{ReplicationReceive, ReplicationSend};
 use crate::shared::sets::ServerMarker;
@@ -70,6 +78,12 @@ pub struct ConnectionManager {
     pub(crate) new_clients: Vec<ClientId>,
     pub(crate) writer: Writer,
 
+    /// Version of the server's protocol. Clients can use this version or an older one
+    protocol_version: ProtocolVersion,
+    /// Clients whose connection was denied during the handshake; they will be disconnected
+    /// once the denial has been sent
+    pub(crate) denied_clients: Vec<ClientId>,
+
     // CONFIG
     replication_config: ReplicationConfig,
     packet_config: PacketConfig,
@@ -85,6 +99,7 @@ impl Default for ConnectionManager {
             ReplicationConfig::default(),
             PacketConfig::default(),
             PingConfig::default(),
+            ProtocolVersion::default(),
         )
     }
 }
@@ -96,6 +111,7 @@ impl ConnectionManager {
         replication_config: ReplicationConfig,
         packet_config: PacketConfig,
         ping_config: PingConfig,
+        protocol_version: ProtocolVersion,
     ) -> Self {
         Self {
             connections: HashMap::default(),
@@ -105,6 +121,8 @@ impl ConnectionManager {
             delta_manager: DeltaManager::default(),
             new_clients: vec![],
             writer: Writer::with_capacity(MAX_PACKET_SIZE),
+            protocol_version,
+            denied_clients: vec![],
             replication_config,
             packet_config,
             ping_config,
@@ -117,8 +135,13 @@ impl ConnectionManager {
     }
 
     /// Return the list of connected [`ClientId`]s
+    ///
+    /// A client is only considered connected once the server has accepted its protocol during the handshake.
     pub fn connected_clients(&self) -> impl Iterator<Item = ClientId> + '_ {
-        self.connections.keys().copied()
+        self.connections
+            .iter()
+            .filter(|(_, connection)| connection.is_accepted())
+            .map(|(client_id, _)| *client_id)
     }
 
     // TODO: we need `&mut self` because MapEntities requires `&mut EntityMapper` even though it's not needed here
@@ -169,6 +192,53 @@ impl ConnectionManager {
         self.send_message_to_target::<C, M>(message, NetworkTarget::Single(client_id))
     }
 
+    /// Queues up a message to be sent to all clients matching the specific [`NetworkTarget`],
+    /// keeping only the latest message for a given `key`.
+    ///
+    /// On unreliable channels, if several messages of type `M` with the same `key` are sent to a client
+    /// before the channel sends its next packet, only the most recent one is sent. This is useful for
+    /// messages where only the latest value matters. The key can be any value that identifies the message,
+    /// for example an entity converted with [`Entity::to_bits`].
+    ///
+    /// Messages are coalesced on the [`ChannelMode::UnorderedUnreliable`], [`ChannelMode::SequencedUnreliable`]
+    /// and [`ChannelMode::UnorderedUnreliableWithAcks`] channels. On reliable channels, all messages are sent.
+    ///
+    /// [`ChannelMode::UnorderedUnreliable`]: crate::prelude::ChannelMode::UnorderedUnreliable
+    /// [`ChannelMode::SequencedUnreliable`]: crate::prelude::ChannelMode::SequencedUnreliable
+    /// [`ChannelMode::UnorderedUnreliableWithAcks`]: crate::prelude::ChannelMode::UnorderedUnreliableWithAcks
+    pub fn send_message_to_target_coalesced<C: Channel, M: Message>(
+        &mut self,
+        message: &mut M,
+        target: NetworkTarget,
+        key: u64,
+    ) -> Result<(), ServerError> {
+        self.buffer_message(
+            message,
+            ChannelKind::of::<C>(),
+            target,
+            Some(CoalescingKey {
+                kind: MessageKind::of::<M>(),
+                key,
+            }),
+        )
+    }
+
+    /// Queues up a message to be sent to a client, keeping only the latest message for a given `key`.
+    ///
+    /// See [`ConnectionManager::send_message_to_target_coalesced`]
+    pub fn send_message_coalesced<C: Channel, M: Message>(
+        &mut self,
+        client_id: ClientId,
+        message: &mut M,
+        key: u64,
+    ) -> Result<(), ServerError> {
+        self.send_message_to_target_coalesced::<C, M>(
+            message,
+            NetworkTarget::Single(client_id),
+            key,
+        )
+    }
+
     /// Update the priority of a `ReplicationGroup` that is replicated to a given client
     pub fn update_priority(
         &mut self,
@@ -188,6 +258,35 @@ impl ConnectionManager {
         Ok(())
     }
 
+    /// Find the list of connected clients that match the provided [`NetworkTarget`] and that know the
+    /// types added in the protocol version `since` (i.e. whose protocol version is at least `since`)
+    pub(crate) fn connected_targets_since(
+        &self,
+        target: NetworkTarget,
+        since: ProtocolVersion,
+    ) -> Box<dyn Iterator<Item = ClientId>> {
+        let targets = self.connected_targets(target);
+        if since == 0 {
+            return targets;
+        }
+        let targets = targets
+            .filter(|client_id| {
+                self.connections
+                    .get(client_id)
+                    .is_some_and(|c| c.protocol_version >= since)
+            })
+            .collect::<Vec<_>>();
+        Box::new(targets.into_iter())
+    }
+
+    /// Oldest version of the protocol in which both the message and the channel are known
+    fn message_version(&self, message: &MessageKind, channel: &ChannelKind) -> ProtocolVersion {
+        self.message_registry
+            .kind_map
+            .version(message)
+            .max(self.channel_registry.version(channel))
+    }
+
     /// Find the list of connected clients that match the provided [`NetworkTarget`]
     pub(crate) fn connected_targets(
         &self,
@@ -197,11 +296,11 @@ impl ConnectionManager {
         match target {
             NetworkTarget::All => {
                 // TODO: maybe only send stuff when the client is time-synced ?
-                let connected_clients = self.connections.keys().copied().collect::<Vec<_>>();
+                let connected_clients = self.connected_clients().collect::<Vec<_>>();
                 Box::new(connected_clients.into_iter())
             }
             NetworkTarget::AllExceptSingle(client_id) => {
-                let connected_clients = self.connections.keys().copied().collect::<Vec<_>>();
+                let connected_clients = self.connected_clients().collect::<Vec<_>>();
                 Box::new(
                     connected_clients
                         .into_iter()
@@ -209,7 +308,7 @@ impl ConnectionManager {
                 )
             }
             NetworkTarget::AllExcept(client_ids) => {
-                let connected_clients = self.connections.keys().copied().collect::<Vec<_>>();
+                let connected_clients = self.connected_clients().collect::<Vec<_>>();
                 Box::new(
                     connected_clients
                         .into_iter()
@@ -217,14 +316,18 @@ impl ConnectionManager {
                 )
             }
             NetworkTarget::Single(client_id) => {
-                if self.connections.contains_key(&client_id) {
+                if self
+                    .connections
+                    .get(&client_id)
+                    .is_some_and(|connection| connection.is_accepted())
+                {
                     Box::new(std::iter::once(client_id))
                 } else {
                     Box::new(std::iter::empty())
                 }
             }
             NetworkTarget::Only(client_ids) => {
-                let connected_clients = self.connections.keys().copied().collect::<Vec<_>>();
+                let connected_clients = self.connected_clients().collect::<Vec<_>>();
                 Box::new(
                     connected_clients
                         .into_iter()
@@ -247,6 +350,13 @@ impl ConnectionManager {
             .ok_or(ServerError::ClientIdNotFound(client_id))
     }
 
+    /// Get the send/receive statistics of the [`Channel`] `C` for the connection with client `client_id`
+    pub fn channel_stats<C: Channel>(&self, client_id: ClientId) -> Option<&ChannelStats> {
+        self.connections
+            .get(&client_id)
+            .and_then(|connection| connection.channel_stats::<C>())
+    }
+
     pub(crate) fn update(
         &mut self,
         world_tick: BevyTick,
@@ -259,6 +369,10 @@ impl ConnectionManager {
     }
 
     /// Add a new [`Connection`] to the list of connections with the given [`ClientId`]
+    ///
+    /// The client is only considered connected (and a [`ConnectEvent`] is emitted) once its
+    /// protocol has been accepted during the handshake, so that we know which version of the
+    /// protocol to use when sending messages and replicating entities to it.
     pub(crate) fn add(&mut self, client_id: ClientId, client_entity: Entity) {
         if let Entry::Vacant(e) = self.connections.entry(client_id) {
             #[cfg(feature = "metrics")]
@@ -272,18 +386,34 @@ impl ConnectionManager {
                 self.replication_config,
                 self.packet_config,
                 self.ping_config,
+                self.protocol_version,
             );
-            self.events.add_connect_event(ConnectEvent {
-                client_id,
-                entity: client_entity,
-            });
-            self.new_clients.push(client_id);
             e.insert(connection);
         } else {
             info!("Client {} was already in the connections list", client_id);
         }
     }
 
+    /// Add the [`Connection`] of the local client in host-server mode.
+    ///
+    /// The local client shares the server's protocol, so it is connected right away.
+    pub(crate) fn add_local_client(&mut self, client_id: ClientId, client_entity: Entity) {
+        self.add(client_id, client_entity);
+        if let Ok(connection) = self.connection_mut(client_id) {
+            connection.set_local_client();
+            self.on_accepted(client_id, client_entity);
+        }
+    }
+
+    /// The client's protocol has been accepted: from now on we can send messages and replicate entities to it
+    fn on_accepted(&mut self, client_id: ClientId, client_entity: Entity) {
+        self.events.add_connect_event(ConnectEvent {
+            client_id,
+            entity: client_entity,
+        });
+        self.new_clients.push(client_id);
+    }
+
     /// Remove the connection associated with the given [`ClientId`],
     /// and returns the [`Entity`] associated with the client
     pub(crate) fn remove(&mut self, client_id: ClientId) -> Entity {
@@ -300,15 +430,20 @@ impl ConnectionManager {
         entity
     }
 
+    /// Buffer the serialized message to be sent to the clients that match the [`NetworkTarget`].
+    ///
+    /// Clients whose protocol version is older than `since` don't know the message or the channel, so we skip them.
     pub(crate) fn buffer_message_bytes(
         &mut self,
         message: Bytes,
         channel: ChannelKind,
         target: NetworkTarget,
+        coalescing_key: Option<CoalescingKey>,
+        since: ProtocolVersion,
     ) -> Result<(), ServerError> {
         self.connections
             .iter_mut()
-            .filter(|(id, _)| target.targets(id))
+            .filter(|(id, c)| c.is_accepted() && target.targets(id) && c.protocol_version >= since)
             .try_for_each(|(_, c)| {
                 // for local clients, we don't want to buffer messages in the MessageManager since
                 // there is no io
@@ -316,7 +451,7 @@ impl ConnectionManager {
                     c.local_messages_to_send.push(message.clone())
                 } else {
                     // NOTE: this clone is O(1), it just increments the reference count
-                    c.buffer_message(message.clone(), channel)?;
+                    c.buffer_message(message.clone(), channel, coalescing_key)?;
                 }
                 Ok::<(), ServerError>(())
             })
@@ -330,10 +465,12 @@ impl ConnectionManager {
         message: &M,
         channel: ChannelKind,
         target: NetworkTarget,
+        coalescing_key: Option<CoalescingKey>,
+        since: ProtocolVersion,
     ) -> Result<(), ServerError> {
         self.connections
             .iter_mut()
-            .filter(|(id, _)| target.targets(id))
+            .filter(|(id, c)| c.is_accepted() && target.targets(id) && c.protocol_version >= since)
             .try_for_each(|(_, c)| {
                 self.message_registry.serialize(
                     message,
@@ -346,7 +483,7 @@ impl ConnectionManager {
                 if c.is_local_client() {
                     c.local_messages_to_send.push(message_bytes);
                 } else {
-                    c.buffer_message(message_bytes, channel)?;
+                    c.buffer_message(message_bytes, channel, coalescing_key)?;
                 }
                 Ok::<(), ServerError>(())
             })
@@ -363,13 +500,29 @@ impl ConnectionManager {
         channel_kind: ChannelKind,
         target: NetworkTarget,
     ) -> Result<(), ServerError> {
+        self.buffer_message(message, channel_kind, target, None)
+    }
+
+    fn buffer_message<M: Message>(
+        &mut self,
+        message: &M,
+        channel_kind: ChannelKind,
+        target: NetworkTarget,
+        coalescing_key: Option<CoalescingKey>,
+    ) -> Result<(), ServerError> {
+        self.message_registry.check_send::<M>(
+            channel_kind,
+            &self.channel_registry,
+            ChannelDirection::ServerToClient,
+        )?;
+        let since = self.message_version(&MessageKind::of::<M>(), &channel_kind);
         if self.message_registry.is_map_entities::<M>() {
-            self.buffer_map_entities_message(message, channel_kind, target)?;
+            self.buffer_map_entities_message(message, channel_kind, target, coalescing_key, since)?;
         } else {
             self.message_registry
                 .serialize(message, &mut self.writer, None)?;
             let message_bytes = self.writer.split();
-            self.buffer_message_bytes(message_bytes, channel_kind, target)?;
+            self.buffer_message_bytes(message_bytes, channel_kind, target, coalescing_key, since)?;
         }
         Ok(())
     }
@@ -400,11 +553,67 @@ impl ConnectionManager {
         tick_manager: &TickManager,
     ) -> Result<(), ServerError> {
         let mut messages_to_rebroadcast = vec![];
+        let mut accepted_clients = vec![];
         // TODO: do this in parallel
+        let handshake_timeout = self.packet_config.handshake_timeout;
         self.connections
             .iter_mut()
             .try_for_each(|(client_id, connection)| {
                 let _span = trace_span!("receive", ?client_id).entered();
+                // read the handshake messages first, so that the other messages are only processed
+                // once the client's protocol has been accepted
+                connection.receive_handshake(time_manager.delta())?;
+
+                // check that the client's protocol matches our protocol for the client's version
+                let fingerprint = connection.received_fingerprint.take();
+                if let HandshakeState::Pending(elapsed) = connection.handshake {
+                    if let Some(fingerprint) = fingerprint {
+                        let version = fingerprint.version();
+                        let expected = ProtocolFingerprint::new(
+                            &self.channel_registry,
+                            component_registry,
+                            message_registry,
+                            version,
+                        );
+                        let difference = if version > self.protocol_version {
+                            Some(format!(
+                                "the client uses version {version} of the protocol, but the server only supports versions up to {}",
+                                self.protocol_version
+                            ))
+                        } else {
+                            expected.first_difference(&fingerprint)
+                        };
+                        if let Some(difference) = difference {
+                            error!(
+                                ?client_id,
+                                server_hash = ?expected.hash(),
+                                client_hash = ?fingerprint.hash(),
+                                "Protocol mismatch: {difference}. Denying connection"
+                            );
+                            connection.deny(DeniedReason::ProtocolMismatch(difference))?;
+                        } else {
+                            debug!(?client_id, ?version, "Client protocol accepted");
+                            connection.protocol_version = version;
+                            connection.handshake = HandshakeState::Accepted;
+                            accepted_clients.push((*client_id, connection.entity));
+                        }
+                    } else if elapsed > handshake_timeout {
+                        error!(
+                            ?client_id,
+                            "The client did not send its protocol fingerprint in time. Denying connection"
+                        );
+                        connection.deny(DeniedReason::ProtocolMismatch(format!(
+                            "no protocol fingerprint received within {handshake_timeout:?}"
+                        )))?;
+                    }
+                }
+                // keep the denied connection open until the client has received the reason of the denial
+                if let HandshakeState::Denied(elapsed) = connection.handshake {
+                    if connection.denial_acked() || elapsed > handshake_timeout {
+                        self.denied_clients.push(*client_id);
+                    }
+                }
+
                 // receive events on the connection
                 let events = connection.receive(
                     world,
@@ -421,8 +630,12 @@ impl ConnectionManager {
                     .extend(std::mem::take(&mut connection.messages_to_rebroadcast));
                 Ok::<(), ServerError>(())
             })?;
-        for (message, target, channel_kind) in messages_to_rebroadcast {
-            self.buffer_message_bytes(message, channel_kind, target)?;
+        for (client_id, client_entity) in accepted_clients {
+            self.on_accepted(client_id, client_entity);
+        }
+        for (message, target, channel_kind, message_kind) in messages_to_rebroadcast {
+            let since = self.message_version(&message_kind, &channel_kind);
+            self.buffer_message_bytes(message, channel_kind, target, None, since)?;
         }
         Ok(())
     }
@@ -452,6 +665,19 @@ impl ConnectionManager {
     }
 }
 
+/// State of the protocol handshake with a client
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum HandshakeState {
+    /// Waiting for the client's protocol fingerprint, since the given duration
+    Pending(Duration),
+    /// The client's protocol matches the server's protocol
+    Accepted,
+    /// The client's connection has been denied, since the given duration.
+    ///
+    /// It will be disconnected once the client has acked the denial, or after the handshake timeout.
+    Denied(Duration),
+}
+
 /// Wrapper that handles the connection between the server and a client
 pub struct Connection {
     client_id: ClientId,
@@ -460,6 +686,8 @@ pub struct Connection {
     entity: Entity,
     pub message_manager: MessageManager,
     pub(crate) replication_sender: ReplicationSender,
+    /// Keeps track of the snapshots sent to the client, when using [`SendUpdatesMode::Snapshot`](crate::prelude::SendUpdatesMode::Snapshot)
+    pub(crate) snapshot_sender: SnapshotSender,
     pub replication_receiver: ReplicationReceiver,
     pub(crate) events: ConnectionEvents,
     pub(crate) ping_manager: PingManager,
@@ -473,11 +701,25 @@ pub struct Connection {
         HashMap<NetId, Vec<(Bytes, NetworkTarget, ChannelKind)>>,
     writer: Writer,
     // messages that we have received that need to be rebroadcasted to other clients
-    pub(crate) messages_to_rebroadcast: Vec<(Bytes, NetworkTarget, ChannelKind)>,
+    pub(crate) messages_to_rebroadcast: Vec<(Bytes, NetworkTarget, ChannelKind, MessageKind)>,
     /// True if this connection corresponds to a local client when running in host-server mode
     is_local_client: bool,
     /// Messages to send to the local client (we don't buffer them in the MessageManager because there is no io)
     pub(crate) local_messages_to_send: Vec<Bytes>,
+    /// Protocol fingerprint sent by the client during the handshake, that hasn't been checked yet
+    received_fingerprint: Option<ProtocolFingerprint>,
+    /// Messages from the client (other than pings) are only processed once the handshake is accepted
+    pub(crate) handshake: HandshakeState,
+    /// Receives the ids of the handshake messages acked by the client
+    handshake_acks: Receiver<MessageId>,
+    /// Id of the message that notified the client of the denial of its connection
+    denial_message_id: Option<MessageId>,
+    /// Version of the protocol used by the client.
+    ///
+    /// It is only known once the handshake is accepted; nothing is sent to the client before that.
+    pub(crate) protocol_version: ProtocolVersion,
+    /// Interpolation delay of the client, if it has sent it
+    interpolation_delay: Option<Duration>,
 }
 
 impl Connection {
@@ -488,6 +730,7 @@ impl Connection {
         replication_config: ReplicationConfig,
         packet_config: PacketConfig,
         ping_config: PingConfig,
+        protocol_version: ProtocolVersion,
     ) -> Self {
         let bandwidth_cap_enabled = packet_config.bandwidth_cap_enabled;
         // create the message manager and the channels
@@ -495,7 +738,10 @@ impl Connection {
             channel_registry,
             packet_config.nack_rtt_multiple,
             packet_config.into(),
+            packet_config.into(),
         );
+        #[cfg(feature = "metrics")]
+        message_manager.set_metrics_connection(&client_id.to_string());
         // get notified about acks/nacks for replication-update messages
         let entity_updates_sender = &mut message_manager
             .channels
@@ -507,6 +753,13 @@ impl Connection {
         // get a channel to get notified when a replication update message gets actually send (to update priority)
         let replication_update_send_receiver =
             message_manager.get_replication_update_send_receiver();
+        // get notified when the client receives the handshake messages
+        let handshake_acks = message_manager
+            .channels
+            .get_mut(&ChannelKind::of::<HandshakeChannel>())
+            .unwrap()
+            .sender
+            .subscribe_acks();
         let replication_sender = ReplicationSender::new(
             update_acks_receiver,
             update_nacks_receiver,
@@ -520,6 +773,7 @@ impl Connection {
             entity,
             message_manager,
             replication_sender,
+            snapshot_sender: SnapshotSender::default(),
             replication_receiver,
             ping_manager: PingManager::new(ping_config),
             events: ConnectionEvents::default(),
@@ -531,12 +785,25 @@ impl Connection {
             messages_to_rebroadcast: vec![],
             is_local_client: false,
             local_messages_to_send: vec![],
+            received_fingerprint: None,
+            handshake: HandshakeState::Pending(Duration::default()),
+            handshake_acks,
+            denial_message_id: None,
+            protocol_version,
+            interpolation_delay: None,
         }
     }
 
     /// Update the connection to make clear that it corresponds to the local client
     pub(crate) fn set_local_client(&mut self) {
         self.is_local_client = true;
+        // the local client shares the server's protocol
+        self.handshake = HandshakeState::Accepted;
+    }
+
+    /// Returns true if the client's protocol has been accepted during the handshake
+    pub(crate) fn is_accepted(&self) -> bool {
+        self.handshake == HandshakeState::Accepted
     }
 
     /// Returns true if this connection corresponds to the local client in HostServer mode
@@ -544,6 +811,22 @@ impl Connection {
         self.is_local_client
     }
 
+    /// Entity that holds the metadata of the client
+    pub(crate) fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// How far behind the latest server state the client is displaying the interpolated entities.
+    ///
+    /// Returns `None` until the client has synced its time with the server and sent its interpolation delay.
+    pub fn interpolation_delay(&self) -> Option<Duration> {
+        if self.is_local_client {
+            // the local client sees the server world directly
+            return Some(Duration::default());
+        }
+        self.interpolation_delay
+    }
+
     /// Return the latest estimate of rtt
     pub fn rtt(&self) -> Duration {
         self.ping_manager.rtt()
@@ -554,6 +837,18 @@ impl Connection {
         self.ping_manager.jitter()
     }
 
+    /// Get the send/receive statistics of the [`Channel`] `C` for this connection
+    pub fn channel_stats<C: Channel>(&self) -> Option<&ChannelStats> {
+        self.message_manager.channel_stats::<C>()
+    }
+
+    /// Maximum number of bytes in the packets sent to this client.
+    ///
+    /// This can increase over time if MTU discovery is enabled.
+    pub fn max_packet_size(&self) -> usize {
+        self.message_manager.max_packet_size()
+    }
+
     pub(crate) fn update(
         &mut self,
         world_tick: BevyTick,
@@ -573,6 +868,7 @@ impl Connection {
         &mut self,
         message: Bytes,
         channel: ChannelKind,
+        coalescing_key: Option<CoalescingKey>,
     ) -> Result<(), ServerError> {
         // TODO: i know channel names never change so i should be able to get them as static
         // TODO: just have a channel registry enum as well?
@@ -582,7 +878,12 @@ impl Connection {
             .name(&channel)
             .ok_or::<ServerError>(MessageError::NotRegistered.into())?;
         // message.emit_send_logs(&channel_name);
-        self.message_manager.buffer_send(message, channel)?;
+        match coalescing_key {
+            Some(key) => self
+                .message_manager
+                .buffer_send_coalesced(message, channel, key)?,
+            None => self.message_manager.buffer_send(message, channel)?,
+        };
         Ok(())
     }
 
@@ -627,6 +928,73 @@ impl Connection {
         Ok(())
     }
 
+    /// Send the snapshot of the entities replicated to the client, delta-encoded against the last
+    /// snapshot that the client acked
+    pub(crate) fn send_snapshot(
+        &mut self,
+        tick: Tick,
+        snapshot: Snapshot,
+    ) -> Result<(), ServerError> {
+        let message = self.snapshot_sender.prepare_message(tick, snapshot);
+        trace!(?tick, baseline = ?message.baseline, "Sending snapshot");
+        message.to_bytes(&mut self.writer)?;
+        let message_bytes = self.writer.split();
+        self.message_manager
+            .buffer_send(message_bytes, ChannelKind::of::<SnapshotChannel>())?;
+        Ok(())
+    }
+
+    fn send_handshake(
+        &mut self,
+        message: HandshakeMessage,
+    ) -> Result<Option<MessageId>, ServerError> {
+        message.to_bytes(&mut self.writer)?;
+        let message_bytes = self.writer.split();
+        Ok(self
+            .message_manager
+            .buffer_send(message_bytes, ChannelKind::of::<HandshakeChannel>())?)
+    }
+
+    /// Send the reason of the denial to the client, and stop processing its messages
+    fn deny(&mut self, reason: DeniedReason) -> Result<(), ServerError> {
+        self.handshake = HandshakeState::Denied(Duration::default());
+        self.denial_message_id = self.send_handshake(HandshakeMessage::Denied(reason))?;
+        Ok(())
+    }
+
+    /// Returns true if the client has acked the message that notified it of the denial of its connection
+    fn denial_acked(&self) -> bool {
+        self.handshake_acks
+            .try_iter()
+            .any(|message_id| Some(message_id) == self.denial_message_id)
+    }
+
+    /// Read the messages received on the [`HandshakeChannel`]
+    fn receive_handshake(&mut self, delta: Duration) -> Result<(), SerializationError> {
+        if let HandshakeState::Pending(elapsed) | HandshakeState::Denied(elapsed) =
+            &mut self.handshake
+        {
+            *elapsed += delta;
+        }
+        let Some(channel) = self
+            .message_manager
+            .channels
+            .get_mut(&ChannelKind::of::<HandshakeChannel>())
+        else {
+            return Ok(());
+        };
+        while let Some((_, single_data)) = channel.receiver.read_message() {
+            let mut reader = Reader::from(single_data);
+            match HandshakeMessage::from_bytes(&mut reader)? {
+                HandshakeMessage::Protocol(fingerprint) => {
+                    self.received_fingerprint = Some(fingerprint);
+                }
+                HandshakeMessage::Denied(_) => {}
+            }
+        }
+        Ok(())
+    }
+
     /// Send packets that are ready to be sent
     pub fn send_packets(
         &mut self,
@@ -672,9 +1040,18 @@ impl Connection {
         tick_manager: &TickManager,
     ) -> Result<ConnectionEvents, ServerError> {
         let _span = trace_span!("receive").entered();
+        let accepted = self.handshake == HandshakeState::Accepted;
         self.message_manager
             .channels
             .iter_mut()
+            // until the client's protocol has been accepted, we keep its messages in the channel buffers
+            // (except for pings, which are needed to sync the client)
+            .filter(|(channel_kind, _)| {
+                **channel_kind != ChannelKind::of::<HandshakeChannel>()
+                    && (accepted
+                        || **channel_kind == ChannelKind::of::<PingChannel>()
+                        || **channel_kind == ChannelKind::of::<PongChannel>())
+            })
             .try_for_each(|(channel_kind, channel)| {
                 while let Some((tick, single_data)) = channel.receiver.read_message() {
                     // let channel_name = self
@@ -699,6 +1076,14 @@ impl Connection {
                         // process the pong
                         self.ping_manager
                             .process_pong(&pong, time_manager.current_time());
+                    } else if channel_kind == &ChannelKind::of::<InterpolationDelayChannel>() {
+                        let InterpolationDelayMessage(delay) =
+                            InterpolationDelayMessage::from_bytes(&mut reader)?;
+                        trace!(?delay, "received client interpolation delay");
+                        self.interpolation_delay = Some(delay);
+                    } else if channel_kind == &ChannelKind::of::<SnapshotAckChannel>() {
+                        let SnapshotAckMessage(tick) = SnapshotAckMessage::from_bytes(&mut reader)?;
+                        self.snapshot_sender.receive_ack(tick);
                     } else if channel_kind == &ChannelKind::of::<EntityActionsChannel>() {
                         let actions = EntityActionsMessage::from_bytes(&mut reader)?;
                         trace!(?tick, ?actions, "received replication actions message");
@@ -730,20 +1115,23 @@ impl Connection {
                         let data = (reader.consume(), target, *channel_kind);
                         match message_registry.message_type(net_id) {
                             #[cfg(feature = "leafwing")]
-                            MessageType::LeafwingInput => self
+                            Some(MessageType::LeafwingInput) => self
                                 .received_leafwing_input_messages
                                 .entry(net_id)
                                 .or_default()
                                 .push(data),
-                            MessageType::NativeInput => {
+                            Some(MessageType::NativeInput) => {
                                 self.received_input_messages
                                     .entry(net_id)
                                     .or_default()
                                     .push(data);
                             }
-                            MessageType::Normal => {
+                            Some(MessageType::Normal) => {
                                 self.received_messages.entry(net_id).or_default().push(data);
                             }
+                            None => {
+                                warn!(?net_id, "Received an unknown message, ignoring it");
+                            }
                         }
                     }
                 }
@@ -790,20 +1178,23 @@ impl Connection {
         let data = (reader.consume(), target, channel_kind);
         match message_registry.message_type(net_id) {
             #[cfg(feature = "leafwing")]
-            MessageType::LeafwingInput => self
+            Some(MessageType::LeafwingInput) => self
                 .received_leafwing_input_messages
                 .entry(net_id)
                 .or_default()
                 .push(data),
-            MessageType::NativeInput => {
+            Some(MessageType::NativeInput) => {
                 self.received_input_messages
                     .entry(net_id)
                     .or_default()
                     .push(data);
             }
-            MessageType::Normal => {
+            Some(MessageType::Normal) => {
                 self.received_messages.entry(net_id).or_default().push(data);
             }
+            None => {
+                warn!(?net_id, "Received an unknown message, ignoring it");
+            }
         }
         Ok(())
     }
@@ -858,29 +1249,35 @@ impl ConnectionManager {
         &mut self,
         mut entity: Entity,
         kind: ComponentNetId,
+        component_registry: &ComponentRegistry,
         group: &ReplicationGroup,
         target: NetworkTarget,
     ) -> Result<(), ServerError> {
         let group_id = group.group_id(Some(entity));
         debug!(?entity, ?kind, "Sending RemoveComponent");
-        self.connected_targets(target).try_for_each(|client_id| {
-            entity = self
-                .connection_mut(client_id)?
-                .replication_receiver
-                .remote_entity_map
-                .to_remote(entity);
-            // TODO: I don't think it's actually correct to only correct the changes since that action.
-            //  what if we do:
-            //  - Frame 1: update is ACKED
-            //  - Frame 2: update
-            //  - Frame 3: action
-            //  - Frame 4: send
-            //  then we won't send the frame-2 update because we only collect changes since frame 3
-            self.connection_mut(client_id)?
-                .replication_sender
-                .prepare_component_remove(entity, group_id, kind);
-            Ok(())
-        })
+        let since = component_registry
+            .kind_map
+            .kind(kind)
+            .map_or(0, |kind| component_registry.version(kind));
+        self.connected_targets_since(target, since)
+            .try_for_each(|client_id| {
+                entity = self
+                    .connection_mut(client_id)?
+                    .replication_receiver
+                    .remote_entity_map
+                    .to_remote(entity);
+                // TODO: I don't think it's actually correct to only correct the changes since that action.
+                //  what if we do:
+                //  - Frame 1: update is ACKED
+                //  - Frame 2: update
+                //  - Frame 3: action
+                //  - Frame 4: send
+                //  then we won't send the frame-2 update because we only collect changes since frame 3
+                self.connection_mut(client_id)?
+                    .replication_sender
+                    .prepare_component_remove(entity, group_id, kind);
+                Ok(())
+            })
     }
 
     // TODO: perf gain if we batch this? (send vec of components) (same for update/removes)
@@ -960,7 +1357,7 @@ impl ConnectionManager {
             };
             raw_data = Some(self.writer.split());
         }
-        self.connected_targets(actual_target)
+        self.connected_targets_since(actual_target, component_registry.version(&kind))
             .try_for_each(|client_id| {
                 // convert the entity to a network entity (in case we need to map it)
                 let entity = self
@@ -1046,10 +1443,11 @@ impl ConnectionManager {
         system_current_tick: BevyTick,
         tick: Tick,
         delta_compression: bool,
+        throttled: bool,
     ) -> Result<(), ServerError> {
         let mut num_targets = 0;
         let mut existing_bytes: Option<Bytes> = None;
-        self.connected_targets(target).try_for_each(|client_id| {
+        self.connected_targets_since(target, registry.version(&kind)).try_for_each(|client_id| {
             let connection = self.connections.get_mut(&client_id).ok_or(ServerError::ClientIdNotFound(client_id))?;
             let send_tick = connection
                 .replication_sender
@@ -1057,6 +1455,20 @@ impl ConnectionManager {
                 .entry(group_id)
                 .or_default()
                 .send_tick;
+            // the group's send_tick might have moved past the change tick of a component throttled by a
+            // ReplicationFrequency while its updates were not sent (or were lost): if the client has not
+            // acked the latest changes of the component, consider that they happened now
+            let component_change_tick = if throttled
+                && connection.replication_sender.throttled_update_pending(
+                    entity,
+                    kind,
+                    component_change_tick,
+                    system_current_tick,
+                ) {
+                system_current_tick
+            } else {
+                component_change_tick
+            };
             // send the update for all changes newer than the last send_tick for the group
             debug!(
                 ?kind,
@@ -1090,11 +1502,14 @@ impl ConnectionManager {
                     }
                     let raw_data = existing_bytes.clone().unwrap();
                     // use the network entity
-                    let entity = connection
+                    let remote_entity = connection
                         .replication_receiver
                         .remote_entity_map
                         .to_remote(entity);
-                    connection.replication_sender.prepare_component_update(entity, group_id, raw_data);
+                    connection.replication_sender.prepare_component_update(remote_entity, group_id, raw_data);
+                }
+                if throttled {
+                    connection.replication_sender.prepare_throttled_update(entity, group_id, kind);
                 }
             }
             Ok::<(), ServerError>(())
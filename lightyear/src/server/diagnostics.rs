@@ -0,0 +1,58 @@
+//! Compute diagnostics about the server connections
+use bevy::app::{App, Plugin, PostUpdate};
+use bevy::diagnostic::Diagnostics;
+use bevy::prelude::{Condition, IntoSystemConfigs, Local, Real, Res, Time};
+use bevy::time::common_conditions::on_timer;
+use bevy::utils::HashMap;
+
+use crate::channel::diagnostics::{ChannelDiagnosticsPlugin, PreviousChannelStats};
+use crate::channel::stats::ChannelStats;
+use crate::prelude::{server::is_started, ChannelKind, ChannelRegistry};
+use crate::server::connection::ConnectionManager;
+
+/// Plugin that computes diagnostics about the server connections.
+///
+/// The channel diagnostics are aggregated over all connected clients. Use
+/// [`ConnectionManager::channel_stats`] to get the statistics of a single connection.
+#[derive(Debug, Default)]
+pub struct ServerDiagnosticsPlugin;
+
+fn channel_diagnostics_system(
+    connection_manager: Res<ConnectionManager>,
+    channel_registry: Res<ChannelRegistry>,
+    time: Res<Time<Real>>,
+    mut previous: Local<PreviousChannelStats>,
+    mut diagnostics: Diagnostics,
+) {
+    let mut stats: HashMap<ChannelKind, ChannelStats> = HashMap::default();
+    connection_manager
+        .connections
+        .values()
+        .filter(|connection| !connection.is_local_client())
+        .flat_map(|connection| connection.message_manager.all_channel_stats())
+        .for_each(|(kind, channel_stats)| {
+            stats.entry(*kind).or_default().merge(channel_stats);
+        });
+    ChannelDiagnosticsPlugin::add_measurements(
+        stats,
+        &mut previous,
+        &channel_registry,
+        time.elapsed(),
+        &mut diagnostics,
+    );
+}
+
+impl Plugin for ServerDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        let channel_plugin = ChannelDiagnosticsPlugin::default();
+        let flush_interval = channel_plugin.flush_interval;
+        // the plugin might already have been added by the client in HostServer mode
+        if !app.is_plugin_added::<ChannelDiagnosticsPlugin>() {
+            app.add_plugins(channel_plugin);
+        }
+        app.add_systems(
+            PostUpdate,
+            channel_diagnostics_system.run_if(on_timer(flush_interval).and_then(is_started)),
+        );
+    }
+}
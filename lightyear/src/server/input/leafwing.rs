@@ -1,6 +1,8 @@
 //! Handles client-generated inputs
 use std::ops::DerefMut;
 
+use crate::channel::builder::InputChannel;
+use crate::client::input::leafwing::LeafwingInputConfig;
 use crate::inputs::leafwing::input_buffer::InputBuffer;
 use crate::inputs::leafwing::input_message::InputTarget;
 use bevy::prelude::*;
@@ -8,23 +10,31 @@ use leafwing_input_manager::prelude::*;
 
 use crate::inputs::leafwing::LeafwingUserAction;
 use crate::prelude::server::MessageEvent;
-use crate::prelude::{server::is_started, InputMessage, MessageRegistry, Mode, TickManager};
+use crate::prelude::{
+    server::is_started, ClientId, InputMessage, MessageRegistry, Mode, ReplicationTarget,
+    TickManager,
+};
 use crate::protocol::message::MessageKind;
 use crate::serialize::reader::Reader;
 use crate::server::config::ServerConfig;
 use crate::server::connection::ConnectionManager;
+use crate::server::relevance::immediate::CachedNetworkRelevance;
 use crate::shared::replication::network_target::NetworkTarget;
 use crate::shared::sets::{InternalMainSet, ServerMarker};
 
 pub struct LeafwingInputPlugin<A> {
-    marker: std::marker::PhantomData<A>,
+    config: LeafwingInputConfig<A>,
+}
+
+impl<A> LeafwingInputPlugin<A> {
+    pub fn new(config: LeafwingInputConfig<A>) -> Self {
+        Self { config }
+    }
 }
 
 impl<A> Default for LeafwingInputPlugin<A> {
     fn default() -> Self {
-        Self {
-            marker: std::marker::PhantomData,
-        }
+        Self::new(LeafwingInputConfig::default())
     }
 }
 
@@ -41,6 +51,7 @@ pub enum InputSystemSet {
 impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A> {
     fn build(&self, app: &mut App) {
         // RESOURCES
+        app.insert_resource(self.config);
         // app.init_resource::<GlobalActions<A>>();
         // TODO: (global action states) add a resource tracking the action-state of all clients
         // SETS
@@ -92,10 +103,12 @@ fn add_action_diff_buffer<A: LeafwingUserAction>(
 
 /// Read the input messages from the server events to update the InputBuffers
 fn receive_input_message<A: LeafwingUserAction>(
+    config: Res<LeafwingInputConfig<A>>,
     message_registry: Res<MessageRegistry>,
     mut connection_manager: ResMut<ConnectionManager>,
     // TODO: currently we do not handle entities that are controlled by multiple clients
     mut query: Query<Option<&mut InputBuffer<A>>>,
+    replicated_query: Query<(&ReplicationTarget, Option<&CachedNetworkRelevance>)>,
     mut commands: Commands,
     mut events: EventWriter<MessageEvent<InputMessage<A>>>,
 ) {
@@ -107,6 +120,8 @@ fn receive_input_message<A: LeafwingUserAction>(
         );
         return;
     };
+    // inputs that will be forwarded to the other clients
+    let mut inputs_to_rebroadcast = vec![];
     // re-borrow to allow split borrows
     let connection_manager = connection_manager.deref_mut();
     for (client_id, connection) in connection_manager.connections.iter_mut() {
@@ -190,9 +205,14 @@ fn receive_input_message<A: LeafwingUserAction>(
                                     reader.consume(),
                                     target,
                                     channel_kind,
+                                    kind,
                                 ));
                             }
                         }
+                        if config.rebroadcast_inputs {
+                            inputs_to_rebroadcast
+                                .push((*client_id, remote_input_message(&message)));
+                        }
                         events.send(MessageEvent::new(message, *client_id));
                     }
                     Err(e) => {
@@ -202,6 +222,85 @@ fn receive_input_message<A: LeafwingUserAction>(
             }
         }
     }
+    rebroadcast_inputs(connection_manager, &replicated_query, inputs_to_rebroadcast);
+}
+
+/// Convert an [`InputMessage`] received from a client into the message that we forward to the other clients.
+///
+/// All the input targets are server entities at this point (pre-predicted entities have been mapped
+/// when the message was received), so they are sent as [`InputTarget::Entity`]: each client maps them
+/// to its own local entity. Global inputs are not forwarded.
+fn remote_input_message<A: LeafwingUserAction>(message: &InputMessage<A>) -> InputMessage<A> {
+    let mut remote_message = InputMessage::new(message.end_tick);
+    remote_message.diffs = message
+        .diffs
+        .iter()
+        .filter_map(|(target, start, diffs)| match target {
+            InputTarget::Entity(entity) | InputTarget::PrePredictedEntity(entity) => {
+                Some((InputTarget::Entity(*entity), start.clone(), diffs.clone()))
+            }
+            InputTarget::Global => None,
+        })
+        .collect();
+    remote_message
+}
+
+/// Forward the inputs of each client to the other clients.
+///
+/// Each client only receives the inputs of the entities that are replicated to it
+/// (according to their [`ReplicationTarget`] and their network relevance).
+/// Local clients share the server's world, so they already have access to the inputs.
+fn rebroadcast_inputs<A: LeafwingUserAction>(
+    connection_manager: &mut ConnectionManager,
+    replicated_query: &Query<(&ReplicationTarget, Option<&CachedNetworkRelevance>)>,
+    inputs: Vec<(ClientId, InputMessage<A>)>,
+) {
+    let is_replicated_to = |entity: Entity, client_id: &ClientId| {
+        replicated_query
+            .get(entity)
+            .is_ok_and(|(replication_target, relevance)| {
+                replication_target.target.targets(client_id)
+                    && relevance.map_or(true, |relevance| relevance.is_relevant(client_id))
+            })
+    };
+    for (sender, message) in inputs {
+        if message.diffs.is_empty() {
+            continue;
+        }
+        let clients: Vec<ClientId> = connection_manager
+            .connected_clients()
+            .filter(|client_id| {
+                *client_id != sender
+                    && connection_manager
+                        .connection(*client_id)
+                        .is_ok_and(|c| !c.is_local_client())
+            })
+            .collect();
+        for client_id in clients {
+            let mut client_message = InputMessage::new(message.end_tick);
+            client_message.diffs = message
+                .diffs
+                .iter()
+                .filter(|(target, _, _)| match target {
+                    InputTarget::Entity(entity) => is_replicated_to(*entity, &client_id),
+                    _ => false,
+                })
+                .cloned()
+                .collect();
+            if client_message.diffs.is_empty() {
+                continue;
+            }
+            trace!(?sender, ?client_id, end_tick = ?message.end_tick, "rebroadcasting input message");
+            if let Err(e) = connection_manager
+                .send_message_to_target::<InputChannel, InputMessage<A>>(
+                    &mut client_message,
+                    NetworkTarget::Single(client_id),
+                )
+            {
+                error!(?e, "could not rebroadcast leafwing input message");
+            }
+        }
+    }
 }
 
 /// Read the InputState for the current tick from the buffer, and use them to update the ActionState
@@ -234,8 +333,12 @@ mod tests {
     use crate::inputs::leafwing::input_buffer::InputBuffer;
     use leafwing_input_manager::prelude::ActionState;
 
-    use crate::prelude::client;
+    use bevy::input::InputPlugin;
+    use bevy::utils::Duration;
+
     use crate::prelude::server::*;
+    use crate::prelude::{client, SharedConfig, TickConfig};
+    use crate::tests::multi_stepper::{MultiBevyStepper, TEST_CLIENT_ID_1, TEST_CLIENT_ID_2};
     use crate::tests::protocol::*;
     use crate::tests::stepper::BevyStepper;
 
@@ -341,4 +444,201 @@ mod tests {
             .unwrap()
             .released(&LeafwingInput1::Jump));
     }
+
+    /// The inputs of a client are forwarded to the other clients, on the entity that they predict
+    #[test]
+    fn test_rebroadcast_inputs() {
+        let mut stepper = MultiBevyStepper::new(
+            SharedConfig {
+                tick: TickConfig::new(Duration::from_millis(10)),
+                ..default()
+            },
+            client::SyncConfig::default().speedup_factor(1.0),
+            client::PredictionConfig::default(),
+            client::InterpolationConfig::default(),
+            Duration::from_millis(10),
+        );
+        let config = LeafwingInputConfig::<LeafwingInput1> {
+            rebroadcast_inputs: true,
+            ..default()
+        };
+        for app in [
+            &mut stepper.server_app,
+            &mut stepper.client_app_1,
+            &mut stepper.client_app_2,
+        ] {
+            app.add_plugins(crate::prelude::LeafwingInputPlugin::<LeafwingInput1> { config });
+        }
+        stepper.client_app_1.add_plugins(InputPlugin);
+        stepper.client_app_2.add_plugins(InputPlugin);
+        stepper.init();
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                ActionState::<LeafwingInput1>::default(),
+                Replicate {
+                    sync: SyncTarget {
+                        prediction: NetworkTarget::All,
+                        ..default()
+                    },
+                    ..default()
+                },
+            ))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let predicted_entity = |app: &App| {
+            let confirmed = app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client");
+            app.world()
+                .get::<client::Confirmed>(confirmed)
+                .unwrap()
+                .predicted
+                .expect("entity is not predicted")
+        };
+        let predicted_1 = predicted_entity(&stepper.client_app_1);
+        let predicted_2 = predicted_entity(&stepper.client_app_2);
+
+        // client 1 controls the entity
+        stepper
+            .client_app_1
+            .world_mut()
+            .entity_mut(predicted_1)
+            .insert(InputMap::<LeafwingInput1>::new([(
+                LeafwingInput1::Jump,
+                KeyCode::KeyA,
+            )]));
+        stepper.frame_step();
+        stepper
+            .client_app_1
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyA);
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        // client 2 received the inputs of client 1, and applied them to its predicted entity
+        assert!(stepper
+            .client_app_2
+            .world()
+            .get::<InputBuffer<LeafwingInput1>>(predicted_2)
+            .expect("the remote inputs were not received")
+            .get_last()
+            .unwrap()
+            .pressed(&LeafwingInput1::Jump));
+        assert!(stepper
+            .client_app_2
+            .world()
+            .get::<ActionState<LeafwingInput1>>(predicted_2)
+            .unwrap()
+            .pressed(&LeafwingInput1::Jump));
+    }
+
+    /// The inputs are only forwarded to the clients that the entity is replicated to
+    #[test]
+    fn test_rebroadcast_inputs_replication_target() {
+        let mut stepper = MultiBevyStepper::new(
+            SharedConfig {
+                tick: TickConfig::new(Duration::from_millis(10)),
+                ..default()
+            },
+            client::SyncConfig::default().speedup_factor(1.0),
+            client::PredictionConfig::default(),
+            client::InterpolationConfig::default(),
+            Duration::from_millis(10),
+        );
+        let config = LeafwingInputConfig::<LeafwingInput1> {
+            rebroadcast_inputs: true,
+            ..default()
+        };
+        for app in [
+            &mut stepper.server_app,
+            &mut stepper.client_app_1,
+            &mut stepper.client_app_2,
+        ] {
+            app.add_plugins(crate::prelude::LeafwingInputPlugin::<LeafwingInput1> { config });
+        }
+        stepper.client_app_1.add_plugins(InputPlugin);
+        stepper.client_app_2.add_plugins(InputPlugin);
+        stepper.init();
+
+        // the entity is only replicated to client 1
+        let client_1 = ClientId::Netcode(TEST_CLIENT_ID_1);
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                ActionState::<LeafwingInput1>::default(),
+                Replicate {
+                    target: ReplicationTarget {
+                        target: NetworkTarget::Single(client_1),
+                    },
+                    sync: SyncTarget {
+                        prediction: NetworkTarget::Single(client_1),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let confirmed = stepper
+            .client_app_1
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        let predicted_1 = stepper
+            .client_app_1
+            .world()
+            .get::<client::Confirmed>(confirmed)
+            .unwrap()
+            .predicted
+            .expect("entity is not predicted");
+
+        // client 1 controls the entity
+        stepper
+            .client_app_1
+            .world_mut()
+            .entity_mut(predicted_1)
+            .insert(InputMap::<LeafwingInput1>::new([(
+                LeafwingInput1::Jump,
+                KeyCode::KeyA,
+            )]));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        // the server received the inputs of client 1
+        assert!(stepper
+            .server_app
+            .world()
+            .get::<InputBuffer<LeafwingInput1>>(server_entity)
+            .is_some_and(|buffer| buffer.start_tick.is_some()));
+        // but did not forward them to client 2
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .channel_stats::<InputChannel>(ClientId::Netcode(TEST_CLIENT_ID_2))
+                .unwrap()
+                .send
+                .messages_sent(),
+            0
+        );
+    }
 }
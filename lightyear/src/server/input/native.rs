@@ -136,6 +136,7 @@ fn receive_input_message<A: UserAction>(
                                 reader.consume(),
                                 target,
                                 channel_kind,
+                                kind,
                             ));
                         }
                     }
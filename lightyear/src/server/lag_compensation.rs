@@ -0,0 +1,667 @@
+/*! Server-side lag compensation, to validate hits against what the client was seeing
+
+# Lag compensation
+
+Clients display the other entities in the past: the interpolated entities are shown `interpolation_delay`
+behind the latest server state, and that state itself took `rtt / 2` to reach the client. When a client
+fires a shot, the server receives it another `rtt / 2` later, so the target has moved in the meantime.
+
+The [`LagCompensationPlugin`] keeps a short history of some components of the replicated entities,
+so that the server can rewind them to the time seen by the client when validating a hit.
+The clients send their interpolation delay to the server when they are synced.
+
+If the client sends the tick at which it performed the action, use [`LagCompensation::client_view_time_at`]
+instead of [`LagCompensation::client_view_time`], which estimates that tick from the round-trip time.
+
+```rust
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use lightyear::prelude::server::*;
+
+fn build(app: &mut App) {
+    app.add_plugins(LagCompensationPlugin::default());
+    // keep the history of the `Transform` of the replicated entities
+    app.add_lag_compensation::<Transform>();
+}
+
+fn hit_detection(
+    lag_compensation: LagCompensation,
+    targets: Query<&LagCompensationHistory<Transform>>,
+) {
+    let client_id = ClientId::Netcode(0);
+    let Ok(time) = lag_compensation.client_view_time(client_id) else {
+        return;
+    };
+    for history in targets.iter() {
+        // position of the target as it was displayed to the client
+        let Some(transform) = history.interpolate(time, |a, b, t| Transform {
+            translation: a.translation.lerp(b.translation, t),
+            rotation: a.rotation.slerp(b.rotation, t),
+            scale: a.scale.lerp(b.scale, t),
+        }) else {
+            continue;
+        };
+        // ... raycast against the rewound transform
+    }
+}
+```
+
+Any `Clone` component can be recorded.
+
+With the `avian2d` or `avian3d` feature, the `AvianLagCompensationPlugin` (in the `avian2d` or `avian3d` module)
+records the `Position`, `Rotation` and `Collider` of the replicated entities, and the `LagCompensatedColliders`
+system parameter returns them as they were seen by a client.
+*/
+use std::collections::VecDeque;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use tracing::trace;
+
+use crate::client::interpolation::plugin::InterpolationDelay;
+use crate::prelude::server::is_started;
+use crate::prelude::{ClientId, ReplicationTarget, Tick, TickManager};
+use crate::server::config::ServerConfig;
+use crate::server::connection::ConnectionManager;
+use crate::server::error::ServerError;
+
+/// Configuration of the lag compensation
+#[derive(Resource, Clone, Debug, Reflect)]
+pub struct LagCompensationConfig {
+    /// Maximum duration that the server can rewind. The history of the components is kept for this duration,
+    /// and the clients with a higher latency are compensated only up to this duration.
+    pub max_rewind: Duration,
+}
+
+impl Default for LagCompensationConfig {
+    fn default() -> Self {
+        Self {
+            max_rewind: Duration::from_millis(500),
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct LagCompensationPlugin {
+    pub config: LagCompensationConfig,
+}
+
+impl LagCompensationPlugin {
+    pub fn new(config: LagCompensationConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LagCompensationSet {
+    /// Record the value of the components in their [`LagCompensationHistory`]
+    UpdateHistory,
+}
+
+impl Plugin for LagCompensationPlugin {
+    fn build(&self, app: &mut App) {
+        // REFLECTION
+        app.register_type::<LagCompensationConfig>();
+        // RESOURCES
+        app.insert_resource(self.config.clone());
+        // SETS
+        // record the history after the physics simulation has run
+        app.configure_sets(
+            FixedLast,
+            LagCompensationSet::UpdateHistory.run_if(is_started),
+        );
+    }
+}
+
+/// Time (in ticks) of the world displayed by a client
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LagCompensationTime {
+    pub tick: Tick,
+    /// Fraction of a tick elapsed after `tick`, between 0.0 and 1.0
+    pub overstep: f32,
+}
+
+/// History of the past values of the component `C` of an entity, recorded every tick
+#[derive(Component, Debug)]
+pub struct LagCompensationHistory<C> {
+    buffer: VecDeque<(Tick, C)>,
+}
+
+impl<C> Default for LagCompensationHistory<C> {
+    fn default() -> Self {
+        Self {
+            buffer: VecDeque::new(),
+        }
+    }
+}
+
+impl<C: Clone> LagCompensationHistory<C> {
+    /// Add the value of the component at the given tick.
+    /// The ticks must be added in increasing order.
+    pub(crate) fn add(&mut self, tick: Tick, value: C) {
+        if self.buffer.back().is_some_and(|(last, _)| *last >= tick) {
+            return;
+        }
+        self.buffer.push_back((tick, value));
+    }
+
+    /// Remove the values that are not needed to compute the value at `oldest_tick` or later
+    pub(crate) fn pop_until(&mut self, oldest_tick: Tick) {
+        // keep the latest value before `oldest_tick`, to be able to interpolate
+        while self
+            .buffer
+            .get(1)
+            .is_some_and(|(tick, _)| *tick <= oldest_tick)
+        {
+            self.buffer.pop_front();
+        }
+    }
+
+    /// Iterate through the recorded values, from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = (Tick, &C)> {
+        self.buffer.iter().map(|(tick, value)| (*tick, value))
+    }
+
+    /// Value of the component at the given tick: the most recent value recorded at or before `tick`
+    ///
+    /// Returns `None` if `tick` is older than the history.
+    pub fn at(&self, tick: Tick) -> Option<&C> {
+        self.buffer
+            .iter()
+            .rev()
+            .find(|(t, _)| *t <= tick)
+            .map(|(_, value)| value)
+    }
+
+    /// Value of the component at the given time, interpolated between the two closest recorded values
+    /// with the `lerp` function.
+    ///
+    /// Returns `None` if `time` is older than the history.
+    pub fn interpolate(
+        &self,
+        time: LagCompensationTime,
+        lerp: impl Fn(&C, &C, f32) -> C,
+    ) -> Option<C> {
+        let idx = self
+            .buffer
+            .iter()
+            .rposition(|(tick, _)| *tick <= time.tick)?;
+        let (start_tick, start) = &self.buffer[idx];
+        let Some((end_tick, end)) = self.buffer.get(idx + 1) else {
+            return Some(start.clone());
+        };
+        let t =
+            ((time.tick - *start_tick) as f32 + time.overstep) / (*end_tick - *start_tick) as f32;
+        Some(lerp(start, end, t.clamp(0.0, 1.0)))
+    }
+}
+
+pub trait AppLagCompensationExt {
+    /// Record the history of the component `C` for every replicated entity, so that it can be rewound
+    /// to the time seen by a client
+    fn add_lag_compensation<C: Component + Clone>(&mut self) -> &mut Self;
+}
+
+impl AppLagCompensationExt for App {
+    fn add_lag_compensation<C: Component + Clone>(&mut self) -> &mut Self {
+        self.add_systems(
+            FixedLast,
+            (systems::add_history::<C>, systems::update_history::<C>)
+                .in_set(LagCompensationSet::UpdateHistory),
+        );
+        self
+    }
+}
+
+/// [`SystemParam`] used to compute the time of the world displayed by a client
+#[derive(SystemParam)]
+pub struct LagCompensation<'w> {
+    connection_manager: Res<'w, ConnectionManager>,
+    tick_manager: Res<'w, TickManager>,
+    server_config: Res<'w, ServerConfig>,
+    config: Res<'w, LagCompensationConfig>,
+}
+
+impl LagCompensation<'_> {
+    /// Interpolation delay of the client.
+    ///
+    /// If the client hasn't sent its delay yet, assume that it uses the default settings
+    fn interpolation_delay(&self, client_id: ClientId) -> Result<Duration, ServerError> {
+        let connection = self.connection_manager.connection(client_id)?;
+        if connection.is_local_client() {
+            return Ok(Duration::default());
+        }
+        Ok(connection.interpolation_delay().unwrap_or_else(|| {
+            InterpolationDelay::default()
+                .to_duration(self.server_config.shared.server_replication_send_interval)
+        }))
+    }
+
+    /// Time that is `duration` before the start of `tick`
+    fn time_before(&self, tick: Tick, duration: Duration) -> LagCompensationTime {
+        let ticks = duration.as_secs_f32() / self.tick_manager.config.tick_duration.as_secs_f32();
+        let whole_ticks = ticks.ceil();
+        LagCompensationTime {
+            tick: tick - whole_ticks as u16,
+            overstep: whole_ticks - ticks,
+        }
+    }
+
+    /// How far in the past the client is displaying the other entities, compared to the current server time.
+    ///
+    /// This is the round-trip time plus the interpolation delay of the client, capped at
+    /// [`LagCompensationConfig::max_rewind`].
+    pub fn rewind(&self, client_id: ClientId) -> Result<Duration, ServerError> {
+        let connection = self.connection_manager.connection(client_id)?;
+        if connection.is_local_client() {
+            return Ok(Duration::default());
+        }
+        let rewind = connection.rtt() + self.interpolation_delay(client_id)?;
+        trace!(?client_id, ?rewind, "lag compensation rewind");
+        Ok(rewind.min(self.config.max_rewind))
+    }
+
+    /// Time of the world that was displayed by the client when it sent the inputs that the server
+    /// is currently processing
+    pub fn client_view_time(
+        &self,
+        client_id: ClientId,
+    ) -> Result<LagCompensationTime, ServerError> {
+        let rewind = self.rewind(client_id)?;
+        Ok(self.time_before(self.tick_manager.tick(), rewind))
+    }
+
+    /// Time of the world that was displayed by the client at the server tick `tick`, for example
+    /// a tick that the client sent along with an action.
+    ///
+    /// This is `tick` minus the interpolation delay of the client, but never older than
+    /// [`LagCompensationConfig::max_rewind`] before the current tick.
+    pub fn client_view_time_at(
+        &self,
+        client_id: ClientId,
+        tick: Tick,
+    ) -> Result<LagCompensationTime, ServerError> {
+        let time = self.time_before(tick, self.interpolation_delay(client_id)?);
+        let oldest = self.time_before(self.tick_manager.tick(), self.config.max_rewind);
+        if ((time.tick - oldest.tick) as f32 + time.overstep - oldest.overstep) < 0.0 {
+            trace!(
+                ?client_id,
+                ?tick,
+                "client view time capped by the max rewind"
+            );
+            return Ok(oldest);
+        }
+        Ok(time)
+    }
+}
+
+/// Lag compensation of the `avian2d` physics components
+#[cfg(feature = "avian2d")]
+pub mod avian2d {
+    use ::avian2d::prelude::{Collider, Position, Rotation};
+
+    use crate::utils::avian2d::{position, rotation};
+
+    use super::*;
+
+    /// Plugin that records the history of the [`Position`], [`Rotation`] and [`Collider`] of the
+    /// replicated entities, so that they can be rewound with [`LagCompensatedColliders`].
+    ///
+    /// The [`LagCompensationPlugin`] must also be added.
+    pub struct AvianLagCompensationPlugin;
+
+    impl Plugin for AvianLagCompensationPlugin {
+        fn build(&self, app: &mut App) {
+            app.add_lag_compensation::<Position>()
+                .add_lag_compensation::<Rotation>()
+                .add_lag_compensation::<Collider>();
+        }
+    }
+
+    /// [`SystemParam`] to get the colliders of the entities as they were displayed to a client
+    #[derive(SystemParam)]
+    pub struct LagCompensatedColliders<'w, 's> {
+        lag_compensation: LagCompensation<'w>,
+        histories: Query<
+            'w,
+            's,
+            (
+                &'static LagCompensationHistory<Position>,
+                &'static LagCompensationHistory<Rotation>,
+                &'static LagCompensationHistory<Collider>,
+            ),
+        >,
+    }
+
+    impl LagCompensatedColliders<'_, '_> {
+        /// Position, rotation and collider of the entity as they were displayed to the client when it
+        /// sent the inputs that the server is currently processing (see [`LagCompensation::client_view_time`])
+        pub fn client_view(
+            &self,
+            client_id: ClientId,
+            entity: Entity,
+        ) -> Result<Option<(Position, Rotation, Collider)>, ServerError> {
+            let time = self.lag_compensation.client_view_time(client_id)?;
+            Ok(self.at(entity, time))
+        }
+
+        /// Position, rotation and collider of the entity at the given time.
+        ///
+        /// Returns `None` if the entity has no history, or if `time` is older than the history.
+        pub fn at(
+            &self,
+            entity: Entity,
+            time: LagCompensationTime,
+        ) -> Option<(Position, Rotation, Collider)> {
+            let (positions, rotations, colliders) = self.histories.get(entity).ok()?;
+            Some((
+                positions.interpolate(time, position::lerp)?,
+                rotations.interpolate(time, rotation::lerp)?,
+                colliders.at(time.tick)?.clone(),
+            ))
+        }
+    }
+}
+
+/// Lag compensation of the `avian3d` physics components
+#[cfg(feature = "avian3d")]
+pub mod avian3d {
+    use ::avian3d::prelude::{Collider, Position, Rotation};
+
+    use crate::utils::avian3d::{position, rotation};
+
+    use super::*;
+
+    /// Plugin that records the history of the [`Position`], [`Rotation`] and [`Collider`] of the
+    /// replicated entities, so that they can be rewound with [`LagCompensatedColliders`].
+    ///
+    /// The [`LagCompensationPlugin`] must also be added.
+    pub struct AvianLagCompensationPlugin;
+
+    impl Plugin for AvianLagCompensationPlugin {
+        fn build(&self, app: &mut App) {
+            app.add_lag_compensation::<Position>()
+                .add_lag_compensation::<Rotation>()
+                .add_lag_compensation::<Collider>();
+        }
+    }
+
+    /// [`SystemParam`] to get the colliders of the entities as they were displayed to a client
+    #[derive(SystemParam)]
+    pub struct LagCompensatedColliders<'w, 's> {
+        lag_compensation: LagCompensation<'w>,
+        histories: Query<
+            'w,
+            's,
+            (
+                &'static LagCompensationHistory<Position>,
+                &'static LagCompensationHistory<Rotation>,
+                &'static LagCompensationHistory<Collider>,
+            ),
+        >,
+    }
+
+    impl LagCompensatedColliders<'_, '_> {
+        /// Position, rotation and collider of the entity as they were displayed to the client when it
+        /// sent the inputs that the server is currently processing (see [`LagCompensation::client_view_time`])
+        pub fn client_view(
+            &self,
+            client_id: ClientId,
+            entity: Entity,
+        ) -> Result<Option<(Position, Rotation, Collider)>, ServerError> {
+            let time = self.lag_compensation.client_view_time(client_id)?;
+            Ok(self.at(entity, time))
+        }
+
+        /// Position, rotation and collider of the entity at the given time.
+        ///
+        /// Returns `None` if the entity has no history, or if `time` is older than the history.
+        pub fn at(
+            &self,
+            entity: Entity,
+            time: LagCompensationTime,
+        ) -> Option<(Position, Rotation, Collider)> {
+            let (positions, rotations, colliders) = self.histories.get(entity).ok()?;
+            Some((
+                positions.interpolate(time, position::lerp)?,
+                rotations.interpolate(time, rotation::lerp)?,
+                colliders.at(time.tick)?.clone(),
+            ))
+        }
+    }
+}
+
+pub(crate) mod systems {
+    use super::*;
+
+    /// Add a [`LagCompensationHistory`] to the replicated entities that have the component `C`
+    pub(crate) fn add_history<C: Component + Clone>(
+        mut commands: Commands,
+        query: Query<
+            Entity,
+            (
+                With<C>,
+                With<ReplicationTarget>,
+                Without<LagCompensationHistory<C>>,
+            ),
+        >,
+    ) {
+        for entity in query.iter() {
+            commands
+                .entity(entity)
+                .insert(LagCompensationHistory::<C>::default());
+        }
+    }
+
+    /// Record the current value of the component `C` and remove the values older than the maximum rewind
+    pub(crate) fn update_history<C: Component + Clone>(
+        config: Res<LagCompensationConfig>,
+        tick_manager: Res<TickManager>,
+        mut query: Query<(&C, &mut LagCompensationHistory<C>)>,
+    ) {
+        let tick = tick_manager.tick();
+        let max_rewind_ticks = (config.max_rewind.as_secs_f32()
+            / tick_manager.config.tick_duration.as_secs_f32())
+        .ceil() as u16;
+        for (component, mut history) in query.iter_mut() {
+            history.add(tick, component.clone());
+            history.pop_until(tick - max_rewind_ticks);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use crate::prelude::server::Replicate;
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    use super::*;
+
+    #[test]
+    fn test_history() {
+        let mut history = LagCompensationHistory::<f32>::default();
+        history.add(Tick(1), 1.0);
+        history.add(Tick(3), 3.0);
+        history.add(Tick(5), 5.0);
+        // ticks must be increasing
+        history.add(Tick(4), 4.0);
+
+        assert_eq!(history.at(Tick(0)), None);
+        assert_eq!(history.at(Tick(2)), Some(&1.0));
+        assert_eq!(history.at(Tick(6)), Some(&5.0));
+        let lerp = |a: &f32, b: &f32, t: f32| a + (b - a) * t;
+        assert_eq!(
+            history.interpolate(
+                LagCompensationTime {
+                    tick: Tick(3),
+                    overstep: 0.5
+                },
+                lerp
+            ),
+            Some(3.5)
+        );
+        assert_eq!(
+            history.interpolate(
+                LagCompensationTime {
+                    tick: Tick(6),
+                    overstep: 0.5
+                },
+                lerp
+            ),
+            Some(5.0)
+        );
+
+        // the latest value before the oldest tick is kept for interpolation
+        history.pop_until(Tick(4));
+        assert_eq!(
+            history.iter().collect::<Vec<_>>(),
+            vec![(Tick(3), &3.0), (Tick(5), &5.0)]
+        );
+    }
+
+    #[test]
+    fn test_lag_compensation() {
+        let mut stepper = BevyStepper::default();
+        LagCompensationPlugin::default().build(&mut stepper.server_app);
+        stepper
+            .server_app
+            .add_lag_compensation::<ComponentSyncModeFull>();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((ComponentSyncModeFull(1.0), Replicate::default()))
+            .id();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        // the history is recorded every tick
+        let history = stepper
+            .server_app
+            .world()
+            .get::<LagCompensationHistory<ComponentSyncModeFull>>(server_entity)
+            .unwrap();
+        assert!(history.iter().count() > 1);
+
+        // the client sent its interpolation delay to the server
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let client_config = stepper
+            .client_app
+            .world()
+            .resource::<crate::prelude::client::ClientConfig>();
+        let interpolation_delay = client_config
+            .interpolation
+            .delay
+            .to_duration(client_config.shared.server_replication_send_interval);
+        let connection_manager = stepper.server_app.world().resource::<ConnectionManager>();
+        let connection = connection_manager.connection(client_id).unwrap();
+        assert_eq!(connection.interpolation_delay(), Some(interpolation_delay));
+    }
+
+    #[test]
+    fn test_client_view_time_at() {
+        let mut stepper = BevyStepper::default();
+        LagCompensationPlugin::default().build(&mut stepper.server_app);
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let tick_duration = stepper.tick_duration;
+        let interpolation_delay = stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .connection(client_id)
+            .unwrap()
+            .interpolation_delay()
+            .unwrap();
+        let delay_ticks = interpolation_delay.as_secs_f32() / tick_duration.as_secs_f32();
+        let max_rewind_ticks =
+            LagCompensationConfig::default().max_rewind.as_secs_f32() / tick_duration.as_secs_f32();
+        let current_tick = stepper.server_tick();
+
+        let mut system_state = SystemState::<LagCompensation>::new(stepper.server_app.world_mut());
+        let lag_compensation = system_state.get(stepper.server_app.world());
+
+        // the interpolation delay of the client is applied to the given tick
+        let tick = current_tick - 3;
+        let time = lag_compensation
+            .client_view_time_at(client_id, tick)
+            .unwrap();
+        let offset = (tick - time.tick) as f32 - time.overstep;
+        assert!((offset - delay_ticks).abs() < 1e-3);
+
+        // the time is never older than the max rewind
+        let time = lag_compensation
+            .client_view_time_at(client_id, current_tick - 1000)
+            .unwrap();
+        let offset = (current_tick - time.tick) as f32 - time.overstep;
+        assert!((offset - max_rewind_ticks).abs() < 1e-3);
+    }
+
+    #[cfg(feature = "avian2d")]
+    #[test]
+    fn test_avian_lag_compensation() {
+        use ::avian2d::prelude::{Collider, Position, Rotation};
+
+        use super::avian2d::{AvianLagCompensationPlugin, LagCompensatedColliders};
+
+        let mut stepper = BevyStepper::default();
+        LagCompensationPlugin::default().build(&mut stepper.server_app);
+        AvianLagCompensationPlugin.build(&mut stepper.server_app);
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Position::from_xy(0.0, 0.0),
+                Rotation::default(),
+                Collider::circle(1.0),
+                Replicate::default(),
+            ))
+            .id();
+        for _ in 0..5 {
+            stepper.frame_step();
+            // the entity moves by 1.0 every tick
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<Position>(server_entity)
+                .unwrap()
+                .x += 1.0;
+        }
+
+        let history = stepper
+            .server_app
+            .world()
+            .get::<LagCompensationHistory<Position>>(server_entity)
+            .unwrap();
+        let (tick, position) = history.iter().nth(1).unwrap();
+        let position = *position;
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+
+        let mut system_state =
+            SystemState::<LagCompensatedColliders>::new(stepper.server_app.world_mut());
+        let colliders = system_state.get(stepper.server_app.world());
+        // the position is interpolated between the recorded ticks
+        let (rewound_position, _, collider) = colliders
+            .at(
+                server_entity,
+                LagCompensationTime {
+                    tick,
+                    overstep: 0.5,
+                },
+            )
+            .unwrap();
+        assert_eq!(rewound_position.x, position.x + 0.5);
+        assert_eq!(collider.shape().as_ball().unwrap().radius, 1.0);
+        // the colliders can be rewound to the time seen by the client
+        assert!(colliders
+            .client_view(client_id, server_entity)
+            .unwrap()
+            .is_some());
+    }
+}
@@ -45,6 +45,7 @@ fn read_message<M: Message>(
                                 reader.consume(),
                                 target,
                                 channel_kind,
+                                kind,
                             ));
                         }
                         event.send(MessageEvent::new(message, *client_id));
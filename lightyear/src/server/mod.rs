@@ -7,12 +7,16 @@ pub mod config;
 
 pub mod connection;
 
+pub mod diagnostics;
+
 pub mod error;
 
 pub mod events;
 
 pub mod input;
 
+pub mod lag_compensation;
+
 pub(crate) mod io;
 
 pub mod plugin;
@@ -20,6 +24,8 @@ pub mod plugin;
 pub(crate) mod message;
 pub(crate) mod prediction;
 
+pub mod priority;
+
 pub mod clients;
 pub(crate) mod networking;
 pub mod relevance;
@@ -98,6 +98,10 @@ pub(crate) fn receive_packets(
     time_manager.update(delta);
     trace!(time = ?time_manager.current_time(), tick = ?tick_manager.tick(), "receive");
 
+    // clients whose connection was denied during the handshake, and that have received the denial
+    // (or did not ack it within the handshake timeout)
+    let denied_clients = std::mem::take(&mut connection_manager.denied_clients);
+
     // update server net connections
     // reborrow trick to enable split borrows
     let netservers = &mut *netservers;
@@ -129,6 +133,16 @@ pub(crate) fn receive_packets(
         let _ = netserver
             .try_update(delta.as_secs_f64())
             .map_err(|e| error!("Error updating netcode server: {:?}", e));
+        // disconnect after the update, so that the disconnections are reported in `new_disconnections`
+        for client_id in denied_clients
+            .iter()
+            .copied()
+            .filter(|client_id| netservers.client_server_map.get(client_id) == Some(&server_idx))
+        {
+            let _ = netserver
+                .disconnect(client_id)
+                .inspect_err(|e| error!(?client_id, "Error disconnecting denied client: {e:?}"));
+        }
         for client_id in netserver.new_connections().iter().copied() {
             netservers.client_server_map.insert(client_id, server_idx);
             // spawn an entity for the client
@@ -320,6 +334,7 @@ fn rebuild_server_connections(world: &mut World) {
         server_config.replication,
         server_config.packet,
         server_config.ping,
+        server_config.shared.protocol_version,
     );
     // // make sure the previous replication metadata is ported over to the new manager
     // if let Some(mut previous_manager) = world.get_resource_mut::<ConnectionManager>() {
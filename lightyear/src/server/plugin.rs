@@ -9,6 +9,7 @@
 //!
 //! Most plugins are truly necessary for the server functionality to work properly, but some could be disabled.
 use crate::server::clients::ClientsMetadataPlugin;
+use crate::server::diagnostics::ServerDiagnosticsPlugin;
 use bevy::app::PluginGroupBuilder;
 use bevy::prelude::*;
 
@@ -29,6 +30,7 @@ use super::config::ServerConfig;
 /// - [`SetupPlugin`]: Adds the [`ServerConfig`] resource and the [`SharedPlugin`] plugin.
 /// - [`ServerEventsPlugin`]: Adds the server network event
 /// - [`ServerNetworkingPlugin`]: Handles the network state (starting/stopping the server, sending/receiving packets)
+/// - [`ServerDiagnosticsPlugin`]: Computes diagnostics about the client connections. Can be disabled if you don't need it.
 /// - [`NetworkRelevancePlugin`]: Handles the network relevance systems. This can be disabled if you don't need fine-grained interest management.
 /// - [`RoomPlugin`]: Handles the room system, which is an addition to the visibility system. This can be disabled if you don't need rooms.
 /// - [`ServerReplicationReceivePlugin`]: Handles the replication of entities and resources from clients to the server. This can be
@@ -61,6 +63,7 @@ impl PluginGroup for ServerPlugins {
             })
             .add(ServerEventsPlugin)
             .add(ServerNetworkingPlugin)
+            .add(ServerDiagnosticsPlugin)
             .add(NetworkRelevancePlugin)
             .add(RoomPlugin)
             .add(ClientsMetadataPlugin)
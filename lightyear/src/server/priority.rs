@@ -0,0 +1,235 @@
+//! Per-client replication priority
+//!
+//! The priority of a [`ReplicationGroup`] is the same for every client, unless it is updated per client with
+//! [`ConnectionManager::update_priority`]. A [`ReplicationPriorityFn`] computes instead a priority multiplier for each
+//! client and each entity replicated to that client every time the replication messages are buffered, so that each client
+//! can prioritize the entities that matter to it (nearby or on-screen entities, for example).
+//!
+//! When the bandwidth cap is enabled, the groups with a higher accumulated priority are sent first.
+//!
+//! ```rust,ignore
+//! // entities within 100.0 of the client are sent with priority 1.0,
+//! // entities further than 500.0 with priority 0.1
+//! app.insert_resource(ReplicationPriorityFn::distance::<Transform>(100.0, 500.0, 0.1));
+//! ```
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use tracing::trace;
+
+use crate::server::connection::ConnectionManager;
+use crate::server::relevance::spatial::SpatialPosition;
+use crate::shared::replication::components::{
+    ReplicationGroup, ReplicationGroupId, ReplicationTarget,
+};
+
+/// Function that returns the priority multiplier of a replicated entity for a given client.
+///
+/// The arguments are the [`World`], the client entity (see [`ConnectionManager::client_entity`])
+/// and the replicated entity.
+pub type PriorityFn = dyn Fn(&World, Entity, Entity) -> f32 + Send + Sync;
+
+/// Resource that holds the function used to compute the priority of the replicated entities for each client.
+///
+/// The base priority of each [`ReplicationGroup`] is multiplied by the value returned by the function
+/// before being accumulated. If the group contains multiple entities, the maximum value is used.
+#[derive(Resource)]
+pub struct ReplicationPriorityFn(Box<PriorityFn>);
+
+impl ReplicationPriorityFn {
+    pub fn new(f: impl Fn(&World, Entity, Entity) -> f32 + Send + Sync + 'static) -> Self {
+        Self(Box::new(f))
+    }
+
+    /// Priority that decreases linearly with the distance between the client entity and the replicated entity,
+    /// computed from their component `P`.
+    ///
+    /// The priority is 1.0 when the distance is smaller than `near`, and `far_priority` when the distance is
+    /// greater than `far`. The priority is 1.0 if either entity doesn't have the component `P`, so `P` needs to
+    /// be added (and kept up-to-date) on the client entity.
+    pub fn distance<P: SpatialPosition>(near: f32, far: f32, far_priority: f32) -> Self {
+        Self::new(move |world, client_entity, entity| {
+            let (Some(client_position), Some(position)) =
+                (world.get::<P>(client_entity), world.get::<P>(entity))
+            else {
+                return 1.0;
+            };
+            let distance = client_position.position().distance(position.position());
+            if distance <= near {
+                1.0
+            } else if distance >= far {
+                far_priority
+            } else {
+                let t = (distance - near) / (far - near);
+                1.0 + t * (far_priority - 1.0)
+            }
+        })
+    }
+
+    pub fn priority(&self, world: &World, client_entity: Entity, entity: Entity) -> f32 {
+        (self.0)(world, client_entity, entity)
+    }
+}
+
+impl std::fmt::Debug for ReplicationPriorityFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplicationPriorityFn").finish()
+    }
+}
+
+pub(crate) mod systems {
+    use super::*;
+
+    /// Compute the dynamic priority of the replication groups of every client, before the priorities
+    /// are accumulated.
+    ///
+    /// The priority function is only evaluated for the groups that are relevant to the client (the groups that
+    /// have been, or are about to be, replicated to it), since the other groups don't have any priority to accumulate.
+    pub(crate) fn compute_dynamic_priority(world: &mut World) {
+        let mut query =
+            world.query_filtered::<(Entity, &ReplicationGroup), With<ReplicationTarget>>();
+        world.resource_scope(|world, mut connection_manager: Mut<ConnectionManager>| {
+            let priority_fn = world.resource::<ReplicationPriorityFn>();
+            // entities of each replication group, shared by all clients
+            let mut group_entities = HashMap::<ReplicationGroupId, Vec<Entity>>::default();
+            for (entity, group) in query.iter(world) {
+                group_entities
+                    .entry(group.group_id(Some(entity)))
+                    .or_default()
+                    .push(entity);
+            }
+            let clients: Vec<_> = connection_manager.connected_clients().collect();
+            for client_id in clients {
+                let Ok(connection) = connection_manager.connection_mut(client_id) else {
+                    continue;
+                };
+                if connection.is_local_client() {
+                    continue;
+                }
+                let client_entity = connection.entity();
+                let priorities = connection
+                    .replication_sender
+                    .group_channels
+                    .keys()
+                    .filter_map(|group_id| {
+                        let entities = group_entities.get(group_id)?;
+                        let priority = entities
+                            .iter()
+                            .map(|entity| {
+                                let priority = priority_fn.priority(world, client_entity, *entity);
+                                trace!(?client_id, ?entity, ?priority, "dynamic priority");
+                                priority
+                            })
+                            .fold(f32::NEG_INFINITY, f32::max);
+                        Some((*group_id, priority))
+                    })
+                    .collect::<HashMap<_, _>>();
+                connection
+                    .replication_sender
+                    .update_dynamic_priority(&priorities);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use crate::prelude::server::Replicate;
+    use crate::prelude::{ClientId, NetworkTarget};
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    use super::*;
+
+    #[test]
+    fn test_distance_priority() {
+        let mut world = World::new();
+        let client_entity = world.spawn(Transform::default()).id();
+        let near = world.spawn(Transform::from_xyz(5.0, 0.0, 0.0)).id();
+        let middle = world.spawn(Transform::from_xyz(20.0, 0.0, 0.0)).id();
+        let far = world.spawn(Transform::from_xyz(100.0, 0.0, 0.0)).id();
+        let no_position = world.spawn_empty().id();
+
+        let priority_fn = ReplicationPriorityFn::distance::<Transform>(10.0, 30.0, 0.0);
+        assert_eq!(priority_fn.priority(&world, client_entity, near), 1.0);
+        assert_eq!(priority_fn.priority(&world, client_entity, middle), 0.5);
+        assert_eq!(priority_fn.priority(&world, client_entity, far), 0.0);
+        assert_eq!(
+            priority_fn.priority(&world, client_entity, no_position),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_dynamic_priority() {
+        let mut stepper = BevyStepper::default();
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let client_entity = stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .client_entity(client_id)
+            .unwrap();
+        stepper
+            .server_app
+            .insert_resource(ReplicationPriorityFn::new(move |_, client, _| {
+                if client == client_entity {
+                    3.0
+                } else {
+                    1.0
+                }
+            }));
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let group_id = ReplicationGroupId(server_entity.to_bits());
+        let dynamic_priority = stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .connection(client_id)
+            .unwrap()
+            .replication_sender
+            .group_channels
+            .get(&group_id)
+            .unwrap()
+            .dynamic_priority;
+        assert_eq!(dynamic_priority, 3.0);
+    }
+
+    /// The priority function is not evaluated for entities that are not replicated to the client
+    #[test]
+    fn test_dynamic_priority_relevant_entities() {
+        let mut stepper = BevyStepper::default();
+        let hidden = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate {
+                target: ReplicationTarget {
+                    target: NetworkTarget::None,
+                },
+                ..default()
+            })
+            .id();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let hidden_calls = calls.clone();
+        stepper
+            .server_app
+            .insert_resource(ReplicationPriorityFn::new(move |_, _, entity| {
+                if entity == hidden {
+                    hidden_calls.fetch_add(1, Ordering::Relaxed);
+                }
+                1.0
+            }));
+        stepper.server_app.world_mut().spawn(Replicate::default());
+        stepper.frame_step();
+        stepper.frame_step();
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+    }
+}
@@ -49,6 +49,15 @@ pub(crate) struct CachedNetworkRelevance {
     pub(crate) clients_cache: HashMap<ClientId, ClientRelevance>,
 }
 
+impl CachedNetworkRelevance {
+    /// Returns true if the entity is currently relevant to the client
+    pub(crate) fn is_relevant(&self, client_id: &ClientId) -> bool {
+        self.clients_cache
+            .get(client_id)
+            .is_some_and(|relevance| *relevance != ClientRelevance::Lost)
+    }
+}
+
 #[derive(Debug, Default)]
 struct RelevanceEvents {
     gained: HashMap<ClientId, EntityHashSet>,
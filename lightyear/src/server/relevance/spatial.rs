@@ -0,0 +1,617 @@
+/*! Spatial network relevance module, where entities are relevant to the clients that are close to them
+
+# Spatial relevance
+
+The [`SpatialRelevancePlugin`] provides interest management based on distance: an entity is relevant to a client
+if it is within the view radius of the client's [`RelevanceViewer`].
+
+The plugin is generic over the component that holds the position of the entities, which has to implement
+[`SpatialPosition`]. The entities whose relevance is managed by the plugin are the entities with this component
+and with [`NetworkRelevanceMode::InterestManagement`].
+
+```rust
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use lightyear::prelude::server::*;
+
+fn build(app: &mut App) {
+    app.add_plugins(SpatialRelevancePlugin::<Transform>::new(SpatialRelevanceConfig {
+        cell_size: 100.0,
+        ..default()
+    }));
+}
+
+fn spawn_player(mut commands: Commands) {
+    // the entities within 200.0 of the player will be relevant to the client
+    commands.spawn((
+        Transform::default(),
+        RelevanceViewer {
+            client_id: ClientId::Netcode(0),
+            radius: 200.0,
+        },
+    ));
+}
+```
+
+## Implementation
+
+The entities are stored in a grid of cells of size [`SpatialRelevanceConfig::cell_size`]; only the entities
+whose position changed are moved between cells. For each viewer, we only look at the cells that intersect its view radius,
+so the cost of an update does not grow with the total number of entities.
+
+Entities become relevant when they get closer than `radius`, but only stop being relevant when they get further than
+`radius + hysteresis`, so that entities at the border of the view radius do not flicker in and out.
+
+The relevance changes are applied with the same functions as in the immediate-mode [`RelevanceManager`].
+*/
+use bevy::ecs::entity::{EntityHashMap, EntityHashSet};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use tracing::trace;
+
+use crate::connection::id::ClientId;
+use crate::prelude::server::is_started;
+use crate::prelude::NetworkRelevanceMode;
+use crate::server::relevance::immediate::{NetworkRelevanceSet, RelevanceManager};
+use crate::shared::sets::{InternalReplicationSet, ServerMarker};
+
+/// Component that holds the position used by the [`SpatialRelevancePlugin`]
+pub trait SpatialPosition: Component {
+    fn position(&self) -> Vec3;
+}
+
+impl SpatialPosition for Transform {
+    fn position(&self) -> Vec3 {
+        self.translation
+    }
+}
+
+impl SpatialPosition for GlobalTransform {
+    fn position(&self) -> Vec3 {
+        self.translation()
+    }
+}
+
+/// Number of dimensions used to compute the distances
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+pub enum GridDimension {
+    /// Only the `x` and `y` coordinates are used
+    #[default]
+    TwoD,
+    ThreeD,
+}
+
+#[derive(Resource, Debug, Clone, PartialEq, Reflect)]
+pub struct SpatialRelevanceConfig {
+    /// Size of the cells of the grid.
+    ///
+    /// Should be of the same order of magnitude as the view radius of the viewers
+    pub cell_size: f32,
+    pub dimension: GridDimension,
+    /// Extra distance (beyond the view radius) that an entity needs to move away from a viewer before
+    /// it stops being relevant to it
+    pub hysteresis: f32,
+}
+
+impl Default for SpatialRelevanceConfig {
+    fn default() -> Self {
+        Self {
+            cell_size: 100.0,
+            dimension: GridDimension::TwoD,
+            hysteresis: 10.0,
+        }
+    }
+}
+
+impl SpatialRelevanceConfig {
+    fn cell(&self, position: Vec3) -> IVec3 {
+        let cell = (position / self.cell_size).floor().as_ivec3();
+        match self.dimension {
+            GridDimension::TwoD => IVec3::new(cell.x, cell.y, 0),
+            GridDimension::ThreeD => cell,
+        }
+    }
+
+    fn distance(&self, a: Vec3, b: Vec3) -> f32 {
+        match self.dimension {
+            GridDimension::TwoD => a.truncate().distance(b.truncate()),
+            GridDimension::ThreeD => a.distance(b),
+        }
+    }
+}
+
+/// Add this component to an entity with a [`SpatialPosition`] to make the entities around it relevant to a client.
+///
+/// There should be at most one viewer per client.
+#[derive(Component, Debug, Clone, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct RelevanceViewer {
+    pub client_id: ClientId,
+    /// Entities closer than this distance are relevant to the client
+    pub radius: f32,
+}
+
+/// Resource that stores the entities in a grid, and the entities that are relevant to each viewer
+#[derive(Resource, Debug, Default)]
+pub struct SpatialGrid {
+    cells: HashMap<IVec3, EntityHashSet>,
+    /// Last known position and cell of each entity
+    entities: EntityHashMap<(Vec3, IVec3)>,
+    /// Entities that are currently relevant to each client
+    relevant: HashMap<ClientId, EntityHashSet>,
+}
+
+impl SpatialGrid {
+    /// Returns true if the entity is currently relevant to the client because of its position
+    pub fn is_relevant(&self, client_id: ClientId, entity: Entity) -> bool {
+        self.relevant
+            .get(&client_id)
+            .is_some_and(|entities| entities.contains(&entity))
+    }
+
+    fn insert(&mut self, config: &SpatialRelevanceConfig, entity: Entity, position: Vec3) {
+        let cell = config.cell(position);
+        if let Some((_, previous_cell)) = self.entities.insert(entity, (position, cell)) {
+            if previous_cell == cell {
+                return;
+            }
+            self.remove_from_cell(previous_cell, entity);
+        }
+        self.cells.entry(cell).or_default().insert(entity);
+    }
+
+    /// Remove the entity from the grid, and return the clients that it was relevant to
+    fn remove(&mut self, entity: Entity) -> Vec<ClientId> {
+        if let Some((_, cell)) = self.entities.remove(&entity) {
+            self.remove_from_cell(cell, entity);
+        }
+        self.relevant
+            .iter_mut()
+            .filter_map(|(client_id, entities)| entities.remove(&entity).then_some(*client_id))
+            .collect()
+    }
+
+    fn remove_from_cell(&mut self, cell: IVec3, entity: Entity) {
+        if let Some(entities) = self.cells.get_mut(&cell) {
+            entities.remove(&entity);
+            if entities.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    /// Entities that should be relevant to a viewer at `center`, given the entities that were relevant before
+    fn visible_entities(
+        &self,
+        config: &SpatialRelevanceConfig,
+        center: Vec3,
+        radius: f32,
+        previous: Option<&EntityHashSet>,
+    ) -> EntityHashSet {
+        let lose_radius = radius + config.hysteresis;
+        let min = config.cell(center - Vec3::splat(lose_radius));
+        let max = config.cell(center + Vec3::splat(lose_radius));
+        let mut visible = EntityHashSet::default();
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    let Some(entities) = self.cells.get(&IVec3::new(x, y, z)) else {
+                        continue;
+                    };
+                    visible.extend(entities.iter().filter(|entity| {
+                        let (position, _) = self.entities[*entity];
+                        let distance = config.distance(center, position);
+                        distance <= radius
+                            || (distance <= lose_radius
+                                && previous.is_some_and(|previous| previous.contains(*entity)))
+                    }));
+                }
+            }
+        }
+        visible
+    }
+}
+
+/// Plugin that updates the network relevance of the entities based on their distance to the [`RelevanceViewer`]s.
+///
+/// `P` is the component that holds the position of the entities.
+pub struct SpatialRelevancePlugin<P> {
+    pub config: SpatialRelevanceConfig,
+    _marker: std::marker::PhantomData<P>,
+}
+
+impl<P> SpatialRelevancePlugin<P> {
+    pub fn new(config: SpatialRelevanceConfig) -> Self {
+        Self {
+            config,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+/// System sets related to spatial relevance
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum SpatialRelevanceSet {
+    /// Update the grid and buffer the relevance changes
+    UpdateGrid,
+}
+
+impl<P: SpatialPosition> Plugin for SpatialRelevancePlugin<P> {
+    fn build(&self, app: &mut App) {
+        // REFLECTION
+        app.register_type::<RelevanceViewer>();
+        // RESOURCES
+        app.insert_resource(self.config.clone());
+        app.init_resource::<SpatialGrid>();
+        // SETS
+        app.configure_sets(
+            PostUpdate,
+            SpatialRelevanceSet::UpdateGrid
+                // the NetworkRelevanceMode must have been handled (CachedNetworkRelevance is inserted)
+                .after(InternalReplicationSet::<ServerMarker>::BeforeBuffer)
+                // the relevance events must be processed after the grid is updated
+                .before(NetworkRelevanceSet::UpdateRelevance)
+                // the grid only needs to be updated every send_interval
+                .in_set(InternalReplicationSet::<ServerMarker>::SendMessages)
+                .run_if(is_started),
+        );
+        // SYSTEMS
+        app.add_systems(
+            PostUpdate,
+            systems::update_spatial_relevance::<P>.in_set(SpatialRelevanceSet::UpdateGrid),
+        );
+        app.observe(systems::handle_client_disconnect);
+        app.observe(systems::handle_viewer_removed);
+        app.observe(systems::handle_removed::<P>);
+        app.observe(systems::handle_removed::<NetworkRelevanceMode>);
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use crate::server::events::DisconnectEvent;
+
+    /// Move the entities whose position changed in the grid, then compute the relevance changes for every viewer
+    pub fn update_spatial_relevance<P: SpatialPosition>(
+        config: Res<SpatialRelevanceConfig>,
+        mut grid: ResMut<SpatialGrid>,
+        mut relevance_manager: ResMut<RelevanceManager>,
+        entities: Query<(Entity, Ref<P>, Ref<NetworkRelevanceMode>)>,
+        viewers: Query<(&RelevanceViewer, &P)>,
+    ) {
+        for (entity, position, relevance_mode) in entities.iter() {
+            if !config.is_changed() && !position.is_changed() && !relevance_mode.is_changed() {
+                continue;
+            }
+            match relevance_mode.as_ref() {
+                NetworkRelevanceMode::InterestManagement => {
+                    grid.insert(&config, entity, position.position())
+                }
+                // the entity is replicated to all clients, no need to update its relevance
+                NetworkRelevanceMode::All => {
+                    grid.remove(entity);
+                }
+            }
+        }
+        for (viewer, position) in viewers.iter() {
+            let visible = grid.visible_entities(
+                &config,
+                position.position(),
+                viewer.radius,
+                grid.relevant.get(&viewer.client_id),
+            );
+            let previous = grid.relevant.entry(viewer.client_id).or_default();
+            for entity in visible.difference(previous) {
+                trace!(?entity, client_id = ?viewer.client_id, "entity entered view radius");
+                relevance_manager.gain_relevance(viewer.client_id, *entity);
+            }
+            for entity in previous.difference(&visible) {
+                trace!(?entity, client_id = ?viewer.client_id, "entity left view radius");
+                relevance_manager.lose_relevance(viewer.client_id, *entity);
+            }
+            *previous = visible;
+        }
+    }
+
+    /// Clear the relevant entities of a client when it disconnects
+    pub fn handle_client_disconnect(
+        trigger: Trigger<DisconnectEvent>,
+        mut grid: ResMut<SpatialGrid>,
+    ) {
+        grid.relevant.remove(&trigger.event().client_id);
+    }
+
+    /// When a viewer is removed, the entities around it are not relevant to the client anymore
+    pub fn handle_viewer_removed(
+        trigger: Trigger<OnRemove, RelevanceViewer>,
+        viewers: Query<&RelevanceViewer>,
+        mut grid: ResMut<SpatialGrid>,
+        mut relevance_manager: ResMut<RelevanceManager>,
+    ) {
+        let Ok(viewer) = viewers.get(trigger.entity()) else {
+            return;
+        };
+        for entity in grid.relevant.remove(&viewer.client_id).unwrap_or_default() {
+            relevance_manager.lose_relevance(viewer.client_id, entity);
+        }
+    }
+
+    /// Remove the entity from the grid when its position or its [`NetworkRelevanceMode`] is removed
+    /// (or when it is despawned): it is not relevant to any client anymore
+    pub fn handle_removed<C: Component>(
+        trigger: Trigger<OnRemove, C>,
+        mut grid: ResMut<SpatialGrid>,
+        mut relevance_manager: ResMut<RelevanceManager>,
+    ) {
+        let entity = trigger.entity();
+        for client_id in grid.remove(entity) {
+            relevance_manager.lose_relevance(client_id, entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Events;
+
+    use crate::prelude::client::*;
+    use crate::prelude::server::Replicate;
+    use crate::prelude::*;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    use super::*;
+
+    #[test]
+    fn test_visible_entities_hysteresis() {
+        let config = SpatialRelevanceConfig {
+            cell_size: 10.0,
+            dimension: GridDimension::TwoD,
+            hysteresis: 5.0,
+        };
+        assert_eq!(
+            config.cell(Vec3::new(-1.0, 25.0, 100.0)),
+            IVec3::new(-1, 2, 0)
+        );
+
+        let mut grid = SpatialGrid::default();
+        let entity = Entity::from_raw(1);
+        // the z coordinate is ignored in 2D
+        grid.insert(&config, entity, Vec3::new(12.0, 0.0, 100.0));
+        assert!(grid
+            .visible_entities(&config, Vec3::ZERO, 10.0, None)
+            .is_empty());
+        grid.insert(&config, entity, Vec3::new(8.0, 0.0, 100.0));
+        let visible = grid.visible_entities(&config, Vec3::ZERO, 10.0, None);
+        assert!(visible.contains(&entity));
+
+        // the entity stays relevant until it is further than radius + hysteresis
+        grid.insert(&config, entity, Vec3::new(14.0, 0.0, 0.0));
+        let visible = grid.visible_entities(&config, Vec3::ZERO, 10.0, Some(&visible));
+        assert!(visible.contains(&entity));
+        grid.insert(&config, entity, Vec3::new(16.0, 0.0, 0.0));
+        let visible = grid.visible_entities(&config, Vec3::ZERO, 10.0, Some(&visible));
+        assert!(visible.is_empty());
+
+        grid.remove(entity);
+        assert!(grid.cells.is_empty());
+    }
+
+    #[test]
+    // an entity is replicated to the client when it enters the view radius of the client's viewer,
+    // and despawned when it leaves it
+    fn test_spatial_relevance() {
+        let mut stepper = BevyStepper::default();
+        // the stepper's apps are already built, so we build the plugin directly
+        SpatialRelevancePlugin::<Transform>::new(SpatialRelevanceConfig {
+            cell_size: 10.0,
+            dimension: GridDimension::TwoD,
+            hysteresis: 1.0,
+        })
+        .build(&mut stepper.server_app);
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        stepper.server_app.world_mut().spawn((
+            Transform::default(),
+            RelevanceViewer {
+                client_id,
+                radius: 10.0,
+            },
+        ));
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(50.0, 0.0, 0.0),
+                Replicate {
+                    relevance_mode: NetworkRelevanceMode::InterestManagement,
+                    ..Default::default()
+                },
+            ))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .is_none());
+
+        // the entity enters the view radius
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<Transform>(server_entity)
+            .unwrap()
+            .translation
+            .x = 5.0;
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<SpatialGrid>()
+            .is_relevant(client_id, server_entity));
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<Events<EntitySpawnEvent>>()
+                .len(),
+            1
+        );
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+
+        // the entity leaves the view radius
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<Transform>(server_entity)
+            .unwrap()
+            .translation
+            .x = 50.0;
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world()
+            .get_entity(client_entity)
+            .is_none());
+    }
+
+    #[test]
+    // an entity that is switched to NetworkRelevanceMode::All is removed from the grid
+    // and stays replicated to the client
+    fn test_spatial_relevance_mode_all() {
+        let mut stepper = BevyStepper::default();
+        SpatialRelevancePlugin::<Transform>::new(SpatialRelevanceConfig {
+            cell_size: 10.0,
+            dimension: GridDimension::TwoD,
+            hysteresis: 1.0,
+        })
+        .build(&mut stepper.server_app);
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        stepper.server_app.world_mut().spawn((
+            Transform::default(),
+            RelevanceViewer {
+                client_id,
+                radius: 10.0,
+            },
+        ));
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(5.0, 0.0, 0.0),
+                Replicate {
+                    relevance_mode: NetworkRelevanceMode::InterestManagement,
+                    ..Default::default()
+                },
+            ))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<SpatialGrid>()
+            .is_relevant(client_id, server_entity));
+
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(server_entity)
+            .insert(NetworkRelevanceMode::All);
+        stepper.frame_step();
+        stepper.frame_step();
+        let grid = stepper.server_app.world().resource::<SpatialGrid>();
+        assert!(!grid.is_relevant(client_id, server_entity));
+        assert!(!grid.entities.contains_key(&server_entity));
+
+        // the entity is still replicated after moving out of the view radius
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<Transform>(server_entity)
+            .unwrap()
+            .translation
+            .x = 50.0;
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .is_some());
+    }
+
+    #[test]
+    // an entity whose position or NetworkRelevanceMode is removed is removed from the grid
+    // and from the entities relevant to the clients
+    fn test_spatial_relevance_component_removed() {
+        let mut stepper = BevyStepper::default();
+        SpatialRelevancePlugin::<Transform>::new(SpatialRelevanceConfig {
+            cell_size: 10.0,
+            dimension: GridDimension::TwoD,
+            hysteresis: 1.0,
+        })
+        .build(&mut stepper.server_app);
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        stepper.server_app.world_mut().spawn((
+            Transform::default(),
+            RelevanceViewer {
+                client_id,
+                radius: 10.0,
+            },
+        ));
+        let spawn = |stepper: &mut BevyStepper| {
+            stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    Transform::from_xyz(5.0, 0.0, 0.0),
+                    Replicate {
+                        relevance_mode: NetworkRelevanceMode::InterestManagement,
+                        ..Default::default()
+                    },
+                ))
+                .id()
+        };
+        let without_mode = spawn(&mut stepper);
+        let without_position = spawn(&mut stepper);
+        stepper.frame_step();
+        stepper.frame_step();
+        let grid = stepper.server_app.world().resource::<SpatialGrid>();
+        assert!(grid.is_relevant(client_id, without_mode));
+        assert!(grid.is_relevant(client_id, without_position));
+
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(without_mode)
+            .remove::<NetworkRelevanceMode>();
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(without_position)
+            .remove::<Transform>();
+        stepper.frame_step();
+        let grid = stepper.server_app.world().resource::<SpatialGrid>();
+        for entity in [without_mode, without_position] {
+            assert!(!grid.is_relevant(client_id, entity));
+            assert!(!grid.entities.contains_key(&entity));
+        }
+        assert!(grid.cells.is_empty());
+    }
+}
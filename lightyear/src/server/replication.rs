@@ -53,13 +53,15 @@ pub(crate) mod send {
     use super::*;
     use crate::prelude::server::AuthorityCommandExt;
     use crate::prelude::{
-        is_host_server, ClientId, ComponentRegistry, DisabledComponent, NetworkRelevanceMode,
-        OverrideTargetComponent, ReplicateHierarchy, ReplicationGroup, ShouldBePredicted,
-        TargetEntity, Tick, TickManager, TimeManager,
+        is_host_server, ChannelDirection, ClientId, ComponentRegistry, DisabledComponent,
+        NetworkRelevanceMode, OverrideTargetComponent, ReplicateHierarchy, ReplicationFrequency,
+        ReplicationGroup, ShouldBePredicted, TargetEntity, Tick, TickManager, TimeManager,
     };
-    use crate::protocol::component::ComponentKind;
+    use crate::protocol::component::{ComponentKind, ComponentNetId};
     use crate::server::error::ServerError;
     use crate::server::prediction::handle_pre_predicted;
+    use crate::server::priority::systems::compute_dynamic_priority;
+    use crate::server::priority::ReplicationPriorityFn;
     use crate::server::relevance::immediate::{CachedNetworkRelevance, ClientRelevance};
     use crate::shared::replication::archetypes::{
         get_erased_component, ServerReplicatedArchetypes,
@@ -70,10 +72,15 @@ pub(crate) mod send {
         ShouldBeInterpolated,
     };
     use crate::shared::replication::network_target::NetworkTarget;
+    use crate::shared::replication::plugin::SendUpdatesMode;
+    use crate::shared::replication::snapshot::Snapshot;
     use crate::shared::replication::ReplicationSend;
     use bevy::ecs::component::ComponentTicks;
+    use bevy::ecs::event::ManualEventReader;
+    use bevy::ecs::removal_detection::RemovedComponentEntity;
     use bevy::ecs::system::SystemChangeTick;
     use bevy::ptr::Ptr;
+    use bevy::utils::HashMap;
 
     #[derive(Default)]
     pub struct ServerReplicationSendPlugin {
@@ -108,6 +115,16 @@ pub(crate) mod send {
                     PostUpdate,
                     InternalReplicationSet::<ServerMarker>::All.run_if(is_started),
                 )
+                // in snapshot mode, the entity actions and updates are replaced by the snapshots
+                .configure_sets(
+                    PostUpdate,
+                    (
+                        InternalReplicationSet::<ServerMarker>::BufferEntityUpdates,
+                        InternalReplicationSet::<ServerMarker>::BufferComponentUpdates,
+                        InternalReplicationSet::<ServerMarker>::BufferDespawnsAndRemovals,
+                    )
+                        .run_if(not(is_snapshot_mode)),
+                )
                 // SYSTEMS
                 .add_systems(
                     PostUpdate,
@@ -117,6 +134,8 @@ pub(crate) mod send {
             app.add_systems(
                 PostUpdate,
                 (
+                    send_reflect_component_removed
+                        .in_set(InternalReplicationSet::<ServerMarker>::BufferDespawnsAndRemovals),
                     // TODO: putting it here means we might miss entities that are spawned and despawned within the send_interval? bug or feature?
                     //  be careful that newly_connected_client is cleared every send_interval, not every frame.
                     replicate
@@ -124,9 +143,15 @@ pub(crate) mod send {
                         .in_set(InternalReplicationSet::<ServerMarker>::BufferComponentUpdates),
                     (
                         handle_replication_target_update,
+                        compute_dynamic_priority
+                            .run_if(resource_exists::<ReplicationPriorityFn>)
+                            .before(buffer_replication_messages),
                         buffer_replication_messages,
                     )
                         .in_set(InternalReplicationSet::<ServerMarker>::AfterBuffer),
+                    send_snapshots
+                        .run_if(is_snapshot_mode)
+                        .in_set(InternalReplicationSet::<ServerMarker>::AfterBuffer),
                 ),
             );
             // HOST-SERVER
@@ -380,6 +405,168 @@ pub(crate) mod send {
         }
     }
 
+    /// Returns true if the server replicates entities with [`SendUpdatesMode::Snapshot`]
+    pub(crate) fn is_snapshot_mode(config: Res<ServerConfig>) -> bool {
+        config.replication.send_updates_mode == SendUpdatesMode::Snapshot
+    }
+
+    /// Send to each client the snapshot of all the entities that are replicated to it.
+    ///
+    /// The snapshot doesn't include:
+    /// - the components that use delta-compression
+    /// - the entities for which the client has authority, or that were originally replicated from the client
+    pub(crate) fn send_snapshots(
+        tick_manager: Res<TickManager>,
+        component_registry: Res<ComponentRegistry>,
+        mut replicated_archetypes: Local<ServerReplicatedArchetypes>,
+        mut set: ParamSet<(&World, ResMut<ConnectionManager>)>,
+    ) {
+        replicated_archetypes.update(set.p0(), &component_registry);
+
+        let mut sender = std::mem::take(&mut *set.p1());
+        let world = set.p0();
+        let mut snapshots: HashMap<ClientId, Snapshot> = sender
+            .connections
+            .iter()
+            // the snapshots are only sent once we know the protocol version of the client
+            .filter(|(_, connection)| connection.is_accepted() && !connection.is_local_client())
+            .map(|(client_id, _)| (*client_id, Snapshot::default()))
+            .collect();
+
+        for replicated_archetype in replicated_archetypes.archetypes.iter() {
+            // SAFETY: update() makes sure that we have a valid archetype
+            let archetype = unsafe {
+                world
+                    .archetypes()
+                    .get(replicated_archetype.id)
+                    .unwrap_unchecked()
+            };
+            let table = unsafe {
+                world
+                    .storages()
+                    .tables
+                    .get(archetype.table_id())
+                    .unwrap_unchecked()
+            };
+            for entity in archetype.entities() {
+                let entity_ref = world.entity(entity.id());
+                // SAFETY: we know that the entity has the ReplicationTarget component
+                // because the archetype is in replicated_archetypes
+                let replication_target =
+                    unsafe { entity_ref.get::<ReplicationTarget>().unwrap_unchecked() };
+                let visibility = entity_ref.get::<CachedNetworkRelevance>();
+                let sync_target = entity_ref.get::<SyncTarget>();
+                let controlled_by = entity_ref.get::<ControlledBy>();
+                let authority_peer = entity_ref.get::<AuthorityPeer>();
+                let initial_replicated = entity_ref.get::<InitialReplicated>();
+
+                for (client_id, snapshot) in snapshots.iter_mut() {
+                    if !replication_target.target.targets(client_id)
+                        || authority_peer == Some(&AuthorityPeer::Client(*client_id))
+                        || initial_replicated.is_some_and(|r| r.from == Some(*client_id))
+                        || visibility.is_some_and(|v| {
+                            v.clients_cache
+                                .get(client_id)
+                                .map_or(true, |r| *r == ClientRelevance::Lost)
+                        })
+                    {
+                        continue;
+                    }
+                    let Some(connection) = sender.connections.get_mut(client_id) else {
+                        continue;
+                    };
+                    let protocol_version = connection.protocol_version;
+                    let entity_map = &mut connection.replication_receiver.remote_entity_map;
+                    let mut components = HashMap::default();
+                    for replicated_component in replicated_archetype.components.iter() {
+                        // the client cannot deserialize components added in a newer protocol version
+                        if protocol_version < component_registry.version(&replicated_component.kind)
+                        {
+                            continue;
+                        }
+                        let override_target = replicated_component.override_target.and_then(|id| {
+                            entity_ref
+                                .get_by_id(id)
+                                // SAFETY: we know the archetype has the OverrideTarget<C> component
+                                // the OverrideTarget<C> component has the same memory layout as NetworkTarget
+                                .map(|ptr| unsafe { ptr.deref::<NetworkTarget>() })
+                        });
+                        if override_target.is_some_and(|target| !target.targets(client_id)) {
+                            continue;
+                        }
+                        let (data, _) = unsafe {
+                            get_erased_component(
+                                table,
+                                &world.storages().sparse_sets,
+                                entity,
+                                replicated_component.storage_type,
+                                replicated_component.id,
+                            )
+                        };
+                        let _ = component_registry
+                            .erased_serialize(
+                                data,
+                                &mut sender.writer,
+                                replicated_component.kind,
+                                Some(&mut entity_map.local_to_remote),
+                            )
+                            .inspect_err(|e| error!("error serializing component: {:?}", e))
+                            .map(|_| {
+                                // SAFETY: the component is registered since it is replicated
+                                let net_id = unsafe {
+                                    *component_registry
+                                        .kind_map
+                                        .net_id(&replicated_component.kind)
+                                        .unwrap_unchecked()
+                                };
+                                components.insert(net_id, sender.writer.split());
+                            });
+                    }
+                    // add the marker components that are usually sent with the entity spawn
+                    if controlled_by.is_some_and(|c| c.targets(client_id)) {
+                        let _ = component_registry
+                            .serialize(&mut Controlled, &mut sender.writer, None)
+                            .map(|_| {
+                                components.insert(
+                                    component_registry.net_id::<Controlled>(),
+                                    sender.writer.split(),
+                                );
+                            });
+                    }
+                    if sync_target.is_some_and(|sync| sync.prediction.targets(client_id)) {
+                        let _ = component_registry
+                            .serialize(&mut ShouldBePredicted, &mut sender.writer, None)
+                            .map(|_| {
+                                components.insert(
+                                    component_registry.net_id::<ShouldBePredicted>(),
+                                    sender.writer.split(),
+                                );
+                            });
+                    }
+                    if sync_target.is_some_and(|sync| sync.interpolation.targets(client_id)) {
+                        let _ = component_registry
+                            .serialize(&mut ShouldBeInterpolated, &mut sender.writer, None)
+                            .map(|_| {
+                                components.insert(
+                                    component_registry.net_id::<ShouldBeInterpolated>(),
+                                    sender.writer.split(),
+                                );
+                            });
+                    }
+                    snapshot.insert(entity_map.to_remote(entity.id()), components);
+                }
+            }
+        }
+
+        for (client_id, snapshot) in snapshots {
+            let _ = sender
+                .connection_mut(client_id)
+                .and_then(|connection| connection.send_snapshot(tick_manager.tick(), snapshot))
+                .inspect_err(|e| error!("error sending snapshot: {:?}", e));
+        }
+        *set.p1() = sender;
+    }
+
     pub(crate) fn replicate(
         tick_manager: Res<TickManager>,
         component_registry: Res<ComponentRegistry>,
@@ -505,6 +692,13 @@ pub(crate) mod send {
                             // the OverrideTarget<C> component has the same memory layout as NetworkTarget
                             .map(|ptr| unsafe { ptr.deref::<NetworkTarget>() })
                     });
+                    let frequency = replicated_component.frequency.and_then(|id| {
+                        entity_ref
+                            .get_by_id(id)
+                            // SAFETY: we know the archetype has the ReplicationFrequency<C> component
+                            // the ReplicationFrequency<C> component has the same memory layout for any C
+                            .map(|ptr| unsafe { ptr.deref::<ReplicationFrequency<()>>() })
+                    });
 
                     replicate_component_updates(
                         tick_manager.tick(),
@@ -521,6 +715,7 @@ pub(crate) mod send {
                         replicated_component.delta_compression,
                         replicated_component.replicate_once,
                         override_target,
+                        frequency,
                         &system_ticks,
                         &mut sender,
                     );
@@ -716,7 +911,12 @@ pub(crate) mod send {
         // TODO: should we use Option<ResMut> so that this observer doesn't trigger
         //  when we are not connected?
         mut sender: ResMut<ConnectionManager>,
+        config: Res<ServerConfig>,
     ) {
+        // in snapshot mode, the despawn is sent with the next snapshot
+        if config.replication.send_updates_mode == SendUpdatesMode::Snapshot {
+            return;
+        }
         let entity = trigger.entity();
         if let Ok((replication_group, network_target, cached_relevance)) = query.get(entity) {
             trace!(?entity, "Replicate entity despawn");
@@ -823,9 +1023,12 @@ pub(crate) mod send {
         delta_compression: bool,
         replicate_once: bool,
         override_target: Option<&NetworkTarget>,
+        frequency: Option<&ReplicationFrequency<()>>,
         system_ticks: &SystemChangeTick,
         sender: &mut ConnectionManager,
     ) {
+        // the updates of the component are only sent when its ReplicationFrequency timer has finished
+        let send_updates = frequency.map_or(true, |f| f.should_send);
         // TODO: maybe iterate through all the connected clients instead, to avoid allocations?
         // use the overriden target if present
         let target = override_target.map_or(&replication_target.target, |override_target| {
@@ -864,7 +1067,9 @@ pub(crate) mod send {
                                                 // to any client
                                                 return;
                                             }
-                                            update_clients.push(*client_id);
+                                            if send_updates {
+                                                update_clients.push(*client_id);
+                                            }
                                         }
                                     }
                                 }
@@ -902,7 +1107,9 @@ pub(crate) mod send {
                         }
                         // otherwise send an update for all components that changed since the
                         // last update we have ack-ed
-                        update_target.union(target);
+                        if send_updates {
+                            update_target.union(target);
+                        }
                     }
 
                     let new_connected_clients = sender.new_connected_clients();
@@ -958,6 +1165,7 @@ pub(crate) mod send {
                         system_ticks.this_run(),
                         current_tick,
                         delta_compression,
+                        frequency.is_some(),
                     )
                     .inspect_err(|e| {
                         error!("error sending component update: {:?}", e);
@@ -966,6 +1174,25 @@ pub(crate) mod send {
         }
     }
 
+    /// Tick the [`ReplicationFrequency`] timers of the component `C`
+    pub(crate) fn tick_replication_frequency<C: Component>(
+        time_manager: Res<TimeManager>,
+        mut query: Query<&mut ReplicationFrequency<C>, With<Replicating>>,
+    ) {
+        for mut frequency in query.iter_mut() {
+            frequency.tick(time_manager.delta());
+        }
+    }
+
+    /// After we buffer updates, reset the `should_send` of the [`ReplicationFrequency`] of the component `C`
+    pub(crate) fn reset_replication_frequency<C: Component>(
+        mut query: Query<&mut ReplicationFrequency<C>, With<Replicating>>,
+    ) {
+        for mut frequency in query.iter_mut() {
+            frequency.should_send = false;
+        }
+    }
+
     /// This system sends updates for all components that were removed
     pub(crate) fn send_component_removed<C: Component>(
         registry: Res<ComponentRegistry>,
@@ -1004,42 +1231,108 @@ pub(crate) mod send {
                     .map_or(&replication_target.target, |override_target| {
                         &override_target.target
                     });
-                let mut target = match visibility {
-                    Some(visibility) => {
-                        visibility
-                            .clients_cache
-                            .iter()
-                            .filter_map(|(client_id, visibility)| {
-                                if base_target.targets(client_id) {
-                                    // TODO: maybe send no matter the vis?
-                                    if matches!(visibility, ClientRelevance::Maintained) {
-                                        // TODO: USE THE CUSTOM REPLICATE TARGET FOR THIS COMPONENT IF PRESENT!
-                                        return Some(*client_id);
-                                    }
-                                };
-                                None
-                            })
-                            .collect()
-                    }
-                    None => {
-                        trace!("sending component remove!");
-                        // TODO: USE THE CUSTOM REPLICATE TARGET FOR THIS COMPONENT IF PRESENT!
-                        base_target.clone()
-                    }
-                };
-                if let Some(AuthorityPeer::Client(c)) = authority_peer {
-                    target.exclude(&NetworkTarget::Single(*c));
-                }
+                let target = component_removed_target(base_target, authority_peer, visibility);
                 if target.is_empty() {
                     return;
                 }
-                let group_id = group.group_id(Some(entity));
                 debug!(?entity, ?kind, "Sending RemoveComponent");
-                let _ = sender.prepare_component_remove(entity, kind, group, target);
+                let _ = sender.prepare_component_remove(entity, kind, &registry, group, target);
             }
         })
     }
 
+    /// Clients that should receive the removal of a component
+    fn component_removed_target(
+        base_target: &NetworkTarget,
+        authority_peer: Option<&AuthorityPeer>,
+        visibility: Option<&CachedNetworkRelevance>,
+    ) -> NetworkTarget {
+        let mut target = match visibility {
+            Some(visibility) => {
+                visibility
+                    .clients_cache
+                    .iter()
+                    .filter_map(|(client_id, visibility)| {
+                        if base_target.targets(client_id) {
+                            // TODO: maybe send no matter the vis?
+                            if matches!(visibility, ClientRelevance::Maintained) {
+                                // TODO: USE THE CUSTOM REPLICATE TARGET FOR THIS COMPONENT IF PRESENT!
+                                return Some(*client_id);
+                            }
+                        };
+                        None
+                    })
+                    .collect()
+            }
+            None => {
+                trace!("sending component remove!");
+                // TODO: USE THE CUSTOM REPLICATE TARGET FOR THIS COMPONENT IF PRESENT!
+                base_target.clone()
+            }
+        };
+        if let Some(AuthorityPeer::Client(c)) = authority_peer {
+            target.exclude(&NetworkTarget::Single(*c));
+        }
+        target
+    }
+
+    /// Send the removals of the components registered with
+    /// [`register_component_reflect`](crate::prelude::AppComponentExt::register_component_reflect).
+    ///
+    /// The component types are only known at runtime, so the removals are read from the World
+    /// with the [`ComponentId`](bevy::ecs::component::ComponentId) of each component.
+    pub(crate) fn send_reflect_component_removed(
+        world: &mut World,
+        mut readers: Local<HashMap<ComponentNetId, ManualEventReader<RemovedComponentEntity>>>,
+    ) {
+        world.resource_scope(|world, registry: Mut<ComponentRegistry>| {
+            world.resource_scope(|world, mut sender: Mut<ConnectionManager>| {
+                for (kind, metadata) in
+                    registry.reflect_replication(ChannelDirection::ServerToClient)
+                {
+                    let Some(events) = world.removed_components().get(metadata.component_id) else {
+                        continue;
+                    };
+                    for entity in readers.entry(kind).or_default().read(events) {
+                        let entity = Entity::from(entity.clone());
+                        let Some(entity_ref) = world.get_entity(entity) else {
+                            continue;
+                        };
+                        let (Some(replication_target), Some(group), true) = (
+                            entity_ref.get::<ReplicationTarget>(),
+                            entity_ref.get::<ReplicationGroup>(),
+                            entity_ref.contains::<Replicating>(),
+                        ) else {
+                            continue;
+                        };
+                        // do not replicate components that are disabled
+                        if entity_ref.contains_id(metadata.disabled_id) {
+                            continue;
+                        }
+                        // use the overriden target if present
+                        let base_target = entity_ref
+                            .get_by_id(metadata.override_target_id)
+                            // SAFETY: the override component only contains a NetworkTarget
+                            .map_or(&replication_target.target, |ptr| unsafe {
+                                ptr.deref::<NetworkTarget>()
+                            });
+                        let target = component_removed_target(
+                            base_target,
+                            entity_ref.get::<AuthorityPeer>(),
+                            entity_ref.get::<CachedNetworkRelevance>(),
+                        );
+                        if target.is_empty() {
+                            continue;
+                        }
+                        debug!(?entity, ?kind, "Sending RemoveComponent");
+                        let _ =
+                            sender.prepare_component_remove(entity, kind, &registry, group, target);
+                    }
+                }
+            });
+        });
+    }
+
     pub(crate) fn register_replicate_component_send<C: Component>(app: &mut App) {
         app.add_systems(
             PostUpdate,
@@ -1049,6 +1342,11 @@ pub(crate) mod send {
                 //  It is ok to run it every frame because it creates at most one message per despawn
                 send_component_removed::<C>
                     .in_set(InternalReplicationSet::<ServerMarker>::BufferDespawnsAndRemovals),
+                tick_replication_frequency::<C>
+                    .in_set(InternalReplicationSet::<ServerMarker>::BeforeBuffer),
+                // note that this runs every send_interval
+                reset_replication_frequency::<C>
+                    .in_set(InternalReplicationSet::<ServerMarker>::AfterBuffer),
                 // // NOTE: we run this system once every `send_interval` because we don't want to send too many Update messages
                 // //  and use up all the bandwidth
                 // send_component_update::<C>
@@ -1067,6 +1365,9 @@ pub(crate) mod send {
             client, server, DeltaCompression, LinkConditionerConfig, ReplicateOnceComponent,
             Replicated,
         };
+        use crate::prelude::{
+            AppComponentExt, ReflectReplicationCommandsExt, SharedConfig, TickConfig,
+        };
         use crate::server::replication::send::SyncTarget;
         use crate::shared::replication::components::{Controlled, ReplicationGroupId};
         use crate::shared::replication::delta::DeltaComponentHistory;
@@ -1075,8 +1376,11 @@ pub(crate) mod send {
         use crate::tests::protocol::*;
         use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
         use bevy::ecs::system::RunSystemOnce;
-        use bevy::prelude::{default, EventReader, Resource, Update};
+        use bevy::prelude::{
+            default, AppTypeRegistry, EventReader, Reflect, ReflectComponent, Resource, Update,
+        };
         use bevy::utils::HashSet;
+        use std::any::TypeId;
 
         // TODO: test entity spawn newly connected client
 
@@ -2104,6 +2408,68 @@ pub(crate) mod send {
             );
         }
 
+        #[test]
+        fn test_component_update_replication_frequency() {
+            let mut stepper = BevyStepper::default();
+
+            // spawn an entity on server
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    Replicate::default(),
+                    ComponentSyncModeFull(1.0),
+                    ComponentSyncModeSimple(1.0),
+                    // send the updates of ComponentSyncModeFull every 10 ticks
+                    ReplicationFrequency::<ComponentSyncModeFull>::new(Duration::from_millis(100)),
+                ))
+                .id();
+            stepper.frame_step();
+            stepper.frame_step();
+            let client_entity = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client");
+
+            // update both components
+            stepper
+                .server_app
+                .world_mut()
+                .entity_mut(server_entity)
+                .insert((ComponentSyncModeFull(2.0), ComponentSyncModeSimple(2.0)));
+            stepper.frame_step();
+            stepper.frame_step();
+
+            // only the component without a ReplicationFrequency was updated
+            let client_entity_ref = stepper.client_app.world().entity(client_entity);
+            assert_eq!(
+                client_entity_ref.get::<ComponentSyncModeSimple>(),
+                Some(&ComponentSyncModeSimple(2.0))
+            );
+            assert_eq!(
+                client_entity_ref.get::<ComponentSyncModeFull>(),
+                Some(&ComponentSyncModeFull(1.0))
+            );
+
+            // the update is sent once the timer finishes, even though the group has sent messages since
+            // the component changed
+            for _ in 0..10 {
+                stepper.frame_step();
+            }
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .entity(client_entity)
+                    .get::<ComponentSyncModeFull>(),
+                Some(&ComponentSyncModeFull(2.0))
+            );
+        }
+
         #[test]
         fn test_component_update_delta() {
             let mut stepper = BevyStepper::default();
@@ -2942,6 +3308,102 @@ pub(crate) mod send {
                 .is_none());
         }
 
+        #[derive(Component, Reflect, PartialEq, Debug, Default)]
+        #[reflect(Component)]
+        struct ModComponent {
+            value: f32,
+        }
+
+        /// Check that a component registered with reflection is replicated like other components
+        #[test]
+        fn test_reflect_component() {
+            let tick_duration = Duration::from_millis(10);
+            let shared_config = SharedConfig {
+                tick: TickConfig::new(tick_duration),
+                ..default()
+            };
+            let mut stepper = BevyStepper::new(
+                shared_config,
+                client::ClientConfig::default(),
+                tick_duration,
+            );
+            let type_id = TypeId::of::<ModComponent>();
+            for app in [&mut stepper.server_app, &mut stepper.client_app] {
+                app.register_type::<ModComponent>();
+                app.world_mut().init_component::<ModComponent>();
+                let registration = app
+                    .world()
+                    .resource::<AppTypeRegistry>()
+                    .read()
+                    .get(type_id)
+                    .unwrap()
+                    .clone();
+                app.register_component_reflect(&registration, ChannelDirection::ServerToClient);
+            }
+            stepper.init();
+
+            // insert
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((Replicate::default(), ModComponent { value: 1.0 }))
+                .id();
+            stepper.frame_step();
+            stepper.frame_step();
+            let client_entity = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client");
+            let client_value = |stepper: &BevyStepper| {
+                stepper
+                    .client_app
+                    .world()
+                    .entity(client_entity)
+                    .get::<ModComponent>()
+                    .map(|c| c.value)
+            };
+            assert_eq!(client_value(&stepper), Some(1.0));
+
+            // update
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<ModComponent>(server_entity)
+                .unwrap()
+                .value = 2.0;
+            stepper.frame_step();
+            stepper.frame_step();
+            assert_eq!(client_value(&stepper), Some(2.0));
+
+            // updates are not replicated while the component is disabled
+            let world = stepper.server_app.world_mut();
+            world
+                .commands()
+                .entity(server_entity)
+                .disable_component_replication(type_id);
+            world.flush_commands();
+            world.get_mut::<ModComponent>(server_entity).unwrap().value = 3.0;
+            stepper.frame_step();
+            stepper.frame_step();
+            assert_eq!(client_value(&stepper), Some(2.0));
+
+            // remove
+            let world = stepper.server_app.world_mut();
+            world
+                .commands()
+                .entity(server_entity)
+                .reset_component_replication(type_id);
+            world.flush_commands();
+            world.entity_mut(server_entity).remove::<ModComponent>();
+            stepper.frame_step();
+            stepper.frame_step();
+            assert_eq!(client_value(&stepper), None);
+        }
+
         /// Check that if we switch the visibility mode, the entity gets spawned
         /// to the clients that now have visibility
         #[test]
@@ -2,6 +2,7 @@
 use bevy::reflect::Reflect;
 use bevy::utils::Duration;
 
+use crate::protocol::registry::ProtocolVersion;
 use crate::shared::tick_manager::TickConfig;
 
 /// Configuration that has to be the same between the server and the client.
@@ -13,6 +14,12 @@ pub struct SharedConfig {
     /// configuration for the [`FixedUpdate`](bevy::prelude::FixedUpdate) schedule
     pub tick: TickConfig,
     pub mode: Mode,
+    /// Version of the protocol used by this peer.
+    ///
+    /// Contrary to the other fields, this can differ between the client and the server: a server can accept
+    /// clients that use an older version of the protocol, and won't send them the types that were added in
+    /// later versions (see `since_version` on the channel, component and message registrations).
+    pub protocol_version: ProtocolVersion,
 }
 
 // TODO: maybe the modes should just be
@@ -42,6 +49,7 @@ impl Default for SharedConfig {
             server_replication_send_interval: Duration::from_millis(0),
             tick: TickConfig::new(Duration::from_millis(16)),
             mode: Mode::default(),
+            protocol_version: 0,
         }
     }
 }
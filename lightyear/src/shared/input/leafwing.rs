@@ -53,7 +53,9 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A> {
             );
         }
         if is_server {
-            app.add_plugins(crate::server::input::leafwing::LeafwingInputPlugin::<A>::default());
+            app.add_plugins(
+                crate::server::input::leafwing::LeafwingInputPlugin::<A>::new(self.config),
+            );
         }
     }
 }
@@ -1,6 +1,10 @@
 use crate::prelude::{Channel, ChannelKind, Message};
+use crate::serialize::reader::Reader;
+use crate::serialize::{SerializationError, ToBytes};
 use crate::shared::replication::network_target::NetworkTarget;
 use bevy::prelude::Resource;
+use bevy::utils::Duration;
+use byteorder::{ReadBytesExt, WriteBytesExt};
 use std::error::Error;
 
 /// Shared trait between client and server to send messages to a target
@@ -19,3 +23,30 @@ pub(crate) trait MessageSend: Resource {
         target: NetworkTarget,
     ) -> Result<(), Self::Error>;
 }
+
+/// Interpolation delay of a client, sent to the server on the
+/// [`InterpolationDelayChannel`](crate::channel::builder::InterpolationDelayChannel)
+/// when it changes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct InterpolationDelayMessage(pub(crate) Duration);
+
+impl ToBytes for InterpolationDelayMessage {
+    fn len(&self) -> usize {
+        4
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        let micros = u32::try_from(self.0.as_micros()).unwrap_or(u32::MAX);
+        buffer.write_u32::<byteorder::NetworkEndian>(micros)?;
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        Ok(Self(Duration::from_micros(
+            buffer.read_u32::<byteorder::NetworkEndian>()? as u64,
+        )))
+    }
+}
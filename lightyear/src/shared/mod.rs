@@ -18,5 +18,6 @@ pub mod tick_manager;
 
 pub mod input;
 pub(crate) mod message;
+pub mod p2p;
 pub mod run_conditions;
 pub mod time_manager;
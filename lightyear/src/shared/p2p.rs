@@ -0,0 +1,1051 @@
+//! Peer-to-peer deterministic rollback session
+//!
+//! In this mode every peer runs the full deterministic simulation, and the peers only exchange their inputs.
+//! The peers are regular clients connected to a lightweight relay server that runs no simulation: the server
+//! only forwards the [`PeerInputMessage`]s and [`PeerChecksumMessage`]s of each peer to the other peers.
+//!
+//! The session reuses the existing client pieces:
+//! - the tick of every peer is synced to the relay server's tick with the [`SyncManager`](crate::client::sync::SyncManager),
+//!   so a given [`Tick`] refers to the same simulation step on all peers
+//! - the local inputs are stored in an [`InputBuffer`] and sent with the same redundancy as the native inputs
+//!   ([`InputConfig::packet_redundancy`](crate::prelude::client::InputConfig::packet_redundancy))
+//! - when the inputs received from a remote peer differ from the inputs that were predicted for it,
+//!   a rollback is started from the first mispredicted tick with the machinery of the
+//!   [`PredictionPlugin`](crate::client::prediction::plugin::PredictionPlugin). The simulated state must be registered
+//!   with [`add_rollback`](crate::prelude::AppComponentExt::add_rollback) or
+//!   [`add_resource_rollback`](crate::prelude::AppComponentExt::add_resource_rollback).
+//!
+//! The inputs of remote peers are predicted by repeating the last input received from them.
+//! The inputs of all peers for the current tick (or rollback tick) are emitted as [`PeerInputEvent`]s in
+//! [`InputSystemSet::WriteInputEvent`](crate::client::input::native::InputSystemSet::WriteInputEvent).
+//!
+//! If a checksum function is provided in the [`P2PConfig`], a checksum of the simulation state is computed every
+//! [`P2PConfig::checksum_interval`] ticks. Once all the inputs for that tick have been received, the checksum
+//! is sent to the other peers, which compare it with their own and emit a [`PeerDesyncEvent`] on mismatch.
+//!
+//! Some situations make it impossible for the local simulation to stay in sync with the other peers, for example
+//! when the local tick snaps to a new value while the session is running. A [`SessionDesyncEvent`] is then emitted,
+//! and the app should resynchronize the session (for example by restarting it from a state shared by all peers).
+//!
+//! Only one [`P2PPlugin`] should be added per app.
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::client::config::ClientConfig;
+use crate::inputs::native::input_buffer::InputBuffer;
+use crate::inputs::native::InputMessage;
+use crate::prelude::{AppMessageExt, ChannelDirection, ClientId, Tick, UserAction};
+use crate::server::config::ServerConfig;
+
+/// Function used to compute a checksum of the simulation state
+pub type ChecksumFn = fn(&World) -> u64;
+
+/// Config of the peer-to-peer session
+#[derive(Clone, Copy, Debug)]
+pub struct P2PConfig {
+    /// Number of ticks between two checksums of the simulation state
+    pub checksum_interval: u16,
+    /// Function used to compute the checksum. If `None`, no checksums are exchanged
+    pub checksum: Option<ChecksumFn>,
+}
+
+impl Default for P2PConfig {
+    fn default() -> Self {
+        Self {
+            checksum_interval: 60,
+            checksum: None,
+        }
+    }
+}
+
+impl P2PConfig {
+    pub fn with_checksum_interval(mut self, checksum_interval: u16) -> Self {
+        self.checksum_interval = checksum_interval;
+        self
+    }
+
+    pub fn with_checksum(mut self, checksum: ChecksumFn) -> Self {
+        self.checksum = Some(checksum);
+        self
+    }
+}
+
+/// Message containing the inputs of a peer
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PeerInputMessage<A> {
+    /// The peer that produced the inputs. It is set by the relay server
+    pub peer: ClientId,
+    pub message: InputMessage<A>,
+}
+
+/// Message containing the checksum of the simulation state of a peer for a given tick
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct PeerChecksumMessage {
+    /// The peer that computed the checksum. It is set by the relay server
+    pub peer: ClientId,
+    pub tick: Tick,
+    pub checksum: u64,
+}
+
+/// Event emitted every tick with the input of each peer (including the local peer)
+#[derive(Event, Debug)]
+pub struct PeerInputEvent<A: UserAction> {
+    input: Option<A>,
+    peer: ClientId,
+}
+
+impl<A: UserAction> PeerInputEvent<A> {
+    pub fn new(input: Option<A>, peer: ClientId) -> Self {
+        Self { input, peer }
+    }
+
+    pub fn input(&self) -> &Option<A> {
+        &self.input
+    }
+
+    pub fn peer(&self) -> ClientId {
+        self.peer
+    }
+}
+
+/// Event emitted when the checksum of a remote peer does not match the local checksum for the same tick
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct PeerDesyncEvent {
+    pub peer: ClientId,
+    pub tick: Tick,
+    pub local_checksum: u64,
+    pub remote_checksum: u64,
+}
+
+/// Event emitted when the local simulation cannot stay in sync with the other peers
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum SessionDesyncEvent {
+    /// The local tick snapped to a new value after inputs were sent to the other peers, so some ticks
+    /// were skipped or will be simulated twice
+    TickSnap { old_tick: Tick, new_tick: Tick },
+    /// The inputs of a remote peer were lost starting from `tick`, and the simulation kept using the
+    /// predicted inputs for the lost ticks
+    InputsLost { peer: ClientId, tick: Tick },
+    /// An input of a remote peer was mispredicted at `tick`, which is older than the prediction history,
+    /// so the rollback could only start from a more recent tick
+    RollbackTooOld { peer: ClientId, tick: Tick },
+}
+
+/// Inputs received from a remote peer
+#[derive(Debug)]
+struct RemotePeer<A> {
+    buffer: InputBuffer<A>,
+    /// Most recent tick up to which we received all the inputs of the peer
+    confirmed_tick: Option<Tick>,
+    /// Ticks after `confirmed_tick` for which we received the input of the peer, when the inputs
+    /// of some ticks in between are missing (because of packet loss or reordering)
+    received_ticks: HashSet<Tick>,
+    /// Most recent tick for which we received the input of the peer
+    last_received_tick: Option<Tick>,
+    /// Input of the peer at `last_received_tick`, used to predict the inputs for the next ticks
+    last_input: Option<A>,
+}
+
+impl<A> Default for RemotePeer<A> {
+    fn default() -> Self {
+        Self {
+            buffer: InputBuffer::default(),
+            confirmed_tick: None,
+            received_ticks: HashSet::default(),
+            last_received_tick: None,
+            last_input: None,
+        }
+    }
+}
+
+impl<A: UserAction> RemotePeer<A> {
+    fn is_received(&self, tick: Tick) -> bool {
+        self.confirmed_tick
+            .is_some_and(|confirmed_tick| tick <= confirmed_tick)
+            || self.received_ticks.contains(&tick)
+    }
+
+    /// Input of the peer for the tick: the received input, or else the prediction
+    fn input(&self, tick: Tick) -> Option<A> {
+        if self.is_received(tick) {
+            return self.buffer.get(tick).cloned();
+        }
+        if self.last_received_tick.map_or(true, |t| tick > t) {
+            return self.last_input.clone();
+        }
+        // the input is missing: repeat the last input received before it
+        // (the loop stops at `confirmed_tick` at the latest)
+        let mut previous_tick = tick - 1;
+        while !self.is_received(previous_tick) {
+            previous_tick = previous_tick - 1;
+        }
+        self.buffer.get(previous_tick).cloned()
+    }
+
+    /// Advance the confirmed tick over the ticks that were received without a gap
+    fn advance_confirmed_tick(&mut self) {
+        while let Some(confirmed_tick) = self.confirmed_tick {
+            if !self.received_ticks.remove(&(confirmed_tick + 1)) {
+                break;
+            }
+            self.confirmed_tick = Some(confirmed_tick + 1);
+        }
+    }
+
+    /// Update the buffer with the received message.
+    ///
+    /// Returns the first tick (that was already simulated) for which the received input differs
+    /// from the predicted input.
+    fn receive(&mut self, message: InputMessage<A>, current_tick: Tick) -> Option<Tick> {
+        let end_tick = message.end_tick;
+        let start_tick = end_tick - message.inputs.len() as u16 + 1;
+        if self.confirmed_tick.is_none() {
+            self.confirmed_tick = Some(start_tick - 1);
+        }
+        // the ticks for which we didn't have the input yet, with the input that was predicted for them
+        let mut new_inputs = vec![];
+        let mut tick = start_tick;
+        while tick <= end_tick {
+            if !self.is_received(tick) {
+                new_inputs.push((tick, self.input(tick)));
+            }
+            tick = tick + 1;
+        }
+        if new_inputs.is_empty() {
+            // the message does not contain any new input
+            return None;
+        }
+        self.buffer.update_from_message(message);
+
+        let mismatch = new_inputs
+            .iter()
+            .find(|(tick, predicted)| {
+                *tick <= current_tick && self.buffer.get(*tick) != predicted.as_ref()
+            })
+            .map(|(tick, _)| *tick);
+        self.received_ticks
+            .extend(new_inputs.into_iter().map(|(tick, _)| tick));
+        self.advance_confirmed_tick();
+        if self.last_received_tick.map_or(true, |tick| end_tick > tick) {
+            self.last_received_tick = Some(end_tick);
+            self.last_input = self.buffer.get(end_tick).cloned();
+        }
+        mismatch
+    }
+
+    /// Give up on the missing inputs older than `oldest_tick`, if more recent inputs were received.
+    /// They won't be resent by the peer, so they keep the input that was predicted for them.
+    ///
+    /// Returns the first tick whose input was lost.
+    fn skip_lost_inputs(&mut self, oldest_tick: Tick) -> Option<Tick> {
+        let confirmed_tick = self.confirmed_tick?;
+        let last_received_tick = self.last_received_tick?;
+        if confirmed_tick >= oldest_tick || last_received_tick <= confirmed_tick {
+            return None;
+        }
+        let lost_tick = confirmed_tick + 1;
+        let end_tick = if last_received_tick < oldest_tick {
+            last_received_tick
+        } else {
+            oldest_tick
+        };
+        let mut tick = lost_tick;
+        while tick <= end_tick {
+            if !self.is_received(tick) {
+                let predicted = self.input(tick);
+                self.buffer.set(tick, predicted);
+                self.received_ticks.insert(tick);
+            }
+            tick = tick + 1;
+        }
+        self.advance_confirmed_tick();
+        Some(lost_tick)
+    }
+}
+
+/// Resource that holds the state of the peer-to-peer session on a peer
+#[derive(Resource, Debug)]
+pub struct P2PSession<A> {
+    config: P2PConfig,
+    local_inputs: InputBuffer<A>,
+    remote_peers: HashMap<ClientId, RemotePeer<A>>,
+    /// Checksums of the local simulation state
+    local_checksums: HashMap<Tick, u64>,
+    /// Checksums received from remote peers that haven't been compared yet
+    remote_checksums: Vec<PeerChecksumMessage>,
+    /// Most recent tick for which the local inputs were sent to the other peers
+    last_sent_input_tick: Option<Tick>,
+    last_sent_checksum_tick: Option<Tick>,
+    last_verified_checksum_tick: Option<Tick>,
+}
+
+impl<A> P2PSession<A> {
+    fn new(config: P2PConfig) -> Self {
+        Self {
+            config,
+            local_inputs: InputBuffer::default(),
+            remote_peers: HashMap::default(),
+            local_checksums: HashMap::default(),
+            remote_checksums: vec![],
+            last_sent_input_tick: None,
+            last_sent_checksum_tick: None,
+            last_verified_checksum_tick: None,
+        }
+    }
+}
+
+impl<A: UserAction> P2PSession<A> {
+    /// Buffer the input of the local peer for the given tick.
+    ///
+    /// The input is ignored if the inputs for that tick were already sent to the other peers.
+    pub fn add_input(&mut self, input: A, tick: Tick) {
+        if self
+            .last_sent_input_tick
+            .is_some_and(|sent_tick| tick <= sent_tick)
+        {
+            return;
+        }
+        self.local_inputs.set(tick, Some(input));
+    }
+
+    /// Input of the local peer for the given tick
+    pub fn local_input(&self, tick: Tick) -> Option<A> {
+        self.local_inputs.get(tick).cloned()
+    }
+
+    /// Input of a remote peer for the given tick. If the input was not received yet,
+    /// this returns the predicted input
+    pub fn remote_input(&self, peer: ClientId, tick: Tick) -> Option<A> {
+        self.remote_peers.get(&peer).and_then(|p| p.input(tick))
+    }
+
+    /// The remote peers that we received inputs from
+    pub fn remote_peers(&self) -> impl Iterator<Item = ClientId> + '_ {
+        self.remote_peers.keys().copied()
+    }
+
+    /// Stop tracking a remote peer (for example when it leaves the session)
+    pub fn remove_peer(&mut self, peer: ClientId) {
+        self.remote_peers.remove(&peer);
+    }
+
+    /// Most recent tick for which we received the inputs of all the remote peers.
+    /// The simulation is deterministic up to that tick.
+    pub fn confirmed_tick(&self) -> Option<Tick> {
+        self.remote_peers
+            .values()
+            .map(|p| p.confirmed_tick)
+            .reduce(|a, b| match (a, b) {
+                (Some(a), Some(b)) => Some(if a < b { a } else { b }),
+                _ => None,
+            })
+            .flatten()
+    }
+
+    /// Most recent tick for which the checksum of a remote peer matched the local checksum
+    pub fn last_verified_checksum_tick(&self) -> Option<Tick> {
+        self.last_verified_checksum_tick
+    }
+}
+
+/// Plugin that adds a peer-to-peer deterministic rollback session for the inputs `A`.
+///
+/// On a client, the plugin sends the local inputs to the other peers and rolls back on mispredicted remote inputs.
+/// On a server, the plugin relays the inputs and checksums between the peers.
+pub struct P2PPlugin<A: UserAction> {
+    config: P2PConfig,
+    _marker: std::marker::PhantomData<A>,
+}
+
+impl<A: UserAction> P2PPlugin<A> {
+    pub fn new(config: P2PConfig) -> Self {
+        Self {
+            config,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<A: UserAction> Default for P2PPlugin<A> {
+    fn default() -> Self {
+        Self::new(P2PConfig::default())
+    }
+}
+
+impl<A: UserAction> Plugin for P2PPlugin<A> {
+    fn build(&self, _app: &mut App) {}
+
+    fn finish(&self, app: &mut App) {
+        app.register_message::<PeerInputMessage<A>>(ChannelDirection::Bidirectional);
+        app.register_message::<PeerChecksumMessage>(ChannelDirection::Bidirectional);
+        let is_client = app.world().get_resource::<ClientConfig>().is_some();
+        let is_server = app.world().get_resource::<ServerConfig>().is_some();
+        if is_client {
+            peer::add_peer_systems::<A>(app, self.config);
+        }
+        if is_server {
+            relay::add_relay_systems::<A>(app);
+        }
+    }
+}
+
+pub(crate) mod peer {
+    use tracing::{debug, error, trace};
+
+    use super::*;
+    use crate::channel::builder::InputChannel;
+    use crate::client::connection::ConnectionManager;
+    use crate::client::events::MessageEvent;
+    use crate::client::input::native::InputSystemSet;
+    use crate::client::prediction::plugin::{is_in_rollback, PredictionSet};
+    use crate::client::prediction::rollback::Rollback;
+    use crate::client::run_conditions::is_synced;
+    use crate::client::sync::SyncSet;
+    use crate::connection::client::{ClientConnection, NetClient};
+    use crate::prelude::{is_host_server, ChannelKind, ChannelRegistry, TickManager};
+    use crate::shared::sets::{ClientMarker, InternalMainSet};
+    use crate::shared::tick_manager::TickEvent;
+
+    pub(crate) fn add_peer_systems<A: UserAction>(app: &mut App, config: P2PConfig) {
+        app.insert_resource(P2PSession::<A>::new(config));
+        app.add_event::<PeerInputEvent<A>>();
+        app.add_event::<PeerDesyncEvent>();
+        app.add_event::<SessionDesyncEvent>();
+        app.configure_sets(
+            FixedPreUpdate,
+            (
+                InputSystemSet::BufferInputs.run_if(not(is_in_rollback)),
+                InputSystemSet::WriteInputEvent,
+            )
+                .chain(),
+        );
+        app.configure_sets(FixedPostUpdate, InputSystemSet::ClearInputEvent);
+        app.configure_sets(
+            PostUpdate,
+            (
+                SyncSet,
+                InputSystemSet::SendInputMessage.run_if(is_synced.and_then(not(is_host_server))),
+                InternalMainSet::<ClientMarker>::Send,
+            )
+                .chain(),
+        );
+        app.add_systems(
+            PreUpdate,
+            (
+                receive_peer_messages::<A>.in_set(PredictionSet::CheckRollback),
+                verify_checksums::<A>
+                    .after(PredictionSet::Rollback)
+                    .run_if(is_synced),
+            ),
+        );
+        app.add_systems(
+            FixedPreUpdate,
+            write_peer_input_events::<A>.in_set(InputSystemSet::WriteInputEvent),
+        );
+        app.add_systems(
+            FixedPostUpdate,
+            (
+                record_checksum::<A>.in_set(PredictionSet::UpdateHistory),
+                clear_peer_input_events::<A>.in_set(InputSystemSet::ClearInputEvent),
+            ),
+        );
+        app.add_systems(
+            PostUpdate,
+            send_peer_messages::<A>.in_set(InputSystemSet::SendInputMessage),
+        );
+        app.observe(receive_tick_events::<A>);
+    }
+
+    /// Buffer the inputs and checksums received from the other peers, and start a rollback
+    /// if a remote input was mispredicted
+    fn receive_peer_messages<A: UserAction>(
+        config: Res<ClientConfig>,
+        tick_manager: Res<TickManager>,
+        rollback: Res<Rollback>,
+        mut session: ResMut<P2PSession<A>>,
+        mut input_messages: ResMut<Events<MessageEvent<PeerInputMessage<A>>>>,
+        mut checksum_messages: ResMut<Events<MessageEvent<PeerChecksumMessage>>>,
+        mut desync_events: EventWriter<SessionDesyncEvent>,
+    ) {
+        let current_tick = tick_manager.tick();
+        let mut mismatch: Option<(ClientId, Tick)> = None;
+        for event in input_messages.drain() {
+            let PeerInputMessage { peer, message } = event.message;
+            trace!(?peer, end_tick = ?message.end_tick, "received peer input message");
+            if let Some(tick) = session
+                .remote_peers
+                .entry(peer)
+                .or_default()
+                .receive(message, current_tick)
+            {
+                debug!(?peer, ?tick, ?current_tick, "mispredicted remote input");
+                if mismatch.map_or(true, |(_, t)| tick < t) {
+                    mismatch = Some((peer, tick));
+                }
+            }
+        }
+        session
+            .remote_checksums
+            .extend(checksum_messages.drain().map(|event| event.message));
+
+        let Some((peer, mut rollback_tick)) = mismatch else {
+            return;
+        };
+        // we cannot roll back further than the prediction history
+        let oldest_tick = current_tick - config.prediction.maximum_predicted_ticks + 1;
+        if rollback_tick < oldest_tick {
+            error!(
+                ?peer,
+                ?rollback_tick,
+                ?oldest_tick,
+                "cannot roll back to the mispredicted tick, the simulation is out of sync"
+            );
+            desync_events.send(SessionDesyncEvent::RollbackTooOld {
+                peer,
+                tick: rollback_tick,
+            });
+            rollback_tick = oldest_tick;
+        }
+        if rollback
+            .get_rollback_tick()
+            .map_or(true, |tick| rollback_tick < tick)
+        {
+            rollback.set_rollback_tick(rollback_tick);
+        }
+    }
+
+    /// Emit the input of every peer for the current tick (or rollback tick)
+    fn write_peer_input_events<A: UserAction>(
+        tick_manager: Res<TickManager>,
+        rollback: Option<Res<Rollback>>,
+        connection: Res<ClientConnection>,
+        session: Res<P2PSession<A>>,
+        mut events: EventWriter<PeerInputEvent<A>>,
+    ) {
+        let tick = rollback.map_or(tick_manager.tick(), |r| {
+            tick_manager.tick_or_rollback_tick(r.as_ref())
+        });
+        // emit the inputs in a deterministic order, so that all peers apply them in the same order
+        let mut inputs: Vec<_> = session
+            .remote_peers
+            .iter()
+            .map(|(peer, remote)| (*peer, remote.input(tick)))
+            .chain(std::iter::once((
+                connection.id(),
+                session.local_input(tick),
+            )))
+            .collect();
+        inputs.sort_by_key(|(peer, _)| peer.to_bits());
+        events.send_batch(
+            inputs
+                .into_iter()
+                .map(|(peer, input)| PeerInputEvent::new(input, peer)),
+        );
+    }
+
+    /// Events are cleared every frame, but we want to clear them every tick instead
+    fn clear_peer_input_events<A: UserAction>(mut events: EventReader<PeerInputEvent<A>>) {
+        events.clear();
+    }
+
+    /// Compute the checksum of the simulation state at the end of the current tick (or rollback tick)
+    fn record_checksum<A: UserAction>(world: &mut World) {
+        let session = world.resource::<P2PSession<A>>();
+        let (Some(checksum_fn), interval) =
+            (session.config.checksum, session.config.checksum_interval)
+        else {
+            return;
+        };
+        let tick_manager = world.resource::<TickManager>();
+        let tick = world
+            .get_resource::<Rollback>()
+            .map_or(tick_manager.tick(), |r| {
+                tick_manager.tick_or_rollback_tick(r)
+            });
+        if interval == 0 || tick.0 % interval != 0 {
+            return;
+        }
+        let checksum = checksum_fn(world);
+        trace!(?tick, ?checksum, "recorded checksum");
+        world
+            .resource_mut::<P2PSession<A>>()
+            .local_checksums
+            .insert(tick, checksum);
+    }
+
+    /// Compare the checksums received from the remote peers with the local checksums,
+    /// once the inputs for their tick have been received from all peers
+    fn verify_checksums<A: UserAction>(
+        mut session: ResMut<P2PSession<A>>,
+        mut desync_events: EventWriter<PeerDesyncEvent>,
+    ) {
+        let Some(confirmed_tick) = session.confirmed_tick() else {
+            return;
+        };
+        let session = session.as_mut();
+        session.remote_checksums.retain(|remote| {
+            if remote.tick > confirmed_tick {
+                return true;
+            }
+            let Some(local_checksum) = session.local_checksums.get(&remote.tick).copied() else {
+                // the tick has not been simulated yet
+                return true;
+            };
+            if local_checksum == remote.checksum {
+                if session
+                    .last_verified_checksum_tick
+                    .map_or(true, |tick| tick < remote.tick)
+                {
+                    session.last_verified_checksum_tick = Some(remote.tick);
+                }
+            } else {
+                error!(
+                    peer = ?remote.peer,
+                    tick = ?remote.tick,
+                    ?local_checksum,
+                    remote_checksum = ?remote.checksum,
+                    "desync detected with peer"
+                );
+                desync_events.send(PeerDesyncEvent {
+                    peer: remote.peer,
+                    tick: remote.tick,
+                    local_checksum,
+                    remote_checksum: remote.checksum,
+                });
+            }
+            false
+        });
+    }
+
+    /// Send the local inputs and the confirmed checksums to the other peers, and clean up the old inputs
+    fn send_peer_messages<A: UserAction>(
+        mut connection_manager: ResMut<ConnectionManager>,
+        connection: Res<ClientConnection>,
+        channel_registry: Res<ChannelRegistry>,
+        config: Res<ClientConfig>,
+        tick_manager: Res<TickManager>,
+        mut session: ResMut<P2PSession<A>>,
+        mut desync_events: EventWriter<SessionDesyncEvent>,
+    ) {
+        let current_tick = tick_manager.tick();
+        let local_peer = connection.id();
+        let input_send_interval = channel_registry
+            .get_builder_from_kind(&ChannelKind::of::<InputChannel>())
+            .unwrap()
+            .settings
+            .send_frequency;
+        let num_tick: u16 =
+            ((input_send_interval.as_nanos() / config.shared.tick.tick_duration.as_nanos()) + 1)
+                .try_into()
+                .unwrap();
+        let message_len = config.input.packet_redundancy * num_tick;
+        let message = session
+            .local_inputs
+            .create_message(current_tick, message_len);
+        if !message.is_empty() {
+            trace!(?current_tick, "sending peer input message");
+            connection_manager
+                .send_message::<InputChannel, _>(&mut PeerInputMessage {
+                    peer: local_peer,
+                    message,
+                })
+                .unwrap_or_else(|err| {
+                    error!("Error while sending peer input message: {:?}", err);
+                });
+            session.last_sent_input_tick = Some(current_tick);
+        }
+
+        // send the checksums for the ticks where we have the inputs of all peers
+        if let Some(confirmed_tick) = session.confirmed_tick() {
+            let last_sent_tick = session.last_sent_checksum_tick;
+            let mut checksums: Vec<_> = session
+                .local_checksums
+                .iter()
+                .filter(|(tick, _)| {
+                    **tick <= confirmed_tick && last_sent_tick.map_or(true, |t| **tick > t)
+                })
+                .map(|(tick, checksum)| (*tick, *checksum))
+                .collect();
+            checksums.sort_by_key(|(tick, _)| *tick);
+            for (tick, checksum) in checksums {
+                connection_manager
+                    .send_message::<InputChannel, _>(&mut PeerChecksumMessage {
+                        peer: local_peer,
+                        tick,
+                        checksum,
+                    })
+                    .unwrap_or_else(|err| {
+                        error!("Error while sending peer checksum message: {:?}", err);
+                    });
+                session.last_sent_checksum_tick = Some(tick);
+            }
+        }
+
+        // keep enough history to resend the inputs and to roll back
+        let history_len = message_len.max(config.prediction.maximum_predicted_ticks);
+        let oldest_tick = current_tick - history_len;
+        session.local_inputs.pop(oldest_tick);
+        session.remote_peers.iter_mut().for_each(|(peer, remote)| {
+            if let Some(tick) = remote.skip_lost_inputs(oldest_tick) {
+                error!(?peer, ?tick, "the inputs of the peer were lost");
+                desync_events.send(SessionDesyncEvent::InputsLost { peer: *peer, tick });
+            }
+            remote.buffer.pop(oldest_tick);
+        });
+        session
+            .local_checksums
+            .retain(|tick, _| *tick > oldest_tick);
+        session
+            .remote_checksums
+            .retain(|remote| remote.tick > oldest_tick);
+    }
+
+    /// Notify the app when the client tick snaps to a new value while the session is running.
+    ///
+    /// Contrary to the other input plugins, the local inputs are not shifted: the inputs that were already
+    /// sent are tied to their tick on all the peers.
+    fn receive_tick_events<A: UserAction>(
+        trigger: Trigger<TickEvent>,
+        session: Res<P2PSession<A>>,
+        mut desync_events: EventWriter<SessionDesyncEvent>,
+    ) {
+        match trigger.event() {
+            TickEvent::TickSnap { old_tick, new_tick } => {
+                if session.last_sent_input_tick.is_none() {
+                    return;
+                }
+                error!(
+                    ?old_tick,
+                    ?new_tick,
+                    "the tick snapped while the peer-to-peer session is running"
+                );
+                desync_events.send(SessionDesyncEvent::TickSnap {
+                    old_tick: *old_tick,
+                    new_tick: *new_tick,
+                });
+            }
+        }
+    }
+}
+
+pub(crate) mod relay {
+    use tracing::{error, trace};
+
+    use super::*;
+    use crate::channel::builder::InputChannel;
+    use crate::prelude::server::is_started;
+    use crate::prelude::NetworkTarget;
+    use crate::server::connection::ConnectionManager;
+    use crate::server::events::MessageEvent;
+    use crate::shared::sets::{InternalMainSet, ServerMarker};
+
+    pub(crate) fn add_relay_systems<A: UserAction>(app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            relay_peer_messages::<A>
+                .after(InternalMainSet::<ServerMarker>::EmitEvents)
+                .run_if(is_started),
+        );
+    }
+
+    /// Forward the inputs and checksums of each peer to the other peers
+    fn relay_peer_messages<A: UserAction>(
+        mut connection_manager: ResMut<ConnectionManager>,
+        mut input_messages: ResMut<Events<MessageEvent<PeerInputMessage<A>>>>,
+        mut checksum_messages: ResMut<Events<MessageEvent<PeerChecksumMessage>>>,
+    ) {
+        for event in input_messages.drain() {
+            let client_id = event.context;
+            let mut message = event.message;
+            // do not trust the peer id sent by the client
+            message.peer = client_id;
+            trace!(?client_id, "relaying peer input message");
+            if let Err(e) = connection_manager.send_message_to_target::<InputChannel, _>(
+                &mut message,
+                NetworkTarget::AllExceptSingle(client_id),
+            ) {
+                error!("could not relay peer input message: {:?}", e);
+            }
+        }
+        for event in checksum_messages.drain() {
+            let client_id = event.context;
+            let mut message = event.message;
+            message.peer = client_id;
+            if let Err(e) = connection_manager.send_message_to_target::<InputChannel, _>(
+                &mut message,
+                NetworkTarget::AllExceptSingle(client_id),
+            ) {
+                error!("could not relay peer checksum message: {:?}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::Duration;
+
+    use super::*;
+    use crate::client::input::native::InputSystemSet;
+    use crate::inputs::native::input_buffer::InputData;
+    use crate::prelude::client::{InterpolationConfig, PredictionConfig, SyncConfig};
+    use crate::prelude::{AppComponentExt, SharedConfig, TickConfig, TickManager};
+    use crate::shared::tick_manager::TickEvent;
+    use crate::tests::multi_stepper::{MultiBevyStepper, TEST_CLIENT_ID_2};
+    use crate::tests::protocol::MyInput;
+
+    /// Deterministic simulation state, which depends on the inputs of all peers and on their order
+    #[derive(Resource, Clone, Debug, Default, PartialEq)]
+    struct State(i64);
+
+    #[derive(Resource)]
+    struct LocalInput {
+        value: i16,
+        start_tick: Tick,
+    }
+
+    /// Corrupt the simulation of a peer
+    #[derive(Resource)]
+    struct Corrupt;
+
+    #[derive(Resource, Default)]
+    struct Desyncs(Vec<PeerDesyncEvent>);
+
+    #[derive(Resource, Default)]
+    struct SessionDesyncs(Vec<SessionDesyncEvent>);
+
+    fn buffer_input(
+        tick_manager: Res<TickManager>,
+        input: Option<Res<LocalInput>>,
+        mut session: ResMut<P2PSession<MyInput>>,
+    ) {
+        let Some(input) = input else {
+            return;
+        };
+        let tick = tick_manager.tick();
+        if tick >= input.start_tick {
+            session.add_input(MyInput(input.value), tick);
+        }
+    }
+
+    fn simulate(
+        mut state: ResMut<State>,
+        corrupt: Option<Res<Corrupt>>,
+        mut events: EventReader<PeerInputEvent<MyInput>>,
+    ) {
+        for event in events.read() {
+            if let Some(input) = event.input() {
+                state.0 = state.0.wrapping_mul(31).wrapping_add(input.0 as i64);
+            }
+        }
+        if corrupt.is_some() {
+            state.0 += 1;
+        }
+    }
+
+    fn checksum(world: &World) -> u64 {
+        world.resource::<State>().0 as u64
+    }
+
+    fn collect_desyncs(mut events: EventReader<PeerDesyncEvent>, mut desyncs: ResMut<Desyncs>) {
+        desyncs.0.extend(events.read().copied());
+    }
+
+    fn collect_session_desyncs(
+        mut events: EventReader<SessionDesyncEvent>,
+        mut desyncs: ResMut<SessionDesyncs>,
+    ) {
+        desyncs.0.extend(events.read().copied());
+    }
+
+    fn setup() -> MultiBevyStepper {
+        let mut stepper = MultiBevyStepper::new(
+            SharedConfig {
+                tick: TickConfig::new(Duration::from_millis(10)),
+                ..default()
+            },
+            SyncConfig::default().speedup_factor(1.0),
+            PredictionConfig::default(),
+            InterpolationConfig::default(),
+            Duration::from_millis(10),
+        );
+        let config = P2PConfig::default()
+            .with_checksum_interval(10)
+            .with_checksum(checksum);
+        stepper
+            .server_app
+            .add_plugins(P2PPlugin::<MyInput>::new(config));
+        for app in [&mut stepper.client_app_1, &mut stepper.client_app_2] {
+            app.add_plugins(P2PPlugin::<MyInput>::new(config));
+            app.init_resource::<State>();
+            app.init_resource::<Desyncs>();
+            app.init_resource::<SessionDesyncs>();
+            app.add_resource_rollback::<State>();
+            app.add_systems(
+                FixedPreUpdate,
+                buffer_input.in_set(InputSystemSet::BufferInputs),
+            );
+            app.add_systems(FixedUpdate, simulate);
+            app.add_systems(Update, (collect_desyncs, collect_session_desyncs));
+        }
+        stepper.init();
+        // start the session at the same tick on both peers
+        let start_tick = stepper
+            .client_app_1
+            .world()
+            .resource::<TickManager>()
+            .tick()
+            + 20;
+        stepper.client_app_1.insert_resource(LocalInput {
+            value: 1,
+            start_tick,
+        });
+        stepper.client_app_2.insert_resource(LocalInput {
+            value: 2,
+            start_tick,
+        });
+        stepper
+    }
+
+    #[test]
+    fn test_p2p_session() {
+        let mut stepper = setup();
+        for _ in 0..80 {
+            stepper.frame_step();
+        }
+        // each peer received the inputs of the other peer, and rolled back the ticks where
+        // they were mispredicted: the checksums of both peers match
+        for app in [&stepper.client_app_1, &stepper.client_app_2] {
+            let session = app.world().resource::<P2PSession<MyInput>>();
+            assert_eq!(session.remote_peers().count(), 1);
+            assert!(session.last_verified_checksum_tick().is_some());
+            assert!(app.world().resource::<Desyncs>().0.is_empty());
+            assert_ne!(app.world().resource::<State>().0, 0);
+        }
+
+        // the simulation of peer 2 diverges
+        stepper.client_app_2.insert_resource(Corrupt);
+        for _ in 0..40 {
+            stepper.frame_step();
+        }
+        let desyncs = &stepper.client_app_1.world().resource::<Desyncs>().0;
+        assert!(!desyncs.is_empty());
+        assert_eq!(desyncs[0].peer, ClientId::Netcode(TEST_CLIENT_ID_2));
+        assert!(!stepper
+            .client_app_2
+            .world()
+            .resource::<Desyncs>()
+            .0
+            .is_empty());
+    }
+
+    fn input_message(start_tick: u16, end_tick: u16) -> InputMessage<MyInput> {
+        InputMessage {
+            end_tick: Tick(end_tick),
+            inputs: (start_tick..=end_tick)
+                .map(|tick| InputData::Input(MyInput(tick as i16)))
+                .collect(),
+        }
+    }
+
+    /// Only the ticks received without a gap are confirmed, the missing ticks stay predicted
+    #[test]
+    fn test_remote_peer_missing_inputs() {
+        let mut peer = RemotePeer::<MyInput>::default();
+        assert_eq!(peer.receive(input_message(1, 3), Tick(10)), Some(Tick(1)));
+        assert_eq!(peer.confirmed_tick, Some(Tick(3)));
+
+        // the inputs for ticks 4 and 5 are missing
+        assert_eq!(peer.receive(input_message(6, 8), Tick(10)), Some(Tick(6)));
+        assert_eq!(peer.confirmed_tick, Some(Tick(3)));
+        assert_eq!(peer.input(Tick(4)), Some(MyInput(3)));
+        assert_eq!(peer.input(Tick(7)), Some(MyInput(7)));
+        assert_eq!(peer.input(Tick(9)), Some(MyInput(8)));
+
+        // the missing inputs arrive late
+        assert_eq!(peer.receive(input_message(4, 5), Tick(10)), Some(Tick(4)));
+        assert_eq!(peer.confirmed_tick, Some(Tick(8)));
+        assert_eq!(peer.input(Tick(5)), Some(MyInput(5)));
+        assert_eq!(peer.receive(input_message(6, 8), Tick(10)), None);
+
+        // the inputs for ticks 9 and 10 are never received
+        peer.receive(input_message(11, 12), Tick(12));
+        assert_eq!(peer.skip_lost_inputs(Tick(8)), None);
+        assert_eq!(peer.skip_lost_inputs(Tick(10)), Some(Tick(9)));
+        assert_eq!(peer.confirmed_tick, Some(Tick(12)));
+        assert_eq!(peer.input(Tick(10)), Some(MyInput(8)));
+    }
+
+    /// The inputs that were already sent are not re-timed when the tick snaps during the session
+    #[test]
+    fn test_p2p_tick_snap() {
+        let mut stepper = setup();
+        for _ in 0..40 {
+            stepper.frame_step();
+        }
+        let session = stepper
+            .client_app_1
+            .world()
+            .resource::<P2PSession<MyInput>>();
+        let sent_tick = session.last_sent_input_tick.unwrap();
+        let sent_input = session.local_input(sent_tick);
+        assert!(sent_input.is_some());
+
+        stepper
+            .client_app_1
+            .world_mut()
+            .trigger(TickEvent::TickSnap {
+                old_tick: sent_tick,
+                new_tick: sent_tick - 5,
+            });
+        stepper.frame_step();
+        let session = stepper
+            .client_app_1
+            .world()
+            .resource::<P2PSession<MyInput>>();
+        assert_eq!(session.local_input(sent_tick), sent_input);
+        assert_eq!(
+            stepper.client_app_1.world().resource::<SessionDesyncs>().0,
+            vec![SessionDesyncEvent::TickSnap {
+                old_tick: sent_tick,
+                new_tick: sent_tick - 5,
+            }]
+        );
+    }
+
+    /// A mispredicted input that is older than the prediction history cannot be rolled back
+    #[test]
+    fn test_p2p_rollback_too_old() {
+        let mut stepper = setup();
+        // run long enough to fill the prediction history
+        for _ in 0..150 {
+            stepper.frame_step();
+        }
+        let current_tick = stepper
+            .client_app_1
+            .world()
+            .resource::<TickManager>()
+            .tick();
+        let peer = ClientId::Netcode(999);
+        let end_tick = current_tick - 150;
+        stepper
+            .client_app_1
+            .world_mut()
+            .send_event(crate::client::events::MessageEvent::new(
+                PeerInputMessage {
+                    peer,
+                    message: input_message(end_tick.0 - 2, end_tick.0),
+                },
+                (),
+            ));
+        stepper.frame_step();
+        assert_eq!(
+            stepper.client_app_1.world().resource::<SessionDesyncs>().0,
+            vec![SessionDesyncEvent::RollbackTooOld {
+                peer,
+                tick: end_tick - 2,
+            }]
+        );
+    }
+}
@@ -13,6 +13,7 @@ use crate::prelude::{
 };
 use crate::shared::config::SharedConfig;
 use crate::shared::replication::authority::AuthorityChange;
+use crate::shared::replication::checksum::ChecksumMessage;
 use crate::shared::replication::components::{Controlled, ShouldBeInterpolated};
 use crate::shared::tick_manager::TickManagerPlugin;
 use crate::shared::time_manager::TimePlugin;
@@ -156,8 +157,15 @@ impl Plugin for SharedPlugin {
 
         app.register_message::<AuthorityChange>(ChannelDirection::ServerToClient)
             .add_map_entities();
+        app.register_message::<ChecksumMessage>(ChannelDirection::ServerToClient);
 
         // check that the protocol was built correctly
-        app.world().resource::<ComponentRegistry>().check();
+        let channel_registry = app.world().resource::<ChannelRegistry>();
+        app.world()
+            .resource::<ComponentRegistry>()
+            .check(channel_registry);
+        app.world()
+            .resource::<MessageRegistry>()
+            .check(channel_registry);
     }
 }
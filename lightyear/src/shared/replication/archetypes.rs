@@ -95,6 +95,7 @@ pub(crate) struct ReplicatedComponent {
     pub(crate) delta_compression: bool,
     pub(crate) replicate_once: bool,
     pub(crate) override_target: Option<ComponentId>,
+    pub(crate) frequency: Option<ComponentId>,
     pub(crate) id: ComponentId,
     pub(crate) kind: ComponentKind,
     pub(crate) storage_type: StorageType,
@@ -188,6 +189,9 @@ impl<C: Component> ReplicatedArchetypes<C> {
                         .components()
                         .any(|c| c == replication_metadata.override_target_id)
                         .then_some(replication_metadata.override_target_id);
+                    let frequency = replication_metadata
+                        .frequency_id
+                        .filter(|id| archetype.components().any(|c| c == *id));
 
                     let disabled = archetype
                         .components()
@@ -199,6 +203,7 @@ impl<C: Component> ReplicatedArchetypes<C> {
                         delta_compression,
                         replicate_once,
                         override_target,
+                        frequency,
                         id: component,
                         kind,
                         storage_type,
@@ -0,0 +1,432 @@
+//! Desync detection with checksums of the replicated state
+//!
+//! A predicted component is usually compared with the server state one component at a time in `check_rollback`,
+//! and a mismatch only shows up as a rollback.
+//! When [`ReplicationConfig::checksum`](crate::prelude::ReplicationConfig::checksum) is set on the server,
+//! the server computes a checksum of the components registered with
+//! [`add_checksum`](crate::prelude::AppComponentExt::add_checksum) every [`ChecksumConfig::interval`] ticks,
+//! for each replicated entity. The checksum is the hash of the serialized component.
+//!
+//! The checksums of each [`ReplicationGroup`](crate::prelude::ReplicationGroup) are sent to the clients that
+//! predict the entities on the [`ChecksumChannel`](crate::channel::builder::ChecksumChannel).
+//! The clients compare them with the values stored in the prediction history for the same tick, and emit a
+//! [`DesyncDetected`] event for each mismatching entity and component.
+//!
+//! With [`ChecksumConfig::debug`], the server also sends the serialized component values, so that the
+//! client can log (and include in the event) both the predicted and the server values.
+//!
+//! Components that contain entities are hashed without entity mapping, so they will always be reported as desynced.
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::client::components::SyncComponent;
+use crate::prelude::{ComponentRegistry, Tick};
+use crate::protocol::component::{ComponentError, ComponentNetId};
+use crate::serialize::writer::Writer;
+use crate::shared::replication::components::ReplicationGroupId;
+
+/// Config to specify how the server sends the state checksums
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct ChecksumConfig {
+    /// Number of ticks between two checksums
+    pub interval: u16,
+    /// If true, the serialized component values are sent along with the checksums, so that the client
+    /// can dump both values when a desync is detected
+    pub debug: bool,
+}
+
+impl Default for ChecksumConfig {
+    fn default() -> Self {
+        Self {
+            interval: 10,
+            debug: false,
+        }
+    }
+}
+
+impl ChecksumConfig {
+    pub fn with_interval(mut self, interval: u16) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+}
+
+/// Checksum of a component of an entity
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ComponentChecksum {
+    /// The entity in the server's world
+    pub entity: Entity,
+    pub component: ComponentNetId,
+    pub checksum: u64,
+    /// The serialized component, only sent in debug mode
+    pub value: Option<Vec<u8>>,
+}
+
+/// Message containing the checksums of the entities of a replication group at a given tick
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChecksumMessage {
+    pub tick: Tick,
+    pub group: ReplicationGroupId,
+    pub checksums: Vec<ComponentChecksum>,
+}
+
+/// Event emitted on the client when the predicted value of a component at a given tick
+/// does not match the server's value
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct DesyncDetected {
+    pub tick: Tick,
+    /// The predicted entity
+    pub entity: Entity,
+    /// The name of the component
+    pub component: &'static str,
+    pub server_checksum: u64,
+    pub client_checksum: u64,
+    /// Debug representation of the predicted value and of the server value, if
+    /// [`ChecksumConfig::debug`] is enabled on the server
+    pub values: Option<(String, String)>,
+}
+
+/// Serialize the component and return its hash along with the serialized bytes
+fn checksum<C: Component + Clone>(
+    registry: &ComponentRegistry,
+    component: &C,
+) -> Result<(u64, Vec<u8>), ComponentError> {
+    let mut writer = Writer::default();
+    registry.serialize(&mut component.clone(), &mut writer, None)?;
+    let bytes = writer.to_bytes().to_vec();
+    Ok((seahash::hash(&bytes), bytes))
+}
+
+pub(crate) mod send {
+    use bevy::utils::HashMap;
+    use tracing::{error, trace};
+
+    use super::*;
+    use crate::channel::builder::ChecksumChannel;
+    use crate::prelude::server::{ServerConfig, SyncTarget};
+    use crate::prelude::{
+        ClientId, NetworkTarget, Replicating, ReplicationGroup, ReplicationTarget, TickManager,
+    };
+    use crate::server::connection::ConnectionManager;
+    use crate::server::replication::send::ServerFilter;
+    use crate::shared::sets::{InternalReplicationSet, ServerMarker};
+
+    /// Checksums computed during the fixed update, that haven't been sent yet
+    #[derive(Resource, Default, Debug)]
+    pub(crate) struct ChecksumBuffer {
+        checksums: HashMap<(Tick, ReplicationGroupId), Vec<ComponentChecksum>>,
+    }
+
+    pub(crate) fn add_checksum_send_systems<C: SyncComponent>(app: &mut App) {
+        if !app.world().contains_resource::<ChecksumBuffer>() {
+            app.init_resource::<ChecksumBuffer>();
+            app.add_systems(
+                PostUpdate,
+                send_checksums.in_set(InternalReplicationSet::<ServerMarker>::AfterBuffer),
+            );
+        }
+        app.add_systems(FixedPostUpdate, compute_checksums::<C>);
+    }
+
+    /// Compute the checksum of the component `C` for every replicated entity at the end of the tick
+    fn compute_checksums<C: SyncComponent>(
+        config: Res<ServerConfig>,
+        registry: Res<ComponentRegistry>,
+        tick_manager: Res<TickManager>,
+        mut buffer: ResMut<ChecksumBuffer>,
+        query: Query<(Entity, &C, &ReplicationGroup), (With<Replicating>, ServerFilter)>,
+    ) {
+        let Some(checksum_config) = config.replication.checksum else {
+            return;
+        };
+        let tick = tick_manager.tick();
+        if checksum_config.interval == 0 || tick.0 % checksum_config.interval != 0 {
+            return;
+        }
+        let Some(net_id) = registry.get_net_id::<C>() else {
+            return;
+        };
+        for (entity, component, group) in query.iter() {
+            let Ok((checksum, bytes)) = checksum(registry.as_ref(), component)
+                .inspect_err(|e| error!(?entity, "could not compute the checksum: {:?}", e))
+            else {
+                continue;
+            };
+            buffer
+                .checksums
+                .entry((tick, group.group_id(Some(entity))))
+                .or_default()
+                .push(ComponentChecksum {
+                    entity,
+                    component: net_id,
+                    checksum,
+                    value: checksum_config.debug.then_some(bytes),
+                });
+        }
+    }
+
+    /// Send the checksums of each replication group to the clients that predict its entities
+    fn send_checksums(
+        mut buffer: ResMut<ChecksumBuffer>,
+        mut connection_manager: ResMut<ConnectionManager>,
+        query: Query<(&ReplicationTarget, Option<&SyncTarget>)>,
+    ) {
+        if buffer.checksums.is_empty() {
+            return;
+        }
+        // local clients share the world of the server, so they cannot be desynced
+        let clients: Vec<ClientId> = connection_manager
+            .connected_clients()
+            .filter(|client_id| {
+                connection_manager
+                    .connection(*client_id)
+                    .is_ok_and(|c| !c.is_local_client())
+            })
+            .collect();
+        for ((tick, group), checksums) in buffer.checksums.drain() {
+            for client_id in clients.iter() {
+                let is_predicted = |entity: Entity| {
+                    query.get(entity).is_ok_and(|(target, sync_target)| {
+                        target.target.targets(client_id)
+                            && sync_target.is_some_and(|s| s.prediction.targets(client_id))
+                    })
+                };
+                let checksums: Vec<_> = checksums
+                    .iter()
+                    .filter(|c| is_predicted(c.entity))
+                    .cloned()
+                    .collect();
+                if checksums.is_empty() {
+                    continue;
+                }
+                trace!(?tick, ?group, ?client_id, "sending checksums");
+                if let Err(e) = connection_manager.send_message_to_target::<ChecksumChannel, _>(
+                    &mut ChecksumMessage {
+                        tick,
+                        group,
+                        checksums,
+                    },
+                    NetworkTarget::Single(*client_id),
+                ) {
+                    error!("could not send checksums: {:?}", e);
+                }
+            }
+        }
+    }
+}
+
+pub(crate) mod receive {
+    use std::fmt::Debug;
+
+    use tracing::{debug, error, trace};
+
+    use super::*;
+    use crate::client::components::Confirmed;
+    use crate::client::connection::ConnectionManager;
+    use crate::client::events::MessageEvent;
+    use crate::client::prediction::plugin::PredictionSet;
+    use crate::client::prediction::predicted_history::{ComponentState, PredictionHistory};
+    use crate::prelude::TickManager;
+    use crate::serialize::reader::Reader;
+    use crate::shared::replication::entity_map::ReceiveEntityMap;
+
+    pub(crate) fn add_checksum_receive_systems<C: SyncComponent + Debug>(app: &mut App) {
+        if !app.world().contains_resource::<Events<DesyncDetected>>() {
+            app.add_event::<DesyncDetected>();
+        }
+        app.add_systems(
+            PreUpdate,
+            check_checksums::<C>.in_set(PredictionSet::CheckDesync),
+        );
+    }
+
+    /// Compare the checksums received from the server with the predicted history of the component `C`
+    fn check_checksums<C: SyncComponent + Debug>(
+        registry: Res<ComponentRegistry>,
+        tick_manager: Res<TickManager>,
+        connection: Res<ConnectionManager>,
+        mut messages: EventReader<MessageEvent<ChecksumMessage>>,
+        confirmed_query: Query<&Confirmed>,
+        predicted_query: Query<&PredictionHistory<C>>,
+        mut desync_events: EventWriter<DesyncDetected>,
+    ) {
+        let Some(net_id) = registry.get_net_id::<C>() else {
+            return;
+        };
+        let kind = std::any::type_name::<C>();
+        let current_tick = tick_manager.tick();
+        for message in messages.read() {
+            let tick = message.message.tick;
+            if tick > current_tick {
+                // we haven't predicted this tick yet
+                continue;
+            }
+            for entry in message
+                .message
+                .checksums
+                .iter()
+                .filter(|c| c.component == net_id)
+            {
+                let Some(predicted) = connection
+                    .replication_receiver
+                    .remote_entity_map
+                    .get_local(entry.entity)
+                    .and_then(|confirmed| confirmed_query.get(confirmed).ok())
+                    .and_then(|confirmed| confirmed.predicted)
+                else {
+                    continue;
+                };
+                let Ok(history) = predicted_query.get(predicted) else {
+                    continue;
+                };
+                let Some(ComponentState::Updated(value)) = history.peek_at_tick(tick) else {
+                    trace!(
+                        ?predicted,
+                        ?tick,
+                        ?kind,
+                        "no predicted value to compare with the checksum"
+                    );
+                    continue;
+                };
+                let Ok((client_checksum, _)) = checksum(registry.as_ref(), value)
+                    .inspect_err(|e| error!(?predicted, "could not compute the checksum: {:?}", e))
+                else {
+                    continue;
+                };
+                if client_checksum == entry.checksum {
+                    continue;
+                }
+                let values = entry.value.as_ref().map(|bytes| {
+                    let mut reader = Reader::from(bytes.clone());
+                    let server_value = registry
+                        .deserialize::<C>(&mut reader, &mut ReceiveEntityMap::default())
+                        .map_or_else(|e| format!("{:?}", e), |c| format!("{:?}", c));
+                    (format!("{:?}", value), server_value)
+                });
+                match &values {
+                    Some((predicted_value, server_value)) => error!(
+                        ?predicted,
+                        ?tick,
+                        ?kind,
+                        predicted_value,
+                        server_value,
+                        "desync detected"
+                    ),
+                    None => debug!(?predicted, ?tick, ?kind, "desync detected"),
+                }
+                desync_events.send(DesyncDetected {
+                    tick,
+                    entity: predicted,
+                    component: kind,
+                    server_checksum: entry.checksum,
+                    client_checksum,
+                    values,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::client::Confirmed;
+    use crate::prelude::server::{Replicate, ServerConfig, SyncTarget};
+    use crate::prelude::{AppComponentExt, NetworkTarget};
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::BevyStepper;
+
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct Desyncs(Vec<DesyncDetected>);
+
+    fn collect_desyncs(mut events: EventReader<DesyncDetected>, mut desyncs: ResMut<Desyncs>) {
+        desyncs.0.extend(events.read().cloned());
+    }
+
+    #[test]
+    fn test_desync_detected() {
+        let mut stepper = BevyStepper::default();
+        stepper.server_app.add_checksum::<ComponentSyncModeFull>();
+        stepper.client_app.add_checksum::<ComponentSyncModeFull>();
+        stepper.client_app.init_resource::<Desyncs>();
+        stepper.client_app.add_systems(Update, collect_desyncs);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .replication
+            .checksum = Some(ChecksumConfig::default().with_interval(2).with_debug(true));
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                ComponentSyncModeFull(1.0),
+                Replicate {
+                    sync: SyncTarget {
+                        prediction: NetworkTarget::All,
+                        ..default()
+                    },
+                    ..default()
+                },
+            ))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let confirmed = stepper
+            .client_app
+            .world()
+            .resource::<crate::prelude::client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        let predicted = stepper
+            .client_app
+            .world()
+            .get::<Confirmed>(confirmed)
+            .unwrap()
+            .predicted
+            .expect("entity is not predicted");
+        // the predicted state matches the server state
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<Desyncs>()
+            .0
+            .is_empty());
+
+        // the client simulation diverges
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(predicted)
+            .insert(ComponentSyncModeFull(5.0));
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let desyncs = &stepper.client_app.world().resource::<Desyncs>().0;
+        assert!(!desyncs.is_empty());
+        let desync = &desyncs[0];
+        assert_eq!(desync.entity, predicted);
+        assert_eq!(
+            desync.component,
+            std::any::type_name::<ComponentSyncModeFull>()
+        );
+        assert_ne!(desync.client_checksum, desync.server_checksum);
+        assert_eq!(
+            desync.values,
+            Some((
+                "ComponentSyncModeFull(5.0)".to_string(),
+                "ComponentSyncModeFull(1.0)".to_string()
+            ))
+        );
+    }
+}
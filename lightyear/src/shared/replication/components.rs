@@ -187,6 +187,46 @@ impl<C> OverrideTargetComponent<C> {
     }
 }
 
+/// This component lets you send the updates of a specific component less often than the rest of the entity.
+///
+/// For example, the position of a character could be sent every `send_interval`, but its health only every 200ms.
+/// The inserts and removals of the component are not affected.
+///
+/// The updates are still part of the [`ReplicationGroup`] of the entity: when the component is sent, it is sent
+/// in the same message as the other components of the group that were updated on the same tick.
+///
+/// This is only used for server to client replication.
+#[derive(Component, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+// the layout must not depend on C, because the replication systems read the component as a ReplicationFrequency<()>
+#[repr(C)]
+pub struct ReplicationFrequency<C> {
+    /// Timer that controls when the updates of the component are sent.
+    /// It is INCORRECT to set it to be more frequent than the sender's send_interval.
+    timer: Timer,
+    /// True if the updates of the component should be sent in the next replication message
+    pub(crate) should_send: bool,
+    _marker: std::marker::PhantomData<C>,
+}
+
+impl<C> ReplicationFrequency<C> {
+    pub fn new(send_frequency: bevy::utils::Duration) -> Self {
+        Self {
+            timer: Timer::new(send_frequency, TimerMode::Repeating),
+            should_send: true,
+            _marker: Default::default(),
+        }
+    }
+
+    /// Tick the timer of the component; the updates should be sent when the timer finishes
+    pub(crate) fn tick(&mut self, delta: bevy::utils::Duration) {
+        self.timer.tick(delta);
+        if self.timer.finished() {
+            self.should_send = true;
+        }
+    }
+}
+
 #[derive(Debug, Default, Copy, Clone, PartialEq, Reflect)]
 pub enum ReplicationGroupIdBuilder {
     // the group id is the entity id
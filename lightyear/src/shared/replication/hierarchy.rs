@@ -1,6 +1,9 @@
 //! This module is responsible for making sure that parent-children hierarchies are replicated correctly.
+use crate::client::components::Confirmed;
+use crate::client::interpolation::Interpolated;
+use crate::client::prediction::Predicted;
 use crate::client::replication::send::ReplicateToServer;
-use bevy::ecs::entity::MapEntities;
+use bevy::ecs::entity::{EntityHashSet, MapEntities};
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -269,16 +272,107 @@ impl<R: ReplicationPeer> Plugin for HierarchyReceivePlugin<R> {
     }
 }
 
+/// A local copy of a [`Confirmed`] entity: either the [`Predicted`] or the [`Interpolated`] entity
+pub(crate) trait ConfirmedCopy: Component {
+    /// The confirmed entity that this entity is a copy of
+    fn confirmed_entity(&self) -> Option<Entity>;
+
+    /// The copy of this kind for the confirmed entity
+    fn copy_of(confirmed: &Confirmed) -> Option<Entity>;
+}
+
+impl ConfirmedCopy for Predicted {
+    fn confirmed_entity(&self) -> Option<Entity> {
+        self.confirmed_entity
+    }
+
+    fn copy_of(confirmed: &Confirmed) -> Option<Entity> {
+        confirmed.predicted
+    }
+}
+
+impl ConfirmedCopy for Interpolated {
+    fn confirmed_entity(&self) -> Option<Entity> {
+        Some(self.confirmed_entity)
+    }
+
+    fn copy_of(confirmed: &Confirmed) -> Option<Entity> {
+        confirmed.interpolated
+    }
+}
+
+/// Mirror the hierarchy of the [`Confirmed`] entities on their copies (Predicted or Interpolated):
+/// if the confirmed entity has a confirmed parent, the copy of the entity will have the copy of the parent as parent.
+///
+/// We update the hierarchy when the `Parent` of a confirmed entity changes, or when a new copy gets spawned
+/// (the copy of the parent and the copy of the child are not necessarily spawned in the same frame).
+///
+/// This only runs on the client
+#[allow(clippy::type_complexity)]
+pub(crate) fn sync_copy_hierarchy<M: ConfirmedCopy>(
+    mut commands: Commands,
+    mut removed_parents: RemovedComponents<Parent>,
+    changed_parents: Query<Entity, (With<Confirmed>, Changed<Parent>)>,
+    new_copies: Query<&M, Added<M>>,
+    confirmed_query: Query<(&Confirmed, Option<&Parent>, Option<&Children>)>,
+    copy_query: Query<Option<&Parent>, With<M>>,
+) {
+    let mut to_update = EntityHashSet::default();
+    to_update.extend(changed_parents.iter());
+    to_update.extend(removed_parents.read());
+    for copy in new_copies.iter() {
+        let Some(confirmed_entity) = copy.confirmed_entity() else {
+            continue;
+        };
+        to_update.insert(confirmed_entity);
+        // the children might have been spawned before their parent's copy
+        if let Ok((_, _, Some(children))) = confirmed_query.get(confirmed_entity) {
+            to_update.extend(children.iter().copied());
+        }
+    }
+    for confirmed_entity in to_update {
+        let Ok((confirmed, parent, _)) = confirmed_query.get(confirmed_entity) else {
+            continue;
+        };
+        let Some(copy) = M::copy_of(confirmed) else {
+            continue;
+        };
+        let Ok(copy_parent) = copy_query.get(copy) else {
+            continue;
+        };
+        let new_parent = parent
+            .and_then(|parent| confirmed_query.get(parent.get()).ok())
+            .and_then(|(parent_confirmed, _, _)| M::copy_of(parent_confirmed));
+        match new_parent {
+            Some(new_parent) => {
+                if copy_parent.map(|p| p.get()) != Some(new_parent) {
+                    trace!(?copy, ?new_parent, "update parent of confirmed copy");
+                    commands.entity(copy).set_parent(new_parent);
+                }
+            }
+            // only remove the parent if it was set by us; the user might have added a local parent
+            None => {
+                if copy_parent.is_some_and(|p| copy_query.contains(p.get())) {
+                    trace!(?copy, "remove parent of confirmed copy");
+                    commands.entity(copy).remove_parent();
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::ops::Deref;
 
-    use bevy::hierarchy::{BuildWorldChildren, Children, Parent};
+    use bevy::hierarchy::{BuildWorldChildren, Children, DespawnRecursiveExt, Parent};
     use bevy::prelude::{default, Entity, With};
 
+    use crate::client::components::Confirmed;
+    use crate::client::prediction::diagnostics::PredictionMetrics;
     use crate::prelude::client;
-    use crate::prelude::server::Replicate;
-    use crate::prelude::ReplicationGroup;
+    use crate::prelude::server::{Replicate, SyncTarget};
+    use crate::prelude::{NetworkTarget, ReplicationGroup};
     use crate::shared::replication::components::ReplicateHierarchy;
     use crate::shared::replication::hierarchy::ParentSync;
     use crate::tests::protocol::*;
@@ -518,6 +612,183 @@ mod tests {
         );
     }
 
+    /// Check that the predicted and interpolated copies of a replicated hierarchy have the same hierarchy,
+    /// and that it survives rollbacks and despawns
+    #[test]
+    fn test_copy_hierarchy() {
+        let (mut stepper, parent, child, _grandchild) = setup_hierarchy();
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(parent)
+            .insert(Replicate {
+                sync: SyncTarget {
+                    prediction: NetworkTarget::All,
+                    interpolation: NetworkTarget::All,
+                },
+                ..default()
+            });
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let copies = |stepper: &BevyStepper, server_entity: Entity| {
+            let confirmed = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated");
+            let confirmed = stepper
+                .client_app
+                .world()
+                .get::<Confirmed>(confirmed)
+                .unwrap();
+            (
+                confirmed.predicted.unwrap(),
+                confirmed.interpolated.unwrap(),
+            )
+        };
+        let (predicted_parent, interpolated_parent) = copies(&stepper, parent);
+        let (predicted_child, interpolated_child) = copies(&stepper, child);
+        let parent_of = |stepper: &BevyStepper, entity: Entity| {
+            stepper
+                .client_app
+                .world()
+                .get::<Parent>(entity)
+                .map(|p| p.get())
+        };
+        assert_eq!(parent_of(&stepper, predicted_child), Some(predicted_parent));
+        assert_eq!(
+            parent_of(&stepper, interpolated_child),
+            Some(interpolated_parent)
+        );
+
+        // trigger a rollback: the hierarchy must not be affected
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(parent)
+            .insert(ComponentSyncModeFull(1.0));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert!(
+            stepper
+                .client_app
+                .world()
+                .resource::<PredictionMetrics>()
+                .rollbacks
+                > 0
+        );
+        assert_eq!(parent_of(&stepper, predicted_child), Some(predicted_parent));
+
+        // despawn the child: it gets removed from the predicted and interpolated parents
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(child)
+            .despawn_recursive();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert!(stepper
+            .client_app
+            .world()
+            .get_entity(predicted_child)
+            .is_none());
+        assert!(stepper
+            .client_app
+            .world()
+            .get_entity(interpolated_child)
+            .is_none());
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<Children>(predicted_parent)
+            .map_or(true, |c| c.is_empty()));
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<Children>(interpolated_parent)
+            .map_or(true, |c| c.is_empty()));
+    }
+
+    /// Despawning the confirmed parent of a child that is not despawned itself keeps the
+    /// predicted and interpolated children alive, but detached from the despawned parent copies
+    #[test]
+    fn test_copy_hierarchy_despawn_parent() {
+        let (mut stepper, parent, child, _grandchild) = setup_hierarchy();
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(parent)
+            .insert(Replicate {
+                sync: SyncTarget {
+                    prediction: NetworkTarget::All,
+                    interpolation: NetworkTarget::All,
+                },
+                ..default()
+            });
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let local = |stepper: &BevyStepper, server_entity: Entity| {
+            stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated")
+        };
+        let confirmed_parent = local(&stepper, parent);
+        let confirmed_child = local(&stepper, child);
+        let copies = |stepper: &BevyStepper, confirmed: Entity| {
+            let confirmed = stepper
+                .client_app
+                .world()
+                .get::<Confirmed>(confirmed)
+                .unwrap();
+            (
+                confirmed.predicted.unwrap(),
+                confirmed.interpolated.unwrap(),
+            )
+        };
+        let (predicted_parent, interpolated_parent) = copies(&stepper, confirmed_parent);
+        let (predicted_child, interpolated_child) = copies(&stepper, confirmed_child);
+        let parent_of = |stepper: &BevyStepper, entity: Entity| {
+            stepper
+                .client_app
+                .world()
+                .get::<Parent>(entity)
+                .map(|p| p.get())
+        };
+        assert_eq!(parent_of(&stepper, predicted_child), Some(predicted_parent));
+        assert_eq!(
+            parent_of(&stepper, interpolated_child),
+            Some(interpolated_parent)
+        );
+
+        // despawning the confirmed parent despawns its predicted and interpolated copies,
+        // which must first detach their children so that they are not despawned recursively
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed_parent)
+            .despawn();
+        stepper.frame_step();
+        let world = stepper.client_app.world();
+        assert!(world.get_entity(predicted_parent).is_none());
+        assert!(world.get_entity(interpolated_parent).is_none());
+        assert!(world.get_entity(predicted_child).is_some());
+        assert!(world.get_entity(interpolated_child).is_some());
+        assert!(world.get::<Parent>(predicted_child).is_none());
+        assert!(world.get::<Parent>(interpolated_child).is_none());
+    }
+
     #[test]
     fn test_remove_child() {
         let mut stepper = BevyStepper::default();
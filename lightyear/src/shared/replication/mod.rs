@@ -23,6 +23,7 @@ use crate::shared::events::connection::{
 };
 use crate::shared::replication::components::ReplicationGroupId;
 
+pub mod checksum;
 pub mod components;
 
 pub(crate) mod archetypes;
@@ -35,9 +36,12 @@ pub mod network_target;
 pub(crate) mod plugin;
 pub(crate) mod prespawn;
 pub(crate) mod receive;
+pub mod reflect;
 pub(crate) mod resources;
 pub(crate) mod send;
+pub mod snapshot;
 pub(crate) mod systems;
+pub mod triggers;
 
 /// Serialize Entity as two varints for the index and generation (because they will probably be low).
 /// Revisit this when relations comes out
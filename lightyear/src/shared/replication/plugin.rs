@@ -1,6 +1,7 @@
 //! This module contains the `ReplicationReceivePlugin` and `ReplicationSendPlugin` plugins, which control
 //! the replication of entities and resources.
 //!
+use crate::shared::replication::checksum::ChecksumConfig;
 use crate::shared::replication::hierarchy::{HierarchyReceivePlugin, HierarchySendPlugin};
 use crate::shared::replication::resources::{
     receive::ResourceReceivePlugin, send::ResourceSendPlugin,
@@ -20,9 +21,14 @@ pub struct ReplicationConfig {
     ///
     /// Set to `Duration::default()` to send updates every frame.
     pub send_interval: Duration,
+    /// If set, the server sends checksums of the components registered with
+    /// [`add_checksum`](crate::prelude::AppComponentExt::add_checksum) so that clients can detect desyncs.
+    ///
+    /// This is only used for server to client replication. See [`checksum`](crate::shared::replication::checksum).
+    pub checksum: Option<ChecksumConfig>,
 }
 
-#[derive(Clone, Copy, Debug, Reflect)]
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
 pub enum SendUpdatesMode {
     /// We send all the updates that happened since the last tick when we received an ACK from the remote
     ///
@@ -40,6 +46,16 @@ pub enum SendUpdatesMode {
     ///
     /// If we receive a NACK (i.e. the packet got lost), we will send the updates since the last ACK.
     SinceLastSend,
+    /// Instead of sending actions and updates per [`ReplicationGroup`](crate::prelude::ReplicationGroup),
+    /// we send every client a snapshot of all the entities replicated to it, delta-encoded against the last
+    /// snapshot that the client acked. Spawns, despawns and component removals are part of the snapshot.
+    ///
+    /// This is only used for server to client replication. See [`snapshot`](crate::shared::replication::snapshot).
+    ///
+    /// Every snapshot contains all the replicated entities, so the [`ReplicationFrequency`](crate::prelude::ReplicationFrequency)
+    /// of the components, the priority of the replication groups and the
+    /// [`ReplicationPriorityFn`](crate::server::priority::ReplicationPriorityFn) are ignored in this mode.
+    Snapshot,
 }
 
 impl Default for ReplicationConfig {
@@ -47,6 +63,7 @@ impl Default for ReplicationConfig {
         Self {
             send_updates_mode: SendUpdatesMode::SinceLastAck,
             send_interval: Duration::default(),
+            checksum: None,
         }
     }
 }
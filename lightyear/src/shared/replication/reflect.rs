@@ -0,0 +1,138 @@
+//! Replication settings for the components registered with
+//! [`register_component_reflect`](crate::prelude::AppComponentExt::register_component_reflect).
+//!
+//! The marker components [`DisabledComponent<C>`], [`ReplicateOnceComponent<C>`] and [`OverrideTargetComponent<C>`]
+//! are generic over the component type, so they cannot be named for a component that is only known through reflection.
+//! Instead, [`ReflectReplicationCommandsExt`] inserts the equivalent markers from the [`TypeId`] of the component.
+//!
+//! ```rust,ignore
+//! let type_id = registration.type_id();
+//! commands
+//!     .entity(entity)
+//!     .override_component_target(type_id, NetworkTarget::Single(client_id));
+//! ```
+use std::any::TypeId;
+
+use bevy::ecs::component::ComponentId;
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::{Entity, World};
+use bevy::ptr::OwningPtr;
+use tracing::error;
+
+use crate::prelude::{
+    ComponentRegistry, DisabledComponent, NetworkTarget, OverrideTargetComponent,
+    ReplicateOnceComponent,
+};
+use crate::protocol::component::{ComponentKind, ReplicationMetadata};
+
+/// Extension trait to control the replication of a component registered with reflection via [`EntityCommands`]
+pub trait ReflectReplicationCommandsExt {
+    /// Do not replicate the component (equivalent of inserting [`DisabledComponent<C>`])
+    fn disable_component_replication(&mut self, type_id: TypeId);
+
+    /// Only replicate the inserts and removals of the component, not the updates
+    /// (equivalent of inserting [`ReplicateOnceComponent<C>`])
+    fn replicate_component_once(&mut self, type_id: TypeId);
+
+    /// Replicate the component to a different target than the entity
+    /// (equivalent of inserting [`OverrideTargetComponent<C>`])
+    fn override_component_target(&mut self, type_id: TypeId, target: NetworkTarget);
+
+    /// Remove all the replication markers of the component: it is replicated like the rest of the entity
+    fn reset_component_replication(&mut self, type_id: TypeId);
+}
+
+/// Replication metadata of a component registered with reflection
+fn reflect_metadata(world: &World, type_id: TypeId) -> Option<ReplicationMetadata> {
+    let registry = world.resource::<ComponentRegistry>();
+    let kind = ComponentKind::from(type_id);
+    if !registry.is_reflect(&kind) {
+        error!(
+            ?type_id,
+            "The component was not registered with `register_component_reflect`"
+        );
+        return None;
+    }
+    registry.replication_map.get(&kind).cloned()
+}
+
+/// SAFETY: the `component_id` must have been created from the descriptor of `T`
+unsafe fn insert_marker<T>(
+    world: &mut World,
+    entity: Entity,
+    component_id: ComponentId,
+    marker: T,
+) {
+    if let Some(mut entity_mut) = world.get_entity_mut(entity) {
+        OwningPtr::make(marker, |ptr| {
+            entity_mut.insert_by_id(component_id, ptr);
+        });
+    }
+}
+
+impl ReflectReplicationCommandsExt for EntityCommands<'_> {
+    fn disable_component_replication(&mut self, type_id: TypeId) {
+        self.add(move |entity: Entity, world: &mut World| {
+            let Some(metadata) = reflect_metadata(world, type_id) else {
+                return;
+            };
+            // SAFETY: the disabled_id of a component registered with reflection is created from DisabledComponent<()>
+            unsafe {
+                insert_marker(
+                    world,
+                    entity,
+                    metadata.disabled_id,
+                    DisabledComponent::<()>::default(),
+                );
+            }
+        });
+    }
+
+    fn replicate_component_once(&mut self, type_id: TypeId) {
+        self.add(move |entity: Entity, world: &mut World| {
+            let Some(metadata) = reflect_metadata(world, type_id) else {
+                return;
+            };
+            // SAFETY: the replicate_once_id of a component registered with reflection is created from ReplicateOnceComponent<()>
+            unsafe {
+                insert_marker(
+                    world,
+                    entity,
+                    metadata.replicate_once_id,
+                    ReplicateOnceComponent::<()>::default(),
+                );
+            }
+        });
+    }
+
+    fn override_component_target(&mut self, type_id: TypeId, target: NetworkTarget) {
+        self.add(move |entity: Entity, world: &mut World| {
+            let Some(metadata) = reflect_metadata(world, type_id) else {
+                return;
+            };
+            // SAFETY: the override_target_id of a component registered with reflection is created from OverrideTargetComponent<()>
+            unsafe {
+                insert_marker(
+                    world,
+                    entity,
+                    metadata.override_target_id,
+                    OverrideTargetComponent::<()>::new(target),
+                );
+            }
+        });
+    }
+
+    fn reset_component_replication(&mut self, type_id: TypeId) {
+        self.add(move |entity: Entity, world: &mut World| {
+            let Some(metadata) = reflect_metadata(world, type_id) else {
+                return;
+            };
+            if let Some(mut entity_mut) = world.get_entity_mut(entity) {
+                entity_mut
+                    .remove_by_id(metadata.disabled_id)
+                    .remove_by_id(metadata.replicate_once_id)
+                    .remove_by_id(metadata.override_target_id);
+            }
+        });
+    }
+}
@@ -47,6 +47,9 @@ pub(crate) struct UpdateMessageMetadata {
     bevy_tick: BevyTick,
     /// The tick at which we buffered the message
     tick: Tick,
+    /// The components throttled by a [`ReplicationFrequency`](crate::prelude::ReplicationFrequency)
+    /// whose updates are included in the message
+    throttled_updates: Vec<(Entity, ComponentKind)>,
 }
 
 #[derive(Debug)]
@@ -73,6 +76,13 @@ pub(crate) struct ReplicationSender {
     /// We update the `send_tick` only when the message was actually sent.
     pub message_send_receiver: Receiver<MessageId>,
 
+    /// For the components throttled by a [`ReplicationFrequency`](crate::prelude::ReplicationFrequency),
+    /// the BevyTick of the last acked update message that contained the component.
+    ///
+    /// The group's `send_tick` can move past the change tick of a throttled component while its updates
+    /// are not sent, so we use this tick instead to know if the remote has received the latest changes.
+    pub(crate) throttled_ack_ticks: EntityHashMap<Entity, HashMap<ComponentKind, BevyTick>>,
+
     replication_config: ReplicationConfig,
     bandwidth_cap_enabled: bool,
 }
@@ -94,6 +104,7 @@ impl ReplicationSender {
             group_with_updates: EntityHashSet::default(),
             // pending_unique_components: EntityHashMap::default(),
             group_channels: Default::default(),
+            throttled_ack_ticks: EntityHashMap::default(),
             replication_config,
             // PRIORITY
             message_send_receiver,
@@ -112,12 +123,18 @@ impl ReplicationSender {
         bevy_tick: BevyTick,
         tick: Tick,
     ) {
+        let throttled_updates = self
+            .group_channels
+            .get_mut(&group_id)
+            .map(|channel| std::mem::take(&mut channel.pending_throttled_updates))
+            .unwrap_or_default();
         self.updates_message_id_to_group_id.insert(
             message_id,
             UpdateMessageMetadata {
                 group_id,
                 bevy_tick,
                 tick,
+                throttled_updates,
             },
         );
         // If we don't have a bandwidth cap, buffering a message is equivalent to sending it
@@ -135,7 +152,7 @@ impl ReplicationSender {
         self.group_channels.get(&group_id).and_then(|channel| {
             match self.replication_config.send_updates_mode {
                 SendUpdatesMode::SinceLastSend => channel.send_tick,
-                SendUpdatesMode::SinceLastAck => channel.ack_bevy_tick,
+                SendUpdatesMode::SinceLastAck | SendUpdatesMode::Snapshot => channel.ack_bevy_tick,
             }
         })
     }
@@ -237,8 +254,17 @@ impl ReplicationSender {
                 group_id,
                 bevy_tick,
                 tick,
+                throttled_updates,
             }) = self.updates_message_id_to_group_id.remove(&message_id)
             {
+                // the remote received the changes of the throttled components included in the message
+                // (if an older message is acked after a newer one, we will only re-send the component)
+                for (entity, kind) in throttled_updates {
+                    self.throttled_ack_ticks
+                        .entry(entity)
+                        .or_default()
+                        .insert(kind, bevy_tick);
+                }
                 if let Some(channel) = self.group_channels.get_mut(&group_id) {
                     // update the ack tick for the channel
                     debug!(?group_id, ?bevy_tick, ?tick, "Update channel ack_tick");
@@ -297,6 +323,21 @@ impl ReplicationSender {
             .base_priority = priority;
     }
 
+    /// Update the dynamic priority of the groups, computed by the
+    /// [`ReplicationPriorityFn`](crate::server::priority::ReplicationPriorityFn).
+    ///
+    /// Groups that are not present in `priorities` are reset to a dynamic priority of 1.0
+    pub(crate) fn update_dynamic_priority(
+        &mut self,
+        priorities: &HashMap<ReplicationGroupId, f32>,
+    ) {
+        self.group_channels
+            .iter_mut()
+            .for_each(|(group_id, channel)| {
+                channel.dynamic_priority = priorities.get(group_id).copied().unwrap_or(1.0);
+            });
+    }
+
     // TODO: how can I emit metrics here that contain the channel kind?
     //  use a OnceCell that gets set with the channel name mapping when the protocol is finalized?
     //  the other option is to have wrappers in Connection, but that's pretty ugly
@@ -336,6 +377,7 @@ impl ReplicationSender {
 
     #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     pub(crate) fn prepare_entity_despawn(&mut self, entity: Entity, group_id: ReplicationGroupId) {
+        self.throttled_ack_ticks.remove(&entity);
         self.group_with_actions.insert(group_id);
         self.group_channels
             .entry(group_id)
@@ -402,6 +444,39 @@ impl ReplicationSender {
             .push(raw_data);
     }
 
+    /// Returns true if the remote might not have received the latest changes of a component
+    /// throttled by a [`ReplicationFrequency`](crate::prelude::ReplicationFrequency),
+    /// i.e. if the component changed since the last acked update message that contained it.
+    pub(crate) fn throttled_update_pending(
+        &self,
+        entity: Entity,
+        kind: ComponentKind,
+        component_change_tick: BevyTick,
+        system_current_tick: BevyTick,
+    ) -> bool {
+        self.throttled_ack_ticks
+            .get(&entity)
+            .and_then(|ticks| ticks.get(&kind))
+            .map_or(true, |ack_tick| {
+                component_change_tick.is_newer_than(*ack_tick, system_current_tick)
+            })
+    }
+
+    /// Keep track that the update of a throttled component was buffered, so that we can
+    /// know when the remote received it
+    pub(crate) fn prepare_throttled_update(
+        &mut self,
+        entity: Entity,
+        group_id: ReplicationGroupId,
+        kind: ComponentKind,
+    ) {
+        self.group_channels
+            .entry(group_id)
+            .or_default()
+            .pending_throttled_updates
+            .push((entity, kind));
+    }
+
     /// Create a component update.
     #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     #[allow(clippy::too_many_arguments)]
@@ -528,7 +603,8 @@ impl ReplicationSender {
     //  Maybe we just want to run the accumulate priority system every frame.
     /// Before sending replication messages, we accumulate the priority for all replication groups.
     ///
-    /// (the priority starts at 0.0, and is accumulated for each group based on the base priority of the group)
+    /// (the priority starts at 0.0, and is accumulated for each group based on the base priority of the group,
+    /// multiplied by the dynamic priority of the group for this connection)
     pub(crate) fn accumulate_priority(&mut self, time_manager: &TimeManager) {
         // let priority_multiplier = if self.replication_config.send_interval == Duration::default() {
         //     1.0
@@ -539,12 +615,13 @@ impl ReplicationSender {
         let priority_multiplier = 1.0;
         self.group_channels.values_mut().for_each(|channel| {
             trace!(
-                "in accumulate priority: accumulated={:?} base={:?} multiplier={:?}, send_interval={:?}, time_manager_delta={:?}",
-                channel.accumulated_priority, channel.base_priority, priority_multiplier,
+                "in accumulate priority: accumulated={:?} base={:?} dynamic={:?} multiplier={:?}, send_interval={:?}, time_manager_delta={:?}",
+                channel.accumulated_priority, channel.base_priority, channel.dynamic_priority, priority_multiplier,
                 self.replication_config.send_interval.as_nanos(),
                 time_manager.delta().as_nanos()
             );
-            channel.accumulated_priority += channel.base_priority * priority_multiplier;
+            channel.accumulated_priority +=
+                channel.base_priority * channel.dynamic_priority * priority_multiplier;
         });
     }
 
@@ -709,6 +786,7 @@ impl ReplicationSender {
                     group_id,
                     bevy_tick,
                     tick,
+                    throttled_updates: std::mem::take(&mut channel.pending_throttled_updates),
                 },
             );
             // If we don't have a bandwidth cap, buffering a message is equivalent to sending it
@@ -737,6 +815,9 @@ pub struct GroupChannel {
     /// to collect new replication messages
     pub pending_actions: EntityHashMap<Entity, EntityActions>,
     pub pending_updates: EntityHashMap<Entity, Vec<Bytes>>,
+    /// Components throttled by a [`ReplicationFrequency`](crate::prelude::ReplicationFrequency)
+    /// whose updates are included in the `pending_updates`
+    pub pending_throttled_updates: Vec<(Entity, ComponentKind)>,
     pub actions_next_send_message_id: MessageId,
 
     // TODO: maybe also keep track of which Tick this bevy-tick corresponds to? (will enable doing diff-compression)
@@ -766,12 +847,16 @@ pub struct GroupChannel {
     /// for this group because of the bandwidth cap, in which case it will be accumulated.
     pub accumulated_priority: f32,
     pub base_priority: f32,
+    /// Multiplier of the base priority that is specific to this connection.
+    /// It is computed by the [`ReplicationPriorityFn`](crate::server::priority::ReplicationPriorityFn), if there is one.
+    pub dynamic_priority: f32,
 }
 
 impl Default for GroupChannel {
     fn default() -> Self {
         Self {
             pending_updates: EntityHashMap::default(),
+            pending_throttled_updates: vec![],
             pending_actions: EntityHashMap::default(),
             actions_next_send_message_id: MessageId(0),
             send_tick: None,
@@ -780,6 +865,7 @@ impl Default for GroupChannel {
             last_action_tick: None,
             accumulated_priority: 0.0,
             base_priority: 1.0,
+            dynamic_priority: 1.0,
         }
     }
 }
@@ -958,7 +1044,8 @@ mod tests {
             Some(&UpdateMessageMetadata {
                 group_id: group_1,
                 bevy_tick: bevy_tick_1,
-                tick: tick_1
+                tick: tick_1,
+                throttled_updates: vec![],
             })
         );
         assert_eq!(group.send_tick, Some(bevy_tick_1));
@@ -972,7 +1059,8 @@ mod tests {
             Some(&UpdateMessageMetadata {
                 group_id: group_1,
                 bevy_tick: bevy_tick_2,
-                tick: tick_2
+                tick: tick_2,
+                throttled_updates: vec![],
             })
         );
         assert_eq!(group.send_tick, Some(bevy_tick_2));
@@ -996,7 +1084,8 @@ mod tests {
             Some(&UpdateMessageMetadata {
                 group_id: group_1,
                 bevy_tick: bevy_tick_3,
-                tick: tick_3
+                tick: tick_3,
+                throttled_updates: vec![],
             })
         );
         assert_eq!(group.send_tick, Some(bevy_tick_3));
@@ -1053,7 +1142,8 @@ mod tests {
             Some(&UpdateMessageMetadata {
                 group_id: group_1,
                 bevy_tick: bevy_tick_1,
-                tick: tick_1
+                tick: tick_1,
+                throttled_updates: vec![],
             })
         );
         assert_eq!(group.send_tick, None);
@@ -1067,6 +1157,54 @@ mod tests {
         assert_eq!(group.ack_bevy_tick, None);
     }
 
+    /// The updates of a component throttled by a ReplicationFrequency are pending
+    /// until a message that contains them is acked
+    #[test]
+    fn test_throttled_update_ack() {
+        let component_registry = ComponentRegistry::default();
+        let mut delta_manager = DeltaManager::default();
+        let (tx_ack, rx_ack) = crossbeam_channel::unbounded();
+        let (tx_nack, rx_nack) = crossbeam_channel::unbounded();
+        let (_, rx_send) = crossbeam_channel::unbounded();
+        let mut sender = ReplicationSender::new(
+            rx_ack,
+            rx_nack,
+            rx_send,
+            ReplicationConfig::default(),
+            false,
+        );
+        let group_1 = ReplicationGroupId(0);
+        let entity = Entity::from_raw(0);
+        let kind = ComponentKind::of::<ComponentSyncModeFull>();
+        let current_tick = BevyTick::new(10);
+
+        // the component was never sent
+        assert!(sender.throttled_update_pending(entity, kind, BevyTick::new(1), current_tick));
+
+        // the message containing the update is lost: the update is still pending
+        sender.prepare_throttled_update(entity, group_1, kind);
+        sender.buffer_replication_update_message(group_1, MessageId(0), BevyTick::new(2), Tick(2));
+        tx_nack.try_send(MessageId(0)).unwrap();
+        sender.update(current_tick);
+        assert!(sender.throttled_update_pending(entity, kind, BevyTick::new(1), current_tick));
+
+        // a message that doesn't contain the component is acked: the update is still pending
+        sender.buffer_replication_update_message(group_1, MessageId(1), BevyTick::new(4), Tick(4));
+        tx_ack.try_send(MessageId(1)).unwrap();
+        sender.recv_update_acks(&component_registry, &mut delta_manager);
+        assert!(sender.throttled_update_pending(entity, kind, BevyTick::new(1), current_tick));
+
+        // the message containing the update is acked
+        sender.prepare_throttled_update(entity, group_1, kind);
+        sender.buffer_replication_update_message(group_1, MessageId(2), BevyTick::new(6), Tick(6));
+        tx_ack.try_send(MessageId(2)).unwrap();
+        sender.recv_update_acks(&component_registry, &mut delta_manager);
+        assert!(!sender.throttled_update_pending(entity, kind, BevyTick::new(1), current_tick));
+
+        // the component changed after the acked message
+        assert!(sender.throttled_update_pending(entity, kind, BevyTick::new(8), current_tick));
+    }
+
     // TODO: add tests for replication with entity relations!
     /// Test calling the `finalize` method to create the final replication messages
     /// from the buffered actions and updates
@@ -0,0 +1,825 @@
+/*! Snapshot replication, where the server sends each client the state of all the entities replicated to it
+
+# Snapshot replication
+
+With [`SendUpdatesMode::Snapshot`](crate::prelude::SendUpdatesMode::Snapshot), the server doesn't send
+entity actions and updates per [`ReplicationGroup`](crate::prelude::ReplicationGroup). Instead, every
+`send_interval` it builds for each client a snapshot of all the entities that are replicated to that client,
+and sends it delta-encoded against the last snapshot that the client acked:
+- entities that are not in the baseline are spawned
+- entities that are not in the snapshot anymore are despawned
+- components that were added, changed or removed since the baseline are sent
+
+If a snapshot is lost, the next one is still encoded against the last acked snapshot, so packet loss and
+newly connected clients (who have no baseline) are handled the same way.
+
+The snapshots are sent on the [`SnapshotChannel`](crate::channel::builder::SnapshotChannel). The client
+reconstructs the full snapshot from its baseline, and applies the difference with the snapshot that it
+applied last to the world.
+
+A snapshot is only acked once the client has reconstructed it: the client sends the tick of its most recent
+snapshot on the [`SnapshotAckChannel`](crate::channel::builder::SnapshotAckChannel). Transport-level acks are not
+enough, because the snapshots can arrive out of order and a snapshot whose baseline was already dropped
+by the client cannot be reconstructed.
+
+Components registered with delta compression are serialized in full inside the snapshots, like the other
+components: the snapshots are already delta-encoded.
+*/
+use std::collections::VecDeque;
+
+use bevy::ecs::entity::EntityHashMap;
+use bevy::prelude::{Entity, World};
+use bevy::utils::HashMap;
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use bytes::Bytes;
+use tracing::{debug, trace, warn};
+
+use super::{EntityActions, EntityActionsMessage, SpawnAction};
+use crate::packet::message::MessageId;
+use crate::prelude::{ComponentRegistry, Tick};
+use crate::protocol::component::ComponentNetId;
+use crate::serialize::reader::Reader;
+use crate::serialize::{SerializationError, ToBytes};
+use crate::shared::events::connection::ConnectionEvents;
+use crate::shared::replication::components::ReplicationGroupId;
+use crate::shared::replication::receive::ReplicationReceiver;
+
+/// Serialized components of every entity in the snapshot
+pub(crate) type Snapshot = EntityHashMap<HashMap<ComponentNetId, Bytes>>;
+
+/// Replication group used on the receiver side for all the entities replicated via snapshots
+pub(crate) const SNAPSHOT_GROUP_ID: ReplicationGroupId = ReplicationGroupId(u64::MAX);
+
+/// Maximum number of snapshots kept in the history.
+///
+/// If the remote hasn't acked any snapshot in that time, the sender goes back to sending full snapshots.
+const MAX_SNAPSHOT_HISTORY: usize = 64;
+
+/// Changes of an entity compared to the baseline snapshot
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum EntitySnapshot {
+    /// The entity is not replicated to the remote anymore
+    Despawn,
+    /// The entity is spawned if it was not in the baseline
+    Update {
+        /// Components that were added or changed since the baseline
+        components: Vec<Bytes>,
+        /// Components that were removed since the baseline
+        removed: Vec<ComponentNetId>,
+    },
+}
+
+impl ToBytes for EntitySnapshot {
+    fn len(&self) -> usize {
+        match self {
+            EntitySnapshot::Despawn => 1,
+            EntitySnapshot::Update {
+                components,
+                removed,
+            } => 1 + ToBytes::len(components) + ToBytes::len(removed),
+        }
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        match self {
+            EntitySnapshot::Despawn => buffer.write_u8(0)?,
+            EntitySnapshot::Update {
+                components,
+                removed,
+            } => {
+                buffer.write_u8(1)?;
+                components.to_bytes(buffer)?;
+                removed.to_bytes(buffer)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        match buffer.read_u8()? {
+            0 => Ok(EntitySnapshot::Despawn),
+            1 => Ok(EntitySnapshot::Update {
+                components: Vec::from_bytes(buffer)?,
+                removed: Vec::from_bytes(buffer)?,
+            }),
+            _ => Err(SerializationError::InvalidPacketType),
+        }
+    }
+}
+
+/// Snapshot of the replicated entities at a given tick, delta-encoded against a baseline snapshot
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct SnapshotMessage {
+    pub(crate) tick: Tick,
+    /// Tick of the snapshot that this message is encoded against.
+    /// If `None`, the message contains the full snapshot.
+    pub(crate) baseline: Option<Tick>,
+    pub(crate) entities: Vec<(Entity, EntitySnapshot)>,
+}
+
+impl ToBytes for SnapshotMessage {
+    fn len(&self) -> usize {
+        self.tick.len() + self.baseline.len() + ToBytes::len(&self.entities)
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        self.tick.to_bytes(buffer)?;
+        self.baseline.to_bytes(buffer)?;
+        self.entities.to_bytes(buffer)?;
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            tick: Tick::from_bytes(buffer)?,
+            baseline: Option::<Tick>::from_bytes(buffer)?,
+            entities: Vec::<(Entity, EntitySnapshot)>::from_bytes(buffer)?,
+        })
+    }
+}
+
+/// Read the [`ComponentNetId`] at the start of a serialized component
+fn component_net_id(component: &Bytes) -> Result<ComponentNetId, SerializationError> {
+    ComponentNetId::from_bytes(&mut Reader::from(component.clone()))
+}
+
+/// Compute the changes between the `baseline` snapshot and the `snapshot`
+fn diff(baseline: &Snapshot, snapshot: &Snapshot) -> Vec<(Entity, EntitySnapshot)> {
+    let mut entities = Vec::new();
+    for (entity, components) in snapshot.iter() {
+        let (changed, removed) = match baseline.get(entity) {
+            None => (components.values().cloned().collect(), vec![]),
+            Some(old_components) => (
+                components
+                    .iter()
+                    .filter(|(net_id, bytes)| old_components.get(*net_id) != Some(*bytes))
+                    .map(|(_, bytes)| bytes.clone())
+                    .collect::<Vec<_>>(),
+                old_components
+                    .keys()
+                    .filter(|net_id| !components.contains_key(*net_id))
+                    .copied()
+                    .collect::<Vec<_>>(),
+            ),
+        };
+        // a new entity is always sent, even if it has no components, so that it gets spawned
+        if baseline.contains_key(entity) && changed.is_empty() && removed.is_empty() {
+            continue;
+        }
+        entities.push((
+            *entity,
+            EntitySnapshot::Update {
+                components: changed,
+                removed,
+            },
+        ));
+    }
+    for entity in baseline.keys() {
+        if !snapshot.contains_key(entity) {
+            entities.push((*entity, EntitySnapshot::Despawn));
+        }
+    }
+    entities
+}
+
+/// Reconstruct the full snapshot from the `baseline` snapshot and the changes
+fn apply_diff(
+    baseline: &Snapshot,
+    entities: Vec<(Entity, EntitySnapshot)>,
+) -> Result<Snapshot, SerializationError> {
+    let mut snapshot = baseline.clone();
+    for (entity, entity_snapshot) in entities {
+        match entity_snapshot {
+            EntitySnapshot::Despawn => {
+                snapshot.remove(&entity);
+            }
+            EntitySnapshot::Update {
+                components,
+                removed,
+            } => {
+                let entry = snapshot.entry(entity).or_default();
+                for component in components {
+                    entry.insert(component_net_id(&component)?, component);
+                }
+                for net_id in removed {
+                    entry.remove(&net_id);
+                }
+            }
+        }
+    }
+    Ok(snapshot)
+}
+
+/// Keeps track of the snapshots sent to a remote, and of which ones were acked
+#[derive(Debug, Default)]
+pub(crate) struct SnapshotSender {
+    /// Snapshots that were sent to the remote, starting from the last acked snapshot
+    history: VecDeque<(Tick, Snapshot)>,
+    /// Tick of the most recent snapshot that the remote reconstructed
+    ack_tick: Option<Tick>,
+}
+
+impl SnapshotSender {
+    /// Handle a [`SnapshotAckMessage`] from the remote, and drop the snapshots older than the acked snapshot.
+    ///
+    /// We don't use the transport-level acks, because a snapshot that was received cannot be reconstructed
+    /// if its baseline is not available on the remote anymore.
+    pub(crate) fn receive_ack(&mut self, tick: Tick) {
+        if self.ack_tick.is_some_and(|ack_tick| tick <= ack_tick) {
+            return;
+        }
+        // the history could have been reset since the remote received the snapshot
+        if !self.history.iter().any(|(t, _)| *t == tick) {
+            return;
+        }
+        trace!(?tick, "snapshot acked");
+        self.ack_tick = Some(tick);
+        while self.history.front().is_some_and(|(t, _)| *t < tick) {
+            self.history.pop_front();
+        }
+    }
+
+    /// Store the snapshot for the current tick, and return the message that encodes it against
+    /// the last acked snapshot
+    pub(crate) fn prepare_message(&mut self, tick: Tick, snapshot: Snapshot) -> SnapshotMessage {
+        if self.history.len() >= MAX_SNAPSHOT_HISTORY {
+            debug!("no snapshot was acked recently, sending a full snapshot");
+            self.history.clear();
+            self.ack_tick = None;
+        }
+        let empty = Snapshot::default();
+        let (baseline, baseline_snapshot) = self
+            .ack_tick
+            .and_then(|ack_tick| self.history.iter().find(|(tick, _)| *tick == ack_tick))
+            .map_or((None, &empty), |(tick, snapshot)| (Some(*tick), snapshot));
+        let message = SnapshotMessage {
+            tick,
+            baseline,
+            entities: diff(baseline_snapshot, &snapshot),
+        };
+        self.history.push_back((tick, snapshot));
+        message
+    }
+}
+
+/// Message sent by the receiver with the tick of the most recent snapshot that it reconstructed,
+/// so that the sender can use it as baseline
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) struct SnapshotAckMessage(pub(crate) Tick);
+
+impl ToBytes for SnapshotAckMessage {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        self.0.to_bytes(buffer)
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        Ok(Self(Tick::from_bytes(buffer)?))
+    }
+}
+
+/// Reconstructs the snapshots received from the remote and applies them to the world
+#[derive(Debug, Default)]
+pub(crate) struct SnapshotReceiver {
+    /// Full snapshots received from the remote, ordered by tick
+    snapshots: VecDeque<(Tick, Snapshot)>,
+    /// Most recent baseline used by the remote
+    latest_baseline: Option<Tick>,
+    /// Tick of the last snapshot that we acked
+    acked_tick: Option<Tick>,
+    /// Snapshot that was last applied to the world
+    applied: Option<(Tick, Snapshot)>,
+}
+
+impl SnapshotReceiver {
+    /// Reconstruct the full snapshot from the message and its baseline
+    pub(crate) fn recv(&mut self, message: SnapshotMessage) -> Result<(), SerializationError> {
+        if self.snapshots.iter().any(|(tick, _)| *tick == message.tick) {
+            return Ok(());
+        }
+        let is_latest = self
+            .snapshots
+            .back()
+            .map_or(true, |(tick, _)| message.tick > *tick);
+        let empty = Snapshot::default();
+        let baseline = match message.baseline {
+            None => &empty,
+            Some(baseline_tick) => {
+                let Some((_, baseline)) = self
+                    .snapshots
+                    .iter()
+                    .find(|(tick, _)| *tick == baseline_tick)
+                else {
+                    // older snapshots can arrive after more recent ones and reference a baseline
+                    // that we dropped, but we don't need them anymore
+                    if is_latest {
+                        warn!(
+                            ?baseline_tick,
+                            "could not find the baseline of the received snapshot"
+                        );
+                    } else {
+                        trace!(?baseline_tick, tick = ?message.tick, "ignoring outdated snapshot");
+                    }
+                    return Ok(());
+                };
+                baseline
+            }
+        };
+        let snapshot = apply_diff(baseline, message.entities)?;
+        // the remote only uses acked snapshots as baseline, and its ack tick only increases, so the
+        // snapshots older than the most recent baseline won't be used by any snapshot that we still need.
+        // We cannot use the baseline of this message, since older snapshots (with older baselines)
+        // could still be in flight.
+        if let Some(baseline_tick) = message.baseline {
+            if self.latest_baseline.map_or(true, |t| baseline_tick > t) {
+                self.latest_baseline = Some(baseline_tick);
+            }
+        }
+        if let Some(latest_baseline) = self.latest_baseline {
+            while self
+                .snapshots
+                .front()
+                .is_some_and(|(tick, _)| *tick < latest_baseline)
+            {
+                self.snapshots.pop_front();
+            }
+        }
+        let idx = self
+            .snapshots
+            .iter()
+            .position(|(tick, _)| *tick > message.tick)
+            .unwrap_or(self.snapshots.len());
+        self.snapshots.insert(idx, (message.tick, snapshot));
+        if self.snapshots.len() > MAX_SNAPSHOT_HISTORY {
+            self.snapshots.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Returns the tick of the most recent snapshot that we reconstructed, if it was not acked yet
+    pub(crate) fn ack(&mut self) -> Option<Tick> {
+        let (tick, _) = self.snapshots.back()?;
+        if self
+            .acked_tick
+            .is_some_and(|acked_tick| acked_tick >= *tick)
+        {
+            return None;
+        }
+        self.acked_tick = Some(*tick);
+        Some(*tick)
+    }
+
+    /// Apply the most recent snapshot to the world, if it is newer than the last snapshot applied
+    pub(crate) fn apply_world(
+        &mut self,
+        world: &mut World,
+        replication_receiver: &mut ReplicationReceiver,
+        component_registry: &ComponentRegistry,
+        events: &mut ConnectionEvents,
+    ) {
+        let Some((tick, snapshot)) = self.snapshots.back() else {
+            return;
+        };
+        let empty = Snapshot::default();
+        let applied = match &self.applied {
+            Some((applied_tick, _)) if applied_tick >= tick => return,
+            Some((_, applied)) => applied,
+            None => &empty,
+        };
+        // convert the changes into entity actions, so that they are applied like the other replication messages
+        let actions = diff(applied, snapshot)
+            .into_iter()
+            .map(|(entity, entity_snapshot)| {
+                let mut actions = EntityActions::default();
+                match entity_snapshot {
+                    EntitySnapshot::Despawn => actions.spawn = SpawnAction::Despawn,
+                    EntitySnapshot::Update {
+                        components,
+                        removed,
+                    } => {
+                        let old_components = applied.get(&entity);
+                        if old_components.is_none() {
+                            actions.spawn = SpawnAction::Spawn;
+                        }
+                        for component in components {
+                            let is_update = component_net_id(&component).is_ok_and(|net_id| {
+                                old_components.is_some_and(|c| c.contains_key(&net_id))
+                            });
+                            if is_update {
+                                actions.updates.push(component);
+                            } else {
+                                actions.insert.push(component);
+                            }
+                        }
+                        actions.remove = removed;
+                    }
+                }
+                (entity, actions)
+            })
+            .collect();
+        let message = EntityActionsMessage {
+            sequence_id: MessageId(0),
+            group_id: SNAPSHOT_GROUP_ID,
+            actions,
+        };
+        trace!(?tick, ?message, "applying snapshot");
+        let channel = replication_receiver
+            .group_channels
+            .entry(SNAPSHOT_GROUP_ID)
+            .or_default();
+        channel.latest_tick = Some(*tick);
+        channel.apply_actions_message(
+            world,
+            None,
+            component_registry,
+            *tick,
+            message,
+            &mut replication_receiver.remote_entity_map,
+            &mut replication_receiver.local_entity_to_group,
+            events,
+        );
+        self.applied = Some((*tick, snapshot.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::server::{Replicate, ServerConfig};
+    use crate::prelude::{
+        client, AppComponentExt, ChannelDirection, SendUpdatesMode, SharedConfig, TickConfig,
+    };
+    use crate::tests::protocol::{
+        ComponentDeltaCompression, ComponentSyncModeFull, ComponentSyncModeSimple,
+    };
+    use crate::tests::stepper::BevyStepper;
+    use bevy::prelude::Component;
+    use bevy::utils::Duration;
+    use serde::{Deserialize, Serialize};
+
+    fn component(net_id: ComponentNetId, value: u8) -> Bytes {
+        let mut bytes = vec![];
+        net_id.to_bytes(&mut bytes).unwrap();
+        bytes.push(value);
+        Bytes::from(bytes)
+    }
+
+    fn snapshot(entities: &[(Entity, &[(ComponentNetId, u8)])]) -> Snapshot {
+        entities
+            .iter()
+            .map(|(entity, components)| {
+                (
+                    *entity,
+                    components
+                        .iter()
+                        .map(|(net_id, value)| (*net_id, component(*net_id, *value)))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_snapshot_delta() {
+        let mut sender = SnapshotSender::default();
+        let mut receiver = SnapshotReceiver::default();
+        let e1 = Entity::from_raw(1);
+        let e2 = Entity::from_raw(2);
+        let e3 = Entity::from_raw(3);
+
+        // no baseline: full snapshot
+        let s1 = snapshot(&[(e1, &[(0, 1), (1, 1)]), (e2, &[(0, 2)])]);
+        let message = sender.prepare_message(Tick(1), s1.clone());
+        assert_eq!(message.baseline, None);
+        assert_eq!(message.entities.len(), 2);
+        receiver.recv(message).unwrap();
+
+        // the snapshot 1 is acked, the next snapshot only contains the changes
+        assert_eq!(receiver.ack(), Some(Tick(1)));
+        assert_eq!(receiver.ack(), None);
+        sender.receive_ack(Tick(1));
+        let s2 = snapshot(&[(e1, &[(0, 5)]), (e3, &[(0, 3)])]);
+        let message = sender.prepare_message(Tick(2), s2.clone());
+        assert_eq!(message.baseline, Some(Tick(1)));
+        assert_eq!(message.entities.len(), 3);
+        assert!(message.entities.contains(&(
+            e1,
+            EntitySnapshot::Update {
+                components: vec![component(0, 5)],
+                removed: vec![1],
+            }
+        )));
+        assert!(message.entities.contains(&(e2, EntitySnapshot::Despawn)));
+
+        // the snapshot 2 is lost: the snapshot 3 is still encoded against the snapshot 1
+        let s3 = snapshot(&[(e1, &[(0, 6)]), (e3, &[(0, 3)])]);
+        let message = sender.prepare_message(Tick(3), s3.clone());
+        assert_eq!(message.baseline, Some(Tick(1)));
+        receiver.recv(message).unwrap();
+        assert_eq!(receiver.snapshots.back(), Some(&(Tick(3), s3)));
+    }
+
+    /// The snapshots are received out of order, and some of them are lost
+    #[test]
+    fn test_snapshot_reordering() {
+        let mut sender = SnapshotSender::default();
+        let mut receiver = SnapshotReceiver::default();
+        let e1 = Entity::from_raw(1);
+        let snapshots: Vec<Snapshot> = (0..8).map(|i| snapshot(&[(e1, &[(0, i)])])).collect();
+
+        // S1 and S2 are received
+        let m1 = sender.prepare_message(Tick(1), snapshots[1].clone());
+        receiver.recv(m1).unwrap();
+        sender.receive_ack(receiver.ack().unwrap());
+        let m2 = sender.prepare_message(Tick(2), snapshots[2].clone());
+        assert_eq!(m2.baseline, Some(Tick(1)));
+        receiver.recv(m2).unwrap();
+        let ack2 = receiver.ack().unwrap();
+        // S3 is sent before the ack of S2 reaches the sender
+        let m3 = sender.prepare_message(Tick(3), snapshots[3].clone());
+        assert_eq!(m3.baseline, Some(Tick(1)));
+        sender.receive_ack(ack2);
+        // S4 and S5 are encoded against S2
+        let m4 = sender.prepare_message(Tick(4), snapshots[4].clone());
+        assert_eq!(m4.baseline, Some(Tick(2)));
+        let m5 = sender.prepare_message(Tick(5), snapshots[5].clone());
+
+        // S5 arrives before S3 and S4. S3's baseline was dropped, but it is outdated anyway
+        receiver.recv(m5).unwrap();
+        let ack5 = receiver.ack().unwrap();
+        assert_eq!(ack5, Tick(5));
+        receiver.recv(m3).unwrap();
+        receiver.recv(m4).unwrap();
+        assert!(receiver
+            .snapshots
+            .contains(&(Tick(4), snapshots[4].clone())));
+        // we only ack the most recent snapshot
+        assert_eq!(receiver.ack(), None);
+
+        // the ack of S5 is lost: S6 is still encoded against S2
+        let m6 = sender.prepare_message(Tick(6), snapshots[6].clone());
+        assert_eq!(m6.baseline, Some(Tick(2)));
+        receiver.recv(m6).unwrap();
+        assert_eq!(
+            receiver.snapshots.back(),
+            Some(&(Tick(6), snapshots[6].clone()))
+        );
+        sender.receive_ack(receiver.ack().unwrap());
+        // an outdated ack is ignored
+        sender.receive_ack(ack5);
+        let m7 = sender.prepare_message(Tick(7), snapshots[7].clone());
+        assert_eq!(m7.baseline, Some(Tick(6)));
+        receiver.recv(m7).unwrap();
+        assert_eq!(
+            receiver.snapshots.back(),
+            Some(&(Tick(7), snapshots[7].clone()))
+        );
+        // the snapshots older than the latest baseline are dropped
+        assert_eq!(receiver.snapshots.front().unwrap().0, Tick(6));
+    }
+
+    #[test]
+    fn test_serde_snapshot_message() {
+        let message = SnapshotMessage {
+            tick: Tick(3),
+            baseline: Some(Tick(1)),
+            entities: vec![
+                (Entity::from_raw(1), EntitySnapshot::Despawn),
+                (
+                    Entity::from_raw(2),
+                    EntitySnapshot::Update {
+                        components: vec![component(0, 1)],
+                        removed: vec![2],
+                    },
+                ),
+            ],
+        };
+        let mut writer = vec![];
+        message.to_bytes(&mut writer).unwrap();
+        let mut reader = Reader::from(writer);
+        assert_eq!(SnapshotMessage::from_bytes(&mut reader).unwrap(), message);
+    }
+
+    #[test]
+    fn test_snapshot_replication() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .replication
+            .send_updates_mode = SendUpdatesMode::Snapshot;
+
+        // spawn
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), ComponentSyncModeFull(1.0)))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+
+        // update and insert
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(server_entity)
+            .insert((ComponentSyncModeFull(2.0), ComponentSyncModeSimple(1.0)));
+        stepper.frame_step();
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity),
+            Some(&ComponentSyncModeFull(2.0))
+        );
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeSimple>(client_entity),
+            Some(&ComponentSyncModeSimple(1.0))
+        );
+
+        // remove
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(server_entity)
+            .remove::<ComponentSyncModeSimple>();
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<ComponentSyncModeSimple>(client_entity)
+            .is_none());
+
+        // despawn
+        stepper.server_app.world_mut().despawn(server_entity);
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world()
+            .get_entity(client_entity)
+            .is_none());
+    }
+
+    /// Components registered with delta compression are serialized in full inside the snapshots
+    #[test]
+    fn test_snapshot_delta_compression() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .replication
+            .send_updates_mode = SendUpdatesMode::Snapshot;
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), ComponentDeltaCompression(vec![1, 2])))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentDeltaCompression>(client_entity),
+            Some(&ComponentDeltaCompression(vec![1, 2]))
+        );
+
+        // update
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ComponentDeltaCompression>(server_entity)
+            .unwrap()
+            .0
+            .push(3);
+        stepper.frame_step();
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentDeltaCompression>(client_entity),
+            Some(&ComponentDeltaCompression(vec![1, 2, 3]))
+        );
+    }
+
+    /// The snapshots sent to a client using an older version of the protocol don't contain
+    /// the components that were added in a newer version
+    #[test]
+    fn test_snapshot_older_protocol_version() {
+        #[derive(Component, Serialize, Deserialize, Debug, PartialEq, Clone)]
+        struct NewComponent;
+
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            protocol_version: 1,
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            client::ClientConfig::default(),
+            frame_duration,
+        );
+        stepper
+            .server_app
+            .register_component::<NewComponent>(ChannelDirection::ServerToClient)
+            // use an explicit net id so that the net ids of the other components are not shifted
+            .with_net_id(100)
+            .since_version(1);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .replication
+            .send_updates_mode = SendUpdatesMode::Snapshot;
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<client::ClientConfig>()
+            .shared
+            .protocol_version = 0;
+        stepper.init();
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate::default(),
+                ComponentSyncModeFull(1.0),
+                NewComponent,
+            ))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+        // the new component was not sent
+        let (_, snapshot) = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .snapshot_receiver
+            .snapshots
+            .back()
+            .unwrap();
+        assert!(!snapshot[&server_entity].contains_key(&100));
+    }
+}
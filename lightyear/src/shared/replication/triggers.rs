@@ -0,0 +1,618 @@
+//! Module to replicate bevy triggers from the server to the clients
+//!
+//! A trigger event `E` registered with [`register_trigger`](crate::prelude::AppMessageExt::register_trigger)
+//! is forwarded to the clients whenever it is triggered on the server:
+//! - `commands.trigger(E)` is sent to every client
+//! - `commands.trigger_targets(E, entity)` is only sent to the clients that the entity is replicated to
+//!   (according to its [`ReplicationTarget`] and its network relevance). The entity is mapped to the
+//!   corresponding client entity, and the trigger is fired on the client with that entity as target.
+//!
+//! A targeted trigger is fired once, on the entity that the player sees: the Predicted entity if there is one,
+//! else the Interpolated entity, else the Confirmed entity. The trigger is fired on a Predicted (resp. Interpolated)
+//! entity once the prediction (resp. interpolation) timeline of the client reaches the server tick at which
+//! the trigger was fired, and on a Confirmed entity as soon as it is received.
+//! With [`TriggerRegistration::fire_on_all_entities`], the trigger is instead fired on the Confirmed entity
+//! and on each of its Predicted and Interpolated entities.
+//! Triggers without a target are fired as soon as they are received.
+//!
+//! The entities contained inside the event itself are not mapped.
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::{ChannelKind, Message, Tick};
+
+/// Message used to send a trigger event `E` over the network
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TriggerMessage<E> {
+    pub event: E,
+    /// The entity targeted by the trigger, if any
+    pub target: Option<Entity>,
+    /// The server tick at which the trigger was fired
+    pub tick: Tick,
+}
+
+/// Resource that stores how the trigger `E` is replicated
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub(crate) struct TriggerMetadata<E> {
+    pub(crate) channel: ChannelKind,
+    /// If true, the trigger is fired on the Confirmed, Predicted and Interpolated entities on the client,
+    /// instead of only on the entity that the player sees
+    pub(crate) fire_on_all_entities: bool,
+    _marker: PhantomData<E>,
+}
+
+impl<E> TriggerMetadata<E> {
+    pub(crate) fn new(channel: ChannelKind) -> Self {
+        Self {
+            channel,
+            fire_on_all_entities: false,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Returned by [`register_trigger`](crate::prelude::AppMessageExt::register_trigger) to configure
+/// how the trigger `E` is replicated
+pub struct TriggerRegistration<'a, E> {
+    pub(crate) app: &'a mut App,
+    pub(crate) _marker: PhantomData<E>,
+}
+
+impl<E: Send + Sync + 'static> TriggerRegistration<'_, E> {
+    /// Fire the trigger on the Confirmed entity as soon as it is received, and also on its Predicted
+    /// and Interpolated entities when their timeline reaches the tick of the trigger.
+    ///
+    /// By default, the trigger is only fired once, on the entity that the player sees.
+    pub fn fire_on_all_entities(self) -> Self {
+        self.app
+            .world_mut()
+            .resource_mut::<TriggerMetadata<E>>()
+            .fire_on_all_entities = true;
+        self
+    }
+}
+
+pub(crate) mod send {
+    use tracing::{error, trace};
+
+    use super::*;
+    use crate::prelude::{ClientId, NetworkTarget, ReplicationTarget, TickManager};
+    use crate::server::connection::ConnectionManager;
+    use crate::server::relevance::immediate::CachedNetworkRelevance;
+
+    pub(crate) fn add_trigger_send_observer<E: Event + Message + Clone>(app: &mut App) {
+        app.observe(send_trigger::<E>);
+    }
+
+    /// Forward the triggers of `E` to the clients
+    fn send_trigger<E: Event + Message + Clone>(
+        trigger: Trigger<E>,
+        metadata: Res<TriggerMetadata<E>>,
+        tick_manager: Res<TickManager>,
+        mut connection_manager: ResMut<ConnectionManager>,
+        query: Query<(&ReplicationTarget, Option<&CachedNetworkRelevance>)>,
+    ) {
+        let entity = trigger.entity();
+        let target_entity = (entity != Entity::PLACEHOLDER).then_some(entity);
+        let is_target = |client_id: &ClientId| match target_entity {
+            None => true,
+            Some(entity) => query
+                .get(entity)
+                .is_ok_and(|(replication_target, relevance)| {
+                    replication_target.target.targets(client_id)
+                        && relevance.map_or(true, |relevance| relevance.is_relevant(client_id))
+                }),
+        };
+        // local clients share the world of the server, so they already see the trigger
+        let clients: Vec<ClientId> = connection_manager
+            .connected_clients()
+            .filter(|client_id| {
+                connection_manager
+                    .connection(*client_id)
+                    .is_ok_and(|c| !c.is_local_client())
+            })
+            .filter(is_target)
+            .collect();
+        if clients.is_empty() {
+            return;
+        }
+        trace!(
+            ?target_entity,
+            ?clients,
+            "sending trigger {}",
+            std::any::type_name::<E>()
+        );
+        let message = TriggerMessage {
+            event: trigger.event().clone(),
+            target: target_entity,
+            tick: tick_manager.tick(),
+        };
+        if let Err(e) = connection_manager.erased_send_message_to_target(
+            &message,
+            metadata.channel,
+            NetworkTarget::Only(clients),
+        ) {
+            error!("could not send trigger: {:?}", e);
+        }
+    }
+}
+
+pub(crate) mod receive {
+    use bevy::ecs::entity::Entities;
+    use bevy::utils::Duration;
+    use tracing::{debug, trace};
+
+    use super::*;
+    use crate::client::components::Confirmed;
+    use crate::client::connection::ConnectionManager;
+    use crate::client::events::MessageEvent;
+    use crate::prelude::{Tick, TickManager};
+    use crate::shared::sets::{ClientMarker, InternalMainSet};
+
+    /// How long a trigger can wait for its target entity to be replicated before being dropped
+    const PENDING_TRIGGER_TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Timeline of a Predicted or Interpolated entity
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Timeline {
+        Prediction,
+        Interpolation,
+    }
+
+    /// Triggers that cannot be fired yet
+    #[derive(Resource)]
+    struct PendingTriggers<E> {
+        /// Triggers whose target entity has not been replicated yet.
+        ///
+        /// The trigger message can arrive before the entity that it targets, since they are not
+        /// sent on the same channel.
+        unmapped: Vec<(Duration, TriggerMessage<E>)>,
+        /// Triggers targeting a Predicted or Interpolated entity, that are waiting for the timeline
+        /// of that entity to reach the tick of the trigger
+        scheduled: Vec<(Timeline, Tick, Entity, E)>,
+    }
+
+    impl<E> Default for PendingTriggers<E> {
+        fn default() -> Self {
+            Self {
+                unmapped: vec![],
+                scheduled: vec![],
+            }
+        }
+    }
+
+    pub(crate) fn add_trigger_receive_systems<E: Event + Message + Clone>(app: &mut App) {
+        app.init_resource::<PendingTriggers<E>>();
+        app.add_systems(
+            PreUpdate,
+            receive_trigger::<E>.after(InternalMainSet::<ClientMarker>::EmitEvents),
+        );
+    }
+
+    /// Fire the triggers received from the server, on the local entity that corresponds to the target entity.
+    ///
+    /// The trigger is fired on the Predicted entity if there is one, else on the Interpolated entity,
+    /// once the prediction (resp. interpolation) timeline reaches the tick at which the trigger was fired
+    /// on the server. Otherwise it is fired on the Confirmed entity as soon as it is received.
+    #[allow(clippy::too_many_arguments)]
+    fn receive_trigger<E: Event + Message + Clone>(
+        mut commands: Commands,
+        metadata: Res<TriggerMetadata<E>>,
+        time: Res<Time<Real>>,
+        tick_manager: Res<TickManager>,
+        connection_manager: Res<ConnectionManager>,
+        mut messages: ResMut<Events<MessageEvent<TriggerMessage<E>>>>,
+        mut pending: ResMut<PendingTriggers<E>>,
+        confirmed: Query<&Confirmed>,
+        entities: &Entities,
+    ) {
+        let now = time.elapsed();
+        let received = messages.drain().map(|event| (now, event.message));
+        let triggers = std::mem::take(&mut pending.unmapped);
+        for (received_at, message) in triggers.into_iter().chain(received) {
+            let Some(remote_entity) = message.target else {
+                trace!("received trigger {}", std::any::type_name::<E>());
+                commands.trigger(message.event);
+                continue;
+            };
+            match connection_manager
+                .replication_receiver
+                .remote_entity_map
+                .get_local(remote_entity)
+            {
+                Some(local_entity) => {
+                    trace!(
+                        ?remote_entity,
+                        ?local_entity,
+                        tick = ?message.tick,
+                        "received trigger {}",
+                        std::any::type_name::<E>()
+                    );
+                    let timelines = confirmed
+                        .get(local_entity)
+                        .map(|confirmed| {
+                            [
+                                confirmed.predicted.map(|e| (Timeline::Prediction, e)),
+                                confirmed.interpolated.map(|e| (Timeline::Interpolation, e)),
+                            ]
+                        })
+                        .unwrap_or_default();
+                    if metadata.fire_on_all_entities {
+                        for (timeline, entity) in timelines.into_iter().flatten() {
+                            pending.scheduled.push((
+                                timeline,
+                                message.tick,
+                                entity,
+                                message.event.clone(),
+                            ));
+                        }
+                        commands.trigger_targets(message.event, local_entity);
+                    } else if let Some((timeline, entity)) = timelines.into_iter().flatten().next()
+                    {
+                        pending
+                            .scheduled
+                            .push((timeline, message.tick, entity, message.event));
+                    } else {
+                        commands.trigger_targets(message.event, local_entity);
+                    }
+                }
+                None => {
+                    if now.saturating_sub(received_at) < PENDING_TRIGGER_TIMEOUT {
+                        pending.unmapped.push((received_at, message));
+                    } else {
+                        debug!(
+                            ?remote_entity,
+                            "dropping trigger {}: the target entity was not replicated",
+                            std::any::type_name::<E>()
+                        );
+                    }
+                }
+            }
+        }
+
+        // the timelines are only meaningful once the client is synced
+        if pending.scheduled.is_empty() || !connection_manager.sync_manager.is_synced() {
+            return;
+        }
+        let prediction_tick = tick_manager.tick();
+        let interpolation_tick = connection_manager
+            .sync_manager
+            .interpolation_tick(tick_manager.as_ref());
+        pending.scheduled.retain(|(timeline, tick, entity, event)| {
+            let timeline_tick = match timeline {
+                Timeline::Prediction => prediction_tick,
+                Timeline::Interpolation => interpolation_tick,
+            };
+            if timeline_tick < *tick {
+                return true;
+            }
+            // the entity could have been despawned in the meantime
+            if entities.contains(*entity) {
+                trace!(
+                    ?entity,
+                    ?timeline,
+                    ?tick,
+                    "firing trigger {}",
+                    std::any::type_name::<E>()
+                );
+                commands.trigger_targets(event.clone(), *entity);
+            }
+            false
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::Duration;
+
+    use crate::client::components::Confirmed;
+    use crate::client::interpolation::plugin::InterpolationDelay;
+    use crate::prelude::client::{ClientConfig, InterpolationConfig};
+    use crate::prelude::server::{Replicate, SyncTarget};
+    use crate::prelude::{
+        AppMessageExt, ClientId, NetworkRelevanceMode, NetworkTarget, SharedConfig, TickConfig,
+    };
+    use crate::tests::protocol::Channel1;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    use super::*;
+
+    #[derive(Event, Serialize, Deserialize, Debug, PartialEq, Clone)]
+    struct TriggerEvent(f32);
+
+    #[derive(Resource, Default)]
+    struct ReceivedTriggers(Vec<(Entity, TriggerEvent)>);
+
+    fn setup_with_client_config(
+        client_config: ClientConfig,
+        fire_on_all_entities: bool,
+    ) -> BevyStepper {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+        stepper
+            .server_app
+            .register_trigger::<TriggerEvent, Channel1>();
+        let registration = stepper
+            .client_app
+            .register_trigger::<TriggerEvent, Channel1>();
+        if fire_on_all_entities {
+            registration.fire_on_all_entities();
+        }
+        stepper
+            .client_app
+            .init_resource::<ReceivedTriggers>()
+            .observe(
+                |trigger: Trigger<TriggerEvent>, mut received: ResMut<ReceivedTriggers>| {
+                    received.0.push((trigger.entity(), trigger.event().clone()));
+                },
+            );
+        stepper.init();
+        stepper
+    }
+
+    fn setup() -> BevyStepper {
+        setup_with_client_config(ClientConfig::default(), false)
+    }
+
+    #[test]
+    fn test_trigger_targets() {
+        let mut stepper = setup();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate::default())
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = *stepper
+            .client_app
+            .world()
+            .resource::<crate::prelude::client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .remote_to_local
+            .get(&server_entity)
+            .expect("entity was not replicated to client");
+
+        stepper
+            .server_app
+            .world_mut()
+            .trigger_targets(TriggerEvent(1.0), server_entity);
+        stepper.server_app.world_mut().trigger(TriggerEvent(2.0));
+        stepper.frame_step();
+        stepper.frame_step();
+
+        // Channel1 is unordered
+        let received = &stepper.client_app.world().resource::<ReceivedTriggers>().0;
+        assert_eq!(received.len(), 2);
+        assert!(received.contains(&(client_entity, TriggerEvent(1.0))));
+        assert!(received.contains(&(Entity::PLACEHOLDER, TriggerEvent(2.0))));
+    }
+
+    /// The trigger is not sent to clients that the entity is not relevant to
+    #[test]
+    fn test_trigger_relevance() {
+        let mut stepper = setup();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate {
+                relevance_mode: NetworkRelevanceMode::InterestManagement,
+                ..default()
+            })
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        stepper
+            .server_app
+            .world_mut()
+            .trigger_targets(TriggerEvent(1.0), server_entity);
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<ReceivedTriggers>()
+            .0
+            .is_empty());
+
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<crate::prelude::server::RelevanceManager>()
+            .gain_relevance(ClientId::Netcode(TEST_CLIENT_ID), server_entity);
+        stepper.frame_step();
+        stepper
+            .server_app
+            .world_mut()
+            .trigger_targets(TriggerEvent(2.0), server_entity);
+        stepper.frame_step();
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<ReceivedTriggers>()
+                .0
+                .iter()
+                .map(|(_, event)| event.clone())
+                .collect::<Vec<_>>(),
+            vec![TriggerEvent(2.0)]
+        );
+    }
+
+    fn setup_with_interpolation_delay(fire_on_all_entities: bool) -> BevyStepper {
+        setup_with_client_config(
+            ClientConfig {
+                interpolation: InterpolationConfig {
+                    delay: InterpolationDelay::default().with_min_delay(Duration::from_millis(100)),
+                },
+                ..default()
+            },
+            fire_on_all_entities,
+        )
+    }
+
+    /// Spawn an entity on the server and return the corresponding Confirmed entity on the client
+    fn spawn_entity(stepper: &mut BevyStepper, sync: SyncTarget) -> (Entity, Entity) {
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate { sync, ..default() })
+            .id();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        let client_entity = *stepper
+            .client_app
+            .world()
+            .resource::<crate::prelude::client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .remote_to_local
+            .get(&server_entity)
+            .expect("entity was not replicated to client");
+        (server_entity, client_entity)
+    }
+
+    /// The trigger is only fired on the Predicted entity, when the prediction timeline
+    /// reaches the tick of the trigger
+    #[test]
+    fn test_trigger_timelines() {
+        let mut stepper = setup_with_interpolation_delay(false);
+        let (server_entity, client_entity) = spawn_entity(
+            &mut stepper,
+            SyncTarget {
+                prediction: NetworkTarget::All,
+                interpolation: NetworkTarget::All,
+            },
+        );
+        let predicted = stepper
+            .client_app
+            .world()
+            .get::<Confirmed>(client_entity)
+            .unwrap()
+            .predicted
+            .unwrap();
+
+        stepper
+            .server_app
+            .world_mut()
+            .trigger_targets(TriggerEvent(1.0), server_entity);
+        stepper.frame_step();
+        stepper.frame_step();
+        // the prediction timeline is ahead of the server
+        assert_eq!(
+            stepper.client_app.world().resource::<ReceivedTriggers>().0,
+            vec![(predicted, TriggerEvent(1.0))]
+        );
+
+        // the trigger is not fired again on the Interpolated or Confirmed entities
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper.client_app.world().resource::<ReceivedTriggers>().0,
+            vec![(predicted, TriggerEvent(1.0))]
+        );
+    }
+
+    /// Without a Predicted entity, the trigger is fired on the Interpolated entity when the
+    /// interpolation timeline reaches the tick of the trigger
+    #[test]
+    fn test_trigger_interpolated() {
+        let mut stepper = setup_with_interpolation_delay(false);
+        let (server_entity, client_entity) = spawn_entity(
+            &mut stepper,
+            SyncTarget {
+                prediction: NetworkTarget::None,
+                interpolation: NetworkTarget::All,
+            },
+        );
+        let interpolated = stepper
+            .client_app
+            .world()
+            .get::<Confirmed>(client_entity)
+            .unwrap()
+            .interpolated
+            .unwrap();
+
+        stepper
+            .server_app
+            .world_mut()
+            .trigger_targets(TriggerEvent(1.0), server_entity);
+        stepper.frame_step();
+        stepper.frame_step();
+        // the interpolation timeline is behind the server
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<ReceivedTriggers>()
+            .0
+            .is_empty());
+
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper.client_app.world().resource::<ReceivedTriggers>().0,
+            vec![(interpolated, TriggerEvent(1.0))]
+        );
+    }
+
+    /// With `fire_on_all_entities`, the trigger is fired on the Confirmed entity right away, and on the
+    /// Predicted and Interpolated entities when their timeline reaches the tick of the trigger
+    #[test]
+    fn test_trigger_fire_on_all_entities() {
+        let mut stepper = setup_with_interpolation_delay(true);
+        let (server_entity, client_entity) = spawn_entity(
+            &mut stepper,
+            SyncTarget {
+                prediction: NetworkTarget::All,
+                interpolation: NetworkTarget::All,
+            },
+        );
+        let confirmed = stepper
+            .client_app
+            .world()
+            .get::<Confirmed>(client_entity)
+            .unwrap();
+        let predicted = confirmed.predicted.unwrap();
+        let interpolated = confirmed.interpolated.unwrap();
+
+        stepper
+            .server_app
+            .world_mut()
+            .trigger_targets(TriggerEvent(1.0), server_entity);
+        stepper.frame_step();
+        stepper.frame_step();
+        // the prediction timeline is ahead of the server, but the interpolation timeline is behind
+        assert_eq!(
+            stepper.client_app.world().resource::<ReceivedTriggers>().0,
+            vec![
+                (client_entity, TriggerEvent(1.0)),
+                (predicted, TriggerEvent(1.0))
+            ]
+        );
+
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper.client_app.world().resource::<ReceivedTriggers>().0,
+            vec![
+                (client_entity, TriggerEvent(1.0)),
+                (predicted, TriggerEvent(1.0)),
+                (interpolated, TriggerEvent(1.0))
+            ]
+        );
+    }
+}
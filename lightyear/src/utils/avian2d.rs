@@ -1,8 +1,9 @@
 //! Implement lightyear traits for some common bevy types
+use crate::client::interpolation::curve::{self, CurveSamples};
 use crate::prelude::client::{InterpolationSet, PredictionSet};
 use crate::shared::replication::delta::Diffable;
 use crate::shared::sets::{ClientMarker, InternalReplicationSet, ServerMarker};
-use avian2d::math::Scalar;
+use avian2d::math::{Scalar, PI, TAU};
 use avian2d::prelude::*;
 use bevy::prelude::{App, FixedPostUpdate, IntoSystemSetConfigs, Plugin};
 use tracing::trace;
@@ -53,6 +54,34 @@ pub mod position {
         res
     }
 
+    /// Catmull-Rom interpolation of the position, going through the neighbouring server samples
+    pub fn catmull_rom(samples: &CurveSamples<Position>, t: f32) -> Position {
+        Position(curve::catmull_rom(
+            samples.before.map(|(time, p)| (Scalar::from(time), p.0)),
+            samples.start.0,
+            samples.end.0,
+            samples.after.map(|(time, p)| (Scalar::from(time), p.0)),
+            Scalar::from(t),
+        ))
+    }
+
+    /// Cubic Hermite interpolation of the position, using the linear velocity
+    pub fn hermite(
+        samples: &CurveSamples<Position>,
+        start_velocity: &LinearVelocity,
+        end_velocity: &LinearVelocity,
+        t: f32,
+    ) -> Position {
+        let duration = Scalar::from(samples.duration);
+        Position(curve::cubic_hermite(
+            samples.start.0,
+            start_velocity.0 * duration,
+            samples.end.0,
+            end_velocity.0 * duration,
+            Scalar::from(t),
+        ))
+    }
+
     impl Diffable for Position {
         type Delta = Self;
 
@@ -93,6 +122,67 @@ pub mod rotation {
         );
         res
     }
+
+    /// Angle to add to `from` to reach `to` with the shortest rotation, in radians
+    fn shortest_angle(from: Scalar, to: Scalar) -> Scalar {
+        (to - from + PI).rem_euclid(TAU) - PI
+    }
+
+    /// Angles of the samples in radians, unwrapped so that consecutive samples don't jump by a full turn
+    fn unwrapped_angles(
+        samples: &CurveSamples<Rotation>,
+    ) -> (
+        Option<(Scalar, Scalar)>,
+        Scalar,
+        Scalar,
+        Option<(Scalar, Scalar)>,
+    ) {
+        let start = samples.start.as_radians();
+        let end = start + shortest_angle(start, samples.end.as_radians());
+        let before = samples.before.map(|(time, r)| {
+            (
+                Scalar::from(time),
+                start - shortest_angle(r.as_radians(), start),
+            )
+        });
+        let after = samples.after.map(|(time, r)| {
+            (
+                Scalar::from(time),
+                end + shortest_angle(end, r.as_radians()),
+            )
+        });
+        (before, start, end, after)
+    }
+
+    /// Catmull-Rom interpolation of the rotation, going through the neighbouring server samples
+    pub fn catmull_rom(samples: &CurveSamples<Rotation>, t: f32) -> Rotation {
+        let (before, start, end, after) = unwrapped_angles(samples);
+        Rotation::radians(curve::catmull_rom(
+            before,
+            start,
+            end,
+            after,
+            Scalar::from(t),
+        ))
+    }
+
+    /// Cubic Hermite interpolation of the rotation, using the angular velocity
+    pub fn hermite(
+        samples: &CurveSamples<Rotation>,
+        start_velocity: &AngularVelocity,
+        end_velocity: &AngularVelocity,
+        t: f32,
+    ) -> Rotation {
+        let (_, start, end, _) = unwrapped_angles(samples);
+        let duration = Scalar::from(samples.duration);
+        Rotation::radians(curve::cubic_hermite(
+            start,
+            start_velocity.0 * duration,
+            end,
+            end_velocity.0 * duration,
+            Scalar::from(t),
+        ))
+    }
 }
 
 pub mod linear_velocity {
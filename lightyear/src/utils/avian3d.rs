@@ -1,4 +1,5 @@
 //! Implement lightyear traits for some common bevy types
+use crate::client::interpolation::curve::{self, CurveSamples};
 use crate::prelude::client::{InterpolationSet, PredictionSet};
 use crate::shared::replication::delta::Diffable;
 use crate::shared::sets::{ClientMarker, InternalReplicationSet, ServerMarker};
@@ -53,6 +54,34 @@ pub mod position {
         res
     }
 
+    /// Catmull-Rom interpolation of the position, going through the neighbouring server samples
+    pub fn catmull_rom(samples: &CurveSamples<Position>, t: f32) -> Position {
+        Position(curve::catmull_rom(
+            samples.before.map(|(time, p)| (Scalar::from(time), p.0)),
+            samples.start.0,
+            samples.end.0,
+            samples.after.map(|(time, p)| (Scalar::from(time), p.0)),
+            Scalar::from(t),
+        ))
+    }
+
+    /// Cubic Hermite interpolation of the position, using the linear velocity
+    pub fn hermite(
+        samples: &CurveSamples<Position>,
+        start_velocity: &LinearVelocity,
+        end_velocity: &LinearVelocity,
+        t: f32,
+    ) -> Position {
+        let duration = Scalar::from(samples.duration);
+        Position(curve::cubic_hermite(
+            samples.start.0,
+            start_velocity.0 * duration,
+            samples.end.0,
+            end_velocity.0 * duration,
+            Scalar::from(t),
+        ))
+    }
+
     impl Diffable for Position {
         type Delta = Self;
 
@@ -72,12 +101,74 @@ pub mod position {
 
 pub mod rotation {
     use super::*;
-    use avian3d::math::Quaternion;
+    use avian3d::math::{Quaternion, Vector};
     use bevy::prelude::Animatable;
 
     pub fn lerp(start: &Rotation, other: &Rotation, t: f32) -> Rotation {
         Rotation(Quaternion::interpolate(&start.0, &other.0, t))
     }
+
+    /// Rotation (as a scaled axis, in radians) that brings `from` to `to` along the shortest path
+    fn rotation_between(from: Quaternion, to: Quaternion) -> Vector {
+        let delta = to * from.inverse();
+        let delta = if delta.w < 0.0 { -delta } else { delta };
+        delta.to_scaled_axis()
+    }
+
+    /// Interpolate from `start` to `end` with the angular velocities `start_velocity` and `end_velocity`
+    /// (expressed per unit of `t`).
+    ///
+    /// We rotate `start` forward with its velocity and `end` backward with its velocity, then blend between
+    /// the two with a smoothstep, so that the curve has the correct velocity at both ends.
+    fn cubic_rotation(
+        start: Quaternion,
+        start_velocity: Vector,
+        end: Quaternion,
+        end_velocity: Vector,
+        t: Scalar,
+    ) -> Quaternion {
+        let from_start = Quaternion::from_scaled_axis(start_velocity * t) * start;
+        let from_end = Quaternion::from_scaled_axis(end_velocity * (t - 1.0)) * end;
+        let weight = t * t * (3.0 - 2.0 * t);
+        from_start.slerp(from_end, weight).normalize()
+    }
+
+    /// Catmull-Rom interpolation of the rotation, going through the neighbouring server samples
+    pub fn catmull_rom(samples: &CurveSamples<Rotation>, t: f32) -> Rotation {
+        let (start, end) = (samples.start.0, samples.end.0);
+        let start_velocity = samples.before.map_or_else(
+            || rotation_between(start, end),
+            |(time, before)| rotation_between(before.0, end) / (1.0 - Scalar::from(time)),
+        );
+        let end_velocity = samples.after.map_or_else(
+            || rotation_between(start, end),
+            |(time, after)| rotation_between(start, after.0) / Scalar::from(time),
+        );
+        Rotation(cubic_rotation(
+            start,
+            start_velocity,
+            end,
+            end_velocity,
+            Scalar::from(t),
+        ))
+    }
+
+    /// Cubic Hermite interpolation of the rotation, using the angular velocity
+    pub fn hermite(
+        samples: &CurveSamples<Rotation>,
+        start_velocity: &AngularVelocity,
+        end_velocity: &AngularVelocity,
+        t: f32,
+    ) -> Rotation {
+        let duration = Scalar::from(samples.duration);
+        Rotation(cubic_rotation(
+            samples.start.0,
+            start_velocity.0 * duration,
+            samples.end.0,
+            end_velocity.0 * duration,
+            Scalar::from(t),
+        ))
+    }
 }
 
 pub mod linear_velocity {
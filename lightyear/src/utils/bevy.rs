@@ -1,9 +1,10 @@
 //! Implement lightyear traits for some common bevy types
 
-use bevy::prelude::{Quat, Transform};
+use bevy::prelude::{Quat, Transform, Vec3};
 use tracing::trace;
 
 use crate::client::components::LerpFn;
+use crate::client::interpolation::curve::{catmull_rom, cubic_hermite, CurveSamples};
 
 pub struct TransformLinearInterpolation;
 
@@ -36,3 +37,102 @@ impl LerpFn<Quat> for QuatSphericalLinearInterpolation {
         start.slerp(*other, t)
     }
 }
+
+/// Rotation (as a scaled axis, in radians) that brings `from` to `to` along the shortest path
+fn rotation_between(from: Quat, to: Quat) -> Vec3 {
+    let delta = to * from.inverse();
+    let delta = if delta.w < 0.0 { -delta } else { delta };
+    delta.to_scaled_axis()
+}
+
+/// Interpolate between two rotations with the angular velocities `start_velocity` and `end_velocity`
+/// (expressed per unit of `t`), so that the rotation speed is continuous across server updates
+pub fn quat_cubic_interpolation(
+    start: Quat,
+    start_velocity: Vec3,
+    end: Quat,
+    end_velocity: Vec3,
+    t: f32,
+) -> Quat {
+    // rotate `start` forward and `end` backward with their velocities, and blend between the two
+    let from_start = Quat::from_scaled_axis(start_velocity * t) * start;
+    let from_end = Quat::from_scaled_axis(end_velocity * (t - 1.0)) * end;
+    let weight = t * t * (3.0 - 2.0 * t);
+    from_start.slerp(from_end, weight).normalize()
+}
+
+/// Catmull-Rom interpolation of a [`Transform`], going through the neighbouring server samples.
+///
+/// Can be registered with [`add_curve_interpolation_fn`](crate::prelude::AppComponentExt::add_curve_interpolation_fn)
+pub fn transform_catmull_rom(samples: &CurveSamples<Transform>, t: f32) -> Transform {
+    let (start, end) = (samples.start, samples.end);
+    let start_angular_velocity = samples.before.map_or_else(
+        || rotation_between(start.rotation, end.rotation),
+        |(time, before)| rotation_between(before.rotation, end.rotation) / (1.0 - time),
+    );
+    let end_angular_velocity = samples.after.map_or_else(
+        || rotation_between(start.rotation, end.rotation),
+        |(time, after)| rotation_between(start.rotation, after.rotation) / time,
+    );
+    Transform {
+        translation: catmull_rom(
+            samples.before.map(|(time, b)| (time, b.translation)),
+            start.translation,
+            end.translation,
+            samples.after.map(|(time, a)| (time, a.translation)),
+            t,
+        ),
+        rotation: quat_cubic_interpolation(
+            start.rotation,
+            start_angular_velocity,
+            end.rotation,
+            end_angular_velocity,
+            t,
+        ),
+        scale: start.scale * (1.0 - t) + end.scale * t,
+    }
+}
+
+/// Velocity of a [`Transform`], used as the derivative component of [`transform_hermite`].
+///
+/// Bevy does not provide a velocity component, so this is implemented by your own replicated component.
+pub trait TransformVelocity {
+    /// Linear velocity, in units per second
+    fn linear_velocity(&self) -> Vec3;
+    /// Angular velocity as a scaled axis, in radians per second
+    fn angular_velocity(&self) -> Vec3;
+}
+
+/// Cubic Hermite interpolation of a [`Transform`], using the velocity component `D` at the start and at the end
+/// of the interpolation interval.
+///
+/// Can be registered with [`add_hermite_interpolation_fn`](crate::prelude::AppComponentExt::add_hermite_interpolation_fn):
+/// ```rust,ignore
+/// app.add_hermite_interpolation_fn::<Transform, Velocity>(transform_hermite::<Velocity>);
+/// ```
+pub fn transform_hermite<D: TransformVelocity>(
+    samples: &CurveSamples<Transform>,
+    start_velocity: &D,
+    end_velocity: &D,
+    t: f32,
+) -> Transform {
+    let (start, end) = (samples.start, samples.end);
+    let duration = samples.duration;
+    Transform {
+        translation: cubic_hermite(
+            start.translation,
+            start_velocity.linear_velocity() * duration,
+            end.translation,
+            end_velocity.linear_velocity() * duration,
+            t,
+        ),
+        rotation: quat_cubic_interpolation(
+            start.rotation,
+            start_velocity.angular_velocity() * duration,
+            end.rotation,
+            end_velocity.angular_velocity() * duration,
+            t,
+        ),
+        scale: start.scale * (1.0 - t) + end.scale * t,
+    }
+}
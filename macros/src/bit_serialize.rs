@@ -0,0 +1,235 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Expr, Field, Fields, LitInt};
+
+/// How a field is written by the bit-packed serialization
+enum FieldEncoding {
+    /// Use the `BitSerialize` implementation of the field
+    Default,
+    /// `#[bits(quantize(min = .., max = .., bits = ..))]`: quantize a float on `bits` bits
+    Quantize { min: Expr, max: Expr, bits: LitInt },
+    /// `#[bits(range(min = .., max = ..))]`: write an integer on the number of bits needed for the range
+    Range { min: Expr, max: Expr },
+}
+
+fn field_encoding(field: &Field) -> syn::Result<FieldEncoding> {
+    let mut encoding = FieldEncoding::Default;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("bits"))
+    {
+        attr.parse_nested_meta(|meta| {
+            let mut min = None;
+            let mut max = None;
+            let mut bits = None;
+            let is_quantize = meta.path.is_ident("quantize");
+            if !is_quantize && !meta.path.is_ident("range") {
+                return Err(meta.error("expected `quantize(..)` or `range(..)`"));
+            }
+            meta.parse_nested_meta(|inner| {
+                if inner.path.is_ident("min") {
+                    min = Some(inner.value()?.parse::<Expr>()?);
+                } else if inner.path.is_ident("max") {
+                    max = Some(inner.value()?.parse::<Expr>()?);
+                } else if is_quantize && inner.path.is_ident("bits") {
+                    bits = Some(inner.value()?.parse::<LitInt>()?);
+                } else {
+                    return Err(inner.error("unexpected argument"));
+                }
+                Ok(())
+            })?;
+            let (Some(min), Some(max)) = (min, max) else {
+                return Err(meta.error("`min` and `max` are required"));
+            };
+            encoding = if is_quantize {
+                let Some(bits) = bits else {
+                    return Err(meta.error("`bits` is required"));
+                };
+                FieldEncoding::Quantize { min, max, bits }
+            } else {
+                FieldEncoding::Range { min, max }
+            };
+            Ok(())
+        })?;
+    }
+    Ok(encoding)
+}
+
+/// Code to encode the field; `access` is an expression that evaluates to a reference to the field
+fn encode_field(
+    field: &Field,
+    access: TokenStream,
+    shared_crate_name: &TokenStream,
+) -> syn::Result<TokenStream> {
+    Ok(match field_encoding(field)? {
+        FieldEncoding::Default => quote! {
+            #shared_crate_name::serialize::bits::BitSerialize::encode(#access, writer)?;
+        },
+        FieldEncoding::Quantize { min, max, bits } => quote! {
+            writer.write_quantized(*#access as f64, (#min) as f64, (#max) as f64, #bits)?;
+        },
+        FieldEncoding::Range { min, max } => quote! {
+            writer.write_bounded(*#access as i64, (#min) as i64, (#max) as i64)?;
+        },
+    })
+}
+
+/// Expression that decodes the field
+fn decode_field(field: &Field, shared_crate_name: &TokenStream) -> syn::Result<TokenStream> {
+    let ty = &field.ty;
+    Ok(match field_encoding(field)? {
+        FieldEncoding::Default => quote! {
+            <#ty as #shared_crate_name::serialize::bits::BitSerialize>::decode(reader)?
+        },
+        FieldEncoding::Quantize { min, max, bits } => quote! {
+            reader.read_quantized((#min) as f64, (#max) as f64, #bits)? as #ty
+        },
+        FieldEncoding::Range { min, max } => quote! {
+            reader.read_bounded((#min) as i64, (#max) as i64)? as #ty
+        },
+    })
+}
+
+/// Returns the bindings used to destructure the fields, the code to encode them,
+/// and the expression to construct the value from the decoded fields
+fn fields_impl(
+    fields: &Fields,
+    shared_crate_name: &TokenStream,
+) -> syn::Result<(TokenStream, TokenStream, TokenStream)> {
+    let mut encode = TokenStream::new();
+    let mut decode = vec![];
+    let mut bindings = vec![];
+    let mut names = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        // bind to generated names, so that fields named `writer` or `reader` do not shadow the arguments
+        let binding = format_ident!("__field_{}", i);
+        encode.extend(encode_field(field, quote! { #binding }, shared_crate_name)?);
+        decode.push(decode_field(field, shared_crate_name)?);
+        bindings.push(binding);
+        names.extend(field.ident.clone());
+    }
+    Ok(match fields {
+        Fields::Named(_) => (
+            quote! { { #(#names: #bindings),* } },
+            encode,
+            quote! { { #(#names: #decode),* } },
+        ),
+        Fields::Unnamed(_) => (
+            quote! { ( #(#bindings),* ) },
+            encode,
+            quote! { ( #(#decode),* ) },
+        ),
+        Fields::Unit => (quote! {}, encode, quote! {}),
+    })
+}
+
+pub fn bit_serialize_impl(
+    input: proc_macro::TokenStream,
+    shared_crate_name: TokenStream,
+) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match bit_serialize_tokens(input, shared_crate_name) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn bit_serialize_tokens(
+    mut input: DeriveInput,
+    shared_crate_name: TokenStream,
+) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let (encode_body, decode_body) = match &input.data {
+        Data::Struct(data) => {
+            let (bindings, encode, construct) = fields_impl(&data.fields, &shared_crate_name)?;
+            (
+                quote! {
+                    let Self #bindings = self;
+                    #encode
+                    Ok(())
+                },
+                quote! { Ok(Self #construct) },
+            )
+        }
+        Data::Enum(data) => {
+            if data.variants.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "cannot derive BitSerialize on an enum without variants",
+                ));
+            }
+            let num_bits = u64::BITS - ((data.variants.len() - 1) as u64).leading_zeros();
+            let mut encode_arms = vec![];
+            let mut decode_arms = vec![];
+            for (index, variant) in data.variants.iter().enumerate() {
+                let variant_name = &variant.ident;
+                let index = index as u64;
+                let (bindings, encode, construct) =
+                    fields_impl(&variant.fields, &shared_crate_name)?;
+                encode_arms.push(quote! {
+                    Self::#variant_name #bindings => {
+                        writer.write_bits(#index, #num_bits)?;
+                        #encode
+                    }
+                });
+                decode_arms.push(quote! {
+                    #index => Self::#variant_name #construct,
+                });
+            }
+            (
+                quote! {
+                    match self {
+                        #(#encode_arms)*
+                    }
+                    Ok(())
+                },
+                quote! {
+                    Ok(match reader.read_bits(#num_bits)? {
+                        #(#decode_arms)*
+                        _ => return Err(#shared_crate_name::serialize::SerializationError::InvalidValue),
+                    })
+                },
+            )
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "cannot derive BitSerialize on a union",
+            ))
+        }
+    };
+
+    // every type parameter must also implement BitSerialize
+    let type_params = input
+        .generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect::<Vec<_>>();
+    let where_clause = input.generics.make_where_clause();
+    for param in type_params {
+        where_clause
+            .predicates
+            .push(parse_quote! { #param: #shared_crate_name::serialize::bits::BitSerialize });
+    }
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #shared_crate_name::serialize::bits::BitSerialize for #name #type_generics #where_clause {
+            #[allow(unused_variables)]
+            fn encode<__W: std::io::Write>(
+                &self,
+                writer: &mut #shared_crate_name::serialize::bits::BitWriter<__W>,
+            ) -> Result<(), #shared_crate_name::serialize::SerializationError> {
+                #encode_body
+            }
+
+            #[allow(unused_variables)]
+            fn decode<__R: std::io::Read>(
+                reader: &mut #shared_crate_name::serialize::bits::BitReader<__R>,
+            ) -> Result<Self, #shared_crate_name::serialize::SerializationError> {
+                #decode_body
+            }
+        }
+    })
+}
@@ -8,8 +8,10 @@ use proc_macro2::{Ident, Span};
 use quote::{format_ident, quote};
 use syn::{parse_macro_input, ItemEnum};
 
+use bit_serialize::bit_serialize_impl;
 use channel::channel_impl;
 
+mod bit_serialize;
 mod channel;
 mod shared;
 
@@ -27,3 +29,22 @@ pub fn channel_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream
     let shared_crate_name = quote! { lightyear };
     channel_impl(input, shared_crate_name)
 }
+
+// BitSerialize
+#[doc(hidden)]
+#[proc_macro_derive(BitSerializeInternal, attributes(bits))]
+pub fn bit_serialize_derive_internal(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let shared_crate_name = quote! { crate };
+    bit_serialize_impl(input, shared_crate_name)
+}
+
+/// Derives the `BitSerialize` trait, to serialize a type with the bit-packed format.
+///
+/// Fields can be annotated with:
+/// - `#[bits(quantize(min = -100.0, max = 100.0, bits = 12))]` to quantize a float on `bits` bits
+/// - `#[bits(range(min = 0, max = 15))]` to write an integer on the number of bits needed for the range
+#[proc_macro_derive(BitSerialize, attributes(bits))]
+pub fn bit_serialize_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let shared_crate_name = quote! { lightyear };
+    bit_serialize_impl(input, shared_crate_name)
+}